@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE han_halight_swaps;
+DROP TABLE han_ethereum_halight_bitcoin_request_messages;
+DROP TABLE han_ethereum_halight_bitcoin_accept_messages;
@@ -0,0 +1,34 @@
+-- Your SQL goes here
+
+CREATE TABLE han_halight_swaps
+(
+    id INTEGER   NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    role         NOT NULL,
+    counterparty NOT NULL
+);
+
+CREATE TABLE han_ethereum_halight_bitcoin_request_messages
+(
+    id INTEGER                 NOT NULL PRIMARY KEY,
+    swap_id UNIQUE             NOT NULL,
+    ethereum_chain_id          NOT NULL,
+    lightning_network          NOT NULL,
+    ether_amount               NOT NULL,
+    bitcoin_amount             NOT NULL,
+    hash_function              NOT NULL,
+    ethereum_refund_identity   NOT NULL,
+    lightning_redeem_identity  NOT NULL,
+    ethereum_expiry            NOT NULL,
+    lightning_cltv_expiry      NOT NULL,
+    secret_hash                NOT NULL
+);
+
+CREATE TABLE han_ethereum_halight_bitcoin_accept_messages
+(
+    id INTEGER                 NOT NULL PRIMARY KEY,
+    swap_id UNIQUE             NOT NULL,
+    ethereum_redeem_identity   NOT NULL,
+    lightning_refund_identity  NOT NULL,
+    at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+CREATE TABLE rfc003_ledger_events
+(
+    id INTEGER       NOT NULL PRIMARY KEY,
+    swap_id          NOT NULL,
+    ledger           NOT NULL,
+    event            NOT NULL,
+    transaction_hash NOT NULL,
+    transaction_data NOT NULL,
+    htlc_location,
+    asset,
+    secret,
+    confirmations,
+    UNIQUE (swap_id, ledger, event)
+);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE rfc003_automatic_actions
+(
+    id INTEGER       NOT NULL PRIMARY KEY,
+    swap_id          NOT NULL,
+    ledger           NOT NULL,
+    action           NOT NULL,
+    transaction_hash NOT NULL,
+    at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE bitcoin_wallet_utxos;
+DROP TABLE bitcoin_wallet_blocks;
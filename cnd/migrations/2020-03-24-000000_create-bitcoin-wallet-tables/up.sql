@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+CREATE TABLE bitcoin_wallet_blocks
+(
+    id INTEGER NOT NULL PRIMARY KEY,
+    block_hash NOT NULL UNIQUE
+);
+
+CREATE TABLE bitcoin_wallet_utxos
+(
+    id INTEGER NOT NULL PRIMARY KEY,
+    txid        NOT NULL,
+    vout        NOT NULL,
+    amount      NOT NULL,
+    received_in NOT NULL,
+    spent_in,
+    UNIQUE (txid, vout)
+);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+CREATE TABLE rfc003_autopilot_swaps
+(
+    id INTEGER NOT NULL PRIMARY KEY,
+    swap_id    NOT NULL UNIQUE
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+CREATE TABLE webhook_deliveries
+(
+    id INTEGER              NOT NULL PRIMARY KEY,
+    url                     NOT NULL,
+    payload                 NOT NULL,
+    attempts INTEGER        NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,54 @@
+-- Your SQL goes here
+
+CREATE TABLE listed_swaps
+(
+    id INTEGER                NOT NULL PRIMARY KEY,
+    swap_id UNIQUE            NOT NULL,
+    protocol                  NOT NULL,
+    role                      NOT NULL,
+    counterparty              NOT NULL,
+    alpha_ledger              NOT NULL,
+    beta_ledger               NOT NULL,
+    alpha_asset               NOT NULL,
+    beta_asset                NOT NULL,
+    status                    NOT NULL DEFAULT 'IN_PROGRESS',
+    created_at DATETIME       DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX listed_swaps_by_status ON listed_swaps (status, id);
+CREATE INDEX listed_swaps_by_counterparty ON listed_swaps (counterparty, id);
+CREATE INDEX listed_swaps_by_created_at ON listed_swaps (created_at);
+
+-- The swaps that existed before are listed as in progress until cnd looked at
+-- their state. They were created when they were accepted, swaps that were not
+-- accepted have no creation time and are left out of filters by it.
+
+INSERT INTO listed_swaps (swap_id, protocol, role, counterparty, alpha_ledger, beta_ledger, alpha_asset, beta_asset, created_at)
+SELECT rfc003.swap_id, 'rfc003', rfc003.role, rfc003.counterparty, rfc003.alpha_ledger, rfc003.beta_ledger, rfc003.alpha_asset, rfc003.beta_asset, COALESCE(bitcoin_ethereum_accepts.at, ethereum_bitcoin_accepts.at)
+FROM (
+SELECT swaps.id, swaps.swap_id, swaps.role, swaps.counterparty, 'bitcoin' AS alpha_ledger, 'ethereum' AS beta_ledger, 'bitcoin' AS alpha_asset, 'ether' AS beta_asset
+FROM rfc003_swaps AS swaps
+INNER JOIN rfc003_bitcoin_ethereum_bitcoin_ether_request_messages AS requests ON requests.swap_id = swaps.swap_id
+UNION ALL
+SELECT swaps.id, swaps.swap_id, swaps.role, swaps.counterparty, 'ethereum', 'bitcoin', 'ether', 'bitcoin'
+FROM rfc003_swaps AS swaps
+INNER JOIN rfc003_ethereum_bitcoin_ether_bitcoin_request_messages AS requests ON requests.swap_id = swaps.swap_id
+UNION ALL
+SELECT swaps.id, swaps.swap_id, swaps.role, swaps.counterparty, 'bitcoin', 'ethereum', 'bitcoin', 'erc20'
+FROM rfc003_swaps AS swaps
+INNER JOIN rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages AS requests ON requests.swap_id = swaps.swap_id
+UNION ALL
+SELECT swaps.id, swaps.swap_id, swaps.role, swaps.counterparty, 'ethereum', 'bitcoin', 'erc20', 'bitcoin'
+FROM rfc003_swaps AS swaps
+INNER JOIN rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages AS requests ON requests.swap_id = swaps.swap_id
+) AS rfc003
+LEFT JOIN rfc003_bitcoin_ethereum_accept_messages AS bitcoin_ethereum_accepts ON bitcoin_ethereum_accepts.swap_id = rfc003.swap_id
+LEFT JOIN rfc003_ethereum_bitcoin_accept_messages AS ethereum_bitcoin_accepts ON ethereum_bitcoin_accepts.swap_id = rfc003.swap_id
+ORDER BY rfc003.id;
+
+INSERT INTO listed_swaps (swap_id, protocol, role, counterparty, alpha_ledger, beta_ledger, alpha_asset, beta_asset, created_at)
+SELECT swaps.swap_id, 'han_halight', swaps.role, swaps.counterparty, 'ethereum', 'lightning', 'ether', 'bitcoin', accepts.at
+FROM han_halight_swaps AS swaps
+INNER JOIN han_ethereum_halight_bitcoin_request_messages AS requests ON requests.swap_id = swaps.swap_id
+LEFT JOIN han_ethereum_halight_bitcoin_accept_messages AS accepts ON accepts.swap_id = swaps.swap_id
+ORDER BY swaps.id;
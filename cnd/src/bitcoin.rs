@@ -6,6 +6,9 @@
 //!       libraries
 //!     - Common functionality that is not (yet) available upstream
 
+pub mod fee_estimates;
+pub mod wallet;
+
 use bitcoin::secp256k1;
 use serde::{
     de::{self, Visitor},
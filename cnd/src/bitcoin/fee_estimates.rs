@@ -0,0 +1,83 @@
+use crate::btsieve::bitcoin::BitcoinConnector;
+use serde::Serialize;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// The confirmation targets in blocks the fee is estimated for.
+pub const TARGETS: [u32; 4] = [1, 3, 6, 144];
+
+/// The confirmation target of the fee suggested for redeem and refund
+/// transactions.
+const SUGGESTED_TARGET: u32 = 6;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Estimate {
+    pub target_blocks: u32,
+    pub fee_per_wu: usize,
+}
+
+/// The most recent fee estimates of the bitcoin backend.
+///
+/// The estimates are kept in memory so that rendering a swap does not have to
+/// wait for the backend.
+#[derive(Clone, Debug)]
+pub struct FeeEstimates {
+    connector: BitcoinConnector,
+    estimates: Arc<RwLock<Vec<Estimate>>>,
+}
+
+impl FeeEstimates {
+    pub fn new(connector: BitcoinConnector) -> Self {
+        Self {
+            connector,
+            estimates: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Returns the estimates for those of `TARGETS` the backend could
+    /// estimate, empty before the first refresh.
+    pub fn latest(&self) -> Vec<Estimate> {
+        self.estimates
+            .read()
+            .expect("estimates are not poisoned")
+            .clone()
+    }
+
+    pub fn suggested_fee_per_wu(&self) -> Option<usize> {
+        self.latest()
+            .into_iter()
+            .find(|estimate| estimate.target_blocks == SUGGESTED_TARGET)
+            .map(|estimate| estimate.fee_per_wu)
+    }
+
+    pub async fn refresh(&self) -> anyhow::Result<()> {
+        let mut estimates = Vec::with_capacity(TARGETS.len());
+        for &target_blocks in TARGETS.iter() {
+            if let Some(fee_per_wu) = self.connector.estimate_fee_per_wu(target_blocks).await? {
+                estimates.push(Estimate {
+                    target_blocks,
+                    fee_per_wu,
+                });
+            }
+        }
+
+        *self.estimates.write().expect("estimates are not poisoned") = estimates;
+
+        Ok(())
+    }
+}
+
+/// Refreshes the fee estimates periodically, never returns.
+pub async fn keep_up_to_date(fee_estimates: FeeEstimates) {
+    loop {
+        if let Err(e) = fee_estimates.refresh().await {
+            tracing::warn!("failed to refresh bitcoin fee estimates: {:?}", e);
+        }
+
+        tokio::time::delay_for(REFRESH_INTERVAL).await;
+    }
+}
@@ -0,0 +1,686 @@
+//! A minimal single-address wallet that allows cnd to execute Bitcoin actions
+//! on behalf of the user.
+//!
+//! The key of the wallet is derived from the `RootSeed`. Its unspent outputs
+//! are tracked by following the blockchain through the bitcoin connector and
+//! persisted in the database. Only confirmed outputs are spendable.
+
+use crate::{
+    asset,
+    bitcoin::PublicKey,
+    btsieve::{
+        bitcoin::{BitcoinConnector, Cache},
+        BlockByHash, LatestBlock,
+    },
+    db::{LoadBitcoinWallet, RollBackBitcoinWalletBlock, SaveBitcoinWalletBlock, Sqlite, Utxo},
+    seed::RootSeed,
+};
+use anyhow::{anyhow, bail};
+use bitcoin::{
+    secp256k1::{Message, SecretKey},
+    util::bip143::SighashComponents,
+    Address, BitcoinHash, Block, BlockHash, OutPoint, Script, Transaction, TxIn, TxOut, Txid,
+};
+use futures_core::compat::Future01CompatExt;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::Mutex;
+
+/// How often the wallet looks for new blocks.
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How far back the wallet walks the blockchain to find a block it processed
+/// before giving up. This is also the number of processed blocks the wallet
+/// remembers to find the common ancestor after a reorg.
+const MAX_SYNC_DEPTH: usize = 1000;
+
+/// Outputs below this value are not created, they are added to the fee
+/// instead.
+const DUST_LIMIT: u64 = 546;
+
+/// Version, input and output count, lock time and the segwit marker and flag.
+const TX_OVERHEAD_WEIGHT: u64 = 4 * (4 + 1 + 1 + 4) + 2;
+
+/// Outpoint, empty script and sequence plus a witness of a DER signature and a
+/// compressed public key.
+const P2WPKH_INPUT_WEIGHT: u64 = 4 * (32 + 4 + 1 + 4) + (1 + 1 + 72 + 1 + 33);
+
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct Wallet {
+    #[derivative(Debug = "ignore")]
+    secret_key: SecretKey,
+    public_key: PublicKey,
+    network: bitcoin::Network,
+    fee_per_wu: usize,
+    connector: Cache<BitcoinConnector>,
+    db: Sqlite,
+    #[derivative(Debug = "ignore")]
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// The most recently processed blocks, oldest first.
+    blocks: Vec<BlockHash>,
+    utxos: HashMap<OutPoint, asset::Bitcoin>,
+    /// Outputs that were spent in a transaction we broadcasted but that has
+    /// not been included in a block yet.
+    reserved: HashSet<OutPoint>,
+}
+
+impl Wallet {
+    /// Creates the wallet and restores the unspent outputs found in previous
+    /// runs from the database.
+    pub async fn new(
+        seed: &RootSeed,
+        network: bitcoin::Network,
+        fee_per_wu: usize,
+        connector: Cache<BitcoinConnector>,
+        db: Sqlite,
+    ) -> anyhow::Result<Self> {
+        let secret_key = SecretKey::from_slice(&seed.sha256_with_seed(&[b"BITCOIN_WALLET"]))
+            .expect("The probability of this happening is < 1 in 2^120");
+        let public_key = PublicKey::from_secret_key(&*crate::SECP, &secret_key);
+
+        let blocks = db.load_bitcoin_wallet_blocks(MAX_SYNC_DEPTH).await?;
+        let utxos = db
+            .load_bitcoin_wallet_utxos()
+            .await?
+            .into_iter()
+            .map(|utxo| (utxo.outpoint, utxo.amount))
+            .collect();
+
+        Ok(Wallet {
+            secret_key,
+            public_key,
+            network,
+            fee_per_wu,
+            connector,
+            db,
+            state: Arc::new(Mutex::new(State {
+                blocks,
+                utxos,
+                reserved: HashSet::new(),
+            })),
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        Address::p2wpkh(&self.public_key.into(), self.network)
+    }
+
+    pub fn network(&self) -> bitcoin::Network {
+        self.network
+    }
+
+    pub fn fee_per_wu(&self) -> usize {
+        self.fee_per_wu
+    }
+
+    /// The sum of all confirmed outputs that are not being spent already.
+    pub async fn balance(&self) -> asset::Bitcoin {
+        let state = self.state.lock().await;
+        let sats = state
+            .utxos
+            .iter()
+            .filter(|(outpoint, _)| !state.reserved.contains(outpoint))
+            .map(|(_, amount)| amount.as_sat())
+            .sum();
+
+        asset::Bitcoin::from_sat(sats)
+    }
+
+    /// Processes all blocks that were mined since the last sync.
+    ///
+    /// On the very first sync, the wallet starts following the blockchain at
+    /// the latest block. Funds sent to the wallet before that are not found.
+    ///
+    /// If the blocks processed last were orphaned, they are rolled back down to
+    /// the common ancestor before the blocks of the new branch are processed.
+    pub async fn sync(&self) -> anyhow::Result<()> {
+        self.sync_with(self.connector.clone()).await
+    }
+
+    async fn sync_with<C>(&self, mut connector: C) -> anyhow::Result<()>
+    where
+        C: LatestBlock<Block = Block, BlockHash = BlockHash>
+            + BlockByHash<Block = Block, BlockHash = BlockHash>,
+    {
+        let processed = self.state.lock().await.blocks.clone();
+
+        let mut block = connector.latest_block().compat().await?;
+        let mut new_blocks = Vec::new();
+
+        let orphaned = if processed.is_empty() {
+            new_blocks.push(block);
+            0
+        } else {
+            loop {
+                let block_hash = block.bitcoin_hash();
+                if let Some(index) = processed.iter().rposition(|hash| *hash == block_hash) {
+                    break processed.len() - index - 1;
+                }
+
+                if new_blocks.len() == MAX_SYNC_DEPTH {
+                    bail!(
+                        "none of the processed blocks is among the {} latest blocks",
+                        MAX_SYNC_DEPTH
+                    );
+                }
+
+                let prev_blockhash = block.header.prev_blockhash;
+                new_blocks.push(block);
+                block = connector.block_by_hash(prev_blockhash).compat().await?;
+            }
+        };
+
+        for block_hash in processed.iter().rev().take(orphaned) {
+            self.roll_back_block(*block_hash).await?;
+        }
+
+        for block in new_blocks.into_iter().rev() {
+            self.process_block(block).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn roll_back_block(&self, block_hash: BlockHash) -> anyhow::Result<()> {
+        let mut state = self.state.lock().await;
+
+        self.db.roll_back_bitcoin_wallet_block(block_hash).await?;
+        let utxos = self
+            .db
+            .load_bitcoin_wallet_utxos()
+            .await?
+            .into_iter()
+            .map(|utxo| (utxo.outpoint, utxo.amount))
+            .collect::<HashMap<_, _>>();
+
+        tracing::info!(
+            "rolled back orphaned block {} in bitcoin wallet",
+            block_hash
+        );
+
+        // Only the wallet can spend its outputs, the ones spent in the orphaned
+        // block were spent by one of our transactions which is likely to be
+        // mined again.
+        let unspent = utxos
+            .keys()
+            .filter(|outpoint| !state.utxos.contains_key(outpoint))
+            .cloned()
+            .collect::<Vec<_>>();
+        state.reserved.extend(unspent);
+        state
+            .reserved
+            .retain(|outpoint| utxos.contains_key(outpoint));
+        state.utxos = utxos;
+        state.blocks.retain(|hash| *hash != block_hash);
+
+        Ok(())
+    }
+
+    async fn process_block(&self, block: Block) -> anyhow::Result<()> {
+        let block_hash = block.bitcoin_hash();
+        let script_pubkey = self.address().script_pubkey();
+
+        let mut state = self.state.lock().await;
+        let mut utxos = state.utxos.clone();
+
+        for transaction in block.txdata.iter() {
+            for input in transaction.input.iter() {
+                utxos.remove(&input.previous_output);
+            }
+
+            let txid = transaction.txid();
+            for (vout, output) in transaction.output.iter().enumerate() {
+                if output.script_pubkey == script_pubkey {
+                    let outpoint = OutPoint {
+                        txid,
+                        vout: vout as u32,
+                    };
+                    utxos.insert(outpoint, asset::Bitcoin::from_sat(output.value));
+                }
+            }
+        }
+
+        let spent = state
+            .utxos
+            .keys()
+            .filter(|outpoint| !utxos.contains_key(outpoint))
+            .cloned()
+            .collect::<Vec<_>>();
+        let received = utxos
+            .iter()
+            .filter(|(outpoint, _)| !state.utxos.contains_key(outpoint))
+            .map(|(outpoint, amount)| Utxo {
+                outpoint: *outpoint,
+                amount: *amount,
+            })
+            .collect::<Vec<_>>();
+
+        self.db
+            .save_bitcoin_wallet_block(block_hash, spent, received)
+            .await?;
+
+        state
+            .reserved
+            .retain(|outpoint| utxos.contains_key(outpoint));
+        state.utxos = utxos;
+        state.blocks.push(block_hash);
+        if state.blocks.len() > MAX_SYNC_DEPTH {
+            state.blocks.remove(0);
+        }
+
+        Ok(())
+    }
+
+    /// Pays `amount` to `to` from the wallet's confirmed outputs, any change is
+    /// sent back to the wallet.
+    pub async fn send_to_address(
+        &self,
+        to: Address,
+        amount: asset::Bitcoin,
+    ) -> anyhow::Result<Txid> {
+        if to.network != self.network {
+            bail!(
+                "cannot send to {} address from {} wallet",
+                to.network,
+                self.network
+            );
+        }
+
+        let mut state = self.state.lock().await;
+
+        let available = state
+            .utxos
+            .iter()
+            .filter(|(outpoint, _)| !state.reserved.contains(outpoint))
+            .map(|(outpoint, amount)| Utxo {
+                outpoint: *outpoint,
+                amount: *amount,
+            })
+            .collect::<Vec<_>>();
+
+        let change = self.address().script_pubkey();
+        let selection = select_coins(
+            available,
+            amount.as_sat(),
+            &to.script_pubkey(),
+            &change,
+            self.fee_per_wu as u64,
+        )
+        .ok_or_else(|| anyhow!("insufficient funds to send {} to {}", amount, to))?;
+
+        let mut output = vec![TxOut {
+            value: amount.as_sat(),
+            script_pubkey: to.script_pubkey(),
+        }];
+        if let Some(value) = selection.change {
+            output.push(TxOut {
+                value,
+                script_pubkey: change,
+            });
+        }
+
+        let transaction = self.sign(&selection.inputs, output);
+        let txid = self.broadcast(&transaction).await?;
+
+        state
+            .reserved
+            .extend(selection.inputs.iter().map(|utxo| utxo.outpoint));
+
+        Ok(txid)
+    }
+
+    pub async fn broadcast(&self, transaction: &Transaction) -> anyhow::Result<Txid> {
+        self.connector
+            .connector
+            .send_raw_transaction(transaction)
+            .await
+    }
+
+    fn sign(&self, inputs: &[Utxo], output: Vec<TxOut>) -> Transaction {
+        let mut transaction = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: inputs
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: Script::new(),
+                    sequence: 0xFFFF_FFFF,
+                    witness: vec![],
+                })
+                .collect(),
+            output,
+        };
+
+        let public_key = bitcoin::PublicKey::from(self.public_key);
+        let script_code = Address::p2pkh(&public_key, self.network).script_pubkey();
+        let sighash_components = SighashComponents::new(&transaction);
+
+        let witnesses = transaction
+            .input
+            .iter()
+            .zip(inputs)
+            .map(|(input, utxo)| {
+                let sighash =
+                    sighash_components.sighash_all(input, &script_code, utxo.amount.as_sat());
+                let message = Message::from_slice(&sighash[..]).expect("sighash is 32 bytes long");
+                let signature = crate::SECP.sign(&message, &self.secret_key);
+
+                let mut signature = signature.serialize_der().to_vec();
+                signature.push(bitcoin::SigHashType::All as u8);
+
+                vec![signature, public_key.to_bytes()]
+            })
+            .collect::<Vec<_>>();
+
+        for (input, witness) in transaction.input.iter_mut().zip(witnesses) {
+            input.witness = witness;
+        }
+
+        transaction
+    }
+}
+
+/// Keeps the wallet up to date with the blockchain, never returns.
+pub async fn keep_in_sync(wallet: Wallet) {
+    loop {
+        if let Err(e) = wallet.sync().await {
+            tracing::warn!("failed to sync bitcoin wallet: {:?}", e);
+        }
+
+        tokio::time::delay_for(SYNC_INTERVAL).await;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct CoinSelection {
+    inputs: Vec<Utxo>,
+    /// `None` if the change would be dust and is added to the fee instead.
+    change: Option<u64>,
+}
+
+/// Selects the largest outputs first until they cover `amount` and the fee
+/// for a transaction paying to `recipient` and `change`.
+fn select_coins(
+    mut utxos: Vec<Utxo>,
+    amount: u64,
+    recipient: &Script,
+    change: &Script,
+    fee_per_wu: u64,
+) -> Option<CoinSelection> {
+    utxos.sort_by(|a, b| b.amount.cmp(&a.amount));
+
+    let mut weight = TX_OVERHEAD_WEIGHT + output_weight(recipient) + output_weight(change);
+    let mut total = 0;
+    let mut inputs = Vec::new();
+
+    for utxo in utxos {
+        total += utxo.amount.as_sat();
+        weight += P2WPKH_INPUT_WEIGHT;
+        inputs.push(utxo);
+
+        let required = amount + weight * fee_per_wu;
+        if total < required {
+            continue;
+        }
+
+        let change = total - required;
+        let change = if change < DUST_LIMIT {
+            None
+        } else {
+            Some(change)
+        };
+
+        return Some(CoinSelection { inputs, change });
+    }
+
+    None
+}
+
+fn output_weight(script_pubkey: &Script) -> u64 {
+    4 * (8 + 1 + script_pubkey.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btsieve::bitcoin::BitcoindConnector;
+    use bitcoin::{blockdata::constants::genesis_block, BlockHeader};
+    use futures::{future, Future};
+    use std::{path::Path, str::FromStr};
+
+    fn utxo(vout: u32, sats: u64) -> Utxo {
+        Utxo {
+            outpoint: OutPoint {
+                txid: Txid::from_str(
+                    "0000000000000000000000000000000000000000000000000000000000000001",
+                )
+                .unwrap(),
+                vout,
+            },
+            amount: asset::Bitcoin::from_sat(sats),
+        }
+    }
+
+    fn p2wpkh_script() -> Script {
+        let secret_key = SecretKey::from_slice(&[0x46; 32]).unwrap();
+        let public_key = PublicKey::from_secret_key(&*crate::SECP, &secret_key);
+
+        Address::p2wpkh(&public_key.into(), bitcoin::Network::Regtest).script_pubkey()
+    }
+
+    #[test]
+    fn selects_largest_outputs_first_and_returns_change() {
+        let script = p2wpkh_script();
+
+        let selection = select_coins(
+            vec![utxo(0, 10_000), utxo(1, 50_000), utxo(2, 30_000)],
+            60_000,
+            &script,
+            &script,
+            1,
+        )
+        .unwrap();
+
+        let weight = TX_OVERHEAD_WEIGHT + 2 * output_weight(&script) + 2 * P2WPKH_INPUT_WEIGHT;
+        assert_eq!(selection.inputs, vec![utxo(1, 50_000), utxo(2, 30_000)]);
+        assert_eq!(selection.change, Some(80_000 - 60_000 - weight));
+    }
+
+    #[test]
+    fn adds_dust_change_to_fee() {
+        let script = p2wpkh_script();
+        let fee = TX_OVERHEAD_WEIGHT + 2 * output_weight(&script) + P2WPKH_INPUT_WEIGHT;
+
+        let selection = select_coins(
+            vec![utxo(0, 50_000 + fee + 100)],
+            50_000,
+            &script,
+            &script,
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(selection.inputs, vec![utxo(0, 50_000 + fee + 100)]);
+        assert_eq!(selection.change, None);
+    }
+
+    #[test]
+    fn fails_if_funds_do_not_cover_amount_and_fee() {
+        let script = p2wpkh_script();
+
+        let selection = select_coins(vec![utxo(0, 50_000)], 50_000, &script, &script, 1);
+
+        assert_eq!(selection, None);
+    }
+
+    #[derive(Clone)]
+    struct Blockchain {
+        latest: Block,
+        blocks: HashMap<BlockHash, Block>,
+    }
+
+    impl Blockchain {
+        fn new(blocks: &[&Block]) -> Self {
+            Blockchain {
+                latest: blocks[blocks.len() - 1].clone(),
+                blocks: blocks
+                    .iter()
+                    .map(|block| (block.bitcoin_hash(), (*block).clone()))
+                    .collect(),
+            }
+        }
+    }
+
+    impl LatestBlock for Blockchain {
+        type Block = Block;
+        type BlockHash = BlockHash;
+
+        fn latest_block(
+            &mut self,
+        ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
+            Box::new(future::ok(self.latest.clone()))
+        }
+    }
+
+    impl BlockByHash for Blockchain {
+        type Block = Block;
+        type BlockHash = BlockHash;
+
+        fn block_by_hash(
+            &self,
+            block_hash: Self::BlockHash,
+        ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
+            let block = self
+                .blocks
+                .get(&block_hash)
+                .cloned()
+                .ok_or_else(|| anyhow!("unknown block {}", block_hash));
+
+            Box::new(future::result(block))
+        }
+    }
+
+    /// The nonce tells apart blocks on different branches of a fork.
+    fn block(prev: &Block, nonce: u32, txdata: Vec<Transaction>) -> Block {
+        Block {
+            header: BlockHeader {
+                version: 1,
+                prev_blockhash: prev.bitcoin_hash(),
+                merkle_root: Default::default(),
+                time: prev.header.time + 600,
+                bits: prev.header.bits,
+                nonce,
+            },
+            txdata,
+        }
+    }
+
+    fn transaction(input: Vec<OutPoint>, value: u64, script_pubkey: Script) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: input
+                .into_iter()
+                .map(|previous_output| TxIn {
+                    previous_output,
+                    script_sig: Script::new(),
+                    sequence: 0xFFFF_FFFF,
+                    witness: vec![],
+                })
+                .collect(),
+            output: vec![TxOut {
+                value,
+                script_pubkey,
+            }],
+        }
+    }
+
+    async fn wallet() -> anyhow::Result<Wallet> {
+        let connector =
+            BitcoindConnector::new("http://localhost:18443".parse()?, bitcoin::Network::Regtest)?;
+
+        Wallet::new(
+            &RootSeed::from(*b"this string is exactly 32 bytes!"),
+            bitcoin::Network::Regtest,
+            1,
+            Cache::new(BitcoinConnector::from(connector), 10),
+            Sqlite::new(Path::new(":memory:"))?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn rolls_back_orphaned_blocks_to_the_common_ancestor() -> anyhow::Result<()> {
+        let wallet = wallet().await?;
+        let script_pubkey = wallet.address().script_pubkey();
+
+        let funding = transaction(vec![], 100_000, script_pubkey.clone());
+        let funding_outpoint = OutPoint {
+            txid: funding.txid(),
+            vout: 0,
+        };
+        let spend = transaction(vec![funding_outpoint], 40_000, script_pubkey.clone());
+        let other_funding = transaction(vec![], 70_000, script_pubkey);
+
+        let genesis = genesis_block(bitcoin::Network::Regtest);
+        let block1 = block(&genesis, 0, vec![funding]);
+        let block2 = block(&block1, 0, vec![spend]);
+        let block2b = block(&block1, 1, vec![other_funding.clone()]);
+        let block3b = block(&block2b, 1, vec![]);
+
+        wallet.sync_with(Blockchain::new(&[&genesis])).await?;
+        wallet
+            .sync_with(Blockchain::new(&[&genesis, &block1, &block2]))
+            .await?;
+        assert_eq!(wallet.balance().await, asset::Bitcoin::from_sat(40_000));
+
+        wallet
+            .sync_with(Blockchain::new(&[
+                &genesis, &block1, &block2, &block2b, &block3b,
+            ]))
+            .await?;
+
+        // The output spent in the orphaned block is reserved until our
+        // spending transaction is mined again.
+        assert_eq!(wallet.balance().await, asset::Bitcoin::from_sat(70_000));
+
+        let mut utxos = wallet.db.load_bitcoin_wallet_utxos().await?;
+        utxos.sort_by_key(|utxo| utxo.amount);
+        assert_eq!(
+            utxos,
+            vec![
+                Utxo {
+                    outpoint: OutPoint {
+                        txid: other_funding.txid(),
+                        vout: 0,
+                    },
+                    amount: asset::Bitcoin::from_sat(70_000),
+                },
+                Utxo {
+                    outpoint: funding_outpoint,
+                    amount: asset::Bitcoin::from_sat(100_000),
+                },
+            ]
+        );
+        assert_eq!(
+            wallet.db.load_bitcoin_wallet_blocks(MAX_SYNC_DEPTH).await?,
+            vec![
+                genesis.bitcoin_hash(),
+                block1.bitcoin_hash(),
+                block2b.bitcoin_hash(),
+                block3b.bitcoin_hash()
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use bitcoin::{consensus::encode::deserialize, hashes::Hash, BitcoinHash, BlockHash};
+use std::{
+    sync::{Arc, RwLock},
+    thread,
+};
+use tokio::sync::broadcast;
+
+/// Number of block notifications a subscriber can lag behind before it misses
+/// some.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// The bitcoind ZMQ publisher new blocks are announced by.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Publisher {
+    /// `zmqpubrawblock`, announces the serialized block.
+    RawBlock(String),
+    /// `zmqpubhashblock`, announces the hash of the block only.
+    HashBlock(String),
+}
+
+impl Publisher {
+    pub fn from_config(
+        zmqpubrawblock: Option<String>,
+        zmqpubhashblock: Option<String>,
+    ) -> Option<Self> {
+        match (zmqpubrawblock, zmqpubhashblock) {
+            (Some(endpoint), _) => Some(Publisher::RawBlock(endpoint)),
+            (None, Some(endpoint)) => Some(Publisher::HashBlock(endpoint)),
+            (None, None) => None,
+        }
+    }
+
+    fn endpoint(&self) -> &str {
+        match self {
+            Publisher::RawBlock(endpoint) | Publisher::HashBlock(endpoint) => endpoint,
+        }
+    }
+
+    fn topic(&self) -> &'static [u8] {
+        match self {
+            Publisher::RawBlock(_) => b"rawblock",
+            Publisher::HashBlock(_) => b"hashblock",
+        }
+    }
+}
+
+/// The latest block announced by bitcoind.
+///
+/// The block itself is only known if it was published through
+/// `zmqpubrawblock` or was fetched already.
+#[derive(Clone, Debug, Default)]
+pub struct Tip {
+    pub hash: Option<BlockHash>,
+    pub block: Option<bitcoin::Block>,
+}
+
+/// New blocks as they are announced by bitcoind's ZMQ interface.
+///
+/// The hash of every new block is sent to a broadcast channel that any number
+/// of watchers can subscribe to, the latest block is kept around so that it
+/// does not have to be requested from bitcoind.
+#[derive(Clone, Debug)]
+pub struct BlockNotifications {
+    tip: Arc<RwLock<Tip>>,
+    new_blocks: broadcast::Sender<BlockHash>,
+}
+
+impl BlockNotifications {
+    /// Connects to the publisher and starts forwarding its notifications.
+    ///
+    /// The socket is read on a dedicated thread because the zmq crate only
+    /// offers a blocking interface. Lost connections are reestablished by zmq.
+    pub fn subscribe(publisher: Publisher) -> anyhow::Result<Self> {
+        let context = zmq::Context::new();
+        let socket = context.socket(zmq::SUB)?;
+        socket.connect(publisher.endpoint())?;
+        socket.set_subscribe(publisher.topic())?;
+
+        let (new_blocks, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let notifications = BlockNotifications {
+            tip: Arc::new(RwLock::new(Tip::default())),
+            new_blocks,
+        };
+
+        tracing::info!("Subscribing to bitcoind blocks at {}", publisher.endpoint());
+
+        thread::Builder::new()
+            .name("bitcoind-zmq".to_owned())
+            .spawn({
+                let notifications = notifications.clone();
+                move || notifications.forward(context, socket, publisher)
+            })?;
+
+        Ok(notifications)
+    }
+
+    /// Returns a receiver for the hashes of all blocks announced from now on.
+    ///
+    /// The chain follower waits on it to scan new blocks as soon as they are
+    /// announced.
+    pub fn subscribe_new_blocks(&self) -> broadcast::Receiver<BlockHash> {
+        self.new_blocks.subscribe()
+    }
+
+    pub fn tip(&self) -> Tip {
+        self.tip.read().expect("tip lock is not poisoned").clone()
+    }
+
+    /// Remembers a block that was fetched for the announced block hash.
+    pub fn fetched(&self, block: bitcoin::Block) {
+        let mut tip = self.tip.write().expect("tip lock is not poisoned");
+
+        if tip.hash == Some(block.bitcoin_hash()) {
+            tip.block = Some(block);
+        }
+    }
+
+    fn forward(&self, _context: zmq::Context, socket: zmq::Socket, publisher: Publisher) {
+        loop {
+            match socket.recv_multipart(0) {
+                Ok(message) => {
+                    if let Err(e) = self.handle_message(&publisher, message) {
+                        tracing::warn!("Could not handle bitcoind ZMQ message: {}", e)
+                    }
+                }
+                Err(e) => tracing::warn!("Could not receive bitcoind ZMQ message: {}", e),
+            }
+        }
+    }
+
+    /// Messages consist of the topic, the body and a sequence number.
+    fn handle_message(&self, publisher: &Publisher, message: Vec<Vec<u8>>) -> anyhow::Result<()> {
+        let body = message
+            .get(1)
+            .ok_or_else(|| anyhow::anyhow!("message without body"))?;
+
+        let (hash, block) = match publisher {
+            Publisher::RawBlock(_) => {
+                let block = deserialize::<bitcoin::Block>(body)?;
+
+                (block.bitcoin_hash(), Some(block))
+            }
+            Publisher::HashBlock(_) => {
+                // bitcoind publishes the hash in the byte order it is displayed in
+                let mut bytes = body.clone();
+                bytes.reverse();
+
+                (BlockHash::from_slice(&bytes)?, None)
+            }
+        };
+
+        tracing::debug!("bitcoind announced block {}", hash);
+        *self.tip.write().expect("tip lock is not poisoned") = Tip {
+            hash: Some(hash),
+            block,
+        };
+
+        // nobody listening is fine, the tip is updated anyway
+        let _ = self.new_blocks.send(hash);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{blockdata::constants::genesis_block, consensus::encode::serialize, Network};
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[test]
+    fn raw_block_publisher_is_preferred() {
+        let publisher = Publisher::from_config(
+            Some("tcp://127.0.0.1:28332".to_owned()),
+            Some("tcp://127.0.0.1:28333".to_owned()),
+        );
+
+        assert_eq!(
+            publisher,
+            Some(Publisher::RawBlock("tcp://127.0.0.1:28332".to_owned()))
+        );
+    }
+
+    #[test]
+    fn no_publisher_without_endpoints() {
+        assert_eq!(Publisher::from_config(None, None), None);
+    }
+
+    #[tokio::test]
+    async fn published_block_wakes_up_watcher() {
+        let context = zmq::Context::new();
+        let socket = context.socket(zmq::PUB).unwrap();
+        socket.bind("tcp://127.0.0.1:*").unwrap();
+        let endpoint = socket.get_last_endpoint().unwrap().unwrap();
+
+        let notifications = BlockNotifications::subscribe(Publisher::RawBlock(endpoint)).unwrap();
+        let mut new_blocks = notifications.subscribe_new_blocks();
+        let block = genesis_block(Network::Regtest);
+
+        // messages published before the subscription reached the publisher are
+        // dropped, hence the block is published until it arrives
+        let announced = timeout(Duration::from_secs(5), async {
+            loop {
+                socket
+                    .send_multipart(
+                        vec![
+                            b"rawblock".to_vec(),
+                            serialize(&block),
+                            0u32.to_le_bytes().to_vec(),
+                        ],
+                        0,
+                    )
+                    .unwrap();
+
+                if let Ok(Ok(hash)) = timeout(Duration::from_millis(100), new_blocks.recv()).await {
+                    break hash;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(announced, block.bitcoin_hash());
+        assert_eq!(notifications.tip().block, Some(block));
+    }
+}
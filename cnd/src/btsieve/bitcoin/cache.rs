@@ -1,5 +1,15 @@
-use crate::btsieve::{BlockByHash, LatestBlock};
-use bitcoin::{util::hash::BitcoinHash, Block, BlockHash as Hash, BlockHash};
+use crate::{
+    btsieve::{
+        disk_cache::{DiskCache, Persist},
+        BlockByHash, LatestBlock,
+    },
+    metrics::{self, CacheLookup},
+};
+use bitcoin::{
+    consensus::encode::{deserialize, serialize},
+    util::hash::BitcoinHash,
+    Block, BlockHash as Hash, BlockHash,
+};
 use derivative::Derivative;
 use futures::Future;
 use futures_core::{
@@ -10,12 +20,15 @@ use lru::LruCache;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+const BLOCKCHAIN: &str = "bitcoin";
+
 #[derive(Derivative, Clone)]
 #[derivative(Debug)]
 pub struct Cache<C> {
     pub connector: C,
     #[derivative(Debug = "ignore")]
     pub block_cache: Arc<Mutex<LruCache<BlockHash, Block>>>,
+    pub disk_block_cache: Option<DiskCache<BlockHash, Block>>,
 }
 
 impl<C> Cache<C> {
@@ -24,10 +37,29 @@ impl<C> Cache<C> {
         Cache {
             connector,
             block_cache,
+            disk_block_cache: None,
+        }
+    }
+
+    /// Keeps the blocks on disk as well, so that they survive a restart.
+    pub fn with_disk_cache(self, disk_block_cache: DiskCache<BlockHash, Block>) -> Self {
+        Cache {
+            disk_block_cache: Some(disk_block_cache),
+            ..self
         }
     }
 }
 
+impl Persist for Block {
+    fn encode(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(serialize(self)))
+    }
+
+    fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(deserialize(bytes)?)
+    }
+}
+
 impl<C> LatestBlock for Cache<C>
 where
     C: LatestBlock<Block = Block, BlockHash = BlockHash> + Clone,
@@ -42,7 +74,11 @@ where
         let mut connector = self.connector.clone();
 
         let future = async move {
-            let block = connector.latest_block().compat().await?;
+            let block = metrics::fetched(
+                BLOCKCHAIN,
+                "latest_block",
+                connector.latest_block().compat().await,
+            )?;
 
             let block_hash = block.bitcoin_hash();
             let mut guard = cache.lock().await;
@@ -59,4 +95,4 @@ where
     }
 }
 
-impl_block_by_hash!();
+impl_block_by_hash!(BLOCKCHAIN);
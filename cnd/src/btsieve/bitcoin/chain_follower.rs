@@ -0,0 +1,636 @@
+use crate::{
+    btsieve::{
+        bitcoin::{check_block_against_pattern, TransactionPattern},
+        BlockByHash, Finality, LatestBlock, Predates, TransactionNotFound, TransactionOrphaned,
+    },
+    transaction,
+};
+use bitcoin::{BitcoinHash, BlockHash, Txid};
+use chrono::NaiveDateTime;
+use derivative::Derivative;
+use futures_core::{
+    channel::oneshot,
+    compat::Future01CompatExt,
+    future::{self, Either},
+};
+use lru::LruCache;
+use std::{
+    cmp,
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{broadcast, watch};
+
+/// How often the latest block is checked if no block notifications are
+/// available.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of blocks of the best chain that are remembered to detect missed
+/// blocks and reorgs.
+const SCANNED_BLOCKS_CAPACITY: u32 = 144;
+
+/// Number of matched transactions whose block is remembered so that their
+/// confirmations can be counted without looking for them again.
+const FOUND_TRANSACTIONS_CAPACITY: usize = 1000;
+
+/// Follows the bitcoin blockchain and matches every new block once against the
+/// transaction patterns all swaps are waiting for.
+///
+/// Without a follower every watched HTLC walks the chain on its own. With it,
+/// watching a transaction is a matter of registering a pattern and looking
+/// into the blocks that were mined before the registration.
+///
+/// The follower also keeps track of the heights of the recent blocks of the
+/// best chain, the confirmations of a transaction are counted from the height
+/// of its block whenever a new block is scanned.
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct ChainFollower<C> {
+    pub connector: C,
+    #[derivative(Debug = "ignore")]
+    registry: Arc<Mutex<Registry>>,
+    #[derivative(Debug = "ignore")]
+    new_tips: watch::Receiver<Option<BlockHash>>,
+}
+
+struct Registry {
+    /// The last block that was matched against the registered patterns.
+    tip: Option<BlockHash>,
+    new_tips: watch::Sender<Option<BlockHash>>,
+    /// Heights of the known blocks, counted from the first block that was
+    /// scanned.
+    heights: HashMap<BlockHash, i64>,
+    /// The known blocks of the best chain by their height.
+    best_chain: BTreeMap<i64, BlockHash>,
+    /// The blocks the transactions matched by a pattern were found in.
+    found_in: LruCache<Txid, BlockHash>,
+    /// Transactions whose confirmations are counted and the block of the best
+    /// chain they were last found in.
+    watched: HashMap<Txid, Watched>,
+    subscriptions: Vec<Subscription>,
+}
+
+struct Watched {
+    watchers: usize,
+    included_in: Option<BlockHash>,
+}
+
+/// Stops counting the confirmations of the transaction once the last watcher
+/// is dropped.
+struct Watch {
+    registry: Arc<Mutex<Registry>>,
+    txid: Txid,
+}
+
+struct Subscription {
+    pattern: TransactionPattern,
+    sender: oneshot::Sender<transaction::Bitcoin>,
+}
+
+/// The result of looking up a transaction in an index of the backend instead
+/// of walking the chain.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexLookup {
+    Found(transaction::Bitcoin),
+    /// The index covers the whole chain and knows no matching transaction.
+    Absent,
+    /// The index cannot tell, e.g. because it only knows unspent outputs or
+    /// the backend does not have one.
+    Inconclusive,
+}
+
+impl<C> ChainFollower<C>
+where
+    C: LatestBlock<Block = bitcoin::Block>
+        + BlockByHash<Block = bitcoin::Block, BlockHash = BlockHash>
+        + Clone,
+{
+    pub fn new(connector: C) -> Self {
+        let (sender, new_tips) = watch::channel(None);
+
+        ChainFollower {
+            connector,
+            registry: Arc::new(Mutex::new(Registry::new(sender))),
+            new_tips,
+        }
+    }
+
+    /// Follows the chain forever.
+    ///
+    /// New blocks are looked for whenever one is announced through
+    /// `new_blocks` and at least every `POLL_INTERVAL`.
+    pub async fn follow(self, mut new_blocks: Option<broadcast::Receiver<BlockHash>>) {
+        let mut connector = self.connector.clone();
+
+        loop {
+            if let Err(e) = self.scan_new_blocks(&mut connector).await {
+                tracing::warn!("Could not scan new bitcoin blocks: {}", e);
+            }
+
+            match new_blocks.as_mut() {
+                Some(new_blocks) => {
+                    let _ = tokio::time::timeout(POLL_INTERVAL, new_blocks.recv()).await;
+                }
+                None => tokio::time::delay_for(POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Resolves with the first transaction that matches the pattern, either in
+    /// a block mined from now on or in one mined after `start_of_swap`.
+    pub async fn matching_transaction(
+        &self,
+        pattern: TransactionPattern,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<transaction::Bitcoin> {
+        let (receiver, tip) = self.register(pattern.clone());
+
+        self.matching_transaction_in_past_or_future(&pattern, receiver, tip, start_of_swap)
+            .await
+    }
+
+    /// Like `matching_transaction` but asks the given index lookup first and
+    /// only walks the chain if it is inconclusive.
+    ///
+    /// The lookup is started after the pattern was registered, every block it
+    /// does not cover is scanned with the pattern.
+    pub async fn matching_transaction_indexed<F>(
+        &self,
+        pattern: TransactionPattern,
+        lookup: F,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<transaction::Bitcoin>
+    where
+        F: std::future::Future<Output = anyhow::Result<IndexLookup>>,
+    {
+        let (receiver, tip) = self.register(pattern.clone());
+
+        match lookup.await {
+            Ok(IndexLookup::Found(transaction)) => return Ok(transaction),
+            Ok(IndexLookup::Absent) => {
+                return receiver
+                    .await
+                    .map_err(|_| anyhow::anyhow!("chain follower stopped"))
+            }
+            Ok(IndexLookup::Inconclusive) => {}
+            Err(e) => tracing::warn!("Could not look up transaction in index: {}", e),
+        }
+
+        self.matching_transaction_in_past_or_future(&pattern, receiver, tip, start_of_swap)
+            .await
+    }
+
+    /// Resolves with whatever comes first, a matching transaction in the past
+    /// or one handed over through the receiver.
+    async fn matching_transaction_in_past_or_future(
+        &self,
+        pattern: &TransactionPattern,
+        receiver: oneshot::Receiver<transaction::Bitcoin>,
+        tip: Option<BlockHash>,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<transaction::Bitcoin> {
+        let past = Box::pin(self.matching_transaction_in_past(pattern, tip, start_of_swap));
+
+        let receiver = match future::select(past, receiver).await {
+            Either::Left((Ok(Some(transaction)), _)) => return Ok(transaction),
+            Either::Left((Ok(None), receiver)) => receiver,
+            Either::Left((Err(e), _)) => return Err(e),
+            Either::Right((transaction, _)) => {
+                return transaction.map_err(|_| anyhow::anyhow!("chain follower stopped"))
+            }
+        };
+
+        receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("chain follower stopped"))
+    }
+
+    /// Registers the pattern and returns the last block that was scanned
+    /// without it.
+    fn register(
+        &self,
+        pattern: TransactionPattern,
+    ) -> (oneshot::Receiver<transaction::Bitcoin>, Option<BlockHash>) {
+        let (sender, receiver) = oneshot::channel();
+
+        let mut registry = self.registry.lock().expect("registry is not poisoned");
+        registry
+            .subscriptions
+            .push(Subscription { pattern, sender });
+
+        (receiver, registry.tip)
+    }
+
+    /// Walks back from the given block, or the latest block if none was
+    /// scanned yet, until a block predates the start of the swap.
+    async fn matching_transaction_in_past(
+        &self,
+        pattern: &TransactionPattern,
+        tip: Option<BlockHash>,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<Option<transaction::Bitcoin>> {
+        let mut block = match tip {
+            Some(tip) => self.connector.block_by_hash(tip).compat().await?,
+            None => self.connector.clone().latest_block().compat().await?,
+        };
+
+        loop {
+            if let Some(transaction) = check_block_against_pattern(&block, pattern) {
+                self.registry
+                    .lock()
+                    .expect("registry is not poisoned")
+                    .found_in
+                    .put(transaction.txid(), block.bitcoin_hash());
+
+                return Ok(Some(transaction.clone()));
+            }
+            if block.predates(start_of_swap) {
+                return Ok(None);
+            }
+
+            block = self
+                .connector
+                .block_by_hash(block.header.prev_blockhash)
+                .compat()
+                .await?;
+        }
+    }
+
+    /// Resolves with the number of confirmations of the given transaction
+    /// once it has at least `min_confirmations`.
+    ///
+    /// The transaction is waited for if it is not included in a block yet.
+    /// Fails if it was included in the best chain but no longer is, i.e. its
+    /// block was orphaned.
+    pub async fn wait_for_confirmations(
+        &self,
+        txid: Txid,
+        min_confirmations: u32,
+        start_of_swap: NaiveDateTime,
+    ) -> Result<u32, TransactionOrphaned> {
+        let mut included = false;
+
+        self.watch_confirmations(txid, start_of_swap, |confirmations| match confirmations {
+            Some(confirmations) if confirmations >= min_confirmations => Some(Ok(confirmations)),
+            Some(confirmations) => {
+                included = true;
+                tracing::debug!(
+                    "transaction {} has {} of {} confirmations",
+                    txid,
+                    confirmations,
+                    min_confirmations
+                );
+                None
+            }
+            None if included => Some(Err(TransactionOrphaned)),
+            None => {
+                tracing::debug!("transaction {} is not included in a block yet", txid);
+                None
+            }
+        })
+        .await
+    }
+
+    /// Resolves once the transaction is either buried under `finality_depth`
+    /// blocks of the best chain or orphaned.
+    ///
+    /// The transaction is orphaned if it was found in the best chain since the
+    /// start of the swap but is no longer part of it. Fails if the transaction
+    /// is not found in the best chain in the first place, its finality is
+    /// unknown then.
+    pub async fn transaction_finality(
+        &self,
+        txid: Txid,
+        finality_depth: u32,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<Finality> {
+        let mut included = false;
+
+        self.watch_confirmations(txid, start_of_swap, |confirmations| match confirmations {
+            Some(confirmations) if confirmations >= finality_depth => Some(Ok(Finality::Final)),
+            Some(_) => {
+                included = true;
+                tracing::trace!("transaction {} is not final yet", txid);
+                None
+            }
+            None if included => Some(Ok(Finality::Orphaned)),
+            None => Some(Err(anyhow::Error::from(TransactionNotFound))),
+        })
+        .await
+    }
+
+    /// Hands the confirmations of the transaction to `decide` whenever a new
+    /// block was scanned, until it returns a result.
+    ///
+    /// Unless the block of the transaction is known from matching it, the
+    /// blocks since the start of the swap are looked through once. The blocks
+    /// scanned from then on are checked for the transaction by the follower.
+    async fn watch_confirmations<F, T>(
+        &self,
+        txid: Txid,
+        start_of_swap: NaiveDateTime,
+        mut decide: F,
+    ) -> T
+    where
+        F: FnMut(Option<u32>) -> Option<T>,
+    {
+        let mut new_tips = self.new_tips.clone();
+        let _watch = self.watch(txid);
+
+        loop {
+            let tip = self.registry.lock().expect("registry is not poisoned").tip;
+            if tip.is_some() {
+                break;
+            }
+
+            let _ = new_tips.recv().await;
+        }
+
+        let located = self
+            .registry
+            .lock()
+            .expect("registry is not poisoned")
+            .is_located(&txid);
+        if !located {
+            while let Err(e) = self.locate(txid, start_of_swap).await {
+                tracing::warn!("Could not look for transaction {}: {}", txid, e);
+                tokio::time::delay_for(POLL_INTERVAL).await;
+            }
+        }
+
+        loop {
+            let confirmations = self
+                .registry
+                .lock()
+                .expect("registry is not poisoned")
+                .confirmations(&txid);
+            if let Some(result) = decide(confirmations) {
+                return result;
+            }
+
+            let _ = new_tips.recv().await;
+        }
+    }
+
+    /// Starts checking the scanned blocks for the transaction.
+    fn watch(&self, txid: Txid) -> Watch {
+        let mut registry = self.registry.lock().expect("registry is not poisoned");
+
+        let found_in = registry
+            .found_in
+            .peek(&txid)
+            .copied()
+            .filter(|block_hash| registry.is_in_best_chain(block_hash));
+        registry
+            .watched
+            .entry(txid)
+            .or_insert(Watched {
+                watchers: 0,
+                included_in: found_in,
+            })
+            .watchers += 1;
+
+        Watch {
+            registry: Arc::clone(&self.registry),
+            txid,
+        }
+    }
+
+    /// Walks back from the last scanned block until the block that includes
+    /// the transaction or one that predates the start of the swap, the heights
+    /// of the blocks on the way are remembered.
+    async fn locate(&self, txid: Txid, start_of_swap: NaiveDateTime) -> anyhow::Result<()> {
+        let (tip, mut height) = {
+            let registry = self.registry.lock().expect("registry is not poisoned");
+            match (registry.tip, registry.tip_height()) {
+                (Some(tip), Some(height)) => (tip, height),
+                _ => anyhow::bail!("no block was scanned yet"),
+            }
+        };
+        let mut block = self.connector.block_by_hash(tip).compat().await?;
+
+        loop {
+            let found = block
+                .txdata
+                .iter()
+                .any(|transaction| transaction.txid() == txid);
+
+            {
+                let mut registry = self.registry.lock().expect("registry is not poisoned");
+                registry.insert_ancestor(block.bitcoin_hash(), height);
+                if found {
+                    if let Some(watched) = registry.watched.get_mut(&txid) {
+                        watched.included_in.get_or_insert(block.bitcoin_hash());
+                    }
+                }
+            }
+
+            if found || block.predates(start_of_swap) {
+                return Ok(());
+            }
+
+            block = self
+                .connector
+                .block_by_hash(block.header.prev_blockhash)
+                .compat()
+                .await?;
+            height -= 1;
+        }
+    }
+
+    /// Scans the latest block and the blocks mined since the last scan.
+    ///
+    /// Blocks are walked back until one whose parent is part of the best
+    /// chain, the blocks on top of that parent are orphaned by the scan.
+    async fn scan_new_blocks(&self, connector: &mut C) -> anyhow::Result<()> {
+        let latest_block = connector.latest_block().compat().await?;
+        let tip = self.registry.lock().expect("registry is not poisoned").tip;
+        if tip == Some(latest_block.bitcoin_hash()) {
+            return Ok(());
+        }
+
+        // Nothing before the first block needs to be scanned, every
+        // subscription looks into the past on its own.
+        let mut new_blocks = vec![latest_block];
+        while new_blocks.len() < SCANNED_BLOCKS_CAPACITY as usize {
+            let prev_blockhash = new_blocks
+                .last()
+                .expect("at least one block")
+                .header
+                .prev_blockhash;
+            let connected = {
+                let registry = self.registry.lock().expect("registry is not poisoned");
+                registry.tip.is_none() || registry.is_in_best_chain(&prev_blockhash)
+            };
+            if connected {
+                break;
+            }
+
+            new_blocks.push(
+                self.connector
+                    .block_by_hash(prev_blockhash)
+                    .compat()
+                    .await?,
+            );
+        }
+
+        let mut registry = self.registry.lock().expect("registry is not poisoned");
+        for block in new_blocks.into_iter().rev() {
+            registry.scan(&block);
+        }
+        registry.prune();
+
+        let tip = registry.tip;
+        let _ = registry.new_tips.broadcast(tip);
+
+        Ok(())
+    }
+}
+
+impl Registry {
+    fn new(new_tips: watch::Sender<Option<BlockHash>>) -> Self {
+        Registry {
+            tip: None,
+            new_tips,
+            heights: HashMap::new(),
+            best_chain: BTreeMap::new(),
+            found_in: LruCache::new(FOUND_TRANSACTIONS_CAPACITY),
+            watched: HashMap::new(),
+            subscriptions: Vec::new(),
+        }
+    }
+
+    fn tip_height(&self) -> Option<i64> {
+        self.tip.and_then(|tip| self.heights.get(&tip).copied())
+    }
+
+    fn is_in_best_chain(&self, block_hash: &BlockHash) -> bool {
+        self.heights
+            .get(block_hash)
+            .and_then(|height| self.best_chain.get(height))
+            == Some(block_hash)
+    }
+
+    fn is_located(&self, txid: &Txid) -> bool {
+        self.watched
+            .get(txid)
+            .map_or(false, |watched| watched.included_in.is_some())
+    }
+
+    /// Remembers a block found by walking back from a block of the best chain.
+    fn insert_ancestor(&mut self, block_hash: BlockHash, height: i64) {
+        self.heights.entry(block_hash).or_insert(height);
+        self.best_chain.entry(height).or_insert(block_hash);
+    }
+
+    /// The number of blocks of the best chain from the block that includes the
+    /// transaction up to the tip, `None` if the block is not part of the best
+    /// chain.
+    fn confirmations(&self, txid: &Txid) -> Option<u32> {
+        let block_hash = self.watched.get(txid)?.included_in?;
+        if !self.is_in_best_chain(&block_hash) {
+            return None;
+        }
+
+        let height = self.heights.get(&block_hash)?;
+        u32::try_from(self.tip_height()? - height + 1).ok()
+    }
+
+    /// Makes the block the tip of the best chain and hands the transactions
+    /// that match a registered pattern to the swaps waiting for them.
+    fn scan(&mut self, block: &bitcoin::Block) {
+        let block_hash = block.bitcoin_hash();
+        let prev_blockhash = block.header.prev_blockhash;
+
+        let height = match (self.heights.get(&prev_blockhash), self.tip_height()) {
+            (Some(height), _) => height + 1,
+            (None, Some(tip_height)) => {
+                tracing::warn!(
+                    "bitcoin block {} is not connected to the blocks scanned before",
+                    block_hash
+                );
+                tip_height + 1
+            }
+            (None, None) => {
+                self.insert_ancestor(prev_blockhash, -1);
+                0
+            }
+        };
+        let orphaned = self.best_chain.split_off(&height);
+        if !orphaned.is_empty() {
+            tracing::info!(
+                "bitcoin block {} orphaned {} blocks",
+                block_hash,
+                orphaned.len()
+            );
+        }
+        self.best_chain.insert(height, block_hash);
+        self.heights.insert(block_hash, height);
+
+        if !self.watched.is_empty() {
+            for transaction in block.txdata.iter() {
+                if let Some(watched) = self.watched.get_mut(&transaction.txid()) {
+                    watched.included_in = Some(block_hash);
+                }
+            }
+        }
+
+        let subscriptions = std::mem::replace(&mut self.subscriptions, Vec::new());
+        for subscription in subscriptions {
+            // nobody is waiting for this transaction anymore
+            if subscription.sender.is_canceled() {
+                continue;
+            }
+
+            match check_block_against_pattern(block, &subscription.pattern) {
+                Some(transaction) => {
+                    self.found_in.put(transaction.txid(), block_hash);
+                    let _ = subscription.sender.send(transaction.clone());
+                }
+                None => self.subscriptions.push(subscription),
+            }
+        }
+
+        tracing::trace!(
+            "scanned bitcoin block {}, {} patterns remain registered",
+            block_hash,
+            self.subscriptions.len()
+        );
+        self.tip = Some(block_hash);
+    }
+
+    /// Forgets the blocks that are too deep to be orphaned, unless a watched
+    /// transaction is included in them or a block above them.
+    fn prune(&mut self) {
+        let tip_height = match self.tip_height() {
+            Some(tip_height) => tip_height,
+            None => return,
+        };
+
+        let heights = &self.heights;
+        let keep_from = self
+            .watched
+            .values()
+            .filter_map(|watched| watched.included_in)
+            .filter_map(|block_hash| heights.get(&block_hash).copied())
+            .fold(tip_height - i64::from(SCANNED_BLOCKS_CAPACITY), cmp::min);
+
+        self.best_chain = self.best_chain.split_off(&keep_from);
+        self.heights.retain(|_, height| *height >= keep_from);
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let mut registry = self.registry.lock().expect("registry is not poisoned");
+
+        if let Some(watched) = registry.watched.get_mut(&self.txid) {
+            watched.watchers -= 1;
+            if watched.watchers == 0 {
+                registry.watched.remove(&self.txid);
+            }
+        }
+    }
+}
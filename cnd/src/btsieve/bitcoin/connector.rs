@@ -0,0 +1,151 @@
+use crate::btsieve::{
+    bitcoin::{BitcoindConnector, EsploraConnector, IndexLookup, TransactionExt},
+    BlockByHash, LatestBlock,
+};
+use bitcoin::{Address, BlockHash, Transaction, Txid};
+use futures::Future;
+use tokio::sync::broadcast;
+
+/// The backend cnd takes the bitcoin blockchain from, selected through the
+/// `[bitcoin]` section of the configuration.
+#[derive(Clone, Debug)]
+pub enum BitcoinConnector {
+    Bitcoind(BitcoindConnector),
+    Esplora(EsploraConnector),
+}
+
+/// The best block a backend knows about.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChainTip {
+    pub height: u64,
+    /// The time of the block as set by its miner, in seconds since the epoch.
+    pub timestamp: u32,
+    /// The median time of the block and its 10 predecessors. A transaction
+    /// locked until a time is only accepted once this passed it, see BIP 113.
+    pub median_time_past: u32,
+}
+
+impl BitcoinConnector {
+    pub async fn chain_tip(&self) -> anyhow::Result<ChainTip> {
+        match self {
+            BitcoinConnector::Bitcoind(connector) => connector.chain_tip().await,
+            BitcoinConnector::Esplora(connector) => connector.chain_tip().await,
+        }
+    }
+
+    pub async fn send_raw_transaction(&self, transaction: &Transaction) -> anyhow::Result<Txid> {
+        match self {
+            BitcoinConnector::Bitcoind(connector) => {
+                connector.send_raw_transaction(transaction).await
+            }
+            BitcoinConnector::Esplora(connector) => {
+                connector.send_raw_transaction(transaction).await
+            }
+        }
+    }
+
+    /// Returns a receiver for the hashes of new blocks if the backend
+    /// announces them.
+    pub fn subscribe_new_blocks(&self) -> Option<broadcast::Receiver<BlockHash>> {
+        match self {
+            BitcoinConnector::Bitcoind(connector) => connector.subscribe_new_blocks(),
+            BitcoinConnector::Esplora(_) => None,
+        }
+    }
+
+    /// Looks up the most recent transaction that pays to the address in the
+    /// address index of the backend.
+    ///
+    /// bitcoind only knows about unspent outputs, Esplora indexes the whole
+    /// history of an address.
+    pub async fn transaction_to_address(&self, address: &Address) -> anyhow::Result<IndexLookup> {
+        match self {
+            BitcoinConnector::Bitcoind(connector) => {
+                match connector.unspent_transaction_to_address(address).await? {
+                    Some(transaction) => Ok(IndexLookup::Found(transaction)),
+                    None => Ok(IndexLookup::Inconclusive),
+                }
+            }
+            BitcoinConnector::Esplora(connector) => {
+                let transaction = connector
+                    .transactions_by_address(address)
+                    .await?
+                    .into_iter()
+                    .find(|transaction| transaction.spends_to(address));
+
+                match transaction {
+                    Some(transaction) => Ok(IndexLookup::Found(transaction)),
+                    None => Ok(IndexLookup::Absent),
+                }
+            }
+        }
+    }
+
+    /// Estimates the fee per weight unit that gets a transaction confirmed
+    /// within `target_blocks`, `None` if the backend has no estimate.
+    pub async fn estimate_fee_per_wu(&self, target_blocks: u32) -> anyhow::Result<Option<usize>> {
+        let sat_per_vbyte = match self {
+            BitcoinConnector::Bitcoind(connector) => {
+                connector.estimate_smart_fee(target_blocks).await?
+            }
+            // Esplora only estimates for some targets, the closest one that
+            // confirms at least as fast is taken.
+            BitcoinConnector::Esplora(connector) => connector
+                .fee_estimates()
+                .await?
+                .range(..=target_blocks)
+                .next_back()
+                .map(|(_, sat_per_vbyte)| *sat_per_vbyte),
+        };
+
+        Ok(sat_per_vbyte.map(fee_per_wu))
+    }
+}
+
+/// A virtual byte is four weight units, rounded up so that the estimate is
+/// not undercut.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fee_per_wu(sat_per_vbyte: f64) -> usize {
+    (sat_per_vbyte / 4.0).ceil() as usize
+}
+
+impl From<BitcoindConnector> for BitcoinConnector {
+    fn from(connector: BitcoindConnector) -> Self {
+        BitcoinConnector::Bitcoind(connector)
+    }
+}
+
+impl From<EsploraConnector> for BitcoinConnector {
+    fn from(connector: EsploraConnector) -> Self {
+        BitcoinConnector::Esplora(connector)
+    }
+}
+
+impl LatestBlock for BitcoinConnector {
+    type Block = bitcoin::Block;
+    type BlockHash = bitcoin::BlockHash;
+
+    fn latest_block(
+        &mut self,
+    ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
+        match self {
+            BitcoinConnector::Bitcoind(connector) => connector.latest_block(),
+            BitcoinConnector::Esplora(connector) => connector.latest_block(),
+        }
+    }
+}
+
+impl BlockByHash for BitcoinConnector {
+    type Block = bitcoin::Block;
+    type BlockHash = bitcoin::BlockHash;
+
+    fn block_by_hash(
+        &self,
+        block_hash: Self::BlockHash,
+    ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
+        match self {
+            BitcoinConnector::Bitcoind(connector) => connector.block_by_hash(block_hash),
+            BitcoinConnector::Esplora(connector) => connector.block_by_hash(block_hash),
+        }
+    }
+}
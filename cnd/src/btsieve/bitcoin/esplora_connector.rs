@@ -0,0 +1,361 @@
+use crate::btsieve::{bitcoin::ChainTip, BlockByHash, LatestBlock, WrongNetwork};
+use anyhow::anyhow;
+use bitcoin::{
+    blockdata::constants::genesis_block,
+    consensus::encode::{deserialize, serialize_hex},
+    Address, BitcoinHash, BlockHash, Network, Transaction, Txid,
+};
+use futures::Future;
+use futures_core::{compat::Future01CompatExt, FutureExt, TryFutureExt};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Esplora returns the confirmed transactions of an address in pages of this
+/// size, a shorter page is the last one.
+const ADDRESS_TXS_PAGE_SIZE: usize = 25;
+
+#[derive(Deserialize)]
+struct AddressTransaction {
+    txid: Txid,
+}
+
+#[derive(Deserialize)]
+struct BlockStatus {
+    height: u64,
+    timestamp: u32,
+    mediantime: u32,
+}
+
+/// Talks to the HTTP API of an Esplora instance, e.g.
+/// `https://blockstream.info/api/`.
+#[derive(Clone, Debug)]
+pub struct EsploraConnector {
+    base_url: Url,
+    network: Network,
+    client: Client,
+}
+
+impl EsploraConnector {
+    pub fn new(base_url: Url, network: Network) -> anyhow::Result<Self> {
+        // Urls are joined relative to the base url, without a trailing slash the
+        // last path segment would be replaced.
+        let base_url = if base_url.path().ends_with('/') {
+            base_url
+        } else {
+            Url::parse(&format!("{}/", base_url))?
+        };
+
+        Ok(Self {
+            base_url,
+            network,
+            client: Client::new(),
+        })
+    }
+
+    fn tip_hash_url(&self) -> Url {
+        self.url("blocks/tip/hash")
+    }
+
+    fn genesis_hash_url(&self) -> Url {
+        self.url("block-height/0")
+    }
+
+    fn block_by_hash_url(&self, block_hash: &BlockHash) -> Url {
+        self.url(&format!("block/{}", block_hash))
+    }
+
+    fn raw_block_by_hash_url(&self, block_hash: &BlockHash) -> Url {
+        self.url(&format!("block/{}/raw", block_hash))
+    }
+
+    fn raw_transaction_by_id_url(&self, txid: &Txid) -> Url {
+        self.url(&format!("tx/{}/raw", txid))
+    }
+
+    fn address_transactions_url(&self, address: &Address, last_seen: Option<&Txid>) -> Url {
+        match last_seen {
+            Some(txid) => self.url(&format!("address/{}/txs/chain/{}", address, txid)),
+            None => self.url(&format!("address/{}/txs/chain", address)),
+        }
+    }
+
+    fn fee_estimates_url(&self) -> Url {
+        self.url("fee-estimates")
+    }
+
+    fn url(&self, path: &str) -> Url {
+        self.base_url.join(path).expect("building url should work")
+    }
+
+    /// Broadcasts the given transaction.
+    pub async fn send_raw_transaction(&self, transaction: &Transaction) -> anyhow::Result<Txid> {
+        let response = self
+            .client
+            .post(self.url("tx"))
+            .body(serialize_hex(transaction))
+            .send()
+            .await?;
+
+        // Esplora answers with the txid on success and the reason of the
+        // rejection otherwise.
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!(
+                "broadcasting transaction failed with {}: {}",
+                status,
+                text
+            ));
+        }
+
+        Ok(text.trim().parse()?)
+    }
+
+    /// Returns the best block of Esplora, failing with `WrongNetwork` if
+    /// Esplora is not on the network the connector was created for.
+    ///
+    /// Esplora does not tell its network, it is recognised by the genesis
+    /// block instead.
+    pub async fn chain_tip(&self) -> anyhow::Result<ChainTip> {
+        let genesis_hash = self.get_text(self.genesis_hash_url()).await?;
+        let network = network_from_genesis_hash(&genesis_hash.trim().parse()?)?;
+        if network != self.network {
+            return Err(WrongNetwork::new(self.network, network).into());
+        }
+
+        let tip_hash = self.get_text(self.tip_hash_url()).await?;
+        let tip = self
+            .client
+            .get(self.block_by_hash_url(&tip_hash.trim().parse()?))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<BlockStatus>()
+            .await?;
+
+        Ok(ChainTip {
+            height: tip.height,
+            timestamp: tip.timestamp,
+            median_time_past: tip.mediantime,
+        })
+    }
+
+    async fn get_text(&self, url: Url) -> anyhow::Result<String> {
+        let text = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(text)
+    }
+
+    /// Returns the fee rates in satoshi per virtual byte that get a
+    /// transaction confirmed within a number of blocks, keyed by that number.
+    pub async fn fee_estimates(&self) -> anyhow::Result<BTreeMap<u32, f64>> {
+        let estimates = self
+            .client
+            .get(self.fee_estimates_url())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(estimates)
+    }
+
+    /// Returns all confirmed transactions that spend from or pay to the
+    /// address, the most recent first.
+    pub async fn transactions_by_address(
+        &self,
+        address: &Address,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let mut txids = Vec::new();
+        loop {
+            let page = self
+                .client
+                .get(self.address_transactions_url(address, txids.last()))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Vec<AddressTransaction>>()
+                .await?;
+            let is_last_page = page.len() < ADDRESS_TXS_PAGE_SIZE;
+
+            txids.extend(page.into_iter().map(|transaction| transaction.txid));
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        let mut transactions = Vec::with_capacity(txids.len());
+        for txid in txids {
+            let bytes = self
+                .client
+                .get(self.raw_transaction_by_id_url(&txid))
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+
+            transactions.push(deserialize(&bytes)?);
+        }
+
+        Ok(transactions)
+    }
+}
+
+fn network_from_genesis_hash(genesis_hash: &BlockHash) -> anyhow::Result<Network> {
+    [Network::Bitcoin, Network::Testnet, Network::Regtest]
+        .iter()
+        .copied()
+        .find(|network| genesis_block(*network).bitcoin_hash() == *genesis_hash)
+        .ok_or_else(|| {
+            anyhow!(
+                "esplora is on a chain with unknown genesis {}",
+                genesis_hash
+            )
+        })
+}
+
+impl LatestBlock for EsploraConnector {
+    type Block = bitcoin::Block;
+    type BlockHash = bitcoin::BlockHash;
+
+    fn latest_block(
+        &mut self,
+    ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
+        let this = self.clone();
+
+        let latest_block = async move {
+            let tip_hash = this
+                .client
+                .get(this.tip_hash_url())
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+
+            let block = this
+                .block_by_hash(tip_hash.trim().parse()?)
+                .compat()
+                .await?;
+
+            Ok(block)
+        };
+
+        Box::new(latest_block.boxed().compat())
+    }
+}
+
+impl BlockByHash for EsploraConnector {
+    type Block = bitcoin::Block;
+    type BlockHash = bitcoin::BlockHash;
+
+    fn block_by_hash(
+        &self,
+        block_hash: Self::BlockHash,
+    ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
+        let url = self.raw_block_by_hash_url(&block_hash);
+
+        let client = self.client.clone();
+        let block = async move {
+            let bytes = client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            let block = deserialize::<Self::Block>(&bytes)?;
+            tracing::debug!(
+                "Fetched block {} with {} transactions from esplora",
+                block_hash,
+                block.txdata.len()
+            );
+
+            Ok(block)
+        }
+        .boxed()
+        .compat();
+
+        Box::new(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn base_urls() -> Vec<Url> {
+        vec![
+            "http://localhost:3000/api".parse().unwrap(),
+            "http://localhost:3000/api/".parse().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn given_different_base_urls_correct_sub_urls_are_built() {
+        for base_url in base_urls() {
+            let connector = EsploraConnector::new(base_url, Network::Regtest).unwrap();
+
+            assert_eq!(
+                connector.tip_hash_url(),
+                Url::parse("http://localhost:3000/api/blocks/tip/hash").unwrap()
+            );
+
+            let block_hash = BlockHash::from_str(
+                "2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02",
+            )
+            .unwrap();
+            assert_eq!(connector.raw_block_by_hash_url(&block_hash), Url::parse("http://localhost:3000/api/block/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02/raw").unwrap());
+
+            let address = Address::from_str("2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM").unwrap();
+            let txid =
+                Txid::from_str("7e7b5a3d8b4d3e1b8d7c3a2b1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f")
+                    .unwrap();
+            assert_eq!(
+                connector.address_transactions_url(&address, None),
+                Url::parse("http://localhost:3000/api/address/2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM/txs/chain").unwrap()
+            );
+            assert_eq!(connector.address_transactions_url(&address, Some(&txid)), Url::parse("http://localhost:3000/api/address/2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM/txs/chain/7e7b5a3d8b4d3e1b8d7c3a2b1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f").unwrap());
+
+            assert_eq!(
+                connector.fee_estimates_url(),
+                Url::parse("http://localhost:3000/api/fee-estimates").unwrap()
+            );
+            assert_eq!(
+                connector.genesis_hash_url(),
+                Url::parse("http://localhost:3000/api/block-height/0").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn network_is_recognised_by_the_genesis_block() {
+        let regtest =
+            BlockHash::from_str("0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206")
+                .unwrap();
+        let testnet =
+            BlockHash::from_str("000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943")
+                .unwrap();
+
+        assert_eq!(
+            network_from_genesis_hash(&regtest).unwrap(),
+            Network::Regtest
+        );
+        assert_eq!(
+            network_from_genesis_hash(&testnet).unwrap(),
+            Network::Testnet
+        );
+    }
+}
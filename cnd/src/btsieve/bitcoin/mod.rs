@@ -1,10 +1,20 @@
 mod bitcoind_connector;
+mod block_notifications;
 mod cache;
+mod chain_follower;
+mod connector;
+mod esplora_connector;
 mod transaction_ext;
 mod transaction_pattern;
 
 pub use self::{
-    bitcoind_connector::BitcoindConnector, cache::Cache, transaction_ext::TransactionExt,
+    bitcoind_connector::BitcoindConnector,
+    block_notifications::Publisher,
+    cache::Cache,
+    chain_follower::{ChainFollower, IndexLookup},
+    connector::{BitcoinConnector, ChainTip},
+    esplora_connector::EsploraConnector,
+    transaction_ext::TransactionExt,
     transaction_pattern::TransactionPattern,
 };
 use crate::{
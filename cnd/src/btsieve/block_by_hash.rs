@@ -1,5 +1,7 @@
+/// Implements `BlockByHash` for the `Cache` of a blockchain, `$blockchain`
+/// labels its metrics.
 macro_rules! impl_block_by_hash {
-    () => {
+    ($blockchain:expr) => {
         impl<C> BlockByHash for Cache<C>
         where
             C: BlockByHash<Block = Block, BlockHash = Hash> + Clone,
@@ -13,13 +15,15 @@ macro_rules! impl_block_by_hash {
             ) -> Box<dyn Future<Item = Self::Block, Error = anyhow::Error> + Send + 'static> {
                 let connector = self.connector.clone();
                 let cache = Arc::clone(&self.block_cache);
-                Box::new(Box::pin(block_by_hash(connector, cache, block_hash)).compat())
+                let disk_cache = self.disk_block_cache.clone();
+                Box::new(Box::pin(block_by_hash(connector, cache, disk_cache, block_hash)).compat())
             }
         }
 
         async fn block_by_hash<C>(
             connector: C,
             cache: Arc<Mutex<LruCache<Hash, Block>>>,
+            disk_cache: Option<DiskCache<Hash, Block>>,
             block_hash: Hash,
         ) -> anyhow::Result<Block>
         where
@@ -27,16 +31,43 @@ macro_rules! impl_block_by_hash {
         {
             if let Some(block) = cache.lock().await.get(&block_hash) {
                 tracing::trace!("Found block in cache: {:x}", block_hash);
+                metrics::cache_lookup($blockchain, "block", CacheLookup::Hit);
                 return Ok(block.clone());
             }
 
-            let block = connector.block_by_hash(block_hash.clone()).compat().await?;
+            if let Some(disk_cache) = &disk_cache {
+                match disk_cache.get(&block_hash).await {
+                    Ok(Some(block)) => {
+                        tracing::trace!("Found block in disk cache: {:x}", block_hash);
+                        metrics::cache_lookup($blockchain, "block", CacheLookup::DiskHit);
+                        cache.lock().await.put(block_hash, block.clone());
+
+                        return Ok(block);
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Could not read block from disk cache: {}", e),
+                }
+            }
+
+            metrics::cache_lookup($blockchain, "block", CacheLookup::Miss);
+            let block = metrics::fetched(
+                $blockchain,
+                "block",
+                connector.block_by_hash(block_hash.clone()).compat().await,
+            )?;
             tracing::trace!("Fetched block from connector: {:x}", block_hash);
 
             // We dropped the lock so at this stage the block may have been inserted by
             // another thread, no worries, inserting the same block twice does not hurt.
             let mut guard = cache.lock().await;
             guard.put(block_hash, block.clone());
+            drop(guard);
+
+            if let Some(disk_cache) = &disk_cache {
+                if let Err(e) = disk_cache.put(&block_hash, &block).await {
+                    tracing::warn!("Could not write block to disk cache: {}", e);
+                }
+            }
 
             Ok(block)
         }
@@ -0,0 +1,211 @@
+use crate::config::Eviction;
+use derivative::Derivative;
+use lru::LruCache;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::LowerHex,
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+const TMP_SUFFIX: &str = ".tmp";
+
+/// Values that can be kept in a `DiskCache`.
+pub trait Persist: Sized {
+    /// Returns `None` for values that are not worth keeping across restarts.
+    fn encode(&self) -> anyhow::Result<Option<Vec<u8>>>;
+    fn decode(bytes: &[u8]) -> anyhow::Result<Self>;
+}
+
+/// Blocks and receipts the node does not know about are not kept.
+impl<T> Persist for Option<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn encode(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.as_ref().map(serde_json::to_vec).transpose()?)
+    }
+
+    fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(Some(serde_json::from_slice(bytes)?))
+    }
+}
+
+/// Blocks or receipts kept as files in a directory, named after their hash.
+///
+/// The entries are evicted in the configured order once there are more than
+/// `capacity` of them. How recently an entry was used is only known while cnd
+/// is running, after a restart the entries are evicted in the order they were
+/// written.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct DiskCache<K, V> {
+    dir: PathBuf,
+    capacity: usize,
+    eviction: Eviction,
+    /// The file names of all entries, the next one to evict first.
+    #[derivative(Debug = "ignore")]
+    entries: Arc<Mutex<LruCache<String, ()>>>,
+    #[derivative(Debug = "ignore")]
+    _marker: PhantomData<fn(K) -> V>,
+}
+
+impl<K, V> DiskCache<K, V>
+where
+    K: LowerHex,
+    V: Persist,
+{
+    /// Opens the cache in `dir` and picks up the entries that were written
+    /// before.
+    pub fn open(dir: &Path, capacity: usize, eviction: Eviction) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut files = Vec::<(SystemTime, String)>::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            // left behind by an interrupted write
+            if name.ends_with(TMP_SUFFIX) {
+                fs::remove_file(entry.path())?;
+                continue;
+            }
+
+            files.push((entry.metadata()?.modified()?, name));
+        }
+        files.sort();
+
+        let cache = DiskCache {
+            dir: dir.to_owned(),
+            capacity,
+            eviction,
+            entries: Arc::new(Mutex::new(LruCache::unbounded())),
+            _marker: PhantomData,
+        };
+        for (_, name) in files {
+            cache.insert(name);
+        }
+
+        Ok(cache)
+    }
+
+    pub async fn get(&self, key: &K) -> anyhow::Result<Option<V>> {
+        let name = format!("{:x}", key);
+
+        {
+            let mut entries = self.entries.lock().expect("entries are not poisoned");
+            let known = match self.eviction {
+                Eviction::LeastRecentlyUsed => entries.get(&name).is_some(),
+                Eviction::OldestFirst => entries.contains(&name),
+            };
+            if !known {
+                return Ok(None);
+            }
+        }
+
+        let bytes = tokio::fs::read(self.dir.join(&name)).await?;
+
+        Ok(Some(V::decode(&bytes)?))
+    }
+
+    pub async fn put(&self, key: &K, value: &V) -> anyhow::Result<()> {
+        let bytes = match value.encode()? {
+            Some(bytes) => bytes,
+            None => return Ok(()),
+        };
+        let name = format!("{:x}", key);
+
+        // Written to a temporary file first so that an interrupted write does
+        // not leave a broken entry behind.
+        let tmp = self.dir.join(format!("{}{}", name, TMP_SUFFIX));
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, self.dir.join(&name)).await?;
+
+        self.insert(name);
+
+        Ok(())
+    }
+
+    fn insert(&self, name: String) {
+        let mut entries = self.entries.lock().expect("entries are not poisoned");
+        entries.put(name, ());
+
+        while entries.len() > self.capacity {
+            if let Some((evicted, _)) = entries.pop_lru() {
+                if let Err(e) = fs::remove_file(self.dir.join(&evicted)) {
+                    tracing::warn!("Could not evict {} from disk cache: {}", evicted, e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Value(Vec<u8>);
+
+    impl Persist for Value {
+        fn encode(&self) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(Some(self.0.clone()))
+        }
+
+        fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+            Ok(Value(bytes.to_vec()))
+        }
+    }
+
+    fn open(dir: &Path, eviction: Eviction) -> DiskCache<u32, Value> {
+        DiskCache::open(dir, 2, eviction).unwrap()
+    }
+
+    #[tokio::test]
+    async fn entries_survive_reopening_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+
+        open(dir.path(), Eviction::LeastRecentlyUsed)
+            .put(&1, &Value(vec![1]))
+            .await
+            .unwrap();
+        let value = open(dir.path(), Eviction::LeastRecentlyUsed)
+            .get(&1)
+            .await
+            .unwrap();
+
+        assert_eq!(value, Some(Value(vec![1])));
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entry_is_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = open(dir.path(), Eviction::LeastRecentlyUsed);
+
+        cache.put(&1, &Value(vec![1])).await.unwrap();
+        cache.put(&2, &Value(vec![2])).await.unwrap();
+        cache.get(&1).await.unwrap();
+        cache.put(&3, &Value(vec![3])).await.unwrap();
+
+        assert_eq!(cache.get(&1).await.unwrap(), Some(Value(vec![1])));
+        assert_eq!(cache.get(&2).await.unwrap(), None);
+        assert!(!dir.path().join("2").exists());
+    }
+
+    #[tokio::test]
+    async fn oldest_entry_is_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = open(dir.path(), Eviction::OldestFirst);
+
+        cache.put(&1, &Value(vec![1])).await.unwrap();
+        cache.put(&2, &Value(vec![2])).await.unwrap();
+        cache.get(&1).await.unwrap();
+        cache.put(&3, &Value(vec![3])).await.unwrap();
+
+        assert_eq!(cache.get(&1).await.unwrap(), None);
+        assert_eq!(cache.get(&2).await.unwrap(), Some(Value(vec![2])));
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
     btsieve::{
+        disk_cache::DiskCache,
         ethereum::{self, Hash},
         BlockByHash, LatestBlock, ReceiptByHash,
     },
     ethereum::TransactionReceipt,
+    metrics::{self, CacheLookup},
 };
 use derivative::Derivative;
 use futures::Future;
@@ -15,6 +17,8 @@ use lru::LruCache;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+const BLOCKCHAIN: &str = "ethereum";
+
 // This makes it a bit obscure that we have an option, the compile will point it
 // out though; this alias allows us to use the macros :)
 type Block = Option<ethereum::Block>;
@@ -27,6 +31,8 @@ pub struct Cache<C> {
     pub block_cache: Arc<Mutex<LruCache<Hash, Block>>>,
     #[derivative(Debug = "ignore")]
     pub receipt_cache: Arc<Mutex<LruCache<Hash, Option<TransactionReceipt>>>>,
+    pub disk_block_cache: Option<DiskCache<Hash, Block>>,
+    pub disk_receipt_cache: Option<DiskCache<Hash, Option<TransactionReceipt>>>,
 }
 
 impl<C> Cache<C> {
@@ -41,6 +47,22 @@ impl<C> Cache<C> {
             connector,
             block_cache,
             receipt_cache,
+            disk_block_cache: None,
+            disk_receipt_cache: None,
+        }
+    }
+
+    /// Keeps the blocks and receipts on disk as well, so that they survive a
+    /// restart.
+    pub fn with_disk_cache(
+        self,
+        disk_block_cache: DiskCache<Hash, Block>,
+        disk_receipt_cache: DiskCache<Hash, Option<TransactionReceipt>>,
+    ) -> Self {
+        Cache {
+            disk_block_cache: Some(disk_block_cache),
+            disk_receipt_cache: Some(disk_receipt_cache),
+            ..self
         }
     }
 }
@@ -59,7 +81,11 @@ where
         let mut connector = self.connector.clone();
 
         let future = async move {
-            let block = connector.latest_block().compat().await?;
+            let block = metrics::fetched(
+                BLOCKCHAIN,
+                "latest_block",
+                connector.latest_block().compat().await,
+            )?;
 
             if let Some(block) = block.clone() {
                 let block_hash = block.hash.expect("no blocks without hash");
@@ -78,7 +104,7 @@ where
     }
 }
 
-impl_block_by_hash!();
+impl_block_by_hash!(BLOCKCHAIN);
 
 impl<C> ReceiptByHash for Cache<C>
 where
@@ -93,13 +119,23 @@ where
     ) -> Box<dyn Future<Item = Self::Receipt, Error = anyhow::Error> + Send + 'static> {
         let connector = self.connector.clone();
         let cache = Arc::clone(&self.receipt_cache);
-        Box::new(Box::pin(receipt_by_hash(connector, cache, transaction_hash)).compat())
+        let disk_cache = self.disk_receipt_cache.clone();
+        Box::new(
+            Box::pin(receipt_by_hash(
+                connector,
+                cache,
+                disk_cache,
+                transaction_hash,
+            ))
+            .compat(),
+        )
     }
 }
 
 async fn receipt_by_hash<C>(
     connector: C,
     cache: Arc<Mutex<LruCache<Hash, Option<TransactionReceipt>>>>,
+    disk_cache: Option<DiskCache<Hash, Option<TransactionReceipt>>>,
     transaction_hash: Hash,
 ) -> anyhow::Result<Option<TransactionReceipt>>
 where
@@ -107,13 +143,33 @@ where
 {
     if let Some(receipt) = cache.lock().await.get(&transaction_hash) {
         tracing::trace!("Found receipt in cache: {:x}", transaction_hash);
+        metrics::cache_lookup(BLOCKCHAIN, "receipt", CacheLookup::Hit);
         return Ok(receipt.clone());
     }
 
-    let receipt = connector
-        .receipt_by_hash(transaction_hash.clone())
-        .compat()
-        .await?;
+    if let Some(disk_cache) = &disk_cache {
+        match disk_cache.get(&transaction_hash).await {
+            Ok(Some(receipt)) => {
+                tracing::trace!("Found receipt in disk cache: {:x}", transaction_hash);
+                metrics::cache_lookup(BLOCKCHAIN, "receipt", CacheLookup::DiskHit);
+                cache.lock().await.put(transaction_hash, receipt.clone());
+
+                return Ok(receipt);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Could not read receipt from disk cache: {}", e),
+        }
+    }
+
+    metrics::cache_lookup(BLOCKCHAIN, "receipt", CacheLookup::Miss);
+    let receipt = metrics::fetched(
+        BLOCKCHAIN,
+        "receipt",
+        connector
+            .receipt_by_hash(transaction_hash.clone())
+            .compat()
+            .await,
+    )?;
 
     tracing::trace!("Fetched receipt from connector: {:x}", transaction_hash);
 
@@ -121,6 +177,13 @@ where
     // another thread, no worries, inserting the same receipt twice does not hurt.
     let mut guard = cache.lock().await;
     guard.put(transaction_hash, receipt.clone());
+    drop(guard);
+
+    if let Some(disk_cache) = &disk_cache {
+        if let Err(e) = disk_cache.put(&transaction_hash, &receipt).await {
+            tracing::warn!("Could not write receipt to disk cache: {}", e);
+        }
+    }
 
     Ok(receipt)
 }
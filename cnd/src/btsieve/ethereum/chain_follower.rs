@@ -0,0 +1,434 @@
+use crate::{
+    btsieve::{
+        ethereum::{
+            fetch_receipt, find_log_for_event_in_receipt, Block, Event, Hash, NewHead, Subscription,
+        },
+        BlockByHash, LatestBlock, Predates, ReceiptByHash,
+    },
+    ethereum::{Address, Bytes, IsStatusOk, Log, Transaction, TransactionReceipt},
+};
+use chrono::NaiveDateTime;
+use derivative::Derivative;
+use ethbloom::Input;
+use futures_core::{
+    channel::oneshot,
+    compat::Future01CompatExt,
+    future::{self, Either},
+};
+use lru::LruCache;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// How often the latest block is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of scanned blocks that are remembered to detect missed blocks.
+const SCANNED_BLOCKS_CAPACITY: usize = 720;
+
+/// What a swap waits for on the ethereum blockchain.
+#[derive(Clone, Debug)]
+pub enum Filter {
+    /// The deployment of a contract with exactly the given code.
+    ContractCreation(Bytes),
+    /// A transaction that emitted a log matching the event.
+    Event(Event),
+}
+
+/// Follows the ethereum blockchain and matches every new block once against the
+/// filters all swaps are waiting for.
+///
+/// Without a follower every watched HTLC walks the chain on its own. With it,
+/// watching a transaction is a matter of registering a filter and looking into
+/// the blocks that were mined before the registration.
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct ChainFollower<C> {
+    pub connector: C,
+    #[derivative(Debug = "ignore")]
+    registry: Arc<Mutex<Registry>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// The last block that was matched against the registered filters.
+    tip: Option<Hash>,
+    registrations: Vec<Registration>,
+}
+
+struct Registration {
+    filter: Filter,
+    sender: oneshot::Sender<(Transaction, TransactionReceipt)>,
+}
+
+/// The result of looking up a transaction in an index of the node, e.g. its
+/// logs, instead of walking the chain.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexLookup {
+    Found(Transaction, TransactionReceipt),
+    /// The index covers the whole chain and knows no matching transaction.
+    Absent,
+    /// The index cannot tell, e.g. because the node does not provide it.
+    Inconclusive,
+}
+
+impl<C> ChainFollower<C>
+where
+    C: LatestBlock<Block = Option<Block>>
+        + BlockByHash<Block = Option<Block>, BlockHash = Hash>
+        + ReceiptByHash<Receipt = Option<TransactionReceipt>, TransactionHash = Hash>
+        + Clone,
+{
+    pub fn new(connector: C) -> Self {
+        ChainFollower {
+            connector,
+            registry: Arc::new(Mutex::new(Registry::default())),
+        }
+    }
+
+    /// Follows the chain forever.
+    ///
+    /// New blocks are looked for whenever one is announced through
+    /// `new_heads` and at least every `POLL_INTERVAL`.
+    pub async fn follow(self, mut new_heads: Option<Subscription<NewHead>>) {
+        let mut connector = self.connector.clone();
+        let mut scanned = LruCache::new(SCANNED_BLOCKS_CAPACITY);
+
+        loop {
+            if let Err(e) = self.scan_new_blocks(&mut connector, &mut scanned).await {
+                tracing::warn!("Could not scan new ethereum blocks: {}", e);
+            }
+
+            match new_heads.as_mut() {
+                Some(subscription) => {
+                    match tokio::time::timeout(POLL_INTERVAL, subscription.notification()).await {
+                        Ok(Some(Ok(_))) | Err(_) => {}
+                        Ok(Some(Err(e))) => {
+                            tracing::warn!("Could not receive new ethereum block: {}", e)
+                        }
+                        Ok(None) => {
+                            tracing::warn!("Subscription to new ethereum blocks was closed");
+                            new_heads = None;
+                        }
+                    }
+                }
+                None => tokio::time::delay_for(POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Resolves with the transaction that deployed a contract with the given
+    /// code and the address of the contract.
+    pub async fn contract_creation(
+        &self,
+        bytecode: Bytes,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<(Transaction, Address)> {
+        let (transaction, receipt) = self
+            .matching_transaction(Filter::ContractCreation(bytecode), start_of_swap)
+            .await?;
+
+        match receipt.contract_address {
+            Some(location) => Ok((transaction, location)),
+            None => Err(anyhow::anyhow!("contract address missing from receipt")),
+        }
+    }
+
+    /// Resolves with the transaction that emitted a log matching the event and
+    /// that log.
+    pub async fn event(
+        &self,
+        event: Event,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<(Transaction, Log)> {
+        let (transaction, receipt) = self
+            .matching_transaction(Filter::Event(event.clone()), start_of_swap)
+            .await?;
+
+        let log = find_log_for_event_in_receipt(&event, receipt)
+            .ok_or_else(|| anyhow::anyhow!("matched receipt does not contain the event"))?;
+
+        Ok((transaction, log))
+    }
+
+    /// Like `event` but asks the given index lookup first and only walks the
+    /// chain if it is inconclusive.
+    ///
+    /// The lookup is started after the filter was registered, every block it
+    /// does not cover is scanned with the filter.
+    pub async fn event_indexed<F>(
+        &self,
+        event: Event,
+        lookup: F,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<(Transaction, Log)>
+    where
+        F: std::future::Future<Output = anyhow::Result<IndexLookup>>,
+    {
+        let filter = Filter::Event(event.clone());
+        let (receiver, tip) = self.register(filter.clone());
+
+        let (transaction, receipt) = match lookup.await {
+            Ok(IndexLookup::Found(transaction, receipt)) => (transaction, receipt),
+            Ok(IndexLookup::Absent) => receiver
+                .await
+                .map_err(|_| anyhow::anyhow!("chain follower stopped"))?,
+            Ok(IndexLookup::Inconclusive) => {
+                self.matching_transaction_in_past_or_future(&filter, receiver, tip, start_of_swap)
+                    .await?
+            }
+            Err(e) => {
+                tracing::warn!("Could not look up event in index: {}", e);
+                self.matching_transaction_in_past_or_future(&filter, receiver, tip, start_of_swap)
+                    .await?
+            }
+        };
+
+        let log = find_log_for_event_in_receipt(&event, receipt)
+            .ok_or_else(|| anyhow::anyhow!("matched receipt does not contain the event"))?;
+
+        Ok((transaction, log))
+    }
+
+    /// Resolves with the first successful transaction that matches the
+    /// filter, either in a block mined from now on or in one mined after
+    /// `start_of_swap`.
+    pub async fn matching_transaction(
+        &self,
+        filter: Filter,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<(Transaction, TransactionReceipt)> {
+        let (receiver, tip) = self.register(filter.clone());
+
+        self.matching_transaction_in_past_or_future(&filter, receiver, tip, start_of_swap)
+            .await
+    }
+
+    /// Resolves with whatever comes first, a matching transaction in the past
+    /// or one handed over through the receiver.
+    async fn matching_transaction_in_past_or_future(
+        &self,
+        filter: &Filter,
+        receiver: oneshot::Receiver<(Transaction, TransactionReceipt)>,
+        tip: Option<Hash>,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<(Transaction, TransactionReceipt)> {
+        let past = Box::pin(self.matching_transaction_in_past(filter, tip, start_of_swap));
+
+        let receiver = match future::select(past, receiver).await {
+            Either::Left((Ok(Some(matched)), _)) => return Ok(matched),
+            Either::Left((Ok(None), receiver)) => receiver,
+            Either::Left((Err(e), _)) => return Err(e),
+            Either::Right((matched, _)) => {
+                return matched.map_err(|_| anyhow::anyhow!("chain follower stopped"))
+            }
+        };
+
+        receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("chain follower stopped"))
+    }
+
+    /// Registers the filter and returns the last block that was scanned
+    /// without it.
+    fn register(
+        &self,
+        filter: Filter,
+    ) -> (
+        oneshot::Receiver<(Transaction, TransactionReceipt)>,
+        Option<Hash>,
+    ) {
+        let (sender, receiver) = oneshot::channel();
+
+        let mut registry = self.registry.lock().expect("registry is not poisoned");
+        registry.registrations.push(Registration { filter, sender });
+
+        (receiver, registry.tip)
+    }
+
+    /// Walks back from the given block, or the latest block if none was
+    /// scanned yet, until a block predates the start of the swap.
+    async fn matching_transaction_in_past(
+        &self,
+        filter: &Filter,
+        tip: Option<Hash>,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<Option<(Transaction, TransactionReceipt)>> {
+        let mut block = match tip {
+            Some(tip) => block_by_hash(&self.connector, tip).await?,
+            None => latest_block(&mut self.connector.clone()).await?,
+        };
+
+        loop {
+            if let Some(matched) = find_match(&self.connector, &block, filter).await? {
+                return Ok(Some(matched));
+            }
+            if block.predates(start_of_swap) {
+                return Ok(None);
+            }
+
+            block = block_by_hash(&self.connector, block.parent_hash).await?;
+        }
+    }
+
+    /// Scans the latest block and the blocks mined since the last scan.
+    async fn scan_new_blocks(
+        &self,
+        connector: &mut C,
+        scanned: &mut LruCache<Hash, ()>,
+    ) -> anyhow::Result<()> {
+        let latest_block = latest_block(connector).await?;
+        if scanned.contains(&block_hash(&latest_block)?) {
+            return Ok(());
+        }
+
+        // Nothing before the first block needs to be scanned, every
+        // registration looks into the past on its own.
+        let mut new_blocks = vec![latest_block];
+        while !scanned.is_empty() && new_blocks.len() < SCANNED_BLOCKS_CAPACITY {
+            let parent_hash = new_blocks.last().expect("at least one block").parent_hash;
+            if scanned.contains(&parent_hash) {
+                break;
+            }
+
+            new_blocks.push(block_by_hash(&self.connector, parent_hash).await?);
+        }
+
+        for block in new_blocks.into_iter().rev() {
+            self.scan(&block).await?;
+            scanned.put(block_hash(&block)?, ());
+        }
+
+        Ok(())
+    }
+
+    /// Hands the transactions that match a registered filter to the swaps
+    /// waiting for them.
+    ///
+    /// The registrations are taken out of the registry while the block is
+    /// scanned. Filters registered in the meantime look into this block on
+    /// their own.
+    async fn scan(&self, block: &Block) -> anyhow::Result<()> {
+        let registrations = {
+            let mut registry = self.registry.lock().expect("registry is not poisoned");
+            registry.tip = Some(block_hash(block)?);
+
+            std::mem::replace(&mut registry.registrations, Vec::new())
+        };
+
+        let mut remaining = Vec::new();
+        let mut result = Ok(());
+        for registration in registrations {
+            // nobody is waiting for this transaction anymore
+            if registration.sender.is_canceled() {
+                continue;
+            }
+
+            match find_match(&self.connector, block, &registration.filter).await {
+                Ok(Some(matched)) => {
+                    let _ = registration.sender.send(matched);
+                }
+                Ok(None) => remaining.push(registration),
+                Err(e) => {
+                    remaining.push(registration);
+                    result = Err(e);
+                }
+            }
+        }
+
+        let mut registry = self.registry.lock().expect("registry is not poisoned");
+        registry.registrations.extend(remaining);
+        tracing::trace!(
+            "scanned ethereum block {:x}, {} filters remain registered",
+            block_hash(block)?,
+            registry.registrations.len()
+        );
+
+        result
+    }
+}
+
+async fn latest_block<C>(connector: &mut C) -> anyhow::Result<Block>
+where
+    C: LatestBlock<Block = Option<Block>>,
+{
+    connector
+        .latest_block()
+        .compat()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Connector returned null latest block"))
+}
+
+async fn block_by_hash<C>(connector: &C, hash: Hash) -> anyhow::Result<Block>
+where
+    C: BlockByHash<Block = Option<Block>, BlockHash = Hash>,
+{
+    connector
+        .block_by_hash(hash)
+        .compat()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Could not fetch block with hash {}", hash))
+}
+
+fn block_hash(block: &Block) -> anyhow::Result<Hash> {
+    block
+        .hash
+        .ok_or_else(|| anyhow::anyhow!("Connector returned block with null hash"))
+}
+
+/// Returns the first successful transaction in the block that matches the
+/// filter along with its receipt.
+async fn find_match<C>(
+    connector: &C,
+    block: &Block,
+    filter: &Filter,
+) -> anyhow::Result<Option<(Transaction, TransactionReceipt)>>
+where
+    C: ReceiptByHash<Receipt = Option<TransactionReceipt>, TransactionHash = Hash> + Clone,
+{
+    if let Filter::Event(event) = filter {
+        let maybe_contains_event = event.topics.iter().all(|topic| {
+            topic.as_ref().map_or(true, |topic| {
+                block
+                    .logs_bloom
+                    .contains_input(Input::Raw(topic.0.as_ref()))
+            })
+        });
+        if !maybe_contains_event {
+            return Ok(None);
+        }
+    }
+
+    for transaction in block.transactions.iter() {
+        if let Filter::ContractCreation(bytecode) = filter {
+            // transaction.to address is None if, and only if, the transaction
+            // creates a contract.
+            if transaction.to.is_some() || &transaction.input != bytecode {
+                continue;
+            }
+        }
+
+        let receipt = fetch_receipt(connector.clone(), transaction.hash).await?;
+        if let Filter::Event(event) = filter {
+            if find_log_for_event_in_receipt(event, receipt.clone()).is_none() {
+                continue;
+            }
+        }
+
+        if !receipt.is_status_ok() {
+            // This can be caused by a failed attempt to complete an action,
+            // for example, sending a transaction with low gas.
+            tracing::warn!(
+                "transaction matched {:x} but status was NOT OK",
+                transaction.hash,
+            );
+            continue;
+        }
+
+        tracing::trace!("transaction matched {:x}", transaction.hash);
+        return Ok(Some((transaction.clone(), receipt)));
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,131 @@
+use crate::{
+    btsieve::ethereum::{
+        fetch_receipt, log_matches_event, Event, IndexLookup, UnsupportedMethod, Web3Connector,
+    },
+    ethereum::{BlockNumber, IsStatusOk, Log, Transaction, TransactionReceipt, U256},
+};
+use chrono::NaiveDateTime;
+use web3::types::{Filter, FilterBuilder};
+
+/// Looks for the first successful transaction that emitted a log matching the
+/// event after `start_of_swap`.
+///
+/// Instead of fetching every block and receipt, the node is asked for the
+/// matching logs of the blocks mined so far through `eth_getLogs`. Blocks
+/// mined afterwards are left to the chain follower, so that no connection is
+/// opened per watched event. The lookup is inconclusive if the node does not
+/// support `eth_getLogs`.
+pub async fn logs_lookup(
+    connector: Web3Connector,
+    event: Event,
+    start_of_swap: NaiveDateTime,
+) -> anyhow::Result<IndexLookup> {
+    match matching_transaction_in_logs(&connector, &event, start_of_swap).await {
+        Ok(Some((transaction, receipt))) => Ok(IndexLookup::Found(transaction, receipt)),
+        Ok(None) => Ok(IndexLookup::Absent),
+        Err(e) if e.is::<UnsupportedMethod>() => {
+            tracing::info!("Scanning blocks for the event instead: {}", e);
+            Ok(IndexLookup::Inconclusive)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn matching_transaction_in_logs(
+    connector: &Web3Connector,
+    event: &Event,
+    start_of_swap: NaiveDateTime,
+) -> anyhow::Result<Option<(Transaction, TransactionReceipt)>> {
+    let latest_block = connector.block_number().await?;
+    let first_block = first_block_since(connector, start_of_swap, latest_block).await?;
+    let logs = connector
+        .logs(filter(event, first_block, latest_block))
+        .await?;
+
+    for log in logs {
+        if let Some(found) = successful_transaction(connector, event, log).await? {
+            return Ok(Some(found));
+        }
+    }
+
+    Ok(None)
+}
+
+fn filter(event: &Event, from_block: u64, to_block: u64) -> Filter {
+    let topic = |index: usize| {
+        event
+            .topics
+            .get(index)
+            .cloned()
+            .flatten()
+            .map(|topic| vec![topic.0])
+    };
+
+    FilterBuilder::default()
+        .address(vec![event.address])
+        .topics(topic(0), topic(1), topic(2), topic(3))
+        .from_block(BlockNumber::Number(from_block))
+        .to_block(BlockNumber::Number(to_block))
+        .build()
+}
+
+/// Finds the first block that was mined at or after the start of the swap by
+/// bisecting the chain.
+async fn first_block_since(
+    connector: &Web3Connector,
+    start_of_swap: NaiveDateTime,
+    latest_block: u64,
+) -> anyhow::Result<u64> {
+    let start_of_swap = U256::from(start_of_swap.timestamp());
+
+    let (mut low, mut high) = (0, latest_block);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let block = connector
+            .block_header_by_number(middle)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Could not fetch block {}", middle))?;
+
+        if block.timestamp < start_of_swap {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Returns the transaction that emitted the log and its receipt if it was
+/// successful.
+async fn successful_transaction(
+    connector: &Web3Connector,
+    event: &Event,
+    log: Log,
+) -> anyhow::Result<Option<(Transaction, TransactionReceipt)>> {
+    // The node matches topics by prefix, we also want the number of topics to
+    // match.
+    if !log_matches_event(event, &log) {
+        return Ok(None);
+    }
+
+    let hash = log
+        .transaction_hash
+        .ok_or_else(|| anyhow::anyhow!("Log without transaction hash"))?;
+
+    let receipt = fetch_receipt(connector.clone(), hash).await?;
+    if !receipt.is_status_ok() {
+        // This can be caused by a failed attempt to complete an action,
+        // for example, sending a transaction with low gas.
+        tracing::warn!("transaction matched {:x} but status was NOT OK", hash);
+        return Ok(None);
+    }
+
+    let transaction = connector
+        .transaction_by_hash(hash)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Could not fetch transaction {:x}", hash))?;
+
+    tracing::trace!("transaction matched {:x}", hash);
+    Ok(Some((transaction, receipt)))
+}
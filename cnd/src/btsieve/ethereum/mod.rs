@@ -1,9 +1,19 @@
 mod cache;
+mod chain_follower;
+mod logs;
 mod web3_connector;
 
-pub use self::{cache::Cache, web3_connector::Web3Connector};
+pub use self::{
+    cache::Cache,
+    chain_follower::{ChainFollower, Filter, IndexLookup},
+    logs::logs_lookup,
+    web3_connector::{NewHead, Subscription, UnsupportedMethod, Web3Connector},
+};
 use crate::{
-    btsieve::{BlockByHash, LatestBlock, Predates, ReceiptByHash},
+    btsieve::{
+        BlockByHash, Finality, LatestBlock, Predates, ReceiptByHash, TransactionNotFound,
+        TransactionOrphaned,
+    },
     ethereum::{Address, Bytes, IsStatusOk, Log, Transaction, TransactionReceipt, H256, U256},
     Never,
 };
@@ -84,9 +94,16 @@ where
 }
 
 fn find_log_for_event_in_receipt(event: &Event, receipt: TransactionReceipt) -> Option<Log> {
+    receipt
+        .logs
+        .into_iter()
+        .find(|log| log_matches_event(event, log))
+}
+
+fn log_matches_event(event: &Event, log: &Log) -> bool {
     match event {
-        Event { topics, .. } if topics.is_empty() => None,
-        Event { address, topics } => receipt.logs.into_iter().find(|log| {
+        Event { topics, .. } if topics.is_empty() => false,
+        Event { address, topics } => {
             if address != &log.address {
                 return false;
             }
@@ -99,7 +116,7 @@ fn find_log_for_event_in_receipt(event: &Event, receipt: TransactionReceipt) ->
                 let topic = &topics[index];
                 topic.as_ref().map_or(true, |topic| tx_topic == &topic.0)
             })
-        }),
+        }
     }
 }
 
@@ -149,6 +166,138 @@ where
     }
 }
 
+/// Resolves with the number of confirmations of the given transaction once it
+/// has at least `min_confirmations`.
+///
+/// The transaction is waited for if it is not included in a block yet. Fails if
+/// it was included in the best chain but no longer is, i.e. its block was
+/// orphaned.
+pub async fn wait_for_confirmations<C>(
+    mut connector: C,
+    transaction_hash: Hash,
+    min_confirmations: u32,
+    start_of_swap: NaiveDateTime,
+) -> Result<u32, TransactionOrphaned>
+where
+    C: LatestBlock<Block = Option<Block>> + BlockByHash<Block = Option<Block>, BlockHash = Hash>,
+{
+    let mut included = false;
+
+    loop {
+        match confirmations(&mut connector, transaction_hash, start_of_swap).await {
+            Ok(Some(confirmations)) if confirmations >= min_confirmations => {
+                return Ok(confirmations)
+            }
+            Ok(Some(confirmations)) => {
+                included = true;
+                tracing::debug!(
+                    "transaction {:x} has {} of {} confirmations",
+                    transaction_hash,
+                    confirmations,
+                    min_confirmations
+                )
+            }
+            Ok(None) if included => return Err(TransactionOrphaned),
+            Ok(None) => tracing::debug!(
+                "transaction {:x} is not included in a block yet",
+                transaction_hash
+            ),
+            Err(e) => tracing::warn!(
+                "Could not count confirmations of {:x}: {}",
+                transaction_hash,
+                e
+            ),
+        }
+
+        tokio::time::delay_for(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+/// Returns the number of confirmations of the transaction, `None` if no block
+/// of the best chain since the start of the swap includes it.
+async fn confirmations<C>(
+    connector: &mut C,
+    transaction_hash: Hash,
+    start_of_swap: NaiveDateTime,
+) -> anyhow::Result<Option<u32>>
+where
+    C: LatestBlock<Block = Option<Block>> + BlockByHash<Block = Option<Block>, BlockHash = Hash>,
+{
+    let mut block = connector
+        .latest_block()
+        .compat()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Connector returned null latest block"))?;
+    let mut depth = 1;
+
+    loop {
+        if block
+            .transactions
+            .iter()
+            .any(|transaction| transaction.hash == transaction_hash)
+        {
+            return Ok(Some(depth));
+        }
+        if block.predates(start_of_swap) {
+            return Ok(None);
+        }
+
+        block = parent_block(connector, block.parent_hash).await?;
+        depth += 1;
+    }
+}
+
+/// Resolves once the transaction is either buried under `finality_depth`
+/// blocks of the best chain or orphaned.
+///
+/// The transaction is orphaned if it was found in the best chain since the
+/// start of the swap but is no longer part of it. Fails if the transaction is
+/// not found in the best chain in the first place, its finality is unknown
+/// then.
+pub async fn transaction_finality<C>(
+    mut connector: C,
+    transaction_hash: Hash,
+    finality_depth: u32,
+    start_of_swap: NaiveDateTime,
+) -> anyhow::Result<Finality>
+where
+    C: LatestBlock<Block = Option<Block>> + BlockByHash<Block = Option<Block>, BlockHash = Hash>,
+{
+    let mut included = false;
+
+    loop {
+        match confirmations(&mut connector, transaction_hash, start_of_swap).await {
+            Ok(Some(confirmations)) if confirmations >= finality_depth => {
+                return Ok(Finality::Final)
+            }
+            Ok(Some(_)) => {
+                included = true;
+                tracing::trace!("transaction {:x} is not final yet", transaction_hash)
+            }
+            Ok(None) if included => return Ok(Finality::Orphaned),
+            Ok(None) => return Err(anyhow::Error::from(TransactionNotFound)),
+            Err(e) => tracing::warn!(
+                "Could not count confirmations of {:x}: {}",
+                transaction_hash,
+                e
+            ),
+        }
+
+        tokio::time::delay_for(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn parent_block<C>(connector: &C, parent_hash: Hash) -> anyhow::Result<Block>
+where
+    C: BlockByHash<Block = Option<Block>, BlockHash = Hash>,
+{
+    connector
+        .block_by_hash(parent_hash)
+        .compat()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Could not fetch block with hash {}", parent_hash))
+}
+
 async fn matching_transaction_and_log<C, F>(
     connector: C,
     start_of_swap: NaiveDateTime,
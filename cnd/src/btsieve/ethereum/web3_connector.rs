@@ -1,26 +1,233 @@
 use crate::{
-    btsieve::{BlockByHash, LatestBlock, ReceiptByHash},
-    ethereum::{BlockId, BlockNumber},
+    btsieve::{BlockByHash, LatestBlock, ReceiptByHash, WrongNetwork},
+    ethereum::{Address, Block, BlockId, BlockNumber, Bytes, Log, Transaction, H256, U256},
+    swap_protocols::ledger::ethereum::ChainId,
     transaction,
 };
 use anyhow::Context;
+use derivative::Derivative;
 use futures::Future;
-use futures_core::{FutureExt, TryFutureExt};
+use futures_core::{stream::BoxStream, FutureExt, SinkExt, StreamExt, TryFutureExt};
 use reqwest::{Client, Url};
-use serde::Serialize;
-use std::sync::Arc;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{marker::PhantomData, sync::Arc};
+use tokio_tungstenite::tungstenite::{self, Message};
+use web3::types::Filter;
+
+/// JSON-RPC error code of a method the node does not provide.
+const METHOD_NOT_FOUND: i64 = -32601;
 
 #[derive(Clone, Debug)]
 pub struct Web3Connector {
     web3: Arc<Client>,
     url: Url,
+    ws_url: Option<Url>,
+    chain_id: ChainId,
 }
 
+/// Returned if the node does not provide a JSON-RPC method, for example
+/// because it was disabled.
+#[derive(Debug, thiserror::Error)]
+#[error("the node does not support {0}")]
+pub struct UnsupportedMethod(pub String);
+
 impl Web3Connector {
-    pub fn new(node_url: Url) -> Self {
+    pub fn new(node_url: Url, chain_id: ChainId) -> Self {
         Self {
             web3: Arc::new(Client::new()),
             url: node_url,
+            ws_url: None,
+            chain_id,
+        }
+    }
+
+    /// Subscriptions are made at the WebSocket endpoint of the node. Without
+    /// one, nothing can be subscribed to.
+    pub fn with_websocket(self, ws_url: Url) -> Self {
+        Self {
+            ws_url: Some(ws_url),
+            ..self
+        }
+    }
+
+    /// Returns the number of transactions sent from the given address,
+    /// including the ones that are still pending. This is the nonce of the
+    /// next transaction of that address.
+    pub async fn transaction_count(&self, address: Address) -> anyhow::Result<U256> {
+        let request = JsonRpcRequest::new("eth_getTransactionCount", vec![
+            serialize(address)?,
+            serialize("pending")?,
+        ]);
+
+        self.send(request).await
+    }
+
+    /// Submits an already signed transaction and returns its hash.
+    pub async fn send_raw_transaction(&self, transaction: Bytes) -> anyhow::Result<H256> {
+        let request = JsonRpcRequest::new("eth_sendRawTransaction", vec![serialize(transaction)?]);
+
+        self.send(request).await
+    }
+
+    /// Returns the amount of gas the transaction is expected to use if it was
+    /// mined in the latest block.
+    pub async fn estimate_gas(
+        &self,
+        from: Address,
+        to: Option<Address>,
+        value: U256,
+        data: Bytes,
+    ) -> anyhow::Result<U256> {
+        let call = CallRequest {
+            from,
+            to,
+            value,
+            data,
+        };
+        let request = JsonRpcRequest::new("eth_estimateGas", vec![serialize(call)?]);
+
+        self.send(request).await
+    }
+
+    /// Returns the gas price the node suggests for new transactions.
+    pub async fn gas_price(&self) -> anyhow::Result<U256> {
+        let request = JsonRpcRequest::new("eth_gasPrice", Vec::<serde_json::Value>::new());
+
+        self.send(request).await
+    }
+
+    /// Returns the id of the chain the node is on with `eth_chainId`.
+    ///
+    /// Nodes that do not support `eth_chainId` are asked for their network id
+    /// with `net_version` instead, it is the same as the chain id on the
+    /// chains cnd is used with.
+    pub async fn chain_id(&self) -> anyhow::Result<ChainId> {
+        let request = JsonRpcRequest::new("eth_chainId", Vec::<serde_json::Value>::new());
+        match self.send::<_, U256>(request).await {
+            Ok(chain_id) => Ok(ChainId::from(chain_id.low_u32())),
+            Err(e) if e.is::<UnsupportedMethod>() => {
+                let request = JsonRpcRequest::new("net_version", Vec::<serde_json::Value>::new());
+                let network_id: String = self.send(request).await?;
+                let network_id = network_id
+                    .parse::<u32>()
+                    .with_context(|| format!("invalid network id {}", network_id))?;
+
+                Ok(ChainId::from(network_id))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fails with `WrongNetwork` if the node is not on the chain the
+    /// connector was created for.
+    pub async fn verify_chain_id(&self) -> anyhow::Result<()> {
+        let chain_id = self.chain_id().await?;
+        if chain_id != self.chain_id {
+            return Err(WrongNetwork::new(
+                format!("chain {}", u32::from(self.chain_id)),
+                format!("chain {}", u32::from(chain_id)),
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of the latest block.
+    pub async fn block_number(&self) -> anyhow::Result<u64> {
+        let request = JsonRpcRequest::new("eth_blockNumber", Vec::<serde_json::Value>::new());
+        let number: U256 = self.send(request).await?;
+
+        Ok(number.low_u64())
+    }
+
+    /// Returns the block with the given number, its transactions are only
+    /// included by hash.
+    pub async fn block_header_by_number(&self, number: u64) -> anyhow::Result<Option<Block<H256>>> {
+        let request = JsonRpcRequest::new("eth_getBlockByNumber", vec![
+            serialize(BlockId::Number(BlockNumber::Number(number)))?,
+            serialize(false)?,
+        ]);
+
+        self.send(request).await
+    }
+
+    pub async fn transaction_by_hash(&self, hash: H256) -> anyhow::Result<Option<Transaction>> {
+        let request = JsonRpcRequest::new("eth_getTransactionByHash", vec![serialize(hash)?]);
+
+        self.send(request).await
+    }
+
+    /// Returns the logs that match the filter through `eth_getLogs`.
+    pub async fn logs(&self, filter: Filter) -> anyhow::Result<Vec<Log>> {
+        let request = JsonRpcRequest::new("eth_getLogs", vec![serialize(filter)?]);
+
+        self.send(request).await
+    }
+
+    /// Subscribes to the headers of new blocks.
+    ///
+    /// Returns `None` if no WebSocket endpoint is configured.
+    pub async fn subscribe_new_heads(&self) -> anyhow::Result<Option<Subscription<NewHead>>> {
+        self.subscribe(vec![serialize("newHeads")?]).await
+    }
+
+    /// Every subscription gets its own connection, the node cancels the
+    /// subscription once the connection is dropped.
+    async fn subscribe<T>(
+        &self,
+        params: Vec<serde_json::Value>,
+    ) -> anyhow::Result<Option<Subscription<T>>> {
+        let ws_url = match &self.ws_url {
+            Some(ws_url) => ws_url.clone(),
+            None => return Ok(None),
+        };
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(ws_url).await?;
+        let request = JsonRpcRequest::new("eth_subscribe", params);
+        socket
+            .send(Message::Text(serde_json::to_string(&request)?))
+            .await?;
+
+        // the answer to the request carries the id of the subscription
+        let id = loop {
+            let text = match socket.next().await {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+                None => anyhow::bail!("connection closed before the subscription was confirmed"),
+            };
+
+            match serde_json::from_str::<JsonRpcResponse<String>>(&text)? {
+                JsonRpcResponse::Success { result } => break result,
+                JsonRpcResponse::Error { error } => return Err(error.into_error(&request.method)),
+            }
+        };
+
+        Ok(Some(Subscription {
+            id,
+            messages: socket.boxed(),
+            item: PhantomData,
+        }))
+    }
+
+    async fn send<P, R>(&self, request: JsonRpcRequest<P>) -> anyhow::Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let response = self
+            .web3
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await?
+            .json::<JsonRpcResponse<R>>()
+            .await?;
+
+        match response {
+            JsonRpcResponse::Success { result } => Ok(result),
+            JsonRpcResponse::Error { error } => Err(error.into_error(&request.method)),
         }
     }
 }
@@ -51,7 +258,9 @@ impl LatestBlock for Web3Connector {
 
             let block = match response {
                 JsonRpcResponse::Success { result } => result,
-                JsonRpcResponse::Error { code, message } => {
+                JsonRpcResponse::Error {
+                    error: JsonRpcError { code, message },
+                } => {
                     tracing::warn!(
                         "eth_getBlockByNumber request failed with {}: {}",
                         code,
@@ -75,6 +284,15 @@ impl LatestBlock for Web3Connector {
     }
 }
 
+#[derive(serde::Serialize)]
+struct CallRequest {
+    from: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<Address>,
+    value: U256,
+    data: Bytes,
+}
+
 #[derive(serde::Serialize)]
 struct JsonRpcRequest<T> {
     id: String,
@@ -98,7 +316,82 @@ impl<T> JsonRpcRequest<T> {
 #[serde(untagged)]
 enum JsonRpcResponse<T> {
     Success { result: T },
-    Error { code: i64, message: String },
+    Error { error: JsonRpcError },
+}
+
+#[derive(serde::Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+impl JsonRpcError {
+    fn into_error(self, method: &str) -> anyhow::Error {
+        if self.code == METHOD_NOT_FOUND {
+            return UnsupportedMethod(method.to_owned()).into();
+        }
+
+        anyhow::anyhow!(
+            "{} request failed with {}: {}",
+            method,
+            self.code,
+            self.message
+        )
+    }
+}
+
+/// The notifications of an `eth_subscribe` subscription.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct Subscription<T> {
+    id: String,
+    #[derivative(Debug = "ignore")]
+    messages: BoxStream<'static, Result<Message, tungstenite::Error>>,
+    #[derivative(Debug = "ignore")]
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T> Subscription<T>
+where
+    T: DeserializeOwned,
+{
+    /// Resolves with the next notification or `None` once the connection was
+    /// closed.
+    pub async fn notification(&mut self) -> Option<anyhow::Result<T>> {
+        loop {
+            let text = match self.messages.next().await? {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e.into())),
+            };
+
+            match serde_json::from_str::<Notification<T>>(&text) {
+                Ok(notification) if notification.params.subscription == self.id => {
+                    return Some(Ok(notification.params.result))
+                }
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Notification<T> {
+    params: NotificationParams<T>,
+}
+
+#[derive(serde::Deserialize)]
+struct NotificationParams<T> {
+    subscription: String,
+    result: T,
+}
+
+/// The header of a block as announced by a `newHeads` subscription.
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+pub struct NewHead {
+    pub hash: H256,
 }
 
 impl BlockByHash for Web3Connector {
@@ -128,7 +421,9 @@ impl BlockByHash for Web3Connector {
 
             let block = match response {
                 JsonRpcResponse::Success { result } => result,
-                JsonRpcResponse::Error { code, message } => {
+                JsonRpcResponse::Error {
+                    error: JsonRpcError { code, message },
+                } => {
                     tracing::warn!(
                         "eth_getBlockByHash request failed with {}: {}",
                         code,
@@ -175,7 +470,9 @@ impl ReceiptByHash for Web3Connector {
 
             let receipt = match response {
                 JsonRpcResponse::Success { result } => result,
-                JsonRpcResponse::Error { code, message } => {
+                JsonRpcResponse::Error {
+                    error: JsonRpcError { code, message },
+                } => {
                     tracing::warn!(
                         "eth_getTransactionReceipt request failed with {}: {}",
                         code,
@@ -204,3 +501,83 @@ where
 
     Ok(value)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_not_found_error_is_unsupported_method() {
+        let response =
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":"1"}"#;
+
+        let error = match serde_json::from_str::<JsonRpcResponse<Vec<Log>>>(response).unwrap() {
+            JsonRpcResponse::Error { error } => error.into_error("eth_getLogs"),
+            JsonRpcResponse::Success { .. } => panic!("expected an error response"),
+        };
+
+        assert!(error.is::<UnsupportedMethod>());
+    }
+
+    #[tokio::test]
+    async fn chain_id_falls_back_to_net_version() {
+        use warp::Filter;
+
+        // a node that only knows `net_version`
+        let node = warp::post()
+            .and(warp::body::json())
+            .map(|request: serde_json::Value| {
+                let response = match request["method"].as_str() {
+                    Some("net_version") => serde_json::json!({ "result": "17" }),
+                    _ => serde_json::json!({
+                        "error": { "code": METHOD_NOT_FOUND, "message": "Method not found" }
+                    }),
+                };
+
+                warp::reply::json(&response)
+            });
+        let (socket, server) = warp::serve(node).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let url = format!("http://{}", socket).parse().unwrap();
+
+        let regtest = Web3Connector::new(url.clone(), ChainId::regtest());
+        let mainnet = Web3Connector::new(url, ChainId::mainnet());
+
+        assert_eq!(regtest.chain_id().await.unwrap(), ChainId::regtest());
+        assert!(regtest.verify_chain_id().await.is_ok());
+        assert!(mainnet
+            .verify_chain_id()
+            .await
+            .unwrap_err()
+            .is::<WrongNetwork>());
+    }
+
+    #[test]
+    fn deserialize_new_head_notification() {
+        let notification = r#"{
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": {
+                "subscription": "0x9ce59a13059e417087c02d3236a0b1cc",
+                "result": {
+                    "hash": "0x7dec4f8d29a1d2b4b1d2e6cde3f8b1e7bb0d1e3dc8d4f3c0b6f4f3e2c1d0b9a8",
+                    "number": "0x1b4",
+                    "parentHash": "0x1e77d8f1267348b516ebc4f4da1e2aa59f85f0cbd853949500ffac8bfc38ba14"
+                }
+            }
+        }"#;
+
+        let notification = serde_json::from_str::<Notification<NewHead>>(notification).unwrap();
+
+        assert_eq!(
+            notification.params.subscription,
+            "0x9ce59a13059e417087c02d3236a0b1cc"
+        );
+        assert_eq!(
+            notification.params.result.hash,
+            "7dec4f8d29a1d2b4b1d2e6cde3f8b1e7bb0d1e3dc8d4f3c0b6f4f3e2c1d0b9a8"
+                .parse()
+                .unwrap()
+        );
+    }
+}
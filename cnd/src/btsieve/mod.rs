@@ -4,6 +4,7 @@
 #[macro_use]
 pub mod block_by_hash;
 pub mod bitcoin;
+pub mod disk_cache;
 pub mod ethereum;
 
 use chrono::NaiveDateTime;
@@ -38,6 +39,46 @@ pub trait ReceiptByHash: Send + Sync + 'static {
     ) -> Box<dyn Future<Item = Self::Receipt, Error = anyhow::Error> + Send + 'static>;
 }
 
+/// What became of the block that included a transaction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Finality {
+    /// The block is buried deeply enough to not be reorganised out of the best
+    /// chain anymore.
+    Final,
+    /// The block is no longer part of the best chain.
+    Orphaned,
+}
+
+/// Returned if the block that included a transaction is no longer part of the
+/// best chain.
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("transaction was reorganised out of the best chain")]
+pub struct TransactionOrphaned;
+
+/// Returned if a transaction that was expected on the best chain cannot be
+/// found there, so whether it is final is unknown.
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("transaction was not found in the best chain since the start of the swap")]
+pub struct TransactionNotFound;
+
+/// Returned by a connector if its node follows another chain than the
+/// configured one.
+#[derive(Debug, thiserror::Error)]
+#[error("node is on {actual} but {expected} is configured")]
+pub struct WrongNetwork {
+    pub expected: String,
+    pub actual: String,
+}
+
+impl WrongNetwork {
+    pub fn new<T: std::fmt::Display>(expected: T, actual: T) -> Self {
+        Self {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+}
+
 /// Checks if a given block predates a certain timestamp.
 pub trait Predates {
     fn predates(&self, timestamp: NaiveDateTime) -> bool;
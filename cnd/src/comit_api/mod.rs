@@ -1,7 +1,11 @@
 use crate::{
     asset::{self, AssetKind},
     libp2p_comit_ext::{FromHeader, ToHeader},
-    swap_protocols::{ledger::Ethereum, rfc003::messages::Decision, SwapId, SwapProtocol},
+    swap_protocols::{
+        ledger::{Ethereum, Lightning},
+        rfc003::messages::Decision,
+        SwapId, SwapProtocol,
+    },
 };
 use libp2p_comit::frame::Header;
 use serde::de::Error;
@@ -14,6 +18,7 @@ pub enum LedgerKind {
     BitcoinTestnet,
     BitcoinRegtest,
     Ethereum(Ethereum),
+    Lightning(Lightning),
 }
 
 impl FromHeader for LedgerKind {
@@ -30,6 +35,16 @@ impl FromHeader for LedgerKind {
                 }
             },
             "ethereum" => LedgerKind::Ethereum(Ethereum::new(header.take_parameter("chain_id")?)),
+            "lightning" => match header.take_parameter::<String>("network")?.as_ref() {
+                "mainnet" => LedgerKind::Lightning(Lightning::new(::bitcoin::Network::Bitcoin)),
+                "testnet" => LedgerKind::Lightning(Lightning::new(::bitcoin::Network::Testnet)),
+                "regtest" => LedgerKind::Lightning(Lightning::new(::bitcoin::Network::Regtest)),
+                _ => {
+                    return Err(serde_json::Error::custom(
+                        "unexpected lightning network variant",
+                    ))
+                }
+            },
             unknown => {
                 return Err(serde_json::Error::custom(format!(
                     "unknown ledger: {}",
@@ -57,6 +72,16 @@ impl ToHeader for LedgerKind {
             LedgerKind::Ethereum(ethereum) => {
                 Header::with_str_value("ethereum").with_parameter("chain_id", ethereum.chain_id)?
             }
+
+            LedgerKind::Lightning(lightning) => Header::with_str_value("lightning")
+                .with_parameter(
+                    "network",
+                    match lightning.network {
+                        ::bitcoin::Network::Bitcoin => "mainnet",
+                        ::bitcoin::Network::Testnet => "testnet",
+                        ::bitcoin::Network::Regtest => "regtest",
+                    },
+                )?,
         })
     }
 }
@@ -77,6 +102,9 @@ impl FromHeader for SwapProtocol {
     fn from_header(mut header: Header) -> Result<Self, serde_json::Error> {
         Ok(match header.value::<String>()?.as_str() {
             "comit-rfc-003" => SwapProtocol::Rfc003(header.take_parameter("hash_function")?),
+            "comit-han-halight" => {
+                SwapProtocol::HanHalight(header.take_parameter("hash_function")?)
+            }
             unknown => {
                 return Err(serde_json::Error::custom(format!(
                     "unknown swap protocol: {}",
@@ -92,6 +120,8 @@ impl ToHeader for SwapProtocol {
         Ok(match self {
             SwapProtocol::Rfc003(hash_function) => Header::with_str_value("comit-rfc-003")
                 .with_parameter("hash_function", hash_function)?,
+            SwapProtocol::HanHalight(hash_function) => Header::with_str_value("comit-han-halight")
+                .with_parameter("hash_function", hash_function)?,
         })
     }
 }
@@ -227,6 +257,20 @@ mod tests {
         assert_eq!(quantity, AssetKind::Bitcoin(amount));
     }
 
+    #[test]
+    fn lightning_ledger_header_roundtrip() {
+        let ledger = LedgerKind::Lightning(Lightning::new(::bitcoin::Network::Regtest));
+        let header = ledger.to_header().unwrap();
+
+        assert_eq!(
+            header,
+            Header::with_str_value("lightning")
+                .with_parameter("network", "regtest")
+                .unwrap()
+        );
+        assert_eq!(LedgerKind::from_header(header).unwrap(), ledger);
+    }
+
     #[test]
     fn ethereum_ledger_to_header() {
         let ledger = LedgerKind::Ethereum(Ethereum::new(ethereum::ChainId::ropsten()));
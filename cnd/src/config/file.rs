@@ -1,11 +1,18 @@
 use crate::{
-    config::{Bitcoind, Data, Lightning, Network, Parity},
+    config::{
+        BitcoinAutoRefund, BitcoinWallet, Bitcoind, Cache, Data, Esplora, EthereumWallet,
+        Lightning, Network, Parity, Webhooks,
+    },
     swap_protocols::ledger::ethereum,
 };
 use config as config_rs;
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
-use std::{ffi::OsStr, net::SocketAddr, path::Path};
+use std::{
+    ffi::OsStr,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 /// This struct aims to represent the configuration file as it appears on disk.
 ///
@@ -21,19 +28,31 @@ pub struct File {
     pub bitcoin: Option<Bitcoin>,
     pub ethereum: Option<Ethereum>,
     pub lightning: Option<Lightning>,
+    pub autopilot: Option<Autopilot>,
+    pub webhooks: Option<Webhooks>,
+    pub cache: Option<Cache>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Bitcoin {
     #[serde(with = "crate::config::serde_bitcoin_network")]
     pub network: bitcoin::Network,
+    pub min_confirmations: Option<u32>,
+    pub finality_depth: Option<u32>,
     pub bitcoind: Option<Bitcoind>,
+    pub esplora: Option<Esplora>,
+    pub auto_refund: Option<BitcoinAutoRefund>,
+    pub wallet: Option<BitcoinWallet>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Ethereum {
     pub chain_id: ethereum::ChainId,
+    pub min_confirmations: Option<u32>,
+    pub finality_depth: Option<u32>,
     pub parity: Option<Parity>,
+    pub auto_refund: Option<bool>,
+    pub wallet: Option<EthereumWallet>,
 }
 
 impl File {
@@ -46,6 +65,9 @@ impl File {
             bitcoin: Option::None,
             ethereum: Option::None,
             lightning: Option::None,
+            autopilot: Option::None,
+            webhooks: Option::None,
+            cache: Option::None,
         }
     }
 
@@ -100,10 +122,37 @@ impl From<Level> for LevelFilter {
     }
 }
 
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Autopilot {
+    pub all_swaps: Option<bool>,
+    pub safety_margin_secs: Option<u32>,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct HttpApi {
     pub socket: SocketAddr,
     pub cors: Option<Cors>,
+    pub auth: Option<Auth>,
+    pub tls: Option<Tls>,
+}
+
+/// Either the paths of a `cert` and its `key` or `self_signed` to generate a
+/// certificate into the data directory, which is valid for localhost and the
+/// `extra_names`.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Tls {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub self_signed: Option<bool>,
+    pub extra_names: Option<Vec<String>>,
+}
+
+/// Either a static `bearer_token` that grants full access or `scoped_tokens`
+/// that are derived from the seed and written to the data directory.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Auth {
+    pub bearer_token: Option<String>,
+    pub scoped_tokens: Option<bool>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -135,9 +184,14 @@ pub enum None {
 mod tests {
     use super::*;
     use crate::{
-        config::{Bitcoind, Lnd, Parity, Settings},
+        config::{
+            BitcoinAutoRefund, BitcoinWallet, Bitcoind, DiskCache, EthereumWallet, Eviction, Lnd,
+            Parity, Settings, Webhook,
+        },
         swap_protocols::ledger::ethereum,
+        webhooks::WebhookEvent,
     };
+    use bitcoin::secp256k1::SecretKey;
     use reqwest::Url;
     use spectral::prelude::*;
     use std::{
@@ -200,6 +254,13 @@ socket = "127.0.0.1:8000"
 [http_api.cors]
 allowed_origins = "all"
 
+[http_api.auth]
+scoped_tokens = true
+
+[http_api.tls]
+self_signed = true
+extra_names = ["cnd.example.com"]
+
 [data]
 dir = "/tmp/comit/"
 
@@ -208,21 +269,42 @@ level = "Debug"
 
 [bitcoin]
 network = "regtest"
+min_confirmations = 3
+finality_depth = 6
 
 [bitcoin.bitcoind]
 node_url = "http://localhost:18443/"
+zmqpubrawblock = "tcp://127.0.0.1:28332"
 
 [ethereum]
 chain_id = 17
+min_confirmations = 12
+finality_depth = 24
 
 [ethereum.parity]
 node_url = "http://localhost:8545/"
+ws_url = "ws://localhost:8546/"
 
 [lightning]
 network = "regtest"
 
 [lightning.lnd]
 rest_api_socket = "127.0.0.1:8080"
+
+[autopilot]
+all_swaps = true
+safety_margin_secs = 7200
+
+[[webhooks.endpoints]]
+url = "http://localhost:3000/cnd"
+secret = "s3cr3t"
+events = ["accepted", "alpha_funded"]
+
+[cache]
+bitcoin_blocks = 288
+
+[cache.disk]
+eviction = "oldest_first"
 "#;
         let file = File {
             network: Some(Network {
@@ -233,6 +315,16 @@ rest_api_socket = "127.0.0.1:8080"
                 cors: Some(Cors {
                     allowed_origins: AllowedOrigins::All(All::All),
                 }),
+                auth: Some(Auth {
+                    bearer_token: None,
+                    scoped_tokens: Some(true),
+                }),
+                tls: Some(Tls {
+                    cert: None,
+                    key: None,
+                    self_signed: Some(true),
+                    extra_names: Some(vec!["cnd.example.com".to_owned()]),
+                }),
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/comit/"),
@@ -242,15 +334,27 @@ rest_api_socket = "127.0.0.1:8080"
             }),
             bitcoin: Some(Bitcoin {
                 network: bitcoin::Network::Regtest,
+                min_confirmations: Some(3),
+                finality_depth: Some(6),
                 bitcoind: Some(Bitcoind {
                     node_url: "http://localhost:18443".parse().unwrap(),
+                    zmqpubrawblock: Some("tcp://127.0.0.1:28332".to_owned()),
+                    zmqpubhashblock: None,
                 }),
+                esplora: None,
+                auto_refund: None,
+                wallet: None,
             }),
             ethereum: Some(Ethereum {
                 chain_id: ethereum::ChainId::regtest(),
+                min_confirmations: Some(12),
+                finality_depth: Some(24),
                 parity: Some(Parity {
                     node_url: "http://localhost:8545".parse().unwrap(),
+                    ws_url: Some("ws://localhost:8546".parse().unwrap()),
                 }),
+                auto_refund: None,
+                wallet: None,
             }),
             lightning: Some(Lightning {
                 network: bitcoin::Network::Regtest,
@@ -262,6 +366,25 @@ rest_api_socket = "127.0.0.1:8080"
                     dir: None,
                 }),
             }),
+            autopilot: Some(Autopilot {
+                all_swaps: Some(true),
+                safety_margin_secs: Some(7200),
+            }),
+            webhooks: Some(Webhooks {
+                endpoints: vec![Webhook {
+                    url: "http://localhost:3000/cnd".parse().unwrap(),
+                    secret: "s3cr3t".to_owned(),
+                    events: Some(vec![WebhookEvent::Accepted, WebhookEvent::AlphaFunded]),
+                }],
+            }),
+            cache: Some(Cache {
+                bitcoin_blocks: 288,
+                disk: Some(DiskCache {
+                    eviction: Eviction::OldestFirst,
+                    ..DiskCache::default()
+                }),
+                ..Cache::default()
+            }),
         };
 
         let config = toml::from_str::<File>(contents);
@@ -308,21 +431,42 @@ rest_api_socket = "127.0.0.1:8080"
         let expected = vec![
             Bitcoin {
                 network: bitcoin::Network::Bitcoin,
+                min_confirmations: None,
+                finality_depth: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:8332").unwrap(),
+                    zmqpubrawblock: None,
+                    zmqpubhashblock: None,
                 }),
+                esplora: None,
+                auto_refund: None,
+                wallet: None,
             },
             Bitcoin {
                 network: bitcoin::Network::Testnet,
+                min_confirmations: None,
+                finality_depth: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18332").unwrap(),
+                    zmqpubrawblock: None,
+                    zmqpubhashblock: None,
                 }),
+                esplora: None,
+                auto_refund: None,
+                wallet: None,
             },
             Bitcoin {
                 network: bitcoin::Network::Regtest,
+                min_confirmations: None,
+                finality_depth: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18443").unwrap(),
+                    zmqpubrawblock: None,
+                    zmqpubhashblock: None,
                 }),
+                esplora: None,
+                auto_refund: None,
+                wallet: None,
             },
         ];
 
@@ -335,6 +479,31 @@ rest_api_socket = "127.0.0.1:8080"
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn bitcoin_with_esplora_deserializes_correctly() {
+        let file_contents = r#"
+            network = "mainnet"
+            [esplora]
+            url = "https://blockstream.info/api/"
+            "#;
+
+        let expected = Bitcoin {
+            network: bitcoin::Network::Bitcoin,
+            min_confirmations: None,
+            finality_depth: None,
+            bitcoind: None,
+            esplora: Some(Esplora {
+                url: Url::parse("https://blockstream.info/api/").unwrap(),
+            }),
+            auto_refund: None,
+            wallet: None,
+        };
+
+        let actual = toml::from_str::<Bitcoin>(file_contents).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn ethereum_deserializes_correctly() {
         let file_contents = vec![
@@ -358,21 +527,36 @@ rest_api_socket = "127.0.0.1:8080"
         let expected = vec![
             Ethereum {
                 chain_id: ethereum::ChainId::regtest(),
+                min_confirmations: None,
+                finality_depth: None,
                 parity: Some(Parity {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    ws_url: None,
                 }),
+                auto_refund: None,
+                wallet: None,
             },
             Ethereum {
                 chain_id: ethereum::ChainId::ropsten(),
+                min_confirmations: None,
+                finality_depth: None,
                 parity: Some(Parity {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    ws_url: None,
                 }),
+                auto_refund: None,
+                wallet: None,
             },
             Ethereum {
                 chain_id: ethereum::ChainId::mainnet(),
+                min_confirmations: None,
+                finality_depth: None,
                 parity: Some(Parity {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    ws_url: None,
                 }),
+                auto_refund: None,
+                wallet: None,
             },
         ];
 
@@ -384,4 +568,119 @@ rest_api_socket = "127.0.0.1:8080"
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn auto_refund_deserializes_correctly() {
+        let private_key = SecretKey::from_slice(&[0x46; 32]).unwrap();
+        let sweep_address = bitcoin::Address::p2wpkh(
+            &bitcoin::PublicKey::from_private_key(
+                &*crate::SECP,
+                &bitcoin::PrivateKey {
+                    compressed: true,
+                    network: bitcoin::Network::Regtest,
+                    key: private_key,
+                },
+            ),
+            bitcoin::Network::Regtest,
+        );
+
+        let contents = format!(
+            r#"
+            [bitcoin]
+            network = "regtest"
+
+            [bitcoin.auto_refund]
+            sweep_address = "{}"
+            fee_per_wu = 10
+
+            [ethereum]
+            chain_id = 17
+            auto_refund = true
+
+            [ethereum.wallet]
+            "#,
+            sweep_address
+        );
+
+        let file = toml::from_str::<File>(&contents).unwrap();
+
+        assert_eq!(
+            file.bitcoin,
+            Some(Bitcoin {
+                network: bitcoin::Network::Regtest,
+                min_confirmations: None,
+                finality_depth: None,
+                bitcoind: None,
+                esplora: None,
+                auto_refund: Some(BitcoinAutoRefund {
+                    sweep_address,
+                    fee_per_wu: 10,
+                }),
+                wallet: None,
+            })
+        );
+        assert_eq!(
+            file.ethereum,
+            Some(Ethereum {
+                chain_id: ethereum::ChainId::regtest(),
+                min_confirmations: None,
+                finality_depth: None,
+                parity: None,
+                auto_refund: Some(true),
+                wallet: Some(EthereumWallet {
+                    private_key: None,
+                    gas_price_gwei: None,
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn bitcoin_wallet_deserializes_correctly() {
+        let contents = r#"
+            network = "regtest"
+
+            [wallet]
+            fee_per_wu = 5
+            "#;
+
+        let bitcoin = toml::from_str::<Bitcoin>(contents).unwrap();
+
+        assert_eq!(bitcoin.wallet, Some(BitcoinWallet { fee_per_wu: 5 }));
+    }
+
+    #[test]
+    fn ethereum_wallet_deserializes_correctly() {
+        let file_contents = vec![
+            r#"
+            chain_id = 17
+            [wallet]
+            "#,
+            r#"
+            chain_id = 17
+            [wallet]
+            private_key = "0x4646464646464646464646464646464646464646464646464646464646464646"
+            gas_price_gwei = 20
+            "#,
+        ];
+
+        let expected = vec![
+            Some(EthereumWallet {
+                private_key: None,
+                gas_price_gwei: None,
+            }),
+            Some(EthereumWallet {
+                private_key: Some(SecretKey::from_slice(&[0x46; 32]).unwrap()),
+                gas_price_gwei: Some(20),
+            }),
+        ];
+
+        let actual = file_contents
+            .into_iter()
+            .map(|contents| toml::from_str::<Ethereum>(contents).map(|ethereum| ethereum.wallet))
+            .collect::<Result<Vec<_>, toml::de::Error>>()
+            .unwrap();
+
+        assert_eq!(actual, expected);
+    }
 }
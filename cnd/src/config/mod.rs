@@ -1,8 +1,9 @@
 pub mod file;
 mod serde_bitcoin_network;
+mod serde_secret_key;
 pub mod settings;
 
-use crate::swap_protocols::ledger::ethereum;
+use crate::{swap_protocols::ledger::ethereum, webhooks::WebhookEvent};
 use libp2p::Multiaddr;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -13,6 +14,15 @@ use std::{
 pub use self::{file::File, settings::Settings};
 use reqwest::Url;
 
+/// Ledger events are acted upon as soon as their transaction is included in a
+/// block unless configured otherwise.
+pub const DEFAULT_MIN_CONFIRMATIONS: u32 = 1;
+
+/// The transactions of ledger events are watched for reorgs until they are
+/// buried under this many blocks unless configured otherwise.
+pub const DEFAULT_BITCOIN_FINALITY_DEPTH: u32 = 6;
+pub const DEFAULT_ETHEREUM_FINALITY_DEPTH: u32 = 12;
+
 lazy_static::lazy_static! {
     pub static ref LND_SOCKET: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
 }
@@ -31,22 +41,69 @@ pub struct Network {
 pub struct Bitcoin {
     #[serde(with = "crate::config::serde_bitcoin_network")]
     pub network: bitcoin::Network,
+    /// Ledger events are only acted upon once their transaction has this many
+    /// confirmations.
+    pub min_confirmations: u32,
+    /// The transactions of ledger events are watched for reorgs until they
+    /// are buried under this many blocks.
+    pub finality_depth: u32,
     pub bitcoind: Bitcoind,
+    /// Blocks are fetched from this Esplora instance instead of bitcoind if
+    /// configured.
+    pub esplora: Option<Esplora>,
+    pub auto_refund: Option<BitcoinAutoRefund>,
+    pub wallet: Option<BitcoinWallet>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Bitcoind {
     pub node_url: reqwest::Url,
+    /// Address of bitcoind's `zmqpubrawblock` publisher, e.g.
+    /// `tcp://127.0.0.1:28332`. New blocks are taken from there instead of
+    /// polling the REST interface.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zmqpubrawblock: Option<String>,
+    /// Address of bitcoind's `zmqpubhashblock` publisher. Only used if
+    /// `zmqpubrawblock` is not set, the announced blocks are fetched over REST.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zmqpubhashblock: Option<String>,
+}
+
+/// An Esplora HTTP API, e.g. `https://blockstream.info/api/`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Esplora {
+    pub url: reqwest::Url,
+}
+
+/// Refund our own Bitcoin HTLCs without user interaction once they expired.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BitcoinAutoRefund {
+    pub sweep_address: bitcoin::Address,
+    pub fee_per_wu: usize,
+}
+
+/// Let cnd manage its own Bitcoin wallet, keys are derived from the seed.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BitcoinWallet {
+    /// Fee rate used for transactions that cnd builds and signs itself.
+    pub fee_per_wu: usize,
 }
 
 impl Default for Bitcoin {
     fn default() -> Self {
         Self {
             network: bitcoin::Network::Regtest,
+            min_confirmations: DEFAULT_MIN_CONFIRMATIONS,
+            finality_depth: DEFAULT_BITCOIN_FINALITY_DEPTH,
             bitcoind: Bitcoind {
                 node_url: Url::parse("http://localhost:18443")
                     .expect("static string to be a valid url"),
+                zmqpubrawblock: None,
+                zmqpubhashblock: None,
             },
+            esplora: None,
+            auto_refund: None,
+            wallet: None,
         }
     }
 }
@@ -55,7 +112,12 @@ impl From<Bitcoin> for file::Bitcoin {
     fn from(bitcoin: Bitcoin) -> Self {
         file::Bitcoin {
             network: bitcoin.network,
+            min_confirmations: Some(bitcoin.min_confirmations),
+            finality_depth: Some(bitcoin.finality_depth),
             bitcoind: Some(bitcoin.bitcoind),
+            esplora: bitcoin.esplora,
+            auto_refund: bitcoin.auto_refund,
+            wallet: bitcoin.wallet,
         }
     }
 }
@@ -63,14 +125,40 @@ impl From<Bitcoin> for file::Bitcoin {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Ethereum {
     pub chain_id: ethereum::ChainId,
+    /// Ledger events are only acted upon once their transaction has this many
+    /// confirmations.
+    pub min_confirmations: u32,
+    /// The transactions of ledger events are watched for reorgs until they
+    /// are buried under this many blocks.
+    pub finality_depth: u32,
     pub parity: Parity,
+    /// Refund our own Ethereum HTLCs without user interaction once they
+    /// expired. The refunds are sent from the wallet, which is therefore
+    /// required.
+    pub auto_refund: bool,
+    pub wallet: Option<EthereumWallet>,
+}
+
+/// Let cnd sign and send its own Ethereum transactions.
+///
+/// The key is derived from the seed unless `private_key` is given.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EthereumWallet {
+    #[serde(default, with = "crate::config::serde_secret_key::option")]
+    pub private_key: Option<bitcoin::secp256k1::SecretKey>,
+    /// The gas price suggested by the node is used if this is not set.
+    pub gas_price_gwei: Option<u64>,
 }
 
 impl From<Ethereum> for file::Ethereum {
     fn from(ethereum: Ethereum) -> Self {
         file::Ethereum {
             chain_id: ethereum.chain_id,
+            min_confirmations: Some(ethereum.min_confirmations),
+            finality_depth: Some(ethereum.finality_depth),
             parity: Some(ethereum.parity),
+            auto_refund: Some(ethereum.auto_refund),
+            wallet: ethereum.wallet,
         }
     }
 }
@@ -79,10 +167,15 @@ impl Default for Ethereum {
     fn default() -> Self {
         Self {
             chain_id: ethereum::ChainId::regtest(),
+            min_confirmations: DEFAULT_MIN_CONFIRMATIONS,
+            finality_depth: DEFAULT_ETHEREUM_FINALITY_DEPTH,
             parity: Parity {
                 node_url: Url::parse("http://localhost:8545")
                     .expect("static string to be a valid url"),
+                ws_url: None,
             },
+            auto_refund: false,
+            wallet: None,
         }
     }
 }
@@ -90,6 +183,66 @@ impl Default for Ethereum {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Parity {
     pub node_url: reqwest::Url,
+    /// WebSocket endpoint of the node, e.g. `ws://localhost:8546`. New blocks
+    /// are subscribed to there instead of polling `node_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_url: Option<reqwest::Url>,
+}
+
+/// How many blocks and receipts are kept so that they are not fetched from the
+/// nodes again.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, derivative::Derivative)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct Cache {
+    #[derivative(Default(value = "144"))]
+    pub bitcoin_blocks: usize,
+    #[derivative(Default(value = "720"))]
+    pub ethereum_blocks: usize,
+    #[derivative(Default(value = "720"))]
+    pub ethereum_receipts: usize,
+    /// Also keep blocks and receipts in the data directory so that they do not
+    /// have to be fetched again after a restart.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<DiskCache>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, derivative::Derivative)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct DiskCache {
+    #[derivative(Default(value = "1008"))]
+    pub bitcoin_blocks: usize,
+    #[derivative(Default(value = "5760"))]
+    pub ethereum_blocks: usize,
+    #[derivative(Default(value = "100_000"))]
+    pub ethereum_receipts: usize,
+    pub eviction: Eviction,
+}
+
+/// Which entry is removed from a full cache.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, derivative::Derivative)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Eviction {
+    #[derivative(Default)]
+    LeastRecentlyUsed,
+    OldestFirst,
+}
+
+/// HTTP endpoints that are notified about the lifecycle of swaps.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Webhooks {
+    pub endpoints: Vec<Webhook>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Webhook {
+    pub url: reqwest::Url,
+    /// Used to sign the payloads, the receiver verifies the signature with it.
+    pub secret: String,
+    /// Only these events are delivered, all of them if this is not set.
+    pub events: Option<Vec<WebhookEvent>>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
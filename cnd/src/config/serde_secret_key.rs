@@ -0,0 +1,64 @@
+use bitcoin::secp256k1::SecretKey;
+use serde::{de, export::fmt, Deserializer, Serializer};
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<SecretKey, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+        type Value = SecretKey;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a hex encoded secp256k1 secret key")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            let bytes = hex::decode(v.trim_start_matches("0x"))
+                .map_err(|e| E::custom(format!("secret key is not valid hex: {}", e)))?;
+
+            SecretKey::from_slice(&bytes)
+                .map_err(|e| E::custom(format!("invalid secret key: {}", e)))
+        }
+    }
+
+    deserializer.deserialize_str(Visitor)
+}
+
+pub fn serialize<S>(value: &SecretKey, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&hex::encode(&value[..]))
+}
+
+pub mod option {
+    use bitcoin::secp256k1::SecretKey;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SecretKey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super")] SecretKey);
+
+        let wrapper = Option::<Wrapper>::deserialize(deserializer)?;
+
+        Ok(wrapper.map(|Wrapper(secret_key)| secret_key))
+    }
+
+    pub fn serialize<S>(value: &Option<SecretKey>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(secret_key) => super::serialize(secret_key, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use crate::config::{
-    default_lnd_dir, file, Bitcoin, Bitcoind, Data, Ethereum, File, Lightning, Lnd, Network,
-    Parity, LND_SOCKET,
+    default_lnd_dir, file, Bitcoin, Bitcoind, Cache, Data, Ethereum, File, Lightning, Lnd, Network,
+    Parity, Webhooks, DEFAULT_BITCOIN_FINALITY_DEPTH, DEFAULT_ETHEREUM_FINALITY_DEPTH,
+    DEFAULT_MIN_CONFIRMATIONS, LND_SOCKET,
 };
 use anyhow::Context;
 use log::LevelFilter;
 use std::{
+    cmp,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
 };
@@ -24,65 +26,117 @@ pub struct Settings {
     pub bitcoin: Bitcoin,
     pub ethereum: Ethereum,
     pub lightning: Lightning,
+    pub autopilot: Autopilot,
+    pub webhooks: Webhooks,
+    pub cache: Cache,
 }
 
-fn derive_url_bitcoin(bitcoin: Option<file::Bitcoin>) -> Bitcoin {
-    match bitcoin {
+fn derive_url_bitcoin(bitcoin: Option<file::Bitcoin>) -> anyhow::Result<Bitcoin> {
+    let bitcoin = match bitcoin {
         None => Bitcoin::default(),
         Some(bitcoin) => {
-            let node_url = match bitcoin.bitcoind {
-                Some(bitcoind) => bitcoind.node_url,
-                None => match bitcoin.network {
-                    bitcoin::Network::Bitcoin => "http://localhost:8332"
-                        .parse()
-                        .expect("to be valid static string"),
-                    bitcoin::Network::Testnet => "http://localhost:18332"
-                        .parse()
-                        .expect("to be valid static string"),
-                    bitcoin::Network::Regtest => "http://localhost:18443"
-                        .parse()
-                        .expect("to be valid static string"),
+            let bitcoind = match bitcoin.bitcoind {
+                Some(bitcoind) => bitcoind,
+                None => Bitcoind {
+                    node_url: match bitcoin.network {
+                        bitcoin::Network::Bitcoin => "http://localhost:8332"
+                            .parse()
+                            .expect("to be valid static string"),
+                        bitcoin::Network::Testnet => "http://localhost:18332"
+                            .parse()
+                            .expect("to be valid static string"),
+                        bitcoin::Network::Regtest => "http://localhost:18443"
+                            .parse()
+                            .expect("to be valid static string"),
+                    },
+                    zmqpubrawblock: None,
+                    zmqpubhashblock: None,
                 },
             };
+            let min_confirmations = bitcoin
+                .min_confirmations
+                .unwrap_or(DEFAULT_MIN_CONFIRMATIONS);
             Bitcoin {
                 network: bitcoin.network,
-                bitcoind: Bitcoind { node_url },
+                min_confirmations,
+                finality_depth: bitcoin
+                    .finality_depth
+                    .unwrap_or_else(|| cmp::max(DEFAULT_BITCOIN_FINALITY_DEPTH, min_confirmations)),
+                bitcoind,
+                esplora: bitcoin.esplora,
+                auto_refund: bitcoin.auto_refund,
+                wallet: bitcoin.wallet,
             }
         }
+    };
+
+    if bitcoin.finality_depth < bitcoin.min_confirmations {
+        anyhow::bail!("bitcoin finality_depth must not be smaller than min_confirmations")
     }
+
+    Ok(bitcoin)
 }
 
-fn derive_url_ethereum(ethereum: Option<file::Ethereum>) -> Ethereum {
-    match ethereum {
+fn derive_url_ethereum(ethereum: Option<file::Ethereum>) -> anyhow::Result<Ethereum> {
+    let ethereum = match ethereum {
         None => Ethereum::default(),
         Some(ethereum) => {
-            let node_url = match ethereum.parity {
-                None => {
+            let parity = match ethereum.parity {
+                None => Parity {
                     // default is always localhost:8545
-                    "http://localhost:8545"
+                    node_url: "http://localhost:8545"
                         .parse()
-                        .expect("to be valid static string")
-                }
-                Some(parity) => parity.node_url,
+                        .expect("to be valid static string"),
+                    ws_url: None,
+                },
+                Some(parity) => parity,
             };
+            let min_confirmations = ethereum
+                .min_confirmations
+                .unwrap_or(DEFAULT_MIN_CONFIRMATIONS);
             Ethereum {
                 chain_id: ethereum.chain_id,
-                parity: Parity { node_url },
+                min_confirmations,
+                finality_depth: ethereum.finality_depth.unwrap_or_else(|| {
+                    cmp::max(DEFAULT_ETHEREUM_FINALITY_DEPTH, min_confirmations)
+                }),
+                parity,
+                auto_refund: ethereum.auto_refund.unwrap_or(false),
+                wallet: ethereum.wallet,
             }
         }
+    };
+
+    if ethereum.finality_depth < ethereum.min_confirmations {
+        anyhow::bail!("ethereum finality_depth must not be smaller than min_confirmations")
     }
+
+    if ethereum.auto_refund && ethereum.wallet.is_none() {
+        anyhow::bail!("automatic refunds on Ethereum require the Ethereum wallet")
+    }
+
+    Ok(ethereum)
 }
 
 impl From<Settings> for File {
     fn from(settings: Settings) -> Self {
         let Settings {
             network,
-            http_api: HttpApi { socket, cors },
+            http_api:
+                HttpApi {
+                    socket,
+                    cors,
+                    auth,
+                    tls,
+                },
             data,
             logging: Logging { level },
             bitcoin,
             ethereum,
             lightning,
+            autopilot,
+            webhooks,
+            cache,
         } = settings;
 
         File {
@@ -96,6 +150,31 @@ impl From<Settings> for File {
                         AllowedOrigins::Some(origins) => file::AllowedOrigins::Some(origins),
                     },
                 }),
+                auth: match auth {
+                    Auth::None => None,
+                    Auth::BearerToken(token) => Some(file::Auth {
+                        bearer_token: Some(token),
+                        scoped_tokens: None,
+                    }),
+                    Auth::ScopedTokens => Some(file::Auth {
+                        bearer_token: None,
+                        scoped_tokens: Some(true),
+                    }),
+                },
+                tls: tls.map(|tls| match tls {
+                    Tls::Files { cert, key } => file::Tls {
+                        cert: Some(cert),
+                        key: Some(key),
+                        self_signed: None,
+                        extra_names: None,
+                    },
+                    Tls::SelfSigned { extra_names } => file::Tls {
+                        cert: None,
+                        key: None,
+                        self_signed: Some(true),
+                        extra_names: Some(extra_names),
+                    },
+                }),
             }),
             data: Some(data),
             logging: Some(file::Logging {
@@ -110,6 +189,12 @@ impl From<Settings> for File {
                     dir: lnd.dir,
                 }),
             }),
+            autopilot: Some(file::Autopilot {
+                all_swaps: Some(autopilot.all_swaps),
+                safety_margin_secs: Some(autopilot.safety_margin_secs),
+            }),
+            webhooks: Some(webhooks),
+            cache: Some(cache),
         }
     }
 }
@@ -118,6 +203,9 @@ impl From<Settings> for File {
 pub struct HttpApi {
     pub socket: SocketAddr,
     pub cors: Cors,
+    pub auth: Auth,
+    /// Serve HTTPS instead of HTTP.
+    pub tls: Option<Tls>,
 }
 
 impl Default for HttpApi {
@@ -125,6 +213,8 @@ impl Default for HttpApi {
         Self {
             socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
             cors: Cors::default(),
+            auth: Auth::default(),
+            tls: None,
         }
     }
 }
@@ -149,6 +239,86 @@ pub enum AllowedOrigins {
     Some(Vec<String>),
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tls {
+    /// Use the certificate and key in these PEM files.
+    Files { cert: PathBuf, key: PathBuf },
+    /// Use a self-signed certificate that is generated into the data directory
+    /// on the first start. It is valid for localhost and the extra names,
+    /// which are either DNS names or IP addresses.
+    SelfSigned { extra_names: Vec<String> },
+}
+
+impl Tls {
+    fn from_file(tls: file::Tls) -> anyhow::Result<Self> {
+        let file::Tls {
+            cert,
+            key,
+            self_signed,
+            extra_names,
+        } = tls;
+
+        match (cert, key, self_signed.unwrap_or(false)) {
+            (None, None, true) => Ok(Tls::SelfSigned {
+                extra_names: extra_names.unwrap_or_default(),
+            }),
+            (Some(_), _, true) | (_, Some(_), true) => anyhow::bail!(
+                "the HTTP API can either use a self_signed certificate or a cert and key"
+            ),
+            (Some(cert), Some(key), false) => Ok(Tls::Files { cert, key }),
+            _ => anyhow::bail!("TLS for the HTTP API requires both a cert and a key"),
+        }
+    }
+}
+
+/// How clients of the HTTP API have to authenticate.
+#[derive(Clone, Debug, PartialEq, derivative::Derivative)]
+#[derivative(Default)]
+pub enum Auth {
+    /// Anyone who can reach the socket has full access.
+    #[derivative(Default)]
+    None,
+    /// Requests have to carry this token, it grants full access.
+    BearerToken(String),
+    /// Requests have to carry one of the tokens derived from the seed, which
+    /// either grant read-only access or also allow executing actions.
+    ScopedTokens,
+}
+
+impl Auth {
+    fn from_file(auth: Option<file::Auth>) -> anyhow::Result<Self> {
+        let auth = match auth {
+            Some(auth) => auth,
+            None => return Ok(Auth::None),
+        };
+
+        match (auth.bearer_token, auth.scoped_tokens.unwrap_or(false)) {
+            (None, false) => Ok(Auth::None),
+            (None, true) => Ok(Auth::ScopedTokens),
+            (Some(token), false) if token.is_empty() => {
+                anyhow::bail!("the bearer_token of the HTTP API must not be empty")
+            }
+            (Some(token), false) => Ok(Auth::BearerToken(token)),
+            (Some(_), true) => anyhow::bail!(
+                "the HTTP API can either use a bearer_token or scoped_tokens, not both"
+            ),
+        }
+    }
+}
+
+/// Lets cnd execute the actions of swaps on its own.
+#[derive(Clone, Copy, Debug, PartialEq, derivative::Derivative)]
+#[derivative(Default)]
+pub struct Autopilot {
+    /// Drive every swap instead of only the ones the autopilot was enabled for
+    /// through the HTTP API.
+    pub all_swaps: bool,
+    /// Funds are only locked up in an HTLC that expires later than this many
+    /// seconds from now.
+    #[derivative(Default(value = "3600"))]
+    pub safety_margin_secs: u32,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, derivative::Derivative)]
 #[derivative(Default)]
 pub struct Logging {
@@ -166,6 +336,9 @@ impl Settings {
             bitcoin,
             ethereum,
             lightning,
+            autopilot,
+            webhooks,
+            cache,
         } = config_file;
 
         Ok(Self {
@@ -178,8 +351,13 @@ impl Settings {
                     listen: vec![default_socket],
                 }
             }),
-            http_api: http_api
-                .map(|file::HttpApi { socket, cors }| {
+            http_api: match http_api {
+                Some(file::HttpApi {
+                    socket,
+                    cors,
+                    auth,
+                    tls,
+                }) => {
                     let cors = cors
                         .map(|cors| {
                             let allowed_origins = match cors.allowed_origins {
@@ -194,9 +372,15 @@ impl Settings {
                         })
                         .unwrap_or_default();
 
-                    HttpApi { socket, cors }
-                })
-                .unwrap_or_default(),
+                    HttpApi {
+                        socket,
+                        cors,
+                        auth: Auth::from_file(auth)?,
+                        tls: tls.map(Tls::from_file).transpose()?,
+                    }
+                }
+                None => HttpApi::default(),
+            },
             data: {
                 let default_data_dir =
                     crate::data_dir().context("unable to determine default data path")?;
@@ -216,8 +400,8 @@ impl Settings {
                     },
                 }
             },
-            bitcoin: derive_url_bitcoin(bitcoin),
-            ethereum: derive_url_ethereum(ethereum),
+            bitcoin: derive_url_bitcoin(bitcoin)?,
+            ethereum: derive_url_ethereum(ethereum)?,
             lightning: match lightning {
                 None => Lightning::default(),
                 Some(lightning) => Lightning {
@@ -231,6 +415,22 @@ impl Settings {
                     },
                 },
             },
+            autopilot: {
+                let default = Autopilot::default();
+                match autopilot {
+                    None => default,
+                    Some(file::Autopilot {
+                        all_swaps,
+                        safety_margin_secs,
+                    }) => Autopilot {
+                        all_swaps: all_swaps.unwrap_or(default.all_swaps),
+                        safety_margin_secs: safety_margin_secs
+                            .unwrap_or(default.safety_margin_secs),
+                    },
+                }
+            },
+            webhooks: webhooks.unwrap_or_default(),
+            cache: cache.unwrap_or_default(),
         })
     }
 
@@ -272,7 +472,10 @@ fn locate_macaroon(dirs: Vec<PathBuf>) -> Option<PathBuf> {
 mod tests {
 
     use super::*;
-    use crate::{config::file, swap_protocols::ledger::ethereum};
+    use crate::{
+        config::{file, EthereumWallet},
+        swap_protocols::ledger::ethereum,
+    };
     use spectral::prelude::*;
     use std::{net::IpAddr, path::PathBuf};
 
@@ -299,6 +502,8 @@ mod tests {
             http_api: Some(file::HttpApi {
                 socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
                 cors: None,
+                auth: None,
+                tls: None,
             }),
             ..File::default()
         };
@@ -330,9 +535,93 @@ mod tests {
                 cors: Cors {
                     allowed_origins: AllowedOrigins::None,
                 },
+                auth: Auth::None,
+                tls: None,
             })
     }
 
+    #[test]
+    fn http_api_tls_is_either_self_signed_or_read_from_files() {
+        let config_file = |cert: Option<&str>, key: Option<&str>, self_signed| File {
+            http_api: Some(file::HttpApi {
+                socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
+                cors: None,
+                auth: None,
+                tls: Some(file::Tls {
+                    cert: cert.map(PathBuf::from),
+                    key: key.map(PathBuf::from),
+                    self_signed,
+                    extra_names: None,
+                }),
+            }),
+            ..File::default()
+        };
+
+        let files = Settings::from_config_file_and_defaults(config_file(
+            Some("/tls/cert.pem"),
+            Some("/tls/key.pem"),
+            None,
+        ));
+        let self_signed =
+            Settings::from_config_file_and_defaults(config_file(None, None, Some(true)));
+        let both = Settings::from_config_file_and_defaults(config_file(
+            Some("/tls/cert.pem"),
+            Some("/tls/key.pem"),
+            Some(true),
+        ));
+        let cert_without_key =
+            Settings::from_config_file_and_defaults(config_file(Some("/tls/cert.pem"), None, None));
+
+        assert_that(&files)
+            .is_ok()
+            .map(|settings| &settings.http_api.tls)
+            .is_equal_to(Some(Tls::Files {
+                cert: PathBuf::from("/tls/cert.pem"),
+                key: PathBuf::from("/tls/key.pem"),
+            }));
+        assert_that(&self_signed)
+            .is_ok()
+            .map(|settings| &settings.http_api.tls)
+            .is_equal_to(Some(Tls::SelfSigned {
+                extra_names: Vec::new(),
+            }));
+        assert_that(&both).is_err();
+        assert_that(&cert_without_key).is_err();
+    }
+
+    #[test]
+    fn http_api_auth_is_either_a_bearer_token_or_scoped_tokens() {
+        let config_file = |bearer_token: Option<&str>, scoped_tokens| File {
+            http_api: Some(file::HttpApi {
+                socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
+                cors: None,
+                auth: Some(file::Auth {
+                    bearer_token: bearer_token.map(String::from),
+                    scoped_tokens,
+                }),
+                tls: None,
+            }),
+            ..File::default()
+        };
+
+        let bearer_token =
+            Settings::from_config_file_and_defaults(config_file(Some("s3cr3t"), None));
+        let scoped_tokens = Settings::from_config_file_and_defaults(config_file(None, Some(true)));
+        let both = Settings::from_config_file_and_defaults(config_file(Some("s3cr3t"), Some(true)));
+        let empty = Settings::from_config_file_and_defaults(config_file(Some(""), None));
+
+        assert_that(&bearer_token)
+            .is_ok()
+            .map(|settings| &settings.http_api.auth)
+            .is_equal_to(Auth::BearerToken("s3cr3t".to_owned()));
+        assert_that(&scoped_tokens)
+            .is_ok()
+            .map(|settings| &settings.http_api.auth)
+            .is_equal_to(Auth::ScopedTokens);
+        assert_that(&both).is_err();
+        assert_that(&empty).is_err();
+    }
+
     #[test]
     fn network_section_defaults() {
         let config_file = File {
@@ -361,9 +650,16 @@ mod tests {
             .map(|settings| &settings.bitcoin)
             .is_equal_to(Bitcoin {
                 network: bitcoin::Network::Regtest,
+                min_confirmations: 1,
+                finality_depth: 6,
                 bitcoind: Bitcoind {
                     node_url: "http://localhost:18443".parse().unwrap(),
+                    zmqpubrawblock: None,
+                    zmqpubhashblock: None,
                 },
+                esplora: None,
+                auto_refund: None,
+                wallet: None,
             })
     }
 
@@ -379,7 +675,12 @@ mod tests {
             let config_file = File {
                 bitcoin: Some(file::Bitcoin {
                     network,
+                    min_confirmations: None,
+                    finality_depth: None,
                     bitcoind: None,
+                    esplora: None,
+                    auto_refund: None,
+                    wallet: None,
                 }),
                 ..File::default()
             };
@@ -391,9 +692,16 @@ mod tests {
                 .map(|settings| &settings.bitcoin)
                 .is_equal_to(Bitcoin {
                     network,
+                    min_confirmations: 1,
+                    finality_depth: 6,
                     bitcoind: Bitcoind {
                         node_url: url.parse().unwrap(),
+                        zmqpubrawblock: None,
+                        zmqpubhashblock: None,
                     },
+                    esplora: None,
+                    auto_refund: None,
+                    wallet: None,
                 })
         }
     }
@@ -409,9 +717,14 @@ mod tests {
             .map(|settings| &settings.ethereum)
             .is_equal_to(Ethereum {
                 chain_id: ethereum::ChainId::regtest(),
+                min_confirmations: 1,
+                finality_depth: 12,
                 parity: Parity {
                     node_url: "http://localhost:8545".parse().unwrap(),
+                    ws_url: None,
                 },
+                auto_refund: false,
+                wallet: None,
             })
     }
 
@@ -426,7 +739,11 @@ mod tests {
         for (chain_id, url) in defaults {
             let ethereum = Some(file::Ethereum {
                 chain_id,
+                min_confirmations: None,
+                finality_depth: None,
                 parity: None,
+                auto_refund: None,
+                wallet: None,
             });
             let config_file = File {
                 ethereum,
@@ -440,13 +757,146 @@ mod tests {
                 .map(|settings| &settings.ethereum)
                 .is_equal_to(Ethereum {
                     chain_id,
+                    min_confirmations: 1,
+                    finality_depth: 12,
                     parity: Parity {
                         node_url: url.parse().unwrap(),
+                        ws_url: None,
                     },
+                    auto_refund: false,
+                    wallet: None,
                 })
         }
     }
 
+    #[test]
+    fn ethereum_auto_refund_requires_wallet() {
+        let config_file = |wallet| File {
+            ethereum: Some(file::Ethereum {
+                chain_id: ethereum::ChainId::regtest(),
+                min_confirmations: None,
+                finality_depth: None,
+                parity: None,
+                auto_refund: Some(true),
+                wallet,
+            }),
+            ..File::default()
+        };
+
+        let wallet = EthereumWallet {
+            private_key: None,
+            gas_price_gwei: None,
+        };
+
+        let with_wallet = Settings::from_config_file_and_defaults(config_file(Some(wallet)));
+        let without_wallet = Settings::from_config_file_and_defaults(config_file(None));
+
+        assert_that(&with_wallet)
+            .is_ok()
+            .map(|settings| &settings.ethereum.auto_refund)
+            .is_equal_to(true);
+        assert_that(&without_wallet).is_err();
+    }
+
+    #[test]
+    fn bitcoin_finality_depth_is_at_least_min_confirmations() {
+        let config_file = |min_confirmations, finality_depth| File {
+            bitcoin: Some(file::Bitcoin {
+                network: bitcoin::Network::Regtest,
+                min_confirmations,
+                finality_depth,
+                bitcoind: None,
+                esplora: None,
+                auto_refund: None,
+                wallet: None,
+            }),
+            ..File::default()
+        };
+
+        let defaulted = Settings::from_config_file_and_defaults(config_file(Some(10), None));
+        let smaller = Settings::from_config_file_and_defaults(config_file(Some(10), Some(6)));
+
+        assert_that(&defaulted)
+            .is_ok()
+            .map(|settings| &settings.bitcoin.finality_depth)
+            .is_equal_to(10);
+        assert_that(&smaller).is_err();
+    }
+
+    #[test]
+    fn autopilot_section_defaults() {
+        let config_file = File {
+            autopilot: None,
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.autopilot)
+            .is_equal_to(Autopilot {
+                all_swaps: false,
+                safety_margin_secs: 3600,
+            })
+    }
+
+    #[test]
+    fn webhooks_section_defaults() {
+        let config_file = File {
+            webhooks: None,
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.webhooks)
+            .is_equal_to(Webhooks { endpoints: vec![] })
+    }
+
+    #[test]
+    fn cache_section_defaults() {
+        let config_file = File {
+            cache: None,
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.cache)
+            .is_equal_to(Cache {
+                bitcoin_blocks: 144,
+                ethereum_blocks: 720,
+                ethereum_receipts: 720,
+                disk: None,
+            })
+    }
+
+    #[test]
+    fn autopilot_safety_margin_defaults() {
+        let config_file = File {
+            autopilot: Some(file::Autopilot {
+                all_swaps: Some(true),
+                safety_margin_secs: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.autopilot)
+            .is_equal_to(Autopilot {
+                all_swaps: true,
+                safety_margin_secs: 3600,
+            })
+    }
+
     #[test]
     fn lightning_section_defaults() {
         let config_file = File {
@@ -0,0 +1,118 @@
+use crate::{
+    db::{
+        schema::rfc003_automatic_actions, wrapper_types::custom_sql_types::Text, LedgerSide, Sqlite,
+    },
+    swap_protocols::{rfc003::actions::ActionKind, SwapId},
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::{self, prelude::*, RunQueryDsl};
+use serde::Serialize;
+
+/// An action that cnd executed on behalf of the user.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AutomaticAction {
+    pub ledger: LedgerSide,
+    pub action: ActionKind,
+    pub transaction_hash: String,
+    pub at: NaiveDateTime,
+}
+
+#[async_trait]
+pub trait SaveAutomaticAction: Send + Sync + 'static {
+    async fn save_automatic_action(
+        &self,
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        action: ActionKind,
+        transaction_hash: String,
+    ) -> anyhow::Result<()>;
+}
+
+/// Load the actions executed on behalf of the user, in the order they were
+/// executed.
+#[async_trait]
+pub trait LoadAutomaticActions: Send + Sync + 'static {
+    async fn load_automatic_actions(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<AutomaticAction>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "rfc003_automatic_actions"]
+struct InsertableAutomaticAction {
+    swap_id: Text<SwapId>,
+    ledger: Text<LedgerSide>,
+    action: Text<ActionKind>,
+    transaction_hash: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableAutomaticAction {
+    _id: i32,
+    _swap_id: Text<SwapId>,
+    ledger: Text<LedgerSide>,
+    action: Text<ActionKind>,
+    transaction_hash: String,
+    at: NaiveDateTime,
+}
+
+impl From<QueryableAutomaticAction> for AutomaticAction {
+    fn from(record: QueryableAutomaticAction) -> Self {
+        AutomaticAction {
+            ledger: *record.ledger,
+            action: *record.action,
+            transaction_hash: record.transaction_hash,
+            at: record.at,
+        }
+    }
+}
+
+#[async_trait]
+impl SaveAutomaticAction for Sqlite {
+    async fn save_automatic_action(
+        &self,
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        action: ActionKind,
+        transaction_hash: String,
+    ) -> anyhow::Result<()> {
+        let insertable = InsertableAutomaticAction {
+            swap_id: Text(swap_id),
+            ledger: Text(ledger),
+            action: Text(action),
+            transaction_hash,
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(rfc003_automatic_actions::table)
+                .values(&insertable)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoadAutomaticActions for Sqlite {
+    async fn load_automatic_actions(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<AutomaticAction>> {
+        let records: Vec<QueryableAutomaticAction> = self
+            .do_in_transaction(|connection| {
+                let key = Text(swap_id);
+
+                rfc003_automatic_actions::table
+                    .filter(rfc003_automatic_actions::swap_id.eq(key))
+                    .order(rfc003_automatic_actions::id.asc())
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(AutomaticAction::from).collect())
+    }
+}
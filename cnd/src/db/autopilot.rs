@@ -0,0 +1,63 @@
+use crate::{
+    db::{schema::rfc003_autopilot_swaps, wrapper_types::custom_sql_types::Text, Sqlite},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use diesel::{self, prelude::*, RunQueryDsl};
+
+/// Hand a swap over to the autopilot.
+///
+/// Enabling the autopilot for a swap that already has it enabled is not an
+/// error.
+#[async_trait]
+pub trait EnableAutopilot: Send + Sync + 'static {
+    async fn enable_autopilot(&self, swap_id: SwapId) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+pub trait LoadAutopilot: Send + Sync + 'static {
+    /// Returns true if the autopilot was enabled for this particular swap.
+    async fn autopilot_enabled(&self, swap_id: &SwapId) -> anyhow::Result<bool>;
+}
+
+#[derive(Insertable, Debug, Clone, Copy)]
+#[table_name = "rfc003_autopilot_swaps"]
+struct InsertableAutopilotSwap {
+    swap_id: Text<SwapId>,
+}
+
+#[async_trait]
+impl EnableAutopilot for Sqlite {
+    async fn enable_autopilot(&self, swap_id: SwapId) -> anyhow::Result<()> {
+        let insertable = InsertableAutopilotSwap {
+            swap_id: Text(swap_id),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_or_ignore_into(rfc003_autopilot_swaps::table)
+                .values(&insertable)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoadAutopilot for Sqlite {
+    async fn autopilot_enabled(&self, swap_id: &SwapId) -> anyhow::Result<bool> {
+        let count: i64 = self
+            .do_in_transaction(|connection| {
+                let key = Text(swap_id);
+
+                rfc003_autopilot_swaps::table
+                    .filter(rfc003_autopilot_swaps::swap_id.eq(key))
+                    .count()
+                    .get_result(connection)
+            })
+            .await?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    asset,
+    db::{
+        schema::{bitcoin_wallet_blocks, bitcoin_wallet_utxos},
+        wrapper_types::{custom_sql_types::Text, Satoshis},
+        Sqlite,
+    },
+};
+use async_trait::async_trait;
+use bitcoin::{BlockHash, OutPoint, Txid};
+use diesel::{self, prelude::*, RunQueryDsl};
+
+/// An unspent output that belongs to the built-in Bitcoin wallet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub amount: asset::Bitcoin,
+}
+
+/// Save the effect a block had on the built-in Bitcoin wallet.
+///
+/// The spent outputs are marked as spent and the received ones added in the
+/// same transaction as the block is recorded, the wallet can therefore never be
+/// in a state where a block was only partially processed. Both remember the
+/// block so that its effect can be rolled back if it is orphaned.
+#[async_trait]
+pub trait SaveBitcoinWalletBlock: Send + Sync + 'static {
+    async fn save_bitcoin_wallet_block(
+        &self,
+        block_hash: BlockHash,
+        spent: Vec<OutPoint>,
+        received: Vec<Utxo>,
+    ) -> anyhow::Result<()>;
+}
+
+/// Undo the effect of a block that is no longer part of the blockchain.
+///
+/// The outputs received in the block are removed, the ones spent in it are
+/// unspent again and the block is forgotten.
+#[async_trait]
+pub trait RollBackBitcoinWalletBlock: Send + Sync + 'static {
+    async fn roll_back_bitcoin_wallet_block(&self, block_hash: BlockHash) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+pub trait LoadBitcoinWallet: Send + Sync + 'static {
+    /// Hashes of the last `limit` blocks the wallet processed, oldest first.
+    /// Empty if the wallet was never synced.
+    async fn load_bitcoin_wallet_blocks(&self, limit: usize) -> anyhow::Result<Vec<BlockHash>>;
+    async fn load_bitcoin_wallet_utxos(&self) -> anyhow::Result<Vec<Utxo>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "bitcoin_wallet_blocks"]
+struct InsertableBlock {
+    block_hash: Text<BlockHash>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "bitcoin_wallet_utxos"]
+struct InsertableUtxo {
+    txid: Text<Txid>,
+    vout: Text<u32>,
+    amount: Text<Satoshis>,
+    received_in: Text<BlockHash>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableUtxo {
+    _id: i32,
+    txid: Text<Txid>,
+    vout: Text<u32>,
+    amount: Text<Satoshis>,
+    _received_in: Text<BlockHash>,
+    _spent_in: Option<Text<BlockHash>>,
+}
+
+impl InsertableUtxo {
+    fn new(utxo: Utxo, received_in: BlockHash) -> Self {
+        InsertableUtxo {
+            txid: Text(utxo.outpoint.txid),
+            vout: Text(utxo.outpoint.vout),
+            amount: Text(utxo.amount.into()),
+            received_in: Text(received_in),
+        }
+    }
+}
+
+impl From<QueryableUtxo> for Utxo {
+    fn from(record: QueryableUtxo) -> Self {
+        Utxo {
+            outpoint: OutPoint {
+                txid: *record.txid,
+                vout: *record.vout,
+            },
+            amount: (*record.amount).into(),
+        }
+    }
+}
+
+#[async_trait]
+impl SaveBitcoinWalletBlock for Sqlite {
+    async fn save_bitcoin_wallet_block(
+        &self,
+        block_hash: BlockHash,
+        spent: Vec<OutPoint>,
+        received: Vec<Utxo>,
+    ) -> anyhow::Result<()> {
+        let block = InsertableBlock {
+            block_hash: Text(block_hash),
+        };
+        let received = received
+            .into_iter()
+            .map(|utxo| InsertableUtxo::new(utxo, block_hash))
+            .collect::<Vec<_>>();
+
+        self.do_in_transaction(|connection| {
+            for outpoint in spent.iter() {
+                diesel::update(
+                    bitcoin_wallet_utxos::table
+                        .filter(bitcoin_wallet_utxos::txid.eq(Text(outpoint.txid)))
+                        .filter(bitcoin_wallet_utxos::vout.eq(Text(outpoint.vout))),
+                )
+                .set(bitcoin_wallet_utxos::spent_in.eq(Some(Text(block_hash))))
+                .execute(connection)?;
+            }
+
+            diesel::insert_into(bitcoin_wallet_utxos::table)
+                .values(&received)
+                .execute(connection)?;
+
+            diesel::insert_into(bitcoin_wallet_blocks::table)
+                .values(&block)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RollBackBitcoinWalletBlock for Sqlite {
+    async fn roll_back_bitcoin_wallet_block(&self, block_hash: BlockHash) -> anyhow::Result<()> {
+        self.do_in_transaction(|connection| {
+            diesel::delete(
+                bitcoin_wallet_utxos::table
+                    .filter(bitcoin_wallet_utxos::received_in.eq(Text(block_hash))),
+            )
+            .execute(connection)?;
+
+            diesel::update(
+                bitcoin_wallet_utxos::table
+                    .filter(bitcoin_wallet_utxos::spent_in.eq(Some(Text(block_hash)))),
+            )
+            .set(bitcoin_wallet_utxos::spent_in.eq(None::<Text<BlockHash>>))
+            .execute(connection)?;
+
+            diesel::delete(
+                bitcoin_wallet_blocks::table
+                    .filter(bitcoin_wallet_blocks::block_hash.eq(Text(block_hash))),
+            )
+            .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoadBitcoinWallet for Sqlite {
+    async fn load_bitcoin_wallet_blocks(&self, limit: usize) -> anyhow::Result<Vec<BlockHash>> {
+        let records: Vec<Text<BlockHash>> = self
+            .do_in_transaction(|connection| {
+                bitcoin_wallet_blocks::table
+                    .select(bitcoin_wallet_blocks::block_hash)
+                    .order(bitcoin_wallet_blocks::id.desc())
+                    .limit(limit as i64)
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .rev()
+            .map(|block_hash| *block_hash)
+            .collect())
+    }
+
+    async fn load_bitcoin_wallet_utxos(&self) -> anyhow::Result<Vec<Utxo>> {
+        let records: Vec<QueryableUtxo> = self
+            .do_in_transaction(|connection| {
+                bitcoin_wallet_utxos::table
+                    .filter(bitcoin_wallet_utxos::spent_in.is_null())
+                    .order(bitcoin_wallet_utxos::id.asc())
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(Utxo::from).collect())
+    }
+}
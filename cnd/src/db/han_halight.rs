@@ -0,0 +1,371 @@
+use crate::{
+    db::{
+        schema::{
+            han_ethereum_halight_bitcoin_accept_messages,
+            han_ethereum_halight_bitcoin_request_messages, han_halight_swaps,
+        },
+        wrapper_types::{
+            custom_sql_types::{Text, U32},
+            BitcoinNetwork, Ether, EthereumAddress, Satoshis,
+        },
+        Error, Save, Sqlite, Swap,
+    },
+    swap_protocols::{
+        han_halight::{Accept, AcceptedSwap, Request},
+        ledger::{Ethereum, Lightning},
+        rfc003::SecretHash,
+        HashFunction, Role, SwapId,
+    },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::{self, prelude::*, RunQueryDsl};
+use libp2p::PeerId;
+
+/// A `Swap` that follows the han-halight protocol.
+///
+/// The rfc003 swaps are stored in their own table, wrapping the swap lets us
+/// tell the two apart when saving.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HanHalightSwap(pub Swap);
+
+/// Load han-halight swaps from database.
+#[async_trait]
+#[ambassador::delegatable_trait]
+pub trait LoadHanHalightSwaps: Send + Sync + 'static {
+    async fn han_halight_swap(&self, swap_id: &SwapId) -> anyhow::Result<Swap>;
+    async fn han_halight_swaps(&self) -> anyhow::Result<Vec<Swap>>;
+    /// Loads the request of a swap, regardless of whether it has been
+    /// answered.
+    async fn load_han_halight_request(&self, swap_id: &SwapId) -> anyhow::Result<Request>;
+    async fn load_han_halight_accepted_swap(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<AcceptedSwap>;
+}
+
+diesel::allow_tables_to_appear_in_same_query!(
+    han_ethereum_halight_bitcoin_request_messages,
+    han_ethereum_halight_bitcoin_accept_messages
+);
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "han_halight_swaps"]
+struct InsertableSwap {
+    pub swap_id: Text<SwapId>,
+    pub role: Text<Role>,
+    pub counterparty: Text<PeerId>,
+}
+
+#[async_trait]
+impl Save<HanHalightSwap> for Sqlite {
+    async fn save(&self, swap: HanHalightSwap) -> anyhow::Result<()> {
+        let HanHalightSwap(swap) = swap;
+        let insertable = InsertableSwap {
+            swap_id: Text(swap.swap_id),
+            role: Text(swap.role),
+            counterparty: Text(swap.counterparty),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(han_halight_swaps::table)
+                .values(&insertable)
+                .execute(&*connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "han_ethereum_halight_bitcoin_request_messages"]
+struct InsertableRequestMessage {
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    lightning_network: Text<BitcoinNetwork>,
+    ether_amount: Text<Ether>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    lightning_redeem_identity: Text<::bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    lightning_cltv_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[async_trait]
+impl Save<Request> for Sqlite {
+    async fn save(&self, message: Request) -> anyhow::Result<()> {
+        let Request {
+            swap_id,
+            alpha_ledger,
+            beta_ledger,
+            alpha_asset,
+            beta_asset,
+            hash_function,
+            alpha_ledger_refund_identity,
+            beta_ledger_redeem_identity,
+            alpha_expiry,
+            beta_cltv_expiry,
+            secret_hash,
+        } = message;
+
+        let insertable = InsertableRequestMessage {
+            swap_id: Text(swap_id),
+            ethereum_chain_id: U32(alpha_ledger.chain_id.into()),
+            lightning_network: Text(beta_ledger.network.into()),
+            ether_amount: Text(alpha_asset.into()),
+            bitcoin_amount: Text(beta_asset.into()),
+            hash_function: Text(hash_function),
+            ethereum_refund_identity: Text(alpha_ledger_refund_identity.into()),
+            lightning_redeem_identity: Text(beta_ledger_redeem_identity.into()),
+            ethereum_expiry: U32(alpha_expiry.into()),
+            lightning_cltv_expiry: U32(beta_cltv_expiry),
+            secret_hash: Text(secret_hash),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(han_ethereum_halight_bitcoin_request_messages::table)
+                .values(&insertable)
+                .execute(&*connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Insertable, Debug, Copy, Clone)]
+#[table_name = "han_ethereum_halight_bitcoin_accept_messages"]
+struct InsertableAcceptMessage {
+    swap_id: Text<SwapId>,
+    ethereum_redeem_identity: Text<EthereumAddress>,
+    lightning_refund_identity: Text<::bitcoin::PublicKey>,
+}
+
+#[async_trait]
+impl Save<Accept> for Sqlite {
+    async fn save(&self, message: Accept) -> anyhow::Result<()> {
+        let Accept {
+            swap_id,
+            alpha_ledger_redeem_identity,
+            beta_ledger_refund_identity,
+        } = message;
+
+        let insertable = InsertableAcceptMessage {
+            swap_id: Text(swap_id),
+            ethereum_redeem_identity: Text(alpha_ledger_redeem_identity.into()),
+            lightning_refund_identity: Text(beta_ledger_refund_identity.into()),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(han_ethereum_halight_bitcoin_accept_messages::table)
+                .values(&insertable)
+                .execute(&*connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct QueryableSwap {
+    pub id: i32,
+    pub swap_id: Text<SwapId>,
+    pub role: Text<Role>,
+    pub counterparty: Text<PeerId>,
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct QueryableRequest {
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    lightning_network: Text<BitcoinNetwork>,
+    ether_amount: Text<Ether>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    lightning_redeem_identity: Text<::bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    lightning_cltv_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+impl From<QueryableRequest> for Request {
+    fn from(record: QueryableRequest) -> Self {
+        Request {
+            swap_id: *record.swap_id,
+            alpha_ledger: Ethereum {
+                chain_id: record.ethereum_chain_id.0.into(),
+            },
+            beta_ledger: Lightning::new(record.lightning_network.0.into()),
+            alpha_asset: record.ether_amount.0.into(),
+            beta_asset: record.bitcoin_amount.0.into(),
+            hash_function: *record.hash_function,
+            alpha_ledger_refund_identity: record.ethereum_refund_identity.0.into(),
+            beta_ledger_redeem_identity: record.lightning_redeem_identity.0.into(),
+            alpha_expiry: record.ethereum_expiry.into(),
+            beta_cltv_expiry: record.lightning_cltv_expiry.into(),
+            secret_hash: *record.secret_hash,
+        }
+    }
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct QueryableAcceptedSwap {
+    // Request fields.
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    lightning_network: Text<BitcoinNetwork>,
+    ether_amount: Text<Ether>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    lightning_redeem_identity: Text<::bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    lightning_cltv_expiry: U32,
+    secret_hash: Text<SecretHash>,
+    // Accept fields.
+    ethereum_redeem_identity: Text<EthereumAddress>,
+    lightning_refund_identity: Text<::bitcoin::PublicKey>,
+
+    at: NaiveDateTime,
+}
+
+impl From<QueryableAcceptedSwap> for AcceptedSwap {
+    fn from(record: QueryableAcceptedSwap) -> Self {
+        (
+            Request {
+                swap_id: *record.swap_id,
+                alpha_ledger: Ethereum {
+                    chain_id: record.ethereum_chain_id.0.into(),
+                },
+                beta_ledger: Lightning::new(record.lightning_network.0.into()),
+                alpha_asset: record.ether_amount.0.into(),
+                beta_asset: record.bitcoin_amount.0.into(),
+                hash_function: *record.hash_function,
+                alpha_ledger_refund_identity: record.ethereum_refund_identity.0.into(),
+                beta_ledger_redeem_identity: record.lightning_redeem_identity.0.into(),
+                alpha_expiry: record.ethereum_expiry.into(),
+                beta_cltv_expiry: record.lightning_cltv_expiry.into(),
+                secret_hash: *record.secret_hash,
+            },
+            Accept {
+                swap_id: *record.swap_id,
+                alpha_ledger_redeem_identity: record.ethereum_redeem_identity.0.into(),
+                beta_ledger_refund_identity: record.lightning_refund_identity.0.into(),
+            },
+            record.at,
+        )
+    }
+}
+
+#[async_trait]
+impl LoadHanHalightSwaps for Sqlite {
+    async fn han_halight_swap(&self, key: &SwapId) -> anyhow::Result<Swap> {
+        let record: QueryableSwap = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                han_halight_swaps::table
+                    .filter(han_halight_swaps::swap_id.eq(key))
+                    .first(connection)
+                    .optional()
+            })
+            .await?
+            .ok_or(Error::SwapNotFound)?;
+
+        Ok(Swap {
+            swap_id: *record.swap_id,
+            role: *record.role,
+            counterparty: (*record.counterparty).clone(),
+        })
+    }
+
+    async fn han_halight_swaps(&self) -> anyhow::Result<Vec<Swap>> {
+        let records: Vec<QueryableSwap> = self
+            .do_in_transaction(|connection| han_halight_swaps::table.load(&*connection))
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Swap {
+                swap_id: *record.swap_id,
+                role: *record.role,
+                counterparty: (*record.counterparty).clone(),
+            })
+            .collect())
+    }
+
+    async fn load_han_halight_request(&self, key: &SwapId) -> anyhow::Result<Request> {
+        use han_ethereum_halight_bitcoin_request_messages as request_messages;
+
+        let record: QueryableRequest = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                request_messages::table
+                    .select((
+                        request_messages::swap_id,
+                        request_messages::ethereum_chain_id,
+                        request_messages::lightning_network,
+                        request_messages::ether_amount,
+                        request_messages::bitcoin_amount,
+                        request_messages::hash_function,
+                        request_messages::ethereum_refund_identity,
+                        request_messages::lightning_redeem_identity,
+                        request_messages::ethereum_expiry,
+                        request_messages::lightning_cltv_expiry,
+                        request_messages::secret_hash,
+                    ))
+                    .filter(request_messages::swap_id.eq(key))
+                    .first(connection)
+                    .optional()
+            })
+            .await?
+            .ok_or(Error::SwapNotFound)?;
+
+        Ok(record.into())
+    }
+
+    async fn load_han_halight_accepted_swap(&self, key: &SwapId) -> anyhow::Result<AcceptedSwap> {
+        use han_ethereum_halight_bitcoin_accept_messages as accept_messages;
+        use han_ethereum_halight_bitcoin_request_messages as request_messages;
+
+        let record: QueryableAcceptedSwap = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                request_messages::table
+                    .inner_join(
+                        accept_messages::table
+                            .on(request_messages::swap_id.eq(accept_messages::swap_id)),
+                    )
+                    .select((
+                        request_messages::swap_id,
+                        request_messages::ethereum_chain_id,
+                        request_messages::lightning_network,
+                        request_messages::ether_amount,
+                        request_messages::bitcoin_amount,
+                        request_messages::hash_function,
+                        request_messages::ethereum_refund_identity,
+                        request_messages::lightning_redeem_identity,
+                        request_messages::ethereum_expiry,
+                        request_messages::lightning_cltv_expiry,
+                        request_messages::secret_hash,
+                        accept_messages::ethereum_redeem_identity,
+                        accept_messages::lightning_refund_identity,
+                        accept_messages::at,
+                    ))
+                    .filter(accept_messages::swap_id.eq(key))
+                    .first(connection)
+                    .optional()
+            })
+            .await?
+            .ok_or(Error::SwapNotFound)?;
+
+        Ok(record.into())
+    }
+}
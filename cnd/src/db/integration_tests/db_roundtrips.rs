@@ -1,17 +1,35 @@
 use crate::{
-    asset::{Bitcoin as BitcoinAsset, Erc20, Ether},
+    asset::{ethereum::FromWei, Bitcoin as BitcoinAsset, Erc20, Ether},
     db::{
         load_swaps::LoadAcceptedSwap,
         swap_types::{DetermineTypes, SwapTypes},
-        AssetKind, BitcoinLedgerKind, LedgerKind, Retrieve, Save, Sqlite, Swap,
+        AssetKind, BitcoinLedgerKind, EnableAutopilot, HanHalightSwap, LedgerKind, LedgerSide,
+        ListedSwap, LoadAutomaticActions, LoadAutopilot, LoadBitcoinWallet, LoadDecline,
+        LoadHanHalightSwaps, LoadLedgerEvents, LoadListedSwaps, LoadRequest, PageRequest, Retrieve,
+        RollBackBitcoinWalletBlock, RollBackLedgerEvents, Save, SaveAutomaticAction,
+        SaveBitcoinWalletBlock, SaveLedgerEvent, Sqlite, Swap, SwapFilter, SwapPage,
+        UpdateSwapStatus, Utxo,
     },
+    ethereum, htlc_location,
+    http_api::SwapStatus,
     identity,
     quickcheck::Quickcheck,
     swap_protocols::{
+        han_halight,
         ledger::Ethereum,
-        rfc003::{Accept, Request},
+        rfc003::{
+            actions::ActionKind,
+            create_swap::{SwapEvent, SwapEventOnLedger},
+            events::{Deployed, Funded, Redeemed, Refunded},
+            ledger_state::HtlcState,
+            Accept, Decline, Request, Secret,
+        },
+        Role, SwapId,
     },
+    transaction,
 };
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 use std::path::Path;
 
 use crate::swap_protocols::ledger::bitcoin::{Mainnet, Regtest, Testnet};
@@ -48,10 +66,11 @@ macro_rules! db_roundtrip_test {
                         ..*accept
                     };
 
-                    let (loaded_swap, loaded_request, loaded_accept, loaded_swap_types) =
+                    let (loaded_swap, loaded_proposed_request, loaded_request, loaded_accept, loaded_swap_types) =
                     tokio::runtime::Runtime::new()?.block_on(async {
                         db.save(saved_swap.clone()).await?;
                         db.save(saved_request.clone()).await?;
+                        let loaded_proposed_request = db.load_request(&swap_id).await?;
                         db.save(saved_accept.clone()).await?;
 
                         let loaded_swap = Retrieve::get(&db, &swap_id).await?;
@@ -59,11 +78,12 @@ macro_rules! db_roundtrip_test {
                         let (loaded_request, loaded_accept, _at) = db.load_accepted_swap(&swap_id).await?;
                         let loaded_swap_types = db.determine_types(&swap_id).await?;
 
-                        anyhow::Result::<_>::Ok((loaded_swap, loaded_request, loaded_accept, loaded_swap_types))
+                        anyhow::Result::<_>::Ok((loaded_swap, loaded_proposed_request, loaded_request, loaded_accept, loaded_swap_types))
                     })?;
 
                     Ok(
-                        saved_request == loaded_request &&
+                        saved_request == loaded_proposed_request &&
+                            saved_request == loaded_request &&
                             saved_accept == loaded_accept &&
                             saved_swap == loaded_swap &&
                             expected_swap_types == loaded_swap_types
@@ -189,3 +209,441 @@ db_roundtrip_test!(
         }
     }
 );
+
+#[test]
+fn roundtrip_test_han_ethereum_ether_halight_lightning_bitcoin() {
+    fn prop(
+        swap: Quickcheck<Swap>,
+        request: Quickcheck<han_halight::Request>,
+        accept: Quickcheck<han_halight::Accept>,
+    ) -> anyhow::Result<bool> {
+        let Swap {
+            swap_id,
+            role,
+            counterparty,
+        } = swap.0;
+
+        let db = Sqlite::new(&Path::new(":memory:"))?;
+
+        let saved_swap = Swap {
+            swap_id,
+            role,
+            counterparty,
+        };
+        let saved_request = han_halight::Request {
+            swap_id,
+            ..(*request).clone()
+        };
+        let saved_accept = han_halight::Accept { swap_id, ..*accept };
+
+        let (loaded_swaps, proposed_request, loaded_request, loaded_accept) =
+            tokio::runtime::Runtime::new()?.block_on(async {
+                db.save(HanHalightSwap(saved_swap.clone())).await?;
+                db.save(saved_request.clone()).await?;
+                let proposed_request = db.load_han_halight_request(&swap_id).await?;
+                db.save(saved_accept).await?;
+
+                let loaded_swaps = db.han_halight_swaps().await?;
+                let (loaded_request, loaded_accept, _at) =
+                    db.load_han_halight_accepted_swap(&swap_id).await?;
+
+                anyhow::Result::<_>::Ok((
+                    loaded_swaps,
+                    proposed_request,
+                    loaded_request,
+                    loaded_accept,
+                ))
+            })?;
+
+        Ok(saved_request == proposed_request
+            && saved_request == loaded_request
+            && saved_accept == loaded_accept
+            && loaded_swaps == vec![saved_swap])
+    }
+
+    quickcheck::quickcheck(
+        prop as fn(
+            Quickcheck<Swap>,
+            Quickcheck<han_halight::Request>,
+            Quickcheck<han_halight::Accept>,
+        ) -> anyhow::Result<bool>,
+    );
+}
+
+#[test]
+fn roundtrip_test_decline() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let declined_swap = SwapId::default();
+    let pending_swap = SwapId::default();
+
+    let (loaded_decline, loaded_pending) = tokio::runtime::Runtime::new()?.block_on(async {
+        db.save(Decline {
+            swap_id: declined_swap,
+            reason: None,
+        })
+        .await?;
+
+        let loaded_decline = db.load_decline(&declined_swap).await?;
+        let loaded_pending = db.load_decline(&pending_swap).await?;
+
+        anyhow::Result::<_>::Ok((loaded_decline, loaded_pending))
+    })?;
+
+    assert_eq!(
+        loaded_decline,
+        Some(Decline {
+            swap_id: declined_swap,
+            reason: None
+        })
+    );
+    assert_eq!(loaded_pending, None);
+
+    Ok(())
+}
+
+#[test]
+fn roundtrip_test_automatic_actions() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let swap_id = SwapId::default();
+    let other_swap_id = SwapId::default();
+
+    let (loaded, loaded_other) = tokio::runtime::Runtime::new()?.block_on(async {
+        db.save_automatic_action(
+            swap_id,
+            LedgerSide::Alpha,
+            ActionKind::Refund,
+            "f00".to_owned(),
+        )
+        .await?;
+
+        let loaded = db.load_automatic_actions(&swap_id).await?;
+        let loaded_other = db.load_automatic_actions(&other_swap_id).await?;
+
+        anyhow::Result::<_>::Ok((loaded, loaded_other))
+    })?;
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].ledger, LedgerSide::Alpha);
+    assert_eq!(loaded[0].action, ActionKind::Refund);
+    assert_eq!(loaded[0].transaction_hash, "f00");
+    assert!(loaded_other.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn roundtrip_test_autopilot() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let swap_id = SwapId::default();
+    let other_swap_id = SwapId::default();
+
+    let (enabled, enabled_other) = tokio::runtime::Runtime::new()?.block_on(async {
+        db.enable_autopilot(swap_id).await?;
+        db.enable_autopilot(swap_id).await?;
+
+        let enabled = db.autopilot_enabled(&swap_id).await?;
+        let enabled_other = db.autopilot_enabled(&other_swap_id).await?;
+
+        anyhow::Result::<_>::Ok((enabled, enabled_other))
+    })?;
+
+    assert!(enabled);
+    assert!(!enabled_other);
+
+    Ok(())
+}
+
+#[test]
+fn roundtrip_test_bitcoin_wallet() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+
+    let first_block = "0000000000000000000000000000000000000000000000000000000000000001"
+        .parse::<bitcoin::BlockHash>()?;
+    let second_block = "0000000000000000000000000000000000000000000000000000000000000002"
+        .parse::<bitcoin::BlockHash>()?;
+    let txid = "0000000000000000000000000000000000000000000000000000000000000003"
+        .parse::<bitcoin::Txid>()?;
+    let spent = Utxo {
+        outpoint: bitcoin::OutPoint { txid, vout: 0 },
+        amount: BitcoinAsset::from_sat(100_000),
+    };
+    let unspent = Utxo {
+        outpoint: bitcoin::OutPoint { txid, vout: 1 },
+        amount: BitcoinAsset::from_sat(200_000),
+    };
+
+    let (empty_blocks, blocks, utxos, rolled_back_blocks, rolled_back_utxos) =
+        tokio::runtime::Runtime::new()?.block_on(async {
+            let empty_blocks = db.load_bitcoin_wallet_blocks(10).await?;
+
+            db.save_bitcoin_wallet_block(first_block, vec![], vec![spent, unspent])
+                .await?;
+            db.save_bitcoin_wallet_block(second_block, vec![spent.outpoint], vec![])
+                .await?;
+
+            let blocks = db.load_bitcoin_wallet_blocks(10).await?;
+            let utxos = db.load_bitcoin_wallet_utxos().await?;
+
+            db.roll_back_bitcoin_wallet_block(second_block).await?;
+
+            let rolled_back_blocks = db.load_bitcoin_wallet_blocks(10).await?;
+            let rolled_back_utxos = db.load_bitcoin_wallet_utxos().await?;
+
+            anyhow::Result::<_>::Ok((
+                empty_blocks,
+                blocks,
+                utxos,
+                rolled_back_blocks,
+                rolled_back_utxos,
+            ))
+        })?;
+
+    assert_eq!(empty_blocks, vec![]);
+    assert_eq!(blocks, vec![first_block, second_block]);
+    assert_eq!(utxos, vec![unspent]);
+    assert_eq!(rolled_back_blocks, vec![first_block]);
+    assert_eq!(rolled_back_utxos, vec![spent, unspent]);
+
+    Ok(())
+}
+
+#[test]
+fn roundtrip_test_rfc003_ledger_events() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let swap_id = SwapId::default();
+
+    let bitcoin_transaction = transaction::Bitcoin {
+        version: 1,
+        lock_time: 0,
+        input: vec![],
+        output: vec![],
+    };
+    let ethereum_transaction = ethereum::Transaction::default();
+
+    let saved_events: Vec<SwapEventOnLedger<Regtest, Ethereum, BitcoinAsset, Ether>> = vec![
+        SwapEvent::AlphaDeployed(Deployed {
+            transaction: bitcoin_transaction.clone(),
+            location: htlc_location::Bitcoin::null(),
+        }),
+        SwapEvent::BetaDeployed(Deployed {
+            transaction: ethereum_transaction.clone(),
+            location: htlc_location::Ethereum::default(),
+        }),
+        SwapEvent::AlphaFunded(Funded {
+            transaction: bitcoin_transaction.clone(),
+            asset: BitcoinAsset::from_sat(100_000_000),
+            confirmations: Some(6),
+        }),
+        SwapEvent::BetaFunded(Funded {
+            transaction: ethereum_transaction.clone(),
+            asset: Ether::from_wei(10_000_000_000_000_000_000u64),
+            confirmations: None,
+        }),
+        SwapEvent::BetaRedeemed(Redeemed {
+            transaction: ethereum_transaction,
+            secret: Secret::from(*b"hello world, you are beautiful!!"),
+        }),
+        SwapEvent::AlphaRefunded(Refunded {
+            transaction: bitcoin_transaction,
+        }),
+    ];
+
+    let loaded_events = tokio::runtime::Runtime::new()?.block_on(async {
+        for event in saved_events.clone() {
+            db.save_ledger_event(swap_id, event).await?;
+        }
+
+        LoadLedgerEvents::<Regtest, Ethereum, BitcoinAsset, Ether>::load_ledger_events(
+            &db, &swap_id,
+        )
+        .await
+    })?;
+
+    assert_eq!(loaded_events, saved_events);
+
+    Ok(())
+}
+
+#[test]
+fn roll_back_rfc003_ledger_events_removes_later_events_of_one_ledger() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let swap_id = SwapId::default();
+
+    let bitcoin_transaction = transaction::Bitcoin {
+        version: 1,
+        lock_time: 0,
+        input: vec![],
+        output: vec![],
+    };
+    let ethereum_transaction = ethereum::Transaction::default();
+
+    let alpha_deployed = SwapEvent::AlphaDeployed(Deployed {
+        transaction: bitcoin_transaction.clone(),
+        location: htlc_location::Bitcoin::null(),
+    });
+    let beta_deployed = SwapEvent::BetaDeployed(Deployed {
+        transaction: ethereum_transaction.clone(),
+        location: htlc_location::Ethereum::default(),
+    });
+    let alpha_funded = SwapEvent::AlphaFunded(Funded {
+        transaction: bitcoin_transaction.clone(),
+        asset: BitcoinAsset::from_sat(100_000_000),
+        confirmations: Some(6),
+    });
+    let beta_funded = SwapEvent::BetaFunded(Funded {
+        transaction: ethereum_transaction,
+        asset: Ether::from_wei(10_000_000_000_000_000_000u64),
+        confirmations: Some(12),
+    });
+    let alpha_refunded = SwapEvent::AlphaRefunded(Refunded {
+        transaction: bitcoin_transaction,
+    });
+
+    let saved_events: Vec<SwapEventOnLedger<Regtest, Ethereum, BitcoinAsset, Ether>> = vec![
+        alpha_deployed.clone(),
+        beta_deployed.clone(),
+        alpha_funded,
+        beta_funded,
+        alpha_refunded,
+    ];
+
+    let loaded_events = tokio::runtime::Runtime::new()?.block_on(async {
+        for event in saved_events {
+            db.save_ledger_event(swap_id, event).await?;
+        }
+
+        db.roll_back_ledger_events(&swap_id, LedgerSide::Alpha, HtlcState::Deployed)
+            .await?;
+        db.roll_back_ledger_events(&swap_id, LedgerSide::Beta, HtlcState::Deployed)
+            .await?;
+
+        LoadLedgerEvents::<Regtest, Ethereum, BitcoinAsset, Ether>::load_ledger_events(
+            &db, &swap_id,
+        )
+        .await
+    })?;
+
+    assert_eq!(loaded_events, vec![alpha_deployed, beta_deployed]);
+
+    Ok(())
+}
+
+#[test]
+fn listed_swaps_are_filtered_and_paged_newest_first() -> anyhow::Result<()> {
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let swap_ids = (0..5).map(|_| SwapId::default()).collect::<Vec<_>>();
+    let swap_types = SwapTypes {
+        alpha_ledger: LedgerKind::Bitcoin(BitcoinLedgerKind::Regtest),
+        beta_ledger: LedgerKind::Ethereum,
+        alpha_asset: AssetKind::Bitcoin,
+        beta_asset: AssetKind::Ether,
+        role: Role::Alice,
+    };
+
+    let (newest, older, newer, done, counts) = tokio::runtime::Runtime::new()?.block_on(async {
+        for swap_id in swap_ids.iter() {
+            let swap = Swap::new(*swap_id, Role::Alice, libp2p::PeerId::random());
+            db.save(ListedSwap::rfc003(swap, swap_types)).await?;
+        }
+        db.update_swap_status(&swap_ids[3], SwapStatus::Swapped)
+            .await?;
+
+        let in_progress = SwapFilter {
+            status: Some(SwapStatus::InProgress),
+            ..SwapFilter::default()
+        };
+        let newest = db
+            .load_swap_page(in_progress.clone(), PageRequest::Newest { limit: 2 })
+            .await?;
+        let older = db
+            .load_swap_page(in_progress.clone(), PageRequest::OlderThan {
+                cursor: newest.older.expect("there are older swaps"),
+                limit: 2,
+            })
+            .await?;
+        let newer = db
+            .load_swap_page(in_progress, PageRequest::NewerThan {
+                cursor: older.newer.expect("there are newer swaps"),
+                limit: 2,
+            })
+            .await?;
+        let done = db.swap_ids_with_status(SwapStatus::Swapped).await?;
+        let counts = db.swap_counts().await?;
+
+        anyhow::Result::<_>::Ok((newest, older, newer, done, counts))
+    })?;
+
+    let ids = |page: &SwapPage| {
+        page.swaps
+            .iter()
+            .map(|swap| swap.swap_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&newest), vec![swap_ids[4], swap_ids[2]]);
+    assert_eq!(newest.newer, None);
+    assert_eq!(ids(&older), vec![swap_ids[1], swap_ids[0]]);
+    assert_eq!(older.older, None);
+    assert_eq!(ids(&newer), ids(&newest));
+    assert_eq!(done, vec![swap_ids[3]]);
+    assert_eq!(counts.len(), 2);
+    assert!(counts.contains(&(SwapStatus::InProgress, Role::Alice, 4)));
+    assert!(counts.contains(&(SwapStatus::Swapped, Role::Alice, 1)));
+
+    Ok(())
+}
+
+#[test]
+fn listed_swaps_without_creation_time_are_left_out_of_time_filters() -> anyhow::Result<()> {
+    use crate::db::{schema::listed_swaps, wrapper_types::custom_sql_types::Text};
+
+    let db = Sqlite::new(&Path::new(":memory:"))?;
+    let (known, unknown) = (SwapId::default(), SwapId::default());
+    let swap_types = SwapTypes {
+        alpha_ledger: LedgerKind::Bitcoin(BitcoinLedgerKind::Regtest),
+        beta_ledger: LedgerKind::Ethereum,
+        alpha_asset: AssetKind::Bitcoin,
+        beta_asset: AssetKind::Ether,
+        role: Role::Alice,
+    };
+
+    let (all, created_after) = tokio::runtime::Runtime::new()?.block_on(async {
+        for swap_id in &[known, unknown] {
+            let swap = Swap::new(*swap_id, Role::Alice, libp2p::PeerId::random());
+            db.save(ListedSwap::rfc003(swap, swap_types)).await?;
+        }
+        // swaps listed by the migration have no creation time if they were
+        // never accepted
+        db.do_in_transaction(|connection| {
+            diesel::update(listed_swaps::table.filter(listed_swaps::swap_id.eq(Text(unknown))))
+                .set(listed_swaps::created_at.eq(None::<NaiveDateTime>))
+                .execute(connection)
+        })
+        .await?;
+
+        let all = db
+            .load_swap_page(SwapFilter::default(), PageRequest::Newest { limit: 10 })
+            .await?;
+        let created_after = db
+            .load_swap_page(
+                SwapFilter {
+                    created_after: Some(NaiveDateTime::from_timestamp(0, 0)),
+                    ..SwapFilter::default()
+                },
+                PageRequest::Newest { limit: 10 },
+            )
+            .await?;
+
+        anyhow::Result::<_>::Ok((all, created_after))
+    })?;
+
+    let ids = |page: &SwapPage| {
+        page.swaps
+            .iter()
+            .map(|swap| swap.swap_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&all), vec![unknown, known]);
+    assert_eq!(ids(&created_after), vec![known]);
+
+    Ok(())
+}
@@ -0,0 +1,476 @@
+use crate::{
+    asset,
+    db::{schema::rfc003_ledger_events, wrapper_types::custom_sql_types::Text, Error, Sqlite},
+    identity,
+    swap_protocols::{
+        rfc003::{
+            create_swap::{SwapEvent, SwapEventOnLedger},
+            events::{Deployed, Funded, Redeemed, Refunded},
+            ledger_state::HtlcState,
+            Ledger, Secret,
+        },
+        SwapId,
+    },
+    transaction,
+};
+use async_trait::async_trait;
+use diesel::{self, prelude::*, RunQueryDsl};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+/// Save the events that happened on the ledgers of an rfc003 swap.
+#[async_trait]
+pub trait SaveLedgerEvent<AL, BL, AA, BA>: Send + Sync + 'static
+where
+    AL: Ledger,
+    BL: Ledger,
+{
+    async fn save_ledger_event(
+        &self,
+        swap_id: SwapId,
+        event: SwapEventOnLedger<AL, BL, AA, BA>,
+    ) -> anyhow::Result<()>;
+}
+
+/// Load the events that happened on the ledgers of an rfc003 swap, in the
+/// order they were saved.
+#[async_trait]
+pub trait LoadLedgerEvents<AL, BL, AA, BA>: Send + Sync + 'static
+where
+    AL: Ledger,
+    BL: Ledger,
+{
+    async fn load_ledger_events(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<SwapEventOnLedger<AL, BL, AA, BA>>>;
+}
+
+/// Remove the events of one ledger of an rfc003 swap that happened after it
+/// reached the given state, e.g. because their transactions were reorganised
+/// out of the chain.
+#[async_trait]
+pub trait RollBackLedgerEvents: Send + Sync + 'static {
+    async fn roll_back_ledger_events(
+        &self,
+        swap_id: &SwapId,
+        ledger: LedgerSide,
+        to: HtlcState,
+    ) -> anyhow::Result<()>;
+}
+
+/// Hash of a transaction as it is stored in the `transaction_hash` column.
+///
+/// The full transaction is stored as well, the hash is only there to make the
+/// table readable for humans.
+pub trait TransactionHash {
+    fn transaction_hash(&self) -> String;
+}
+
+impl TransactionHash for transaction::Bitcoin {
+    fn transaction_hash(&self) -> String {
+        self.txid().to_string()
+    }
+}
+
+impl TransactionHash for transaction::Ethereum {
+    fn transaction_hash(&self) -> String {
+        format!("{:x}", self.hash)
+    }
+}
+
+/// Conversion of an asset from and to its representation in the `asset`
+/// column.
+pub trait AssetColumn: Sized {
+    fn to_column(&self) -> anyhow::Result<String>;
+    fn from_column(column: &str) -> anyhow::Result<Self>;
+}
+
+impl AssetColumn for asset::Bitcoin {
+    fn to_column(&self) -> anyhow::Result<String> {
+        Ok(self.as_sat().to_string())
+    }
+
+    fn from_column(column: &str) -> anyhow::Result<Self> {
+        Ok(asset::Bitcoin::from_sat(u64::from_str(column)?))
+    }
+}
+
+impl AssetColumn for asset::Ether {
+    fn to_column(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    fn from_column(column: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(column)?)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Erc20Column {
+    token_contract: identity::Ethereum,
+    quantity: asset::Erc20Quantity,
+}
+
+impl AssetColumn for asset::Erc20 {
+    fn to_column(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(&Erc20Column {
+            token_contract: self.token_contract,
+            quantity: self.quantity.clone(),
+        })?)
+    }
+
+    fn from_column(column: &str) -> anyhow::Result<Self> {
+        let Erc20Column {
+            token_contract,
+            quantity,
+        } = serde_json::from_str(column)?;
+
+        Ok(asset::Erc20::new(token_contract, quantity))
+    }
+}
+
+/// The ledger of an rfc003 swap something happened on.
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerSide {
+    Alpha,
+    Beta,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+enum EventKind {
+    Deployed,
+    Funded,
+    Redeemed,
+    Refunded,
+}
+
+impl EventKind {
+    /// The events that can only happen after a ledger reached the given state.
+    fn after(state: HtlcState) -> &'static [EventKind] {
+        match state {
+            HtlcState::NotDeployed => &[
+                EventKind::Deployed,
+                EventKind::Funded,
+                EventKind::Redeemed,
+                EventKind::Refunded,
+            ],
+            HtlcState::Deployed => &[EventKind::Funded, EventKind::Redeemed, EventKind::Refunded],
+            HtlcState::Funded | HtlcState::IncorrectlyFunded => {
+                &[EventKind::Redeemed, EventKind::Refunded]
+            }
+            HtlcState::Redeemed | HtlcState::Refunded => &[],
+        }
+    }
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "rfc003_ledger_events"]
+struct InsertableLedgerEvent {
+    swap_id: Text<SwapId>,
+    ledger: Text<LedgerSide>,
+    event: Text<EventKind>,
+    transaction_hash: String,
+    transaction_data: String,
+    htlc_location: Option<String>,
+    asset: Option<String>,
+    secret: Option<String>,
+    confirmations: Option<i32>,
+}
+
+impl InsertableLedgerEvent {
+    fn new<T>(
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        event: EventKind,
+        transaction: &T,
+    ) -> anyhow::Result<Self>
+    where
+        T: Serialize + TransactionHash,
+    {
+        Ok(InsertableLedgerEvent {
+            swap_id: Text(swap_id),
+            ledger: Text(ledger),
+            event: Text(event),
+            transaction_hash: transaction.transaction_hash(),
+            transaction_data: serde_json::to_string(transaction)?,
+            htlc_location: None,
+            asset: None,
+            secret: None,
+            confirmations: None,
+        })
+    }
+
+    fn deployed<T, H>(
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        deployed: Deployed<T, H>,
+    ) -> anyhow::Result<Self>
+    where
+        T: Serialize + TransactionHash,
+        H: Serialize,
+    {
+        Ok(InsertableLedgerEvent {
+            htlc_location: Some(serde_json::to_string(&deployed.location)?),
+            ..Self::new(swap_id, ledger, EventKind::Deployed, &deployed.transaction)?
+        })
+    }
+
+    fn funded<T, A>(
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        funded: Funded<T, A>,
+    ) -> anyhow::Result<Self>
+    where
+        T: Serialize + TransactionHash,
+        A: AssetColumn,
+    {
+        Ok(InsertableLedgerEvent {
+            asset: Some(funded.asset.to_column()?),
+            confirmations: funded
+                .confirmations
+                .map(|confirmations| confirmations as i32),
+            ..Self::new(swap_id, ledger, EventKind::Funded, &funded.transaction)?
+        })
+    }
+
+    fn redeemed<T>(
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        redeemed: Redeemed<T>,
+    ) -> anyhow::Result<Self>
+    where
+        T: Serialize + TransactionHash,
+    {
+        Ok(InsertableLedgerEvent {
+            secret: Some(format!("{:x}", redeemed.secret)),
+            ..Self::new(swap_id, ledger, EventKind::Redeemed, &redeemed.transaction)?
+        })
+    }
+
+    fn refunded<T>(
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        refunded: Refunded<T>,
+    ) -> anyhow::Result<Self>
+    where
+        T: Serialize + TransactionHash,
+    {
+        Self::new(swap_id, ledger, EventKind::Refunded, &refunded.transaction)
+    }
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableLedgerEvent {
+    _id: i32,
+    _swap_id: Text<SwapId>,
+    ledger: Text<LedgerSide>,
+    event: Text<EventKind>,
+    _transaction_hash: String,
+    transaction_data: String,
+    htlc_location: Option<String>,
+    asset: Option<String>,
+    secret: Option<String>,
+    confirmations: Option<i32>,
+}
+
+impl QueryableLedgerEvent {
+    fn transaction<T>(&self) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_str(&self.transaction_data)?)
+    }
+
+    fn deployed<T, H>(&self) -> anyhow::Result<Deployed<T, H>>
+    where
+        T: DeserializeOwned,
+        H: DeserializeOwned,
+    {
+        let location = self
+            .htlc_location
+            .as_ref()
+            .ok_or(Error::IncompleteLedgerEvent("htlc_location"))?;
+
+        Ok(Deployed {
+            transaction: self.transaction()?,
+            location: serde_json::from_str(location)?,
+        })
+    }
+
+    fn funded<T, A>(&self) -> anyhow::Result<Funded<T, A>>
+    where
+        T: DeserializeOwned,
+        A: AssetColumn,
+    {
+        let asset = self
+            .asset
+            .as_ref()
+            .ok_or(Error::IncompleteLedgerEvent("asset"))?;
+
+        Ok(Funded {
+            transaction: self.transaction()?,
+            asset: A::from_column(asset)?,
+            confirmations: self.confirmations.map(|confirmations| confirmations as u32),
+        })
+    }
+
+    fn redeemed<T>(&self) -> anyhow::Result<Redeemed<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let secret = self
+            .secret
+            .as_ref()
+            .ok_or(Error::IncompleteLedgerEvent("secret"))?;
+
+        Ok(Redeemed {
+            transaction: self.transaction()?,
+            secret: Secret::from_str(secret)?,
+        })
+    }
+
+    fn refunded<T>(&self) -> anyhow::Result<Refunded<T>>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(Refunded {
+            transaction: self.transaction()?,
+        })
+    }
+
+    fn into_swap_event<AL, BL, AA, BA>(self) -> anyhow::Result<SwapEventOnLedger<AL, BL, AA, BA>>
+    where
+        AL: Ledger,
+        BL: Ledger,
+        AA: AssetColumn,
+        BA: AssetColumn,
+    {
+        let event = match (*self.ledger, *self.event) {
+            (LedgerSide::Alpha, EventKind::Deployed) => SwapEvent::AlphaDeployed(self.deployed()?),
+            (LedgerSide::Alpha, EventKind::Funded) => SwapEvent::AlphaFunded(self.funded()?),
+            (LedgerSide::Alpha, EventKind::Redeemed) => SwapEvent::AlphaRedeemed(self.redeemed()?),
+            (LedgerSide::Alpha, EventKind::Refunded) => SwapEvent::AlphaRefunded(self.refunded()?),
+            (LedgerSide::Beta, EventKind::Deployed) => SwapEvent::BetaDeployed(self.deployed()?),
+            (LedgerSide::Beta, EventKind::Funded) => SwapEvent::BetaFunded(self.funded()?),
+            (LedgerSide::Beta, EventKind::Redeemed) => SwapEvent::BetaRedeemed(self.redeemed()?),
+            (LedgerSide::Beta, EventKind::Refunded) => SwapEvent::BetaRefunded(self.refunded()?),
+        };
+
+        Ok(event)
+    }
+}
+
+#[async_trait]
+impl<AL, BL, AA, BA> SaveLedgerEvent<AL, BL, AA, BA> for Sqlite
+where
+    AL: Ledger,
+    BL: Ledger,
+    AL::Transaction: TransactionHash,
+    BL::Transaction: TransactionHash,
+    AA: AssetColumn + Send + 'static,
+    BA: AssetColumn + Send + 'static,
+{
+    async fn save_ledger_event(
+        &self,
+        swap_id: SwapId,
+        event: SwapEventOnLedger<AL, BL, AA, BA>,
+    ) -> anyhow::Result<()> {
+        let insertable = match event {
+            SwapEvent::AlphaDeployed(deployed) => {
+                InsertableLedgerEvent::deployed(swap_id, LedgerSide::Alpha, deployed)?
+            }
+            SwapEvent::AlphaFunded(funded) => {
+                InsertableLedgerEvent::funded(swap_id, LedgerSide::Alpha, funded)?
+            }
+            SwapEvent::AlphaRedeemed(redeemed) => {
+                InsertableLedgerEvent::redeemed(swap_id, LedgerSide::Alpha, redeemed)?
+            }
+            SwapEvent::AlphaRefunded(refunded) => {
+                InsertableLedgerEvent::refunded(swap_id, LedgerSide::Alpha, refunded)?
+            }
+            SwapEvent::BetaDeployed(deployed) => {
+                InsertableLedgerEvent::deployed(swap_id, LedgerSide::Beta, deployed)?
+            }
+            SwapEvent::BetaFunded(funded) => {
+                InsertableLedgerEvent::funded(swap_id, LedgerSide::Beta, funded)?
+            }
+            SwapEvent::BetaRedeemed(redeemed) => {
+                InsertableLedgerEvent::redeemed(swap_id, LedgerSide::Beta, redeemed)?
+            }
+            SwapEvent::BetaRefunded(refunded) => {
+                InsertableLedgerEvent::refunded(swap_id, LedgerSide::Beta, refunded)?
+            }
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(rfc003_ledger_events::table)
+                .values(&insertable)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<AL, BL, AA, BA> LoadLedgerEvents<AL, BL, AA, BA> for Sqlite
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: AssetColumn + Send + 'static,
+    BA: AssetColumn + Send + 'static,
+{
+    async fn load_ledger_events(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<SwapEventOnLedger<AL, BL, AA, BA>>> {
+        let records: Vec<QueryableLedgerEvent> = self
+            .do_in_transaction(|connection| {
+                let key = Text(swap_id);
+
+                rfc003_ledger_events::table
+                    .filter(rfc003_ledger_events::swap_id.eq(key))
+                    .order(rfc003_ledger_events::id.asc())
+                    .load(connection)
+            })
+            .await?;
+
+        records
+            .into_iter()
+            .map(QueryableLedgerEvent::into_swap_event)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl RollBackLedgerEvents for Sqlite {
+    async fn roll_back_ledger_events(
+        &self,
+        swap_id: &SwapId,
+        ledger: LedgerSide,
+        to: HtlcState,
+    ) -> anyhow::Result<()> {
+        let events = EventKind::after(to)
+            .iter()
+            .copied()
+            .map(Text)
+            .collect::<Vec<_>>();
+
+        self.do_in_transaction(|connection| {
+            diesel::delete(
+                rfc003_ledger_events::table
+                    .filter(rfc003_ledger_events::swap_id.eq(Text(swap_id)))
+                    .filter(rfc003_ledger_events::ledger.eq(Text(ledger)))
+                    .filter(rfc003_ledger_events::event.eq_any(events.clone())),
+            )
+            .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+use crate::{
+    db::{
+        schema::listed_swaps, wrapper_types::custom_sql_types::Text, AssetKind, LedgerKind, Save,
+        Sqlite, Swap, SwapTypes,
+    },
+    http_api::SwapStatus,
+    swap_protocols::{Role, SwapId},
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::{self, prelude::*, RunQueryDsl};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+/// A swap as it appears in the list of all swaps.
+///
+/// Swaps are listed in the order they were created in, their status is
+/// updated as they progress.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListedSwap {
+    pub swap_id: SwapId,
+    pub protocol: ProtocolName,
+    pub role: Role,
+    pub counterparty: PeerId,
+    pub alpha_ledger: LedgerName,
+    pub beta_ledger: LedgerName,
+    pub alpha_asset: AssetName,
+    pub beta_asset: AssetName,
+}
+
+impl ListedSwap {
+    pub fn rfc003(swap: Swap, types: SwapTypes) -> Self {
+        ListedSwap {
+            swap_id: swap.swap_id,
+            protocol: ProtocolName::Rfc003,
+            role: swap.role,
+            counterparty: swap.counterparty,
+            alpha_ledger: types.alpha_ledger.into(),
+            beta_ledger: types.beta_ledger.into(),
+            alpha_asset: types.alpha_asset.into(),
+            beta_asset: types.beta_asset.into(),
+        }
+    }
+
+    /// han-halight swaps always exchange ether on Ethereum for bitcoin on
+    /// Lightning.
+    pub fn han_halight(swap: Swap) -> Self {
+        ListedSwap {
+            swap_id: swap.swap_id,
+            protocol: ProtocolName::HanHalight,
+            role: swap.role,
+            counterparty: swap.counterparty,
+            alpha_ledger: LedgerName::Ethereum,
+            beta_ledger: LedgerName::Lightning,
+            alpha_asset: AssetName::Ether,
+            beta_asset: AssetName::Bitcoin,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Display, EnumString, Deserialize, Serialize, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolName {
+    Rfc003,
+    HanHalight,
+}
+
+#[derive(Clone, Copy, Debug, Display, EnumString, Deserialize, Serialize, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerName {
+    Bitcoin,
+    Ethereum,
+    Lightning,
+}
+
+impl From<LedgerKind> for LedgerName {
+    fn from(ledger: LedgerKind) -> Self {
+        match ledger {
+            LedgerKind::Bitcoin(_) => LedgerName::Bitcoin,
+            LedgerKind::Ethereum => LedgerName::Ethereum,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Display, EnumString, Deserialize, Serialize, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AssetName {
+    Bitcoin,
+    Ether,
+    Erc20,
+}
+
+impl From<AssetKind> for AssetName {
+    fn from(asset: AssetKind) -> Self {
+        match asset {
+            AssetKind::Bitcoin => AssetName::Bitcoin,
+            AssetKind::Ether => AssetName::Ether,
+            AssetKind::Erc20 => AssetName::Erc20,
+        }
+    }
+}
+
+/// Restricts the listed swaps to those that match all of the given criteria.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapFilter {
+    pub status: Option<SwapStatus>,
+    pub role: Option<Role>,
+    pub counterparty: Option<PeerId>,
+    pub alpha_ledger: Option<LedgerName>,
+    pub beta_ledger: Option<LedgerName>,
+    pub alpha_asset: Option<AssetName>,
+    pub beta_asset: Option<AssetName>,
+    /// Swaps without a known creation time never match a time range.
+    pub created_after: Option<NaiveDateTime>,
+    pub created_before: Option<NaiveDateTime>,
+}
+
+/// Which page of the listed swaps to load, the newest swaps come first.
+///
+/// Pages are delimited by cursors instead of offsets so that swaps created in
+/// the meantime do not shift the pages.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PageRequest {
+    Newest { limit: u32 },
+    OlderThan { cursor: i32, limit: u32 },
+    NewerThan { cursor: i32, limit: u32 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapPage {
+    pub swaps: Vec<ListedSwap>,
+    /// Loads the page with the swaps that were created before this one.
+    pub older: Option<i32>,
+    /// Loads the page with the swaps that were created after this one.
+    pub newer: Option<i32>,
+}
+
+#[async_trait]
+pub trait LoadListedSwaps: Send + Sync + 'static {
+    async fn listed_swap(&self, swap_id: &SwapId) -> anyhow::Result<ListedSwap>;
+    async fn load_swap_page(
+        &self,
+        filter: SwapFilter,
+        page: PageRequest,
+    ) -> anyhow::Result<SwapPage>;
+    /// Returns the ids of all swaps that are listed with the given status.
+    async fn swap_ids_with_status(&self, status: SwapStatus) -> anyhow::Result<Vec<SwapId>>;
+    /// Counts the listed swaps by status and role, combinations without swaps
+    /// are left out.
+    async fn swap_counts(&self) -> anyhow::Result<Vec<(SwapStatus, Role, i64)>>;
+}
+
+#[async_trait]
+pub trait UpdateSwapStatus: Send + Sync + 'static {
+    async fn update_swap_status(&self, swap_id: &SwapId, status: SwapStatus) -> anyhow::Result<()>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "listed_swaps"]
+struct InsertableListedSwap {
+    swap_id: Text<SwapId>,
+    protocol: Text<ProtocolName>,
+    role: Text<Role>,
+    counterparty: Text<PeerId>,
+    alpha_ledger: Text<LedgerName>,
+    beta_ledger: Text<LedgerName>,
+    alpha_asset: Text<AssetName>,
+    beta_asset: Text<AssetName>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableListedSwap {
+    id: i32,
+    swap_id: Text<SwapId>,
+    protocol: Text<ProtocolName>,
+    role: Text<Role>,
+    counterparty: Text<PeerId>,
+    alpha_ledger: Text<LedgerName>,
+    beta_ledger: Text<LedgerName>,
+    alpha_asset: Text<AssetName>,
+    beta_asset: Text<AssetName>,
+    _status: Text<SwapStatus>,
+    _created_at: Option<NaiveDateTime>,
+}
+
+impl From<QueryableListedSwap> for ListedSwap {
+    fn from(record: QueryableListedSwap) -> Self {
+        ListedSwap {
+            swap_id: *record.swap_id,
+            protocol: *record.protocol,
+            role: *record.role,
+            counterparty: (*record.counterparty).clone(),
+            alpha_ledger: *record.alpha_ledger,
+            beta_ledger: *record.beta_ledger,
+            alpha_asset: *record.alpha_asset,
+            beta_asset: *record.beta_asset,
+        }
+    }
+}
+
+#[async_trait]
+impl Save<ListedSwap> for Sqlite {
+    async fn save(&self, swap: ListedSwap) -> anyhow::Result<()> {
+        let insertable = InsertableListedSwap {
+            swap_id: Text(swap.swap_id),
+            protocol: Text(swap.protocol),
+            role: Text(swap.role),
+            counterparty: Text(swap.counterparty),
+            alpha_ledger: Text(swap.alpha_ledger),
+            beta_ledger: Text(swap.beta_ledger),
+            alpha_asset: Text(swap.alpha_asset),
+            beta_asset: Text(swap.beta_asset),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(listed_swaps::table)
+                .values(&insertable)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UpdateSwapStatus for Sqlite {
+    async fn update_swap_status(&self, swap_id: &SwapId, status: SwapStatus) -> anyhow::Result<()> {
+        self.do_in_transaction(|connection| {
+            diesel::update(listed_swaps::table.filter(listed_swaps::swap_id.eq(Text(swap_id))))
+                .set(listed_swaps::status.eq(Text(status)))
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoadListedSwaps for Sqlite {
+    async fn listed_swap(&self, swap_id: &SwapId) -> anyhow::Result<ListedSwap> {
+        let record: QueryableListedSwap = self
+            .do_in_transaction(|connection| {
+                listed_swaps::table
+                    .filter(listed_swaps::swap_id.eq(Text(swap_id)))
+                    .first(connection)
+            })
+            .await?;
+
+        Ok(ListedSwap::from(record))
+    }
+
+    async fn load_swap_page(
+        &self,
+        filter: SwapFilter,
+        page: PageRequest,
+    ) -> anyhow::Result<SwapPage> {
+        let (limit, newest_first) = match page {
+            PageRequest::Newest { limit } | PageRequest::OlderThan { limit, .. } => (limit, true),
+            PageRequest::NewerThan { limit, .. } => (limit, false),
+        };
+
+        let mut records: Vec<QueryableListedSwap> = self
+            .do_in_transaction(|connection| {
+                let mut query = filtered(&filter);
+
+                query = match page {
+                    PageRequest::Newest { .. } => query.order(listed_swaps::id.desc()),
+                    PageRequest::OlderThan { cursor, .. } => query
+                        .filter(listed_swaps::id.lt(cursor))
+                        .order(listed_swaps::id.desc()),
+                    PageRequest::NewerThan { cursor, .. } => query
+                        .filter(listed_swaps::id.gt(cursor))
+                        .order(listed_swaps::id.asc()),
+                };
+
+                // One more than requested tells us whether there is another page.
+                query.limit(i64::from(limit) + 1).load(connection)
+            })
+            .await?;
+
+        let more = records.len() > limit as usize;
+        records.truncate(limit as usize);
+        if !newest_first {
+            records.reverse();
+        }
+
+        let first = records.first().map(|record| record.id);
+        let last = records.last().map(|record| record.id);
+        let (older, newer) = match page {
+            PageRequest::Newest { .. } => (last.filter(|_| more), None),
+            PageRequest::OlderThan { .. } => (last.filter(|_| more), first),
+            PageRequest::NewerThan { .. } => (last, first.filter(|_| more)),
+        };
+
+        Ok(SwapPage {
+            swaps: records.into_iter().map(ListedSwap::from).collect(),
+            older,
+            newer,
+        })
+    }
+
+    async fn swap_ids_with_status(&self, status: SwapStatus) -> anyhow::Result<Vec<SwapId>> {
+        let records: Vec<Text<SwapId>> = self
+            .do_in_transaction(|connection| {
+                listed_swaps::table
+                    .filter(listed_swaps::status.eq(Text(status)))
+                    .select(listed_swaps::swap_id)
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(|swap_id| *swap_id).collect())
+    }
+
+    async fn swap_counts(&self) -> anyhow::Result<Vec<(SwapStatus, Role, i64)>> {
+        let records: Vec<(Text<SwapStatus>, Text<Role>, i64)> = self
+            .do_in_transaction(|connection| {
+                listed_swaps::table
+                    .group_by((listed_swaps::status, listed_swaps::role))
+                    .select((
+                        listed_swaps::status,
+                        listed_swaps::role,
+                        diesel::dsl::count_star(),
+                    ))
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(status, role, count)| (*status, *role, count))
+            .collect())
+    }
+}
+
+fn filtered(filter: &SwapFilter) -> listed_swaps::BoxedQuery<'static, diesel::sqlite::Sqlite> {
+    let mut query = listed_swaps::table.into_boxed();
+
+    if let Some(status) = filter.status {
+        query = query.filter(listed_swaps::status.eq(Text(status)));
+    }
+    if let Some(role) = filter.role {
+        query = query.filter(listed_swaps::role.eq(Text(role)));
+    }
+    if let Some(counterparty) = &filter.counterparty {
+        query = query.filter(listed_swaps::counterparty.eq(Text(counterparty.clone())));
+    }
+    if let Some(alpha_ledger) = filter.alpha_ledger {
+        query = query.filter(listed_swaps::alpha_ledger.eq(Text(alpha_ledger)));
+    }
+    if let Some(beta_ledger) = filter.beta_ledger {
+        query = query.filter(listed_swaps::beta_ledger.eq(Text(beta_ledger)));
+    }
+    if let Some(alpha_asset) = filter.alpha_asset {
+        query = query.filter(listed_swaps::alpha_asset.eq(Text(alpha_asset)));
+    }
+    if let Some(beta_asset) = filter.beta_asset {
+        query = query.filter(listed_swaps::beta_asset.eq(Text(beta_asset)));
+    }
+    if let Some(created_after) = filter.created_after {
+        query = query.filter(listed_swaps::created_at.gt(created_after));
+    }
+    if let Some(created_before) = filter.created_before {
+        query = query.filter(listed_swaps::created_at.lt(created_before));
+    }
+
+    query
+}
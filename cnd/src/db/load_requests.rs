@@ -0,0 +1,467 @@
+use crate::{
+    asset::{self},
+    db::{
+        schema::{self, rfc003_decline_messages},
+        wrapper_types::{
+            custom_sql_types::{Text, U32},
+            BitcoinNetwork, Erc20Amount, Ether, EthereumAddress, Satoshis,
+        },
+        Sqlite,
+    },
+    identity,
+    swap_protocols::{
+        ledger::{bitcoin, Ethereum},
+        rfc003::{
+            messages::{Decline, Request},
+            SecretHash,
+        },
+        HashFunction, SwapId,
+    },
+};
+use async_trait::async_trait;
+use diesel::{self, prelude::*, RunQueryDsl};
+use impl_template::impl_template;
+
+/// Load the request of a swap, regardless of whether it has been answered.
+#[async_trait]
+pub trait LoadRequest<AL, BL, AA, BA, AI, BI> {
+    async fn load_request(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Request<AL, BL, AA, BA, AI, BI>>;
+}
+
+/// Load the decline message of a swap, if the swap was declined.
+#[async_trait]
+pub trait LoadDecline {
+    async fn load_decline(&self, swap_id: &SwapId) -> anyhow::Result<Option<Decline>>;
+}
+
+#[async_trait]
+impl LoadDecline for Sqlite {
+    async fn load_decline(&self, swap_id: &SwapId) -> anyhow::Result<Option<Decline>> {
+        let record: Option<Text<SwapId>> = self
+            .do_in_transaction(|connection| {
+                let key = Text(swap_id);
+
+                rfc003_decline_messages::table
+                    .filter(rfc003_decline_messages::swap_id.eq(key))
+                    .select(rfc003_decline_messages::swap_id)
+                    .first(connection)
+                    .optional()
+            })
+            .await?;
+
+        // The reason is not persisted, see `Save<Decline>`.
+        Ok(record.map(|swap_id| Decline {
+            swap_id: *swap_id,
+            reason: None,
+        }))
+    }
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct BitcoinEthereumBitcoinEtherRequest {
+    swap_id: Text<SwapId>,
+    bitcoin_network: Text<BitcoinNetwork>,
+    ethereum_chain_id: U32,
+    bitcoin_amount: Text<Satoshis>,
+    ether_amount: Text<Ether>,
+    hash_function: Text<HashFunction>,
+    bitcoin_refund_identity: Text<::bitcoin::PublicKey>,
+    ethereum_redeem_identity: Text<EthereumAddress>,
+    bitcoin_expiry: U32,
+    ethereum_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[impl_template]
+impl From<BitcoinEthereumBitcoinEtherRequest>
+    for Request<
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        Ethereum,
+        asset::Bitcoin,
+        asset::Ether,
+        identity::Bitcoin,
+        identity::Ethereum,
+    >
+{
+    fn from(record: BitcoinEthereumBitcoinEtherRequest) -> Self {
+        Request {
+            swap_id: *record.swap_id,
+            alpha_ledger: __TYPE0__,
+            beta_ledger: Ethereum {
+                chain_id: record.ethereum_chain_id.0.into(),
+            },
+            alpha_asset: record.bitcoin_amount.0.into(),
+            beta_asset: record.ether_amount.0.into(),
+            hash_function: *record.hash_function,
+            alpha_ledger_refund_identity: record.bitcoin_refund_identity.0.into(),
+            beta_ledger_redeem_identity: record.ethereum_redeem_identity.0.into(),
+            alpha_expiry: record.bitcoin_expiry.0.into(),
+            beta_expiry: record.ethereum_expiry.0.into(),
+            secret_hash: *record.secret_hash,
+        }
+    }
+}
+
+#[impl_template]
+#[async_trait]
+impl
+    LoadRequest<
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        Ethereum,
+        asset::Bitcoin,
+        asset::Ether,
+        identity::Bitcoin,
+        identity::Ethereum,
+    > for Sqlite
+{
+    async fn load_request(
+        &self,
+        key: &SwapId,
+    ) -> anyhow::Result<
+        Request<
+            __TYPE0__,
+            Ethereum,
+            asset::Bitcoin,
+            asset::Ether,
+            identity::Bitcoin,
+            identity::Ethereum,
+        >,
+    > {
+        use schema::rfc003_bitcoin_ethereum_bitcoin_ether_request_messages as request_messages;
+
+        let record: BitcoinEthereumBitcoinEtherRequest = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                request_messages::table
+                    .select((
+                        request_messages::swap_id,
+                        request_messages::bitcoin_network,
+                        request_messages::ethereum_chain_id,
+                        request_messages::bitcoin_amount,
+                        request_messages::ether_amount,
+                        request_messages::hash_function,
+                        request_messages::bitcoin_refund_identity,
+                        request_messages::ethereum_redeem_identity,
+                        request_messages::bitcoin_expiry,
+                        request_messages::ethereum_expiry,
+                        request_messages::secret_hash,
+                    ))
+                    .filter(request_messages::swap_id.eq(key))
+                    .first(connection)
+            })
+            .await?;
+
+        Ok(record.into())
+    }
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct EthereumBitcoinEtherBitcoinRequest {
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    bitcoin_network: Text<BitcoinNetwork>,
+    ether_amount: Text<Ether>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    bitcoin_redeem_identity: Text<::bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    bitcoin_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[impl_template]
+impl From<EthereumBitcoinEtherBitcoinRequest>
+    for Request<
+        Ethereum,
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        asset::Ether,
+        asset::Bitcoin,
+        identity::Ethereum,
+        identity::Bitcoin,
+    >
+{
+    fn from(record: EthereumBitcoinEtherBitcoinRequest) -> Self {
+        Request {
+            swap_id: *record.swap_id,
+            alpha_ledger: Ethereum {
+                chain_id: record.ethereum_chain_id.0.into(),
+            },
+            beta_ledger: __TYPE0__,
+            alpha_asset: record.ether_amount.0.into(),
+            beta_asset: record.bitcoin_amount.0.into(),
+            hash_function: *record.hash_function,
+            alpha_ledger_refund_identity: record.ethereum_refund_identity.0.into(),
+            beta_ledger_redeem_identity: record.bitcoin_redeem_identity.0.into(),
+            alpha_expiry: record.ethereum_expiry.0.into(),
+            beta_expiry: record.bitcoin_expiry.0.into(),
+            secret_hash: *record.secret_hash,
+        }
+    }
+}
+
+#[impl_template]
+#[async_trait]
+impl
+    LoadRequest<
+        Ethereum,
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        asset::Ether,
+        asset::Bitcoin,
+        identity::Ethereum,
+        identity::Bitcoin,
+    > for Sqlite
+{
+    async fn load_request(
+        &self,
+        key: &SwapId,
+    ) -> anyhow::Result<
+        Request<
+            Ethereum,
+            __TYPE0__,
+            asset::Ether,
+            asset::Bitcoin,
+            identity::Ethereum,
+            identity::Bitcoin,
+        >,
+    > {
+        use schema::rfc003_ethereum_bitcoin_ether_bitcoin_request_messages as request_messages;
+
+        let record: EthereumBitcoinEtherBitcoinRequest = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                request_messages::table
+                    .select((
+                        request_messages::swap_id,
+                        request_messages::ethereum_chain_id,
+                        request_messages::bitcoin_network,
+                        request_messages::ether_amount,
+                        request_messages::bitcoin_amount,
+                        request_messages::hash_function,
+                        request_messages::ethereum_refund_identity,
+                        request_messages::bitcoin_redeem_identity,
+                        request_messages::ethereum_expiry,
+                        request_messages::bitcoin_expiry,
+                        request_messages::secret_hash,
+                    ))
+                    .filter(request_messages::swap_id.eq(key))
+                    .first(connection)
+            })
+            .await?;
+
+        Ok(record.into())
+    }
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct BitcoinEthereumBitcoinErc20Request {
+    swap_id: Text<SwapId>,
+    bitcoin_network: Text<BitcoinNetwork>,
+    ethereum_chain_id: U32,
+    bitcoin_amount: Text<Satoshis>,
+    erc20_token_contract: Text<EthereumAddress>,
+    erc20_amount: Text<Erc20Amount>,
+    hash_function: Text<HashFunction>,
+    bitcoin_refund_identity: Text<::bitcoin::PublicKey>,
+    ethereum_redeem_identity: Text<EthereumAddress>,
+    bitcoin_expiry: U32,
+    ethereum_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[impl_template]
+impl From<BitcoinEthereumBitcoinErc20Request>
+    for Request<
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        Ethereum,
+        asset::Bitcoin,
+        asset::Erc20,
+        identity::Bitcoin,
+        identity::Ethereum,
+    >
+{
+    fn from(record: BitcoinEthereumBitcoinErc20Request) -> Self {
+        Request {
+            swap_id: *record.swap_id,
+            alpha_ledger: __TYPE0__,
+            beta_ledger: Ethereum {
+                chain_id: record.ethereum_chain_id.0.into(),
+            },
+            alpha_asset: record.bitcoin_amount.0.into(),
+            beta_asset: asset::Erc20::new(
+                record.erc20_token_contract.0.into(),
+                record.erc20_amount.0.into(),
+            ),
+            hash_function: *record.hash_function,
+            alpha_ledger_refund_identity: record.bitcoin_refund_identity.0.into(),
+            beta_ledger_redeem_identity: record.ethereum_redeem_identity.0.into(),
+            alpha_expiry: record.bitcoin_expiry.0.into(),
+            beta_expiry: record.ethereum_expiry.0.into(),
+            secret_hash: *record.secret_hash,
+        }
+    }
+}
+
+#[impl_template]
+#[async_trait]
+impl
+    LoadRequest<
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        Ethereum,
+        asset::Bitcoin,
+        asset::Erc20,
+        identity::Bitcoin,
+        identity::Ethereum,
+    > for Sqlite
+{
+    async fn load_request(
+        &self,
+        key: &SwapId,
+    ) -> anyhow::Result<
+        Request<
+            __TYPE0__,
+            Ethereum,
+            asset::Bitcoin,
+            asset::Erc20,
+            identity::Bitcoin,
+            identity::Ethereum,
+        >,
+    > {
+        use schema::rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages as request_messages;
+
+        let record: BitcoinEthereumBitcoinErc20Request = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                request_messages::table
+                    .select((
+                        request_messages::swap_id,
+                        request_messages::bitcoin_network,
+                        request_messages::ethereum_chain_id,
+                        request_messages::bitcoin_amount,
+                        request_messages::erc20_token_contract,
+                        request_messages::erc20_amount,
+                        request_messages::hash_function,
+                        request_messages::bitcoin_refund_identity,
+                        request_messages::ethereum_redeem_identity,
+                        request_messages::bitcoin_expiry,
+                        request_messages::ethereum_expiry,
+                        request_messages::secret_hash,
+                    ))
+                    .filter(request_messages::swap_id.eq(key))
+                    .first(connection)
+            })
+            .await?;
+
+        Ok(record.into())
+    }
+}
+
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct EthereumBitcoinErc20BitcoinRequest {
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    bitcoin_network: Text<BitcoinNetwork>,
+    erc20_token_contract: Text<EthereumAddress>,
+    erc20_amount: Text<Erc20Amount>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    bitcoin_redeem_identity: Text<::bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    bitcoin_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[impl_template]
+impl From<EthereumBitcoinErc20BitcoinRequest>
+    for Request<
+        Ethereum,
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        asset::Erc20,
+        asset::Bitcoin,
+        identity::Ethereum,
+        identity::Bitcoin,
+    >
+{
+    fn from(record: EthereumBitcoinErc20BitcoinRequest) -> Self {
+        Request {
+            swap_id: *record.swap_id,
+            alpha_ledger: Ethereum {
+                chain_id: record.ethereum_chain_id.0.into(),
+            },
+            beta_ledger: __TYPE0__,
+            alpha_asset: asset::Erc20::new(
+                record.erc20_token_contract.0.into(),
+                record.erc20_amount.0.into(),
+            ),
+            beta_asset: record.bitcoin_amount.0.into(),
+            hash_function: *record.hash_function,
+            alpha_ledger_refund_identity: record.ethereum_refund_identity.0.into(),
+            beta_ledger_redeem_identity: record.bitcoin_redeem_identity.0.into(),
+            alpha_expiry: record.ethereum_expiry.0.into(),
+            beta_expiry: record.bitcoin_expiry.0.into(),
+            secret_hash: *record.secret_hash,
+        }
+    }
+}
+
+#[impl_template]
+#[async_trait]
+impl
+    LoadRequest<
+        Ethereum,
+        ((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest)),
+        asset::Erc20,
+        asset::Bitcoin,
+        identity::Ethereum,
+        identity::Bitcoin,
+    > for Sqlite
+{
+    async fn load_request(
+        &self,
+        key: &SwapId,
+    ) -> anyhow::Result<
+        Request<
+            Ethereum,
+            __TYPE0__,
+            asset::Erc20,
+            asset::Bitcoin,
+            identity::Ethereum,
+            identity::Bitcoin,
+        >,
+    > {
+        use schema::rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages as request_messages;
+
+        let record: EthereumBitcoinErc20BitcoinRequest = self
+            .do_in_transaction(|connection| {
+                let key = Text(key);
+
+                request_messages::table
+                    .select((
+                        request_messages::swap_id,
+                        request_messages::ethereum_chain_id,
+                        request_messages::bitcoin_network,
+                        request_messages::erc20_token_contract,
+                        request_messages::erc20_amount,
+                        request_messages::bitcoin_amount,
+                        request_messages::hash_function,
+                        request_messages::ethereum_refund_identity,
+                        request_messages::bitcoin_redeem_identity,
+                        request_messages::ethereum_expiry,
+                        request_messages::bitcoin_expiry,
+                        request_messages::secret_hash,
+                    ))
+                    .filter(request_messages::swap_id.eq(key))
+                    .first(connection)
+            })
+            .await?;
+
+        Ok(record.into())
+    }
+}
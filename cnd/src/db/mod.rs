@@ -1,5 +1,13 @@
+mod automatic_actions;
+mod autopilot;
+mod bitcoin_wallet;
+#[macro_use]
+mod han_halight;
 #[cfg(test)]
 mod integration_tests;
+mod ledger_events;
+mod listed_swaps;
+mod load_requests;
 mod load_swaps;
 mod save;
 mod schema;
@@ -8,15 +16,30 @@ mod wrapper_types;
 mod swap;
 #[macro_use]
 mod swap_types;
+mod webhook_deliveries;
 #[macro_use]
 pub mod with_swap_types;
 embed_migrations!("./migrations");
 
 pub use self::{
+    automatic_actions::{AutomaticAction, LoadAutomaticActions, SaveAutomaticAction},
+    autopilot::{EnableAutopilot, LoadAutopilot},
+    bitcoin_wallet::{LoadBitcoinWallet, RollBackBitcoinWalletBlock, SaveBitcoinWalletBlock, Utxo},
+    han_halight::{HanHalightSwap, LoadHanHalightSwaps},
+    ledger_events::{
+        AssetColumn, LedgerSide, LoadLedgerEvents, RollBackLedgerEvents, SaveLedgerEvent,
+        TransactionHash,
+    },
+    listed_swaps::{
+        AssetName, LedgerName, ListedSwap, LoadListedSwaps, PageRequest, ProtocolName, SwapFilter,
+        SwapPage, UpdateSwapStatus,
+    },
+    load_requests::{LoadDecline, LoadRequest},
     load_swaps::{AcceptedSwap, LoadAcceptedSwap},
     save::*,
     swap::*,
     swap_types::*,
+    webhook_deliveries::{LoadWebhookDeliveries, SaveWebhookDelivery, WebhookDelivery},
 };
 
 use crate::{
@@ -73,6 +96,14 @@ impl Sqlite {
         })
     }
 
+    /// Makes sure the database can still be queried.
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        self.do_in_transaction(|connection| diesel::sql_query("SELECT 1").execute(connection))
+            .await?;
+
+        Ok(())
+    }
+
     async fn do_in_transaction<F, T, E>(&self, f: F) -> Result<T, E>
     where
         F: Fn(&SqliteConnection) -> Result<T, E>,
@@ -130,6 +161,8 @@ struct QueryableSwapRole {
 pub enum Error {
     #[error("swap not found")]
     SwapNotFound,
+    #[error("ledger event is missing its {0}")]
+    IncompleteLedgerEvent(&'static str),
 }
 
 #[cfg(test)]
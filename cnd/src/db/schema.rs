@@ -104,3 +104,116 @@ table! {
        counterparty -> Text,
    }
 }
+
+table! {
+   han_halight_swaps {
+       id -> Integer,
+       swap_id -> Text,
+       role -> Text,
+       counterparty -> Text,
+   }
+}
+
+table! {
+   han_ethereum_halight_bitcoin_request_messages {
+       id -> Integer,
+       swap_id -> Text,
+       ethereum_chain_id -> BigInt,
+       lightning_network -> Text,
+       ether_amount -> Text,
+       bitcoin_amount -> Text,
+       hash_function -> Text,
+       ethereum_refund_identity -> Text,
+       lightning_redeem_identity -> Text,
+       ethereum_expiry -> BigInt,
+       lightning_cltv_expiry -> BigInt,
+       secret_hash -> Text,
+   }
+}
+
+table! {
+   han_ethereum_halight_bitcoin_accept_messages {
+       id -> Integer,
+       swap_id -> Text,
+       ethereum_redeem_identity -> Text,
+       lightning_refund_identity -> Text,
+       at -> Timestamp,
+   }
+}
+
+table! {
+   rfc003_ledger_events {
+       id -> Integer,
+       swap_id -> Text,
+       ledger -> Text,
+       event -> Text,
+       transaction_hash -> Text,
+       transaction_data -> Text,
+       htlc_location -> Nullable<Text>,
+       asset -> Nullable<Text>,
+       secret -> Nullable<Text>,
+       confirmations -> Nullable<Integer>,
+   }
+}
+
+table! {
+   rfc003_automatic_actions {
+       id -> Integer,
+       swap_id -> Text,
+       ledger -> Text,
+       action -> Text,
+       transaction_hash -> Text,
+       at -> Timestamp,
+   }
+}
+
+table! {
+   bitcoin_wallet_blocks {
+       id -> Integer,
+       block_hash -> Text,
+   }
+}
+
+table! {
+   bitcoin_wallet_utxos {
+       id -> Integer,
+       txid -> Text,
+       vout -> Text,
+       amount -> Text,
+       received_in -> Text,
+       spent_in -> Nullable<Text>,
+   }
+}
+
+table! {
+   rfc003_autopilot_swaps {
+       id -> Integer,
+       swap_id -> Text,
+   }
+}
+
+table! {
+   webhook_deliveries {
+       id -> Integer,
+       url -> Text,
+       payload -> Text,
+       attempts -> Integer,
+       next_attempt_at -> Timestamp,
+   }
+}
+
+table! {
+   listed_swaps {
+       id -> Integer,
+       swap_id -> Text,
+       protocol -> Text,
+       role -> Text,
+       counterparty -> Text,
+       alpha_ledger -> Text,
+       beta_ledger -> Text,
+       alpha_asset -> Text,
+       beta_asset -> Text,
+       status -> Text,
+       created_at -> Nullable<Timestamp>,
+   }
+}
@@ -0,0 +1,137 @@
+use crate::db::{schema::webhook_deliveries, wrapper_types::custom_sql_types::Text, Sqlite};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::{self, prelude::*, RunQueryDsl};
+use reqwest::Url;
+use std::convert::TryFrom;
+
+/// A webhook notification that was not delivered yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub url: Url,
+    pub payload: String,
+    /// How many times the delivery failed so far.
+    pub attempts: u32,
+}
+
+#[async_trait]
+pub trait SaveWebhookDelivery: Send + Sync + 'static {
+    /// Schedules the delivery of `payload` to `url` right away.
+    async fn save_webhook_delivery(&self, url: Url, payload: String) -> anyhow::Result<()>;
+
+    /// Forgets about a delivery, either because it succeeded or because we
+    /// gave up on it.
+    async fn remove_webhook_delivery(&self, id: i32) -> anyhow::Result<()>;
+
+    /// Records a failed attempt and schedules the next one.
+    async fn reschedule_webhook_delivery(
+        &self,
+        id: i32,
+        next_attempt_at: NaiveDateTime,
+    ) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+pub trait LoadWebhookDeliveries: Send + Sync + 'static {
+    /// Load the deliveries that are scheduled at or before `now`, oldest
+    /// first.
+    async fn load_due_webhook_deliveries(
+        &self,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Vec<WebhookDelivery>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "webhook_deliveries"]
+struct InsertableWebhookDelivery {
+    url: Text<Url>,
+    payload: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableWebhookDelivery {
+    id: i32,
+    url: Text<Url>,
+    payload: String,
+    attempts: i32,
+    _next_attempt_at: NaiveDateTime,
+}
+
+impl From<QueryableWebhookDelivery> for WebhookDelivery {
+    fn from(record: QueryableWebhookDelivery) -> Self {
+        WebhookDelivery {
+            id: record.id,
+            url: (*record.url).clone(),
+            payload: record.payload,
+            // The counter starts at zero and is only ever incremented.
+            attempts: u32::try_from(record.attempts).unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl SaveWebhookDelivery for Sqlite {
+    async fn save_webhook_delivery(&self, url: Url, payload: String) -> anyhow::Result<()> {
+        let insertable = InsertableWebhookDelivery {
+            url: Text(url),
+            payload,
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(webhook_deliveries::table)
+                .values(&insertable)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_webhook_delivery(&self, id: i32) -> anyhow::Result<()> {
+        self.do_in_transaction(|connection| {
+            diesel::delete(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reschedule_webhook_delivery(
+        &self,
+        id: i32,
+        next_attempt_at: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.do_in_transaction(|connection| {
+            diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+                .set((
+                    webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+                    webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+                ))
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoadWebhookDeliveries for Sqlite {
+    async fn load_due_webhook_deliveries(
+        &self,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let records: Vec<QueryableWebhookDelivery> = self
+            .do_in_transaction(|connection| {
+                webhook_deliveries::table
+                    .filter(webhook_deliveries::next_attempt_at.le(now))
+                    .order(webhook_deliveries::id.asc())
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(WebhookDelivery::from).collect())
+    }
+}
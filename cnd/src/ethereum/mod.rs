@@ -1,6 +1,10 @@
 #![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
 #![forbid(unsafe_code)]
 
+mod signer;
+pub mod wallet;
+
+pub use self::signer::{Signer, UnsignedTransaction};
 pub use web3::types::{
     Address, Block, BlockId, BlockNumber, Bytes, Log, Transaction, TransactionReceipt,
     TransactionRequest, H160, H2048, H256, U128, U256,
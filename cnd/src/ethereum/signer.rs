@@ -0,0 +1,144 @@
+use crate::{
+    ethereum::{Address, Bytes, U256},
+    swap_protocols::ledger::ethereum::ChainId,
+};
+use ::bitcoin::secp256k1::{Message, PublicKey, SecretKey};
+use rlp::RlpStream;
+use std::convert::TryFrom;
+use tiny_keccak::{Hasher, Keccak};
+
+/// An Ethereum transaction that is ready to be signed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsignedTransaction {
+    pub nonce: U256,
+    pub gas_price: U256,
+    pub gas_limit: U256,
+    /// The receiver of the transaction, `None` if it deploys a contract.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+/// Signs transactions for a single Ethereum account on a single chain.
+///
+/// Signatures follow EIP-155, i.e. they commit to the chain id and can
+/// therefore not be replayed on other chains.
+#[derive(Clone, Copy, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct Signer {
+    #[derivative(Debug = "ignore")]
+    secret_key: SecretKey,
+    chain_id: ChainId,
+}
+
+impl Signer {
+    pub fn new(secret_key: SecretKey, chain_id: ChainId) -> Self {
+        Self {
+            secret_key,
+            chain_id,
+        }
+    }
+
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+
+    pub fn address(&self) -> Address {
+        let public_key = PublicKey::from_secret_key(&*crate::SECP, &self.secret_key);
+        let hash = keccak256(&public_key.serialize_uncompressed()[1..]);
+
+        Address::from_slice(&hash[12..])
+    }
+
+    /// Returns the signed transaction encoded as RLP, ready to be passed to
+    /// `eth_sendRawTransaction`.
+    pub fn sign(&self, transaction: &UnsignedTransaction) -> Bytes {
+        let chain_id = U256::from(u32::from(self.chain_id));
+
+        let mut stream = RlpStream::new_list(9);
+        append_transaction(&mut stream, transaction);
+        stream.append(&chain_id);
+        stream.append(&U256::zero());
+        stream.append(&U256::zero());
+
+        let hash = keccak256(&stream.out());
+        let message = Message::from_slice(&hash).expect("keccak256 hash is 32 bytes long");
+        let signature = crate::SECP.sign_recoverable(&message, &self.secret_key);
+        let (recovery_id, signature) = signature.serialize_compact();
+
+        let recovery_id =
+            u64::try_from(recovery_id.to_i32()).expect("recovery id is never negative");
+        let v = U256::from(recovery_id) + chain_id * 2 + 35;
+        let r = U256::from_big_endian(&signature[..32]);
+        let s = U256::from_big_endian(&signature[32..]);
+
+        let mut stream = RlpStream::new_list(9);
+        append_transaction(&mut stream, transaction);
+        stream.append(&v);
+        stream.append(&r);
+        stream.append(&s);
+
+        Bytes(stream.out())
+    }
+}
+
+fn append_transaction(stream: &mut RlpStream, transaction: &UnsignedTransaction) {
+    stream.append(&transaction.nonce);
+    stream.append(&transaction.gas_price);
+    stream.append(&transaction.gas_limit);
+    match transaction.to {
+        Some(to) => stream.append(&to),
+        None => stream.append_empty_data(),
+    };
+    stream.append(&transaction.value);
+    stream.append(&transaction.data.0);
+}
+
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut hash = [0u8; 32];
+
+    hasher.update(bytes);
+    hasher.finalize(&mut hash);
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example taken from https://eips.ethereum.org/EIPS/eip-155
+    #[test]
+    fn signs_eip155_example_transaction() {
+        let secret_key = SecretKey::from_slice(&[0x46; 32]).unwrap();
+        let signer = Signer::new(secret_key, ChainId::mainnet());
+
+        let transaction = UnsignedTransaction {
+            nonce: 9.into(),
+            gas_price: U256::from(20_000_000_000u64),
+            gas_limit: 21000.into(),
+            to: Some(Address::from_slice(&[0x35; 20])),
+            value: U256::from(1_000_000_000_000_000_000u64),
+            data: Bytes(vec![]),
+        };
+
+        let signed = signer.sign(&transaction);
+
+        assert_eq!(
+            hex::encode(signed.0),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn derives_address_from_secret_key() {
+        let secret_key = SecretKey::from_slice(&[0x46; 32]).unwrap();
+        let signer = Signer::new(secret_key, ChainId::mainnet());
+
+        assert_eq!(
+            signer.address(),
+            Address::from_slice(&hex::decode("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap())
+        );
+    }
+}
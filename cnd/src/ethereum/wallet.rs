@@ -0,0 +1,194 @@
+use crate::{
+    btsieve::{ethereum::Web3Connector, LatestBlock},
+    config,
+    ethereum::{Address, Bytes, Signer, UnsignedTransaction, H256, U256},
+    seed::RootSeed,
+    swap_protocols::{
+        actions::ethereum::{CallContract, DeployContract},
+        ledger::ethereum::ChainId,
+    },
+};
+use anyhow::{anyhow, bail};
+use bitcoin::secp256k1::SecretKey;
+use futures_core::compat::Future01CompatExt;
+use std::{cmp, sync::Arc};
+use tokio::sync::Mutex;
+
+/// An Ethereum account that cnd uses to execute actions on behalf of the user.
+///
+/// Transactions are sent one at a time. The nonce is taken from the node but
+/// never falls behind the nonce of the last transaction we sent, in case the
+/// node has not seen it yet.
+#[derive(Clone, Debug)]
+pub struct Wallet {
+    signer: Signer,
+    gas_price: Option<U256>,
+    connector: Web3Connector,
+    next_nonce: Arc<Mutex<U256>>,
+}
+
+impl Wallet {
+    /// Uses `private_key` if given, otherwise the key is derived from the seed.
+    ///
+    /// If `gas_price` is `None`, the gas price suggested by the node is used for
+    /// every transaction.
+    pub fn new(
+        seed: &RootSeed,
+        private_key: Option<SecretKey>,
+        chain_id: ChainId,
+        gas_price: Option<U256>,
+        connector: Web3Connector,
+    ) -> Self {
+        let secret_key = private_key.unwrap_or_else(|| {
+            SecretKey::from_slice(&seed.sha256_with_seed(&[b"ETHEREUM_WALLET"]))
+                .expect("The probability of this happening is < 1 in 2^120")
+        });
+
+        Wallet {
+            signer: Signer::new(secret_key, chain_id),
+            gas_price,
+            connector,
+            next_nonce: Arc::new(Mutex::new(U256::zero())),
+        }
+    }
+
+    /// Returns `None` if no wallet is configured for Ethereum.
+    pub fn from_config(
+        seed: &RootSeed,
+        ethereum: &config::Ethereum,
+        connector: Web3Connector,
+    ) -> Option<Self> {
+        ethereum.wallet.as_ref().map(|wallet| {
+            Wallet::new(
+                seed,
+                wallet.private_key,
+                ethereum.chain_id,
+                wallet
+                    .gas_price_gwei
+                    .map(|gwei| U256::from(gwei) * U256::exp10(9)),
+                connector,
+            )
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    pub fn chain_id(&self) -> ChainId {
+        self.signer.chain_id()
+    }
+
+    pub async fn deploy_contract(&self, action: DeployContract) -> anyhow::Result<H256> {
+        let DeployContract {
+            data,
+            amount,
+            gas_limit,
+            chain_id,
+        } = action;
+
+        self.ensure_chain_id(chain_id)?;
+
+        self.send_transaction(None, amount.to_u256(), data, gas_limit)
+            .await
+    }
+
+    pub async fn call_contract(&self, action: CallContract) -> anyhow::Result<H256> {
+        let CallContract {
+            to,
+            data,
+            gas_limit,
+            chain_id,
+            min_block_timestamp,
+        } = action;
+
+        self.ensure_chain_id(chain_id)?;
+
+        // Contracts such as the refund path of an HTLC only accept the call in
+        // a block past a certain time, sending it earlier would only waste gas.
+        if let Some(min_block_timestamp) = min_block_timestamp {
+            let latest_block = self
+                .connector
+                .clone()
+                .latest_block()
+                .compat()
+                .await?
+                .ok_or_else(|| anyhow!("failed to fetch latest block"))?;
+
+            if latest_block.timestamp < u32::from(min_block_timestamp).into() {
+                bail!("latest block was mined before {:?}", min_block_timestamp);
+            }
+        }
+
+        self.send_transaction(
+            Some(to),
+            U256::zero(),
+            data.unwrap_or_else(|| Bytes(vec![])),
+            gas_limit,
+        )
+        .await
+    }
+
+    /// Signs and submits a transaction and returns its hash.
+    ///
+    /// The gas limit of the transaction is the estimate of the node, it must
+    /// not exceed `max_gas_limit`.
+    async fn send_transaction(
+        &self,
+        to: Option<Address>,
+        value: U256,
+        data: Bytes,
+        max_gas_limit: U256,
+    ) -> anyhow::Result<H256> {
+        let from = self.address();
+
+        let gas_limit = self
+            .connector
+            .estimate_gas(from, to, value, data.clone())
+            .await?;
+        if gas_limit > max_gas_limit {
+            bail!(
+                "estimated gas {} exceeds the gas limit {} of the action",
+                gas_limit,
+                max_gas_limit
+            );
+        }
+
+        let gas_price = match self.gas_price {
+            Some(gas_price) => gas_price,
+            None => self.connector.gas_price().await?,
+        };
+
+        let mut next_nonce = self.next_nonce.lock().await;
+        let nonce = cmp::max(self.connector.transaction_count(from).await?, *next_nonce);
+
+        let transaction = UnsignedTransaction {
+            nonce,
+            gas_price,
+            gas_limit,
+            to,
+            value,
+            data,
+        };
+
+        let hash = self
+            .connector
+            .send_raw_transaction(self.signer.sign(&transaction))
+            .await?;
+        *next_nonce = nonce + 1;
+
+        Ok(hash)
+    }
+
+    fn ensure_chain_id(&self, chain_id: ChainId) -> anyhow::Result<()> {
+        if chain_id != self.chain_id() {
+            bail!(
+                "action for chain {} cannot be executed on chain {}",
+                u32::from(chain_id),
+                u32::from(self.chain_id())
+            );
+        }
+
+        Ok(())
+    }
+}
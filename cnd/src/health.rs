@@ -0,0 +1,462 @@
+//! Health of the nodes and the database cnd depends on, served by the HTTP
+//! API at `/health` and `/ready`.
+//!
+//! Both are served without authentication so that probes do not need a
+//! token, they only reveal whether the nodes can be reached.
+//!
+//! The nodes are checked periodically instead of on every request so that a
+//! probe of the HTTP API does not wait for a node that does not answer.
+//!
+//! A ledger whose node was found on another network than the configured one
+//! is unavailable, no swaps are created or acted upon on it until a later
+//! check finds the node on the right network again.
+
+use crate::{
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector, WrongNetwork},
+    comit_api,
+    config::{self, Settings},
+    db::{self, Sqlite},
+    lnd::{LndConnector, LndNotConfigured},
+    timestamp::Timestamp,
+};
+use anyhow::Context;
+use bitcoin::Network;
+use serde::Serialize;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Not checked yet.
+    Unknown,
+    Ok,
+    Unreachable,
+    /// The node follows another chain than the configured one.
+    WrongNetwork,
+    /// The node is optional and cnd is not configured to use it.
+    NotConfigured,
+}
+
+/// The ledgers swaps are made on, each one is watched through a node.
+#[derive(Clone, Copy, Debug, Display, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum Ledger {
+    Bitcoin,
+    Ethereum,
+    Lightning,
+}
+
+impl From<db::LedgerKind> for Ledger {
+    fn from(ledger: db::LedgerKind) -> Self {
+        match ledger {
+            db::LedgerKind::Bitcoin(_) => Ledger::Bitcoin,
+            db::LedgerKind::Ethereum => Ledger::Ethereum,
+        }
+    }
+}
+
+impl From<comit_api::LedgerKind> for Ledger {
+    fn from(ledger: comit_api::LedgerKind) -> Self {
+        match ledger {
+            comit_api::LedgerKind::BitcoinMainnet
+            | comit_api::LedgerKind::BitcoinTestnet
+            | comit_api::LedgerKind::BitcoinRegtest => Ledger::Bitcoin,
+            comit_api::LedgerKind::Ethereum(_) => Ledger::Ethereum,
+            comit_api::LedgerKind::Lightning(_) => Ledger::Lightning,
+        }
+    }
+}
+
+/// Checks the ledgers of a swap before an action is executed without the user
+/// asking for it, the HTTP API checks `Health` itself.
+pub trait EnsureAvailable: Send + Sync + 'static {
+    /// Fails if one of the ledgers is unavailable.
+    fn ensure_available(&self, ledgers: &[Ledger]) -> Result<(), LedgerUnavailable>;
+}
+
+/// Returned for swaps on a ledger whose node is on another network than the
+/// configured one.
+#[derive(Debug, thiserror::Error)]
+#[error("{ledger} is unavailable: {reason}")]
+pub struct LedgerUnavailable {
+    pub ledger: Ledger,
+    pub reason: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Block {
+    pub height: u64,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Component {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The best block of the node the last time it could be reached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_block: Option<Block>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<Timestamp>,
+}
+
+impl Default for Component {
+    fn default() -> Self {
+        Self {
+            status: Status::Unknown,
+            error: None,
+            last_block: None,
+            checked_at: None,
+        }
+    }
+}
+
+impl Component {
+    fn checked(&self, result: anyhow::Result<Option<Block>>) -> Self {
+        let checked_at = Some(Timestamp::now());
+
+        match result {
+            Ok(block) => Self {
+                status: Status::Ok,
+                error: None,
+                last_block: block.or(self.last_block),
+                checked_at,
+            },
+            // Not an error, there is just nothing to check.
+            Err(e) if e.is::<LndNotConfigured>() => Self {
+                status: Status::NotConfigured,
+                error: None,
+                last_block: None,
+                checked_at,
+            },
+            Err(e) => Self {
+                status: if e.is::<WrongNetwork>() {
+                    Status::WrongNetwork
+                } else {
+                    Status::Unreachable
+                },
+                error: Some(format!("{:#}", e)),
+                last_block: self.last_block,
+                checked_at,
+            },
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == Status::Ok
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Report {
+    pub bitcoin: Component,
+    pub ethereum: Component,
+    pub lightning: Component,
+    pub database: Component,
+}
+
+impl Report {
+    /// lnd is only needed for swaps over lightning, cnd is ready without it.
+    pub fn is_ready(&self) -> bool {
+        self.bitcoin.is_ok() && self.ethereum.is_ok() && self.database.is_ok()
+    }
+
+    /// Fails if the node of the ledger was found on another network.
+    ///
+    /// A node that cannot be reached does not make its ledger unavailable,
+    /// watching the ledger continues once the node is back.
+    pub fn ensure_available(&self, ledger: Ledger) -> Result<(), LedgerUnavailable> {
+        let component = match ledger {
+            Ledger::Bitcoin => &self.bitcoin,
+            Ledger::Ethereum => &self.ethereum,
+            Ledger::Lightning => &self.lightning,
+        };
+
+        match component.status {
+            Status::WrongNetwork => Err(LedgerUnavailable {
+                ledger,
+                reason: component.error.clone().unwrap_or_default(),
+            }),
+            Status::Unknown | Status::Ok | Status::Unreachable | Status::NotConfigured => Ok(()),
+        }
+    }
+
+    fn components(&self) -> [(&'static str, &Component); 4] {
+        [
+            ("bitcoin", &self.bitcoin),
+            ("ethereum", &self.ethereum),
+            ("lightning", &self.lightning),
+            ("database", &self.database),
+        ]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Health {
+    bitcoin_connector: BitcoinConnector,
+    ethereum_connector: Web3Connector,
+    lnd: Option<config::Lnd>,
+    lightning_network: Network,
+    db: Sqlite,
+    report: Arc<RwLock<Report>>,
+}
+
+impl Health {
+    pub fn new(
+        settings: &Settings,
+        bitcoin_connector: BitcoinConnector,
+        ethereum_connector: Web3Connector,
+        db: Sqlite,
+    ) -> Self {
+        Self {
+            bitcoin_connector,
+            ethereum_connector,
+            lnd: settings.lightning.lnd.clone(),
+            lightning_network: settings.lightning.network,
+            db,
+            report: Arc::new(RwLock::new(Report::default())),
+        }
+    }
+
+    /// Returns the report of the last refresh, every component is unknown
+    /// before the first one.
+    pub fn latest(&self) -> Report {
+        self.report
+            .read()
+            .expect("health report is not poisoned")
+            .clone()
+    }
+
+    /// Fails if one of the ledgers of a swap is unavailable.
+    pub fn ensure_available(&self, ledgers: &[Ledger]) -> Result<(), LedgerUnavailable> {
+        let report = self.latest();
+
+        ledgers
+            .iter()
+            .try_for_each(|ledger| report.ensure_available(*ledger))
+    }
+
+    /// Checks every component and returns the new report.
+    pub async fn refresh(&self) -> Report {
+        let previous = self.latest();
+
+        let report = Report {
+            bitcoin: previous.bitcoin.checked(self.check_bitcoin().await),
+            ethereum: previous.ethereum.checked(self.check_ethereum().await),
+            lightning: previous.lightning.checked(self.check_lightning().await),
+            database: previous
+                .database
+                .checked(self.db.ping().await.map(|_| None)),
+        };
+
+        for ((name, before), (_, after)) in previous.components().iter().zip(&report.components()) {
+            if before.status != after.status {
+                match &after.error {
+                    Some(error) => tracing::warn!("{} is {:?}: {}", name, after.status, error),
+                    None => tracing::info!("{} is {:?}", name, after.status),
+                }
+            }
+        }
+
+        *self.report.write().expect("health report is not poisoned") = report.clone();
+
+        report
+    }
+
+    async fn check_bitcoin(&self) -> anyhow::Result<Option<Block>> {
+        let tip = self.bitcoin_connector.chain_tip().await?;
+
+        Ok(Some(Block {
+            height: tip.height,
+            timestamp: Timestamp::from(tip.timestamp),
+        }))
+    }
+
+    async fn check_ethereum(&self) -> anyhow::Result<Option<Block>> {
+        self.ethereum_connector.verify_chain_id().await?;
+
+        let height = self.ethereum_connector.block_number().await?;
+        let block = self
+            .ethereum_connector
+            .block_header_by_number(height)
+            .await?
+            .context("node does not know its latest block")?;
+
+        Ok(Some(Block {
+            height,
+            timestamp: Timestamp::from(block.timestamp.low_u32()),
+        }))
+    }
+
+    async fn check_lightning(&self) -> anyhow::Result<Option<Block>> {
+        let lnd = self.lnd.clone().ok_or(LndNotConfigured)?;
+        // The files of lnd are read on every check, it might have been
+        // started after cnd.
+        let info = LndConnector::new(lnd, self.lightning_network)?
+            .get_info()
+            .await?;
+
+        match info.network {
+            Some(network) if network == self.lightning_network => {}
+            Some(network) => {
+                return Err(WrongNetwork::new(self.lightning_network, network).into());
+            }
+            None => {
+                return Err(WrongNetwork::new(
+                    self.lightning_network.to_string(),
+                    "an unknown network".to_owned(),
+                )
+                .into());
+            }
+        }
+
+        Ok(Some(Block {
+            height: u64::from(info.block_height),
+            timestamp: Timestamp::from(info.best_header_timestamp),
+        }))
+    }
+}
+
+/// Fails if cnd is misconfigured, i.e. a node is on the wrong network or the
+/// database cannot be used.
+///
+/// Nodes that cannot be reached are only warned about, they might just not
+/// be up yet.
+pub fn startup_check(report: &Report) -> anyhow::Result<()> {
+    for (name, component) in report.components().iter() {
+        let error = component.error.as_deref().unwrap_or_default();
+
+        match component.status {
+            Status::WrongNetwork => anyhow::bail!("{} is misconfigured: {}", name, error),
+            Status::Unreachable if *name == "database" => {
+                anyhow::bail!("database cannot be used: {}", error)
+            }
+            Status::Unreachable => tracing::warn!("{} cannot be reached: {}", name, error),
+            Status::Ok | Status::Unknown | Status::NotConfigured => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Refreshes the health report periodically, never returns.
+pub async fn keep_up_to_date(health: Health) {
+    loop {
+        tokio::time::delay_for(REFRESH_INTERVAL).await;
+
+        health.refresh().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok() -> Component {
+        Component::default().checked(Ok(None))
+    }
+
+    fn unreachable() -> Component {
+        Component::default().checked(Err(anyhow::anyhow!("connection refused")))
+    }
+
+    #[test]
+    fn ready_without_lightning_but_not_without_a_ledger() {
+        let report = Report {
+            bitcoin: ok(),
+            ethereum: ok(),
+            lightning: unreachable(),
+            database: ok(),
+        };
+        assert!(report.is_ready());
+
+        let report = Report {
+            ethereum: unreachable(),
+            ..report
+        };
+        assert!(!report.is_ready());
+    }
+
+    #[test]
+    fn lightning_without_lnd_is_not_configured_instead_of_unreachable() {
+        let component = Component::default().checked(Err(LndNotConfigured.into()));
+        assert_eq!(component.status, Status::NotConfigured);
+        assert_eq!(component.error, None);
+
+        let report = Report {
+            bitcoin: ok(),
+            ethereum: ok(),
+            lightning: component,
+            database: ok(),
+        };
+        assert!(report.is_ready());
+        assert!(startup_check(&report).is_ok());
+    }
+
+    #[test]
+    fn last_block_is_kept_when_the_node_becomes_unreachable() {
+        let block = Block {
+            height: 100,
+            timestamp: Timestamp::from(1_588_000_000),
+        };
+
+        let component = Component::default()
+            .checked(Ok(Some(block)))
+            .checked(Err(anyhow::anyhow!("connection refused")));
+
+        assert_eq!(component.status, Status::Unreachable);
+        assert_eq!(component.last_block, Some(block));
+    }
+
+    #[test]
+    fn startup_fails_on_wrong_network_but_not_on_unreachable_node() {
+        let wrong_network = Component::default().checked(Err(WrongNetwork::new(
+            Network::Regtest,
+            Network::Bitcoin,
+        )
+        .into()));
+        assert_eq!(wrong_network.status, Status::WrongNetwork);
+
+        let report = Report {
+            bitcoin: unreachable(),
+            ethereum: ok(),
+            lightning: unreachable(),
+            database: ok(),
+        };
+        assert!(startup_check(&report).is_ok());
+
+        let report = Report {
+            bitcoin: wrong_network,
+            ..report
+        };
+        assert!(startup_check(&report).is_err());
+    }
+
+    #[test]
+    fn only_ledgers_on_the_wrong_network_are_unavailable() {
+        let report = Report {
+            bitcoin: Component::default().checked(Err(WrongNetwork::new(
+                Network::Regtest,
+                Network::Testnet,
+            )
+            .into())),
+            ethereum: unreachable(),
+            lightning: ok(),
+            database: ok(),
+        };
+
+        let unavailable = report.ensure_available(Ledger::Bitcoin).unwrap_err();
+        assert_eq!(
+            unavailable.to_string(),
+            "bitcoin is unavailable: node is on testnet but regtest is configured"
+        );
+        assert!(report.ensure_available(Ledger::Ethereum).is_ok());
+        assert!(report.ensure_available(Ledger::Lightning).is_ok());
+    }
+}
@@ -1,13 +1,18 @@
 use crate::{
     asset,
-    http_api::{problem, Http, MissingQueryParameters, UnexpectedQueryParameters},
+    bitcoin::wallet::Wallet,
+    http_api::{
+        problem, CannotExecuteAction, Http, MissingQueryParameters, UnexpectedQueryParameters,
+    },
     identity,
     swap_protocols::{
         actions::{
             bitcoin::{SendToAddress, SpendOutput},
-            ethereum,
+            ethereum, lnd,
         },
-        ledger, SwapId,
+        ledger,
+        rfc003::{Secret, SecretHash},
+        Facade, SwapId,
     },
     timestamp::Timestamp,
     transaction,
@@ -34,9 +39,27 @@ pub enum ActionExecutionParameters {
         address: bitcoin::Address,
         fee_per_wu: String,
     },
+    /// Asks cnd to execute the action itself instead of describing it.
+    Execute {
+        execute: ExecuteFlag,
+    },
     None {},
 }
 
+/// Only `execute=true` selects `ActionExecutionParameters::Execute`, any other
+/// value falls through to `ActionExecutionParameters::None`.
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum ExecuteFlag {
+    #[serde(rename = "true")]
+    True,
+}
+
+/// Executes an action with the wallets managed by cnd.
+#[async_trait::async_trait]
+pub trait ExecuteAction<A>: Send + Sync + 'static {
+    async fn execute_action(&self, action: A) -> anyhow::Result<ActionResponseBody>;
+}
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(tag = "type", content = "payload")]
@@ -51,6 +74,12 @@ pub enum ActionResponseBody {
         network: Http<bitcoin::Network>,
         #[serde(skip_serializing_if = "Option::is_none")]
         min_median_block_time: Option<Timestamp>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        warning: Option<String>,
+    },
+    BitcoinTransactionBroadcast {
+        txid: bitcoin::Txid,
+        network: Http<bitcoin::Network>,
     },
     EthereumDeployContract {
         data: crate::ethereum::Bytes,
@@ -67,6 +96,31 @@ pub enum ActionResponseBody {
         #[serde(skip_serializing_if = "Option::is_none")]
         min_block_timestamp: Option<Timestamp>,
     },
+    EthereumTransactionSent {
+        transaction_hash: crate::ethereum::H256,
+        chain_id: ledger::ethereum::ChainId,
+    },
+    LndAddHoldInvoice {
+        amount: String,
+        secret_hash: SecretHash,
+        expiry: u32,
+        cltv_expiry: u32,
+        network: Http<bitcoin::Network>,
+        self_public_key: identity::Lightning,
+    },
+    LndSendPayment {
+        to_public_key: identity::Lightning,
+        amount: String,
+        secret_hash: SecretHash,
+        final_cltv_delta: u32,
+        network: Http<bitcoin::Network>,
+        self_public_key: identity::Lightning,
+    },
+    LndSettleInvoice {
+        secret: Secret,
+        network: Http<bitcoin::Network>,
+        self_public_key: identity::Lightning,
+    },
     None,
 }
 
@@ -74,6 +128,7 @@ impl ActionResponseBody {
     fn bitcoin_broadcast_signed_transaction(
         transaction: &transaction::Bitcoin,
         network: bitcoin::Network,
+        warning: Option<String>,
     ) -> Self {
         let min_median_block_time = if transaction.lock_time == 0 {
             None
@@ -88,6 +143,7 @@ impl ActionResponseBody {
             hex: bitcoin::consensus::encode::serialize_hex(transaction),
             network: Http(network),
             min_median_block_time,
+            warning,
         }
     }
 }
@@ -175,9 +231,15 @@ impl IntoResponsePayload for SpendOutput {
                             }
                         })?;
 
+                let warning = uneconomic_fee_warning(&transaction, fee_per_wu);
+                if let Some(warning) = &warning {
+                    tracing::warn!("{}", warning);
+                }
+
                 Ok(ActionResponseBody::bitcoin_broadcast_signed_transaction(
                     &transaction,
                     network,
+                    warning,
                 ))
             }
             _ => Err(anyhow::Error::from(MissingQueryParameters {
@@ -200,6 +262,31 @@ impl IntoResponsePayload for SpendOutput {
     }
 }
 
+/// Spending an HTLC is reported as uneconomic if the fee takes more than this
+/// share of the HTLC amount.
+const UNECONOMIC_FEE_PERCENT: u64 = 10;
+
+/// Returns a warning if the transaction spending the HTLC pays a fee that is
+/// out of proportion to the amount it spends.
+fn uneconomic_fee_warning(transaction: &transaction::Bitcoin, fee_per_wu: usize) -> Option<String> {
+    let fee = transaction.get_weight() as u64 * fee_per_wu as u64;
+    let received = transaction
+        .output
+        .iter()
+        .map(|output| output.value)
+        .sum::<u64>();
+    let htlc_amount = received + fee;
+
+    if fee * 100 > htlc_amount * UNECONOMIC_FEE_PERCENT {
+        Some(format!(
+            "The fee of {} satoshi is more than {}% of the HTLC amount of {} satoshi.",
+            fee, UNECONOMIC_FEE_PERCENT, htlc_amount
+        ))
+    } else {
+        None
+    }
+}
+
 impl ListRequiredFields for SpendOutput {
     fn list_required_fields() -> Vec<siren::Field> {
         vec![
@@ -293,6 +380,108 @@ impl ListRequiredFields for ethereum::CallContract {
     }
 }
 
+impl IntoResponsePayload for lnd::AddHoldInvoice {
+    fn into_response_payload(
+        self,
+        query_params: ActionExecutionParameters,
+    ) -> anyhow::Result<ActionResponseBody> {
+        let lnd::AddHoldInvoice {
+            amount,
+            secret_hash,
+            expiry,
+            cltv_expiry,
+            ledger,
+            self_public_key,
+        } = self;
+        match query_params {
+            ActionExecutionParameters::None {} => Ok(ActionResponseBody::LndAddHoldInvoice {
+                amount: amount.as_sat().to_string(),
+                secret_hash,
+                expiry,
+                cltv_expiry,
+                network: Http(ledger.network),
+                self_public_key,
+            }),
+            _ => Err(anyhow::Error::from(UnexpectedQueryParameters {
+                action: "lnd::AddHoldInvoice",
+                parameters: &["address", "fee_per_wu"],
+            })),
+        }
+    }
+}
+
+impl ListRequiredFields for lnd::AddHoldInvoice {
+    fn list_required_fields() -> Vec<siren::Field> {
+        vec![]
+    }
+}
+
+impl IntoResponsePayload for lnd::SendPayment {
+    fn into_response_payload(
+        self,
+        query_params: ActionExecutionParameters,
+    ) -> anyhow::Result<ActionResponseBody> {
+        let lnd::SendPayment {
+            to_public_key,
+            amount,
+            secret_hash,
+            final_cltv_delta,
+            ledger,
+            self_public_key,
+        } = self;
+        match query_params {
+            ActionExecutionParameters::None {} => Ok(ActionResponseBody::LndSendPayment {
+                to_public_key,
+                amount: amount.as_sat().to_string(),
+                secret_hash,
+                final_cltv_delta,
+                network: Http(ledger.network),
+                self_public_key,
+            }),
+            _ => Err(anyhow::Error::from(UnexpectedQueryParameters {
+                action: "lnd::SendPayment",
+                parameters: &["address", "fee_per_wu"],
+            })),
+        }
+    }
+}
+
+impl ListRequiredFields for lnd::SendPayment {
+    fn list_required_fields() -> Vec<siren::Field> {
+        vec![]
+    }
+}
+
+impl IntoResponsePayload for lnd::SettleInvoice {
+    fn into_response_payload(
+        self,
+        query_params: ActionExecutionParameters,
+    ) -> anyhow::Result<ActionResponseBody> {
+        let lnd::SettleInvoice {
+            secret,
+            ledger,
+            self_public_key,
+        } = self;
+        match query_params {
+            ActionExecutionParameters::None {} => Ok(ActionResponseBody::LndSettleInvoice {
+                secret,
+                network: Http(ledger.network),
+                self_public_key,
+            }),
+            _ => Err(anyhow::Error::from(UnexpectedQueryParameters {
+                action: "lnd::SettleInvoice",
+                parameters: &["address", "fee_per_wu"],
+            })),
+        }
+    }
+}
+
+impl ListRequiredFields for lnd::SettleInvoice {
+    fn list_required_fields() -> Vec<siren::Field> {
+        vec![]
+    }
+}
+
 impl ListRequiredFields for Infallible {
     fn list_required_fields() -> Vec<siren::Field> {
         unreachable!("how did you manage to construct Infallible?")
@@ -308,6 +497,116 @@ impl IntoResponsePayload for Infallible {
     }
 }
 
+fn bitcoin_wallet(dependencies: &Facade, action: &'static str) -> anyhow::Result<&Wallet> {
+    dependencies.bitcoin_wallet.as_ref().ok_or_else(|| {
+        anyhow::Error::from(CannotExecuteAction {
+            action,
+            reason: "no bitcoin wallet is configured",
+        })
+    })
+}
+
+#[async_trait::async_trait]
+impl ExecuteAction<SendToAddress> for Facade {
+    async fn execute_action(&self, action: SendToAddress) -> anyhow::Result<ActionResponseBody> {
+        let wallet = bitcoin_wallet(self, "bitcoin::SendToAddress")?;
+        let SendToAddress {
+            to,
+            amount,
+            network,
+        } = action;
+
+        let txid = wallet.send_to_address(to, amount).await?;
+
+        Ok(ActionResponseBody::BitcoinTransactionBroadcast {
+            txid,
+            network: Http(network),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecuteAction<SpendOutput> for Facade {
+    async fn execute_action(&self, action: SpendOutput) -> anyhow::Result<ActionResponseBody> {
+        let wallet = bitcoin_wallet(self, "bitcoin::SpendOutput")?;
+        let network = action.network;
+
+        if network != wallet.network() {
+            anyhow::bail!(
+                "cannot spend output on {} to {} wallet",
+                network,
+                wallet.network()
+            );
+        }
+
+        let transaction = action
+            .spend_to(wallet.address())
+            .sign_with_rate(&*crate::SECP, wallet.fee_per_wu())
+            .map_err(|e| anyhow::anyhow!("failed to sign bitcoin transaction: {:?}", e))?;
+        let txid = wallet.broadcast(&transaction).await?;
+
+        Ok(ActionResponseBody::BitcoinTransactionBroadcast {
+            txid,
+            network: Http(network),
+        })
+    }
+}
+
+fn ethereum_wallet(
+    dependencies: &Facade,
+    action: &'static str,
+) -> anyhow::Result<&crate::ethereum::wallet::Wallet> {
+    dependencies.ethereum_wallet.as_ref().ok_or_else(|| {
+        anyhow::Error::from(CannotExecuteAction {
+            action,
+            reason: "no ethereum wallet is configured",
+        })
+    })
+}
+
+#[async_trait::async_trait]
+impl ExecuteAction<ethereum::DeployContract> for Facade {
+    async fn execute_action(
+        &self,
+        action: ethereum::DeployContract,
+    ) -> anyhow::Result<ActionResponseBody> {
+        let wallet = ethereum_wallet(self, "ethereum::ContractDeploy")?;
+        let chain_id = action.chain_id;
+
+        let transaction_hash = wallet.deploy_contract(action).await?;
+
+        Ok(ActionResponseBody::EthereumTransactionSent {
+            transaction_hash,
+            chain_id,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecuteAction<ethereum::CallContract> for Facade {
+    async fn execute_action(
+        &self,
+        action: ethereum::CallContract,
+    ) -> anyhow::Result<ActionResponseBody> {
+        let wallet = ethereum_wallet(self, "ethereum::SendTransaction")?;
+        let chain_id = action.chain_id;
+
+        let transaction_hash = wallet.call_contract(action).await?;
+
+        Ok(ActionResponseBody::EthereumTransactionSent {
+            transaction_hash,
+            chain_id,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecuteAction<Infallible> for Facade {
+    async fn execute_action(&self, _: Infallible) -> anyhow::Result<ActionResponseBody> {
+        unreachable!("how did you manage to construct Infallible?")
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -337,6 +636,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn given_execute_true_deserialize_to_execute() {
+        let s = "execute=true";
+
+        let res = serde_urlencoded::from_str::<ActionExecutionParameters>(s);
+        assert_eq!(
+            res,
+            Ok(ActionExecutionParameters::Execute {
+                execute: ExecuteFlag::True
+            })
+        );
+    }
+
+    #[test]
+    fn given_execute_false_deserialize_to_none() {
+        let s = "execute=false";
+
+        let res = serde_urlencoded::from_str::<ActionExecutionParameters>(s);
+        assert_eq!(res, Ok(ActionExecutionParameters::None {}));
+    }
+
     #[test]
     fn call_contract_serializes_correctly_to_json_with_none() {
         let addr =
@@ -356,6 +676,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn ethereum_transaction_sent_serializes_correctly_to_json() {
+        let body = ActionResponseBody::EthereumTransactionSent {
+            transaction_hash: crate::ethereum::H256::from(&[1u8; 32]),
+            chain_id: ChainId::from(3),
+        };
+        let serialized = serde_json::to_string(&body).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"ethereum-transaction-sent","payload":{"transaction_hash":"0x0101010101010101010101010101010101010101010101010101010101010101","chain_id":3}}"#
+        );
+    }
+
+    fn spend_transaction(value: u64) -> transaction::Bitcoin {
+        transaction::Bitcoin {
+            version: 2,
+            lock_time: 0,
+            input: vec![bitcoin::TxIn {
+                previous_output: bitcoin::OutPoint::null(),
+                script_sig: bitcoin::Script::new(),
+                sequence: 0xFFFF_FFFF,
+                witness: vec![],
+            }],
+            output: vec![bitcoin::TxOut {
+                value,
+                script_pubkey: bitcoin::Script::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn fee_taking_a_large_share_of_the_htlc_amount_is_uneconomic() {
+        let fee = spend_transaction(0).get_weight() as u64 * 10;
+
+        assert!(uneconomic_fee_warning(&spend_transaction(fee), 10).is_some());
+        assert!(uneconomic_fee_warning(&spend_transaction(fee * 100), 10).is_none());
+    }
+
     #[test]
     fn bitcoin_send_amount_to_address_serializes_correctly_to_json() {
         let to = BitcoinAddress::from_str("2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9").unwrap();
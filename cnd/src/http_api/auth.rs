@@ -0,0 +1,316 @@
+use crate::{config::settings, seed::RootSeed};
+use bitcoin::hashes::{
+    hmac::{Hmac, HmacEngine},
+    sha256, Hash, HashEngine,
+};
+use http_api_problem::HttpApiProblem;
+use std::{
+    fs::{self, OpenOptions, Permissions},
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::Path,
+    sync::Arc,
+};
+use strum_macros::{Display, EnumString};
+use warp::{http::StatusCode, Filter, Rejection};
+
+/// The identifier every scoped token starts from.
+const TOKEN_IDENTIFIER: &[u8] = b"cnd";
+
+/// What a client of the HTTP API is allowed to do.
+///
+/// Scopes are ordered, a token for `Execute` also grants `ReadOnly` access.
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq, PartialOrd, Ord)]
+#[strum(serialize_all = "snake_case")]
+pub enum Scope {
+    /// Look at swaps, peers and fees.
+    ReadOnly,
+    /// Create swaps and execute their actions.
+    Execute,
+}
+
+/// Checks the bearer tokens of requests to the HTTP API.
+#[derive(Clone, Debug)]
+pub struct Authenticator {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Disabled,
+    BearerToken(String),
+    /// Macaroon-style tokens: the signature of a token is an HMAC chain over
+    /// the identifier and its caveats, keyed with the root key. Only the
+    /// holder of the root key can create valid tokens, the caveats cannot be
+    /// removed without invalidating the signature.
+    ScopedTokens {
+        root_key: [u8; 32],
+    },
+}
+
+impl Authenticator {
+    pub fn new(auth: &settings::Auth, seed: &RootSeed) -> Self {
+        let inner = match auth {
+            settings::Auth::None => Inner::Disabled,
+            settings::Auth::BearerToken(token) => Inner::BearerToken(token.clone()),
+            settings::Auth::ScopedTokens => Inner::ScopedTokens {
+                root_key: seed.sha256_with_seed(&[b"HTTP_API_AUTH"]),
+            },
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Returns the token for the given scope if tokens are scoped.
+    pub fn scoped_token(&self, scope: Scope) -> Option<String> {
+        match &*self.inner {
+            Inner::ScopedTokens { root_key } => Some(mint(root_key, scope)),
+            Inner::Disabled | Inner::BearerToken(_) => None,
+        }
+    }
+
+    /// Writes the scoped tokens to `<dir>/<scope>.token` so that clients can
+    /// pick the one they need, does nothing if tokens are not scoped.
+    ///
+    /// Only the owner may read the token files.
+    pub fn write_scoped_tokens(&self, dir: &Path) -> anyhow::Result<()> {
+        for &scope in [Scope::ReadOnly, Scope::Execute].iter() {
+            if let Some(token) = self.scoped_token(scope) {
+                fs::create_dir_all(dir)?;
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .mode(0o600)
+                    .open(dir.join(format!("{}.token", scope)))?;
+                // the mode only applies to new files, a file left by a previous
+                // run keeps its permissions unless they are set explicitly
+                file.set_permissions(Permissions::from_mode(0o600))?;
+                file.write_all(token.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the scope granted by the `Authorization` header of a request.
+    fn granted_scope(&self, authorization: Option<&str>) -> Result<Scope, HttpApiProblem> {
+        match &*self.inner {
+            Inner::Disabled => Ok(Scope::Execute),
+            Inner::BearerToken(expected) => {
+                let token = bearer_token(authorization)?;
+
+                if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+                    Ok(Scope::Execute)
+                } else {
+                    Err(invalid_token())
+                }
+            }
+            Inner::ScopedTokens { root_key } => {
+                let token = bearer_token(authorization)?;
+
+                verify(root_key, token).ok_or_else(invalid_token)
+            }
+        }
+    }
+
+    fn authorize(
+        &self,
+        authorization: Option<&str>,
+        required: Scope,
+    ) -> Result<(), HttpApiProblem> {
+        let granted = self.granted_scope(authorization)?;
+
+        if granted < required {
+            return Err(HttpApiProblem::new("Insufficient scope.")
+                .set_status(StatusCode::FORBIDDEN)
+                .set_detail(format!(
+                    "This request requires a token for the {} scope.",
+                    required
+                )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects requests that do not carry a bearer token for the required scope.
+pub fn authorized(
+    authenticator: Authenticator,
+    required: Scope,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            let result = authenticator
+                .authorize(authorization.as_deref(), required)
+                .map_err(warp::reject::custom);
+
+            async move { result }
+        })
+        .untuple_one()
+}
+
+fn bearer_token(authorization: Option<&str>) -> Result<&str, HttpApiProblem> {
+    const PREFIX: &str = "Bearer ";
+
+    match authorization {
+        Some(authorization) if authorization.starts_with(PREFIX) => {
+            Ok(authorization[PREFIX.len()..].trim())
+        }
+        Some(_) => Err(unauthorized("Only bearer tokens are supported.")),
+        None => Err(unauthorized("The request carries no bearer token.")),
+    }
+}
+
+fn invalid_token() -> HttpApiProblem {
+    unauthorized("The bearer token is not valid.")
+}
+
+fn unauthorized(detail: &str) -> HttpApiProblem {
+    HttpApiProblem::new("Unauthorized.")
+        .set_status(StatusCode::UNAUTHORIZED)
+        .set_detail(detail)
+}
+
+/// Creates a token of the form `<scope>.<hex signature>`.
+fn mint(root_key: &[u8], scope: Scope) -> String {
+    format!("{}.{}", scope, hex::encode(signature(root_key, scope)))
+}
+
+fn verify(root_key: &[u8], token: &str) -> Option<Scope> {
+    let mut parts = token.splitn(2, '.');
+    let scope = parts.next()?.parse::<Scope>().ok()?;
+    let signature_of_token = hex::decode(parts.next()?).ok()?;
+
+    if constant_time_eq(&signature_of_token, &signature(root_key, scope)) {
+        Some(scope)
+    } else {
+        None
+    }
+}
+
+fn signature(root_key: &[u8], scope: Scope) -> [u8; 32] {
+    let caveats = [format!("scope = {}", scope)];
+
+    let mut signature = hmac(root_key, TOKEN_IDENTIFIER);
+    for caveat in caveats.iter() {
+        signature = hmac(&signature, caveat.as_bytes());
+    }
+
+    signature
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut engine = HmacEngine::<sha256::Hash>::new(key);
+    engine.input(message);
+
+    Hmac::<sha256::Hash>::from_engine(engine).into_inner()
+}
+
+/// Compares without returning early so that the time taken does not reveal
+/// how much of a token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scoped() -> Authenticator {
+        Authenticator::new(&settings::Auth::ScopedTokens, &RootSeed::from([1u8; 32]))
+    }
+
+    fn bearer(token: &str) -> String {
+        format!("Bearer {}", token)
+    }
+
+    #[test]
+    fn execute_token_grants_every_scope_and_read_only_token_only_reading() {
+        let authenticator = scoped();
+        let read_only = bearer(&authenticator.scoped_token(Scope::ReadOnly).unwrap());
+        let execute = bearer(&authenticator.scoped_token(Scope::Execute).unwrap());
+
+        assert!(authenticator
+            .authorize(Some(&read_only), Scope::ReadOnly)
+            .is_ok());
+        assert!(authenticator
+            .authorize(Some(&execute), Scope::ReadOnly)
+            .is_ok());
+        assert!(authenticator
+            .authorize(Some(&execute), Scope::Execute)
+            .is_ok());
+
+        let forbidden = authenticator
+            .authorize(Some(&read_only), Scope::Execute)
+            .unwrap_err();
+        assert_eq!(forbidden.status, Some(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn scope_of_a_token_cannot_be_changed() {
+        let authenticator = scoped();
+        let read_only = authenticator.scoped_token(Scope::ReadOnly).unwrap();
+        let escalated = read_only.replacen("read_only", "execute", 1);
+
+        let unauthorized = authenticator
+            .authorize(Some(&bearer(&escalated)), Scope::ReadOnly)
+            .unwrap_err();
+        assert_eq!(unauthorized.status, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn tokens_of_another_seed_are_rejected() {
+        let other = Authenticator::new(&settings::Auth::ScopedTokens, &RootSeed::from([2u8; 32]));
+        let token = other.scoped_token(Scope::Execute).unwrap();
+
+        assert!(scoped()
+            .authorize(Some(&bearer(&token)), Scope::ReadOnly)
+            .is_err());
+    }
+
+    #[test]
+    fn static_bearer_token_grants_full_access() {
+        let authenticator = Authenticator::new(
+            &settings::Auth::BearerToken("s3cr3t".to_owned()),
+            &RootSeed::from([1u8; 32]),
+        );
+
+        assert!(authenticator
+            .authorize(Some("Bearer s3cr3t"), Scope::Execute)
+            .is_ok());
+        assert!(authenticator
+            .authorize(Some("Bearer s3cr3"), Scope::ReadOnly)
+            .is_err());
+        assert!(authenticator.authorize(None, Scope::ReadOnly).is_err());
+        assert!(authenticator
+            .authorize(Some("Basic czNjcjN0"), Scope::ReadOnly)
+            .is_err());
+    }
+
+    #[test]
+    fn disabled_authentication_lets_every_request_through() {
+        let authenticator = Authenticator::new(&settings::Auth::None, &RootSeed::from([1u8; 32]));
+
+        assert!(authenticator.authorize(None, Scope::Execute).is_ok());
+    }
+
+    #[test]
+    fn token_files_are_only_readable_by_the_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        let stale = dir.path().join("execute.token");
+        fs::write(&stale, "stale").unwrap();
+        fs::set_permissions(&stale, Permissions::from_mode(0o644)).unwrap();
+
+        scoped().write_scoped_tokens(dir.path()).unwrap();
+
+        for scope in &["read_only", "execute"] {
+            let path = dir.path().join(format!("{}.token", scope));
+            let mode = fs::metadata(path).unwrap().permissions().mode();
+
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod auth;
 pub mod route_factory;
 pub mod routes;
 #[macro_use]
@@ -5,6 +6,7 @@ pub mod impl_serialize_http;
 pub mod action;
 mod problem;
 mod swap_resource;
+pub mod tls;
 
 pub use self::{
     problem::*,
@@ -108,6 +110,7 @@ impl Serialize for Http<SwapProtocol> {
         match &self.0 {
             // Currently we do not expose the hash_function protocol parameter via REST.
             SwapProtocol::Rfc003(_hash_function) => serializer.serialize_str("rfc003"),
+            SwapProtocol::HanHalight(_hash_function) => serializer.serialize_str("han-halight"),
         }
     }
 }
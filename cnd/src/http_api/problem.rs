@@ -1,7 +1,9 @@
 use crate::{
     db,
-    http_api::routes::rfc003::handlers::{
-        post_swap::UnsupportedSwap, InvalidAction, InvalidActionInvocation,
+    health::LedgerUnavailable,
+    http_api::routes::{
+        han_halight,
+        rfc003::handlers::{post_swap::UnsupportedSwap, InvalidAction, InvalidActionInvocation},
     },
 };
 use http_api_problem::HttpApiProblem;
@@ -31,6 +33,13 @@ pub struct UnexpectedQueryParameters {
     pub parameters: &'static [&'static str],
 }
 
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("cannot execute {action} action: {reason}")]
+pub struct CannotExecuteAction {
+    pub action: &'static str,
+    pub reason: &'static str,
+}
+
 // tracing trippers clippy warning, issue reported: https://github.com/tokio-rs/tracing/issues/553
 #[allow(clippy::cognitive_complexity)]
 pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
@@ -71,6 +80,22 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
         return problem;
     }
 
+    if let Some(e) = e.downcast_ref::<CannotExecuteAction>() {
+        tracing::warn!("{}", e);
+
+        return HttpApiProblem::new("Action cannot be executed.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(e.reason);
+    }
+
+    if let Some(e) = e.downcast_ref::<LedgerUnavailable>() {
+        tracing::warn!("{}", e);
+
+        return HttpApiProblem::new("Ledger unavailable.")
+            .set_status(StatusCode::SERVICE_UNAVAILABLE)
+            .set_detail(e.to_string());
+    }
+
     if e.is::<serde_json::Error>() {
         tracing::error!("deserialization error: {}", e);
 
@@ -79,14 +104,15 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
             .set_detail(format!("{:?}", e));
     }
 
-    if e.is::<InvalidActionInvocation>() {
+    if e.is::<InvalidActionInvocation>() || e.is::<han_halight::handlers::InvalidActionInvocation>()
+    {
         tracing::warn!("{}", e);
 
         return HttpApiProblem::new("Invalid action invocation")
             .set_status(http::StatusCode::METHOD_NOT_ALLOWED);
     }
 
-    if e.is::<InvalidAction>() {
+    if e.is::<InvalidAction>() || e.is::<han_halight::handlers::InvalidAction>() {
         tracing::warn!("{}", e);
 
         return HttpApiProblem::new("Invalid action.")
@@ -1,12 +1,17 @@
 use crate::{
     config::settings::AllowedOrigins,
-    http_api,
+    http_api::{
+        self,
+        auth::{self, Authenticator, Scope},
+    },
+    metrics,
     network::LocalPeerId,
     swap_protocols::{self, Facade, SwapId},
 };
 use warp::{self, filters::BoxedFilter, Filter, Reply};
 
 pub const RFC003: &str = "rfc003";
+pub const HAN_HALIGHT: &str = "han-halight";
 
 pub fn swap_path(id: SwapId) -> String {
     format!("/{}/{}/{}", http_api::PATH, RFC003, id)
@@ -16,20 +21,32 @@ pub fn new_action_link(id: &SwapId, action: &str) -> String {
     format!("{}/{}", swap_path(*id), action)
 }
 
+pub fn han_halight_swap_path(id: SwapId) -> String {
+    format!("/{}/{}/{}", http_api::PATH, HAN_HALIGHT, id)
+}
+
+pub fn new_han_halight_action_link(id: &SwapId, action: &str) -> String {
+    format!("{}/{}", han_halight_swap_path(*id), action)
+}
+
 pub fn create(
     dependencies: Facade,
     allowed_origins: &AllowedOrigins,
+    authenticator: Authenticator,
 ) -> BoxedFilter<(impl Reply,)> {
     let peer_id = dependencies.local_peer_id();
     let swaps = warp::path(http_api::PATH);
     let rfc003 = swaps.and(warp::path(RFC003));
+    let han_halight = swaps.and(warp::path(HAN_HALIGHT));
     let peer_id = warp::any().map(move || peer_id.clone());
     let empty_json_body = warp::any().map(|| serde_json::json!({}));
     let dependencies = warp::any().map(move || dependencies.clone());
+    let read_only = auth::authorized(authenticator.clone(), Scope::ReadOnly);
+    let execute = auth::authorized(authenticator, Scope::Execute);
 
     let cors = warp::cors()
         .allow_methods(vec!["GET", "POST"])
-        .allow_header("content-type");
+        .allow_headers(vec!["authorization", "content-type"]);
     let cors = match allowed_origins {
         AllowedOrigins::None => cors.allow_origins(Vec::<&str>::new()),
         AllowedOrigins::All => cors.allow_any_origin(),
@@ -43,6 +60,7 @@ pub fn create(
     let rfc003_post_swap = rfc003
         .and(warp::path::end())
         .and(warp::post())
+        .and(execute.clone())
         .and(dependencies.clone())
         .and(warp::body::json())
         .and_then(http_api::routes::rfc003::post_swap);
@@ -52,14 +70,36 @@ pub fn create(
         .and(dependencies.clone())
         .and(warp::path::param())
         .and(warp::path::end())
+        .and(read_only.clone())
         .and_then(http_api::routes::rfc003::get_swap);
 
     let get_swaps = swaps
         .and(warp::get())
         .and(warp::path::end())
+        .and(read_only.clone())
         .and(dependencies.clone())
+        .and(warp::query::<http_api::routes::index::SwapsQuery>())
         .and_then(http_api::routes::index::get_swaps);
 
+    let rfc003_enable_autopilot = rfc003
+        .and(warp::post())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("autopilot"))
+        .and(warp::path::end())
+        .and(execute.clone())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::rfc003::enable_autopilot);
+
+    let get_events = swaps
+        .and(warp::path("events"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(read_only.clone())
+        .and(warp::sse::last_event_id::<u64>())
+        .and(warp::query::<http_api::routes::events::EventsQuery>())
+        .and(dependencies.clone())
+        .map(http_api::routes::events::get_events);
+
     let rfc003_action = warp::method()
         .and(rfc003)
         .and(warp::path::param::<SwapId>())
@@ -67,28 +107,78 @@ pub fn create(
             swap_protocols::rfc003::actions::ActionKind,
         >())
         .and(warp::path::end())
+        .and(execute.clone())
         .and(warp::query::<http_api::action::ActionExecutionParameters>())
         .and(dependencies.clone())
-        .and(warp::body::json().or(empty_json_body).unify())
+        .and(warp::body::json().or(empty_json_body.clone()).unify())
         .and_then(http_api::routes::rfc003::action);
 
+    let han_halight_get_swap = han_halight
+        .and(warp::get())
+        .and(dependencies.clone())
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::han_halight::get_swap);
+
+    let han_halight_action = warp::method()
+        .and(han_halight)
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path::param::<
+            swap_protocols::han_halight::actions::ActionKind,
+        >())
+        .and(warp::path::end())
+        .and(execute.clone())
+        .and(warp::query::<http_api::action::ActionExecutionParameters>())
+        .and(dependencies.clone())
+        .and(warp::body::json().or(empty_json_body).unify())
+        .and_then(http_api::routes::han_halight::action);
+
     let get_peers = warp::get()
         .and(warp::path("peers"))
         .and(warp::path::end())
+        .and(read_only.clone())
         .and(dependencies.clone())
         .and_then(http_api::routes::peers::get_peers);
 
+    let get_bitcoin_fees = warp::get()
+        .and(warp::path!("fees" / "bitcoin"))
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::fees::get_bitcoin_fees);
+
+    let get_metrics = warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::metrics::get_metrics);
+
+    let get_health = warp::get()
+        .and(warp::path("health"))
+        .and(warp::path::end())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::health::get_health);
+
+    let get_ready = warp::get()
+        .and(warp::path("ready"))
+        .and(warp::path::end())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::health::get_ready);
+
     let get_info_siren = warp::get()
         .and(warp::path::end())
         .and(warp::header::exact("accept", "application/vnd.siren+json"))
+        .and(read_only.clone())
         .and(peer_id.clone())
         .and(dependencies.clone())
         .and_then(http_api::routes::index::get_info_siren);
 
     let get_info = warp::get()
         .and(warp::path::end())
+        .and(read_only)
         .and(peer_id)
-        .and(dependencies)
+        .and(dependencies.clone())
         .and_then(http_api::routes::index::get_info);
 
     let han_ether_halight_bitcoin = swaps
@@ -97,7 +187,10 @@ pub fn create(
         ))
         .and(warp::post())
         .and(warp::path::end())
-        .and_then(http_api::routes::index::post_lightning_route);
+        .and(execute.clone())
+        .and(dependencies)
+        .and(warp::body::json())
+        .and_then(http_api::routes::han_halight::post_swap);
 
     let herc20_erc20_halight_bitcoin = swaps
         .and(warp::path!(
@@ -105,6 +198,7 @@ pub fn create(
         ))
         .and(warp::post())
         .and(warp::path::end())
+        .and(execute.clone())
         .and_then(http_api::routes::index::post_lightning_route);
 
     let halight_bitcoin_han_ether = swaps
@@ -113,6 +207,7 @@ pub fn create(
         ))
         .and(warp::post())
         .and(warp::path::end())
+        .and(execute.clone())
         .and_then(http_api::routes::index::post_lightning_route);
 
     let halight_bitcoin_herc20_erc20 = swaps
@@ -121,14 +216,23 @@ pub fn create(
         ))
         .and(warp::post())
         .and(warp::path::end())
+        .and(execute)
         .and_then(http_api::routes::index::post_lightning_route);
 
     preflight_cors_route
         .or(rfc003_get_swap)
         .or(rfc003_post_swap)
+        .or(rfc003_enable_autopilot)
         .or(rfc003_action)
+        .or(han_halight_get_swap)
+        .or(han_halight_action)
         .or(get_swaps)
+        .or(get_events)
         .or(get_peers)
+        .or(get_bitcoin_fees)
+        .or(get_metrics)
+        .or(get_health)
+        .or(get_ready)
         .or(get_info_siren)
         .or(get_info)
         .or(han_ether_halight_bitcoin)
@@ -137,6 +241,7 @@ pub fn create(
         .or(halight_bitcoin_herc20_erc20)
         .recover(http_api::unpack_problem)
         .with(warp::log("http"))
+        .with(warp::log::custom(metrics::http_request))
         .with(cors)
         .boxed()
 }
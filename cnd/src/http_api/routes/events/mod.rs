@@ -0,0 +1,53 @@
+use crate::swap_protocols::{state_changes::Notification, Facade, SwapId};
+use futures_core::{
+    future,
+    stream::{self, StreamExt},
+};
+use serde::Deserialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, RecvError};
+use warp::{sse, Reply};
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct EventsQuery {
+    swap_id: Option<SwapId>,
+}
+
+/// Streams the state changes of all swaps, or of the one given in the query,
+/// as server-sent events.
+///
+/// A client that reconnects with the `Last-Event-ID` header first receives
+/// the state changes it missed, as long as they are still in memory.
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_events(
+    last_event_id: Option<u64>,
+    query: EventsQuery,
+    dependencies: Facade,
+) -> impl Reply {
+    let (missed, receiver) = dependencies.state_changes.subscribe(last_event_id);
+
+    let events = stream::iter(missed)
+        .chain(stream::unfold(receiver, next_notification))
+        .filter(move |notification| {
+            future::ready(query.swap_id.map_or(true, |id| id == notification.swap_id))
+        })
+        .map(|notification| {
+            Ok::<_, Infallible>((
+                sse::id(notification.id.to_string()),
+                sse::json(notification),
+            ))
+        });
+
+    sse::reply(sse::keep_alive().stream(events))
+}
+
+async fn next_notification(
+    mut receiver: broadcast::Receiver<Notification>,
+) -> Option<(Notification, broadcast::Receiver<Notification>)> {
+    match receiver.recv().await {
+        Ok(notification) => Some((notification, receiver)),
+        // Ending the stream makes the client reconnect with the id of the last
+        // event it received and catch up from there.
+        Err(RecvError::Lagged(_)) | Err(RecvError::Closed) => None,
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{bitcoin::fee_estimates::Estimate, swap_protocols::Facade};
+use serde::Serialize;
+use warp::{Rejection, Reply};
+
+#[derive(Serialize, Debug)]
+pub struct BitcoinFeesResource {
+    estimates: Vec<Estimate>,
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_bitcoin_fees(dependencies: Facade) -> Result<impl Reply, Rejection> {
+    let estimates = dependencies.bitcoin_fee_estimates.latest();
+
+    Ok(warp::reply::json(&BitcoinFeesResource { estimates }))
+}
@@ -0,0 +1,314 @@
+use crate::{
+    db::{LoadHanHalightSwaps, Save},
+    health::Ledger,
+    http_api::{
+        action::{
+            ActionExecutionParameters, ActionResponseBody, IntoResponsePayload, ToSirenAction,
+        },
+        route_factory::new_han_halight_action_link,
+        routes::rfc003::decline::{decline_required_fields, to_swap_decline_reason, DeclineBody},
+    },
+    identity,
+    init_swap::init_accepted_han_halight_swap,
+    libp2p_comit_ext::ToHeader,
+    network::PendingRequestFor,
+    seed::DeriveSwapSeed,
+    swap_protocols::{
+        actions::Actions,
+        han_halight::{
+            self,
+            actions::{Action, ActionKind, Fund, Redeem},
+            messages::{AcceptResponseBody, Decision, DeclineResponseBody},
+            State,
+        },
+        state_changes::{PublishStateChange, StateChange},
+        Facade, SwapId,
+    },
+};
+use anyhow::Context;
+use libp2p_comit::frame::Response;
+use serde::Deserialize;
+use warp::http;
+
+#[allow(clippy::cognitive_complexity)]
+pub async fn handle_action(
+    method: http::Method,
+    swap_id: SwapId,
+    action_kind: ActionKind,
+    body: serde_json::Value,
+    query_params: ActionExecutionParameters,
+    dependencies: Facade,
+) -> anyhow::Result<ActionResponseBody> {
+    let state = dependencies
+        .han_halight_states
+        .get(&swap_id)
+        .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", swap_id))?;
+    dependencies
+        .health
+        .ensure_available(&[Ledger::Ethereum, Ledger::Lightning])?;
+
+    let action = select_action(state.actions(), action_kind, method)?;
+
+    match action {
+        Action::Accept => {
+            let body = serde_json::from_value::<AcceptBody>(body)
+                .context("failed to deserialize accept body")?;
+
+            let channel = dependencies
+                .pending_request_for(swap_id)
+                .await
+                .with_context(|| format!("unable to find response channel for swap {}", swap_id))?;
+
+            let accept_message = han_halight::Accept {
+                swap_id,
+                alpha_ledger_redeem_identity: body.alpha_ledger_redeem_identity,
+                beta_ledger_refund_identity: body.beta_ledger_refund_identity,
+            };
+
+            Save::save(&dependencies, accept_message).await?;
+
+            tracing::trace!("received accept action: {}", swap_id);
+
+            channel.send(accept_response(accept_message)).map_err(|_| {
+                anyhow::anyhow!(
+                    "failed to send response through channel for swap {}",
+                    swap_id
+                )
+            })?;
+            dependencies.publish_state_change(swap_id, StateChange::Accepted);
+
+            let accepted = dependencies
+                .load_han_halight_accepted_swap(&swap_id)
+                .await?;
+            init_accepted_han_halight_swap(
+                &dependencies,
+                dependencies.han_halight_states.clone(),
+                accepted,
+                state.role,
+            )?;
+
+            Ok(ActionResponseBody::None)
+        }
+        Action::Decline => {
+            let body = serde_json::from_value::<DeclineBody>(body)?;
+
+            let channel = dependencies
+                .pending_request_for(swap_id)
+                .await
+                .with_context(|| format!("unable to find response channel for swap {}", swap_id))?;
+
+            let decline_message = han_halight::Decline {
+                swap_id,
+                reason: to_swap_decline_reason(body.reason),
+            };
+
+            Save::save(&dependencies, decline_message).await?;
+
+            tracing::trace!("received decline action: {}", swap_id);
+
+            channel
+                .send(decline_response(decline_message))
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "failed to send response through channel for swap {}",
+                        swap_id
+                    )
+                })?;
+
+            let seed = dependencies.derive_swap_seed(swap_id);
+            let state = State::declined(state.role, state.request().clone(), decline_message, seed);
+            dependencies.han_halight_states.insert(swap_id, state);
+            dependencies.publish_state_change(swap_id, StateChange::Declined);
+
+            Ok(ActionResponseBody::None)
+        }
+        action => {
+            tracing::trace!("received {} action", ActionKind::from(&action));
+            action.into_response_payload(query_params)
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+struct AcceptBody {
+    alpha_ledger_redeem_identity: identity::Ethereum,
+    beta_ledger_refund_identity: identity::Lightning,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("attempt to invoke {action_kind} action with http method {method}, which is an invalid combination")]
+pub struct InvalidActionInvocation {
+    action_kind: ActionKind,
+    method: http::Method,
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error, PartialEq)]
+#[error("action {action_kind} is invalid for this swap")]
+pub struct InvalidAction {
+    action_kind: ActionKind,
+}
+
+fn select_action(
+    actions: Vec<Action>,
+    action_kind: ActionKind,
+    method: http::Method,
+) -> anyhow::Result<Action> {
+    let action = actions
+        .into_iter()
+        .find(|action| ActionKind::from(action) == action_kind)
+        .ok_or_else(|| anyhow::Error::from(InvalidAction { action_kind }))?;
+
+    if http::Method::from(action_kind) != method {
+        return Err(anyhow::Error::from(InvalidActionInvocation {
+            action_kind,
+            method,
+        }));
+    }
+
+    Ok(action)
+}
+
+fn accept_response(message: han_halight::Accept) -> Response {
+    Response::empty()
+        .with_header(
+            "decision",
+            Decision::Accepted
+                .to_header()
+                .expect("Decision should not fail to serialize"),
+        )
+        .with_body(
+            serde_json::to_value(AcceptResponseBody::from(message))
+                .expect("body should always serialize into serde_json::Value"),
+        )
+}
+
+fn decline_response(message: han_halight::Decline) -> Response {
+    Response::empty()
+        .with_header(
+            "decision",
+            Decision::Declined
+                .to_header()
+                .expect("Decision shouldn't fail to serialize"),
+        )
+        .with_body(
+            serde_json::to_value(DeclineResponseBody {
+                reason: message.reason,
+            })
+            .expect("decline body should always serialize into serde_json::Value"),
+        )
+}
+
+impl From<ActionKind> for http::Method {
+    fn from(action_kind: ActionKind) -> Self {
+        match action_kind {
+            ActionKind::Accept => http::Method::POST,
+            ActionKind::Decline => http::Method::POST,
+            ActionKind::Init => http::Method::GET,
+            ActionKind::Fund => http::Method::GET,
+            ActionKind::Redeem => http::Method::GET,
+            ActionKind::Refund => http::Method::GET,
+        }
+    }
+}
+
+impl IntoResponsePayload for Action {
+    fn into_response_payload(
+        self,
+        query_params: ActionExecutionParameters,
+    ) -> anyhow::Result<ActionResponseBody> {
+        match self {
+            Action::Init(payload) => payload.into_response_payload(query_params),
+            Action::Fund(Fund::Han(payload)) => payload.into_response_payload(query_params),
+            Action::Fund(Fund::Halight(payload)) => payload.into_response_payload(query_params),
+            Action::Redeem(Redeem::Han(payload)) => payload.into_response_payload(query_params),
+            Action::Redeem(Redeem::Halight(payload)) => payload.into_response_payload(query_params),
+            Action::Refund(payload) => payload.into_response_payload(query_params),
+            Action::Accept | Action::Decline => Err(anyhow::anyhow!(
+                "IntoResponsePayload is not available for Accept/Decline"
+            )),
+        }
+    }
+}
+
+impl ToSirenAction for Action {
+    fn to_siren_action(&self, id: &SwapId) -> siren::Action {
+        let action_kind = ActionKind::from(self);
+        let method = http::Method::from(action_kind);
+        let name = action_kind.to_string();
+
+        let media_type = match method {
+            // GET + DELETE cannot have a body
+            http::Method::GET | http::Method::DELETE => None,
+            _ => Some("application/json".to_owned()),
+        };
+
+        let fields = match self {
+            Action::Accept => accept_required_fields(),
+            Action::Decline => decline_required_fields(),
+            _ => vec![],
+        };
+
+        siren::Action {
+            href: new_han_halight_action_link(id, &name),
+            name,
+            method: Some(method),
+            _type: media_type,
+            fields,
+            class: vec![],
+            title: None,
+        }
+    }
+}
+
+fn accept_required_fields() -> Vec<siren::Field> {
+    vec![
+        siren::Field {
+            name: "alpha_ledger_redeem_identity".to_owned(),
+            class: vec!["ethereum".to_owned(), "address".to_owned()],
+            _type: Some("text".to_owned()),
+            value: None,
+            title: Some("Alpha ledger redeem identity".to_owned()),
+        },
+        siren::Field {
+            name: "beta_ledger_refund_identity".to_owned(),
+            class: vec!["lightning".to_owned(), "public-key".to_owned()],
+            _type: Some("text".to_owned()),
+            value: None,
+            title: Some("Beta ledger refund identity".to_owned()),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectral_ext::AnyhowResultAssertions;
+    use spectral::prelude::*;
+
+    #[test]
+    fn action_not_available_should_return_409_conflict() {
+        let result = select_action(vec![], ActionKind::Accept, http::Method::POST);
+
+        assert_that(&result)
+            .is_inner_err::<InvalidAction>()
+            .is_equal_to(&InvalidAction {
+                action_kind: ActionKind::Accept,
+            });
+    }
+
+    #[test]
+    fn accept_action_cannot_be_invoked_with_http_get() {
+        let result = select_action(
+            vec![Action::Accept, Action::Decline],
+            ActionKind::Accept,
+            http::Method::GET,
+        );
+
+        assert_that(&result)
+            .is_inner_err::<InvalidActionInvocation>()
+            .is_equal_to(&InvalidActionInvocation {
+                action_kind: ActionKind::Accept,
+                method: http::Method::GET,
+            });
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    db::{LoadHanHalightSwaps, Swap},
+    http_api::{
+        action::ToSirenAction,
+        route_factory::han_halight_swap_path,
+        routes::{
+            han_halight::swap_state::{
+                swap_status, InvoiceState, SwapCommunication, SwapParameters, SwapState,
+            },
+            rfc003::LedgerState,
+        },
+        swap_resource::IncludeState,
+        Http, SwapResource, SwapStatus,
+    },
+    swap_protocols::{
+        actions::Actions,
+        han_halight::{self, State},
+        Facade, SwapId, SwapProtocol,
+    },
+};
+use anyhow::anyhow;
+use http_api_problem::HttpApiProblem;
+use warp::http::StatusCode;
+
+pub async fn handle_get_swap(dependencies: Facade, id: SwapId) -> anyhow::Result<siren::Entity> {
+    let swap = dependencies.han_halight_swap(&id).await?;
+
+    build_han_halight_siren_entity(&dependencies.han_halight_states, swap, IncludeState::Yes)
+}
+
+pub fn build_han_halight_siren_entity(
+    states: &han_halight::States,
+    swap: Swap,
+    include_state: IncludeState,
+) -> anyhow::Result<siren::Entity> {
+    let id = swap.swap_id;
+
+    let state: State = states
+        .get(&id)
+        .ok_or_else(|| anyhow!("state store did not contain an entry for {}", id))?;
+
+    let communication = SwapCommunication::from(state.swap_communication.clone());
+    let alpha_ledger = LedgerState::from(state.alpha_ledger_state.clone());
+    let beta_ledger = InvoiceState::from(state.beta_ledger_state);
+    let parameters = SwapParameters::from(state.request().clone());
+    let hash_function = state.request().hash_function;
+    let actions = state.actions();
+
+    let status = swap_status(
+        communication.status,
+        alpha_ledger.status,
+        beta_ledger.status,
+    );
+
+    let swap = SwapResource {
+        id: Http(id),
+        status,
+        protocol: Http(SwapProtocol::HanHalight(hash_function)),
+        parameters,
+        role: swap.role.to_string(),
+        counterparty: Http(swap.counterparty),
+        state: match include_state {
+            IncludeState::Yes => Some(SwapState {
+                communication,
+                alpha_ledger,
+                beta_ledger,
+            }),
+            IncludeState::No => None,
+        },
+    };
+
+    let entity = siren::Entity::default()
+        .with_class_member("swap")
+        .with_properties(swap)
+        .map_err(|e| {
+            tracing::error!("failed to set properties of entity: {:?}", e);
+            HttpApiProblem::with_title_and_type_from_status(StatusCode::INTERNAL_SERVER_ERROR)
+        })?
+        .with_link(siren::NavigationalLink::new(
+            &["self"],
+            han_halight_swap_path(id),
+        ));
+
+    let entity = actions.into_iter().fold(entity, |acc, action| {
+        let action = action.to_siren_action(&id);
+        acc.with_action(action)
+    });
+
+    Ok(entity)
+}
+
+/// Determines the status of a han-halight swap from its state.
+pub fn han_halight_swap_status(
+    states: &han_halight::States,
+    id: SwapId,
+) -> anyhow::Result<SwapStatus> {
+    let state: State = states
+        .get(&id)
+        .ok_or_else(|| anyhow!("state store did not contain an entry for {}", id))?;
+
+    let communication = SwapCommunication::from(state.swap_communication.clone());
+    let alpha_ledger = LedgerState::from(state.alpha_ledger_state.clone());
+    let beta_ledger = InvoiceState::from(state.beta_ledger_state);
+
+    Ok(swap_status(
+        communication.status,
+        alpha_ledger.status,
+        beta_ledger.status,
+    ))
+}
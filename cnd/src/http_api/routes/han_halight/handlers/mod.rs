@@ -0,0 +1,9 @@
+mod action;
+mod get_swap;
+pub mod post_swap;
+
+pub use self::{
+    action::{handle_action, InvalidAction, InvalidActionInvocation},
+    get_swap::{build_han_halight_siren_entity, han_halight_swap_status, handle_get_swap},
+    post_swap::handle_post_swap,
+};
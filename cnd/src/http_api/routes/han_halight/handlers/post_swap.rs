@@ -0,0 +1,174 @@
+use crate::{
+    asset,
+    db::{HanHalightSwap, ListedSwap, LoadHanHalightSwaps, Save, Swap},
+    health::Ledger,
+    http_api::Http,
+    identity,
+    init_swap::init_accepted_han_halight_swap,
+    network::{DialInformation, SendHanHalightRequest},
+    seed::DeriveSwapSeed,
+    swap_protocols::{
+        han_halight::{self, State},
+        ledger::{ethereum::ChainId, Ethereum, Lightning},
+        rfc003::DeriveSecret,
+        state_changes::{PublishStateChange, StateChange},
+        Facade, HashFunction, Role, SwapId,
+    },
+    timestamp::Timestamp,
+};
+use anyhow::Context;
+use futures_core::future::TryFutureExt;
+use serde::{Deserialize, Serialize};
+
+pub async fn handle_post_swap(
+    dependencies: Facade,
+    body: serde_json::Value,
+) -> anyhow::Result<SwapCreated> {
+    let id = SwapId::default();
+    let seed = dependencies.derive_swap_seed(id);
+    let secret_hash = seed.derive_secret().hash();
+
+    let body = serde_json::from_value::<SwapRequestBody>(body)?;
+    let peer = body.peer.clone();
+    dependencies
+        .health
+        .ensure_available(&[Ledger::Ethereum, Ledger::Lightning])?;
+
+    let swap_request = han_halight::Request {
+        swap_id: id,
+        alpha_ledger: Ethereum::new(body.alpha.chain_id),
+        beta_ledger: Lightning::new(*body.beta.network),
+        alpha_asset: body.alpha.amount,
+        beta_asset: *body.beta.amount,
+        hash_function: HashFunction::Sha256,
+        alpha_ledger_refund_identity: body.alpha.identity,
+        beta_ledger_redeem_identity: body.beta.identity,
+        alpha_expiry: body.alpha.absolute_expiry,
+        beta_cltv_expiry: body.beta.cltv_expiry,
+        secret_hash,
+    };
+
+    initiate_request(dependencies, id, peer, swap_request).await?;
+
+    Ok(SwapCreated { id })
+}
+
+async fn initiate_request(
+    dependencies: Facade,
+    id: SwapId,
+    peer: DialInformation,
+    swap_request: han_halight::Request,
+) -> anyhow::Result<()> {
+    tracing::trace!("initiating new han-halight request: {}", id);
+
+    let counterparty = peer.peer_id.clone();
+    let seed = dependencies.derive_swap_seed(id);
+
+    let swap = Swap::new(id, Role::Alice, counterparty);
+
+    Save::save(&dependencies, HanHalightSwap(swap.clone())).await?;
+    Save::save(&dependencies, swap_request.clone()).await?;
+    Save::save(&dependencies, ListedSwap::han_halight(swap)).await?;
+
+    let state = State::proposed(Role::Alice, swap_request.clone(), seed);
+    dependencies.han_halight_states.insert(id, state);
+    dependencies.publish_state_change(id, StateChange::Requested);
+
+    let future = {
+        async move {
+            let response = dependencies
+                .send_han_halight_request(peer.clone(), swap_request.clone())
+                .await
+                .with_context(|| format!("Failed to send swap request to {}", peer.clone()))?;
+
+            match response {
+                Ok(accept) => {
+                    Save::save(&dependencies, accept).await?;
+                    dependencies.publish_state_change(id, StateChange::Accepted);
+                    let accepted = dependencies.load_han_halight_accepted_swap(&id).await?;
+                    init_accepted_han_halight_swap(
+                        &dependencies,
+                        dependencies.han_halight_states.clone(),
+                        accepted,
+                        Role::Alice,
+                    )?;
+                }
+                Err(decline) => {
+                    tracing::info!("Swap declined: {}", decline.swap_id);
+                    let state = State::declined(Role::Alice, swap_request, decline, seed);
+                    dependencies.han_halight_states.insert(id, state);
+                    Save::save(&dependencies, decline).await?;
+                    dependencies.publish_state_change(id, StateChange::Declined);
+                }
+            };
+            Ok(())
+        }
+    };
+
+    tokio::task::spawn(future.map_err(|e: anyhow::Error| {
+        tracing::error!("{}", e);
+    }));
+
+    Ok(())
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct SwapCreated {
+    pub id: SwapId,
+}
+
+/// The HTTP body for creating a new han-ether/halight-bitcoin swap.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapRequestBody {
+    alpha: HanEthereumEtherParams,
+    beta: HalightLightningBitcoinParams,
+    peer: DialInformation,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HanEthereumEtherParams {
+    amount: asset::Ether,
+    chain_id: ChainId,
+    identity: identity::Ethereum,
+    absolute_expiry: Timestamp,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HalightLightningBitcoinParams {
+    amount: Http<asset::Bitcoin>,
+    network: Http<bitcoin::Network>,
+    identity: identity::Lightning,
+    cltv_expiry: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn can_deserialize_swap_request_body() {
+        let body = r#"{
+                "alpha": {
+                    "amount": "1000000000000000000",
+                    "chain_id": 17,
+                    "identity": "0x00a329c0648769a73afac7f9381e08fb43dbea72",
+                    "absolute_expiry": 2000000000
+                },
+                "beta": {
+                    "amount": "100000",
+                    "network": "regtest",
+                    "identity": "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+                    "cltv_expiry": 144
+                },
+                "peer": "Qma9T5YraSnpRDZqRR4krcSJabThc8nwZuJV3LercPHufi"
+            }"#;
+
+        let body = serde_json::from_str::<SwapRequestBody>(body);
+
+        assert_that(&body).is_ok();
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    htlc_location,
+    http_api::{routes::rfc003, Http, HttpAsset, HttpLedger, SwapStatus},
+    identity,
+    swap_protocols::{
+        han_halight::{self, halight::InvoiceStatus},
+        rfc003::{HtlcState, SecretHash},
+    },
+    timestamp::Timestamp,
+    transaction,
+};
+use serde::Serialize;
+
+pub use crate::http_api::routes::rfc003::SwapCommunicationState;
+
+#[derive(Debug, Serialize)]
+pub struct SwapState {
+    pub communication: SwapCommunication,
+    pub alpha_ledger: rfc003::LedgerState<htlc_location::Ethereum, transaction::Ethereum>,
+    pub beta_ledger: InvoiceState,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapCommunication {
+    pub status: SwapCommunicationState,
+    pub alpha_expiry: Timestamp,
+    pub beta_cltv_expiry: u32,
+    pub alpha_redeem_identity: Option<Http<identity::Ethereum>>,
+    pub beta_redeem_identity: Http<identity::Lightning>,
+    pub alpha_refund_identity: Http<identity::Ethereum>,
+    pub beta_refund_identity: Option<Http<identity::Lightning>>,
+    pub secret_hash: SecretHash,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct InvoiceState {
+    pub status: InvoiceStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapParameters {
+    pub alpha_ledger: HttpLedger,
+    pub beta_ledger: LightningLedger,
+    pub alpha_asset: HttpAsset,
+    pub beta_asset: HttpAsset,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+pub enum LightningLedger {
+    Lightning { network: Http<bitcoin::Network> },
+}
+
+impl From<han_halight::SwapCommunication> for SwapCommunication {
+    fn from(communication: han_halight::SwapCommunication) -> Self {
+        use han_halight::SwapCommunication::*;
+        match communication {
+            Proposed { request } => Self {
+                status: SwapCommunicationState::Sent,
+                alpha_expiry: request.alpha_expiry,
+                beta_cltv_expiry: request.beta_cltv_expiry,
+                alpha_redeem_identity: None,
+                beta_redeem_identity: Http(request.beta_ledger_redeem_identity),
+                alpha_refund_identity: Http(request.alpha_ledger_refund_identity),
+                beta_refund_identity: None,
+                secret_hash: request.secret_hash,
+            },
+            Accepted { request, response } => Self {
+                status: SwapCommunicationState::Accepted,
+                alpha_expiry: request.alpha_expiry,
+                beta_cltv_expiry: request.beta_cltv_expiry,
+                alpha_redeem_identity: Some(Http(response.alpha_ledger_redeem_identity)),
+                beta_redeem_identity: Http(request.beta_ledger_redeem_identity),
+                alpha_refund_identity: Http(request.alpha_ledger_refund_identity),
+                beta_refund_identity: Some(Http(response.beta_ledger_refund_identity)),
+                secret_hash: request.secret_hash,
+            },
+            Declined { request, .. } => Self {
+                status: SwapCommunicationState::Declined,
+                alpha_expiry: request.alpha_expiry,
+                beta_cltv_expiry: request.beta_cltv_expiry,
+                alpha_redeem_identity: None,
+                beta_redeem_identity: Http(request.beta_ledger_redeem_identity),
+                alpha_refund_identity: Http(request.alpha_ledger_refund_identity),
+                beta_refund_identity: None,
+                secret_hash: request.secret_hash,
+            },
+        }
+    }
+}
+
+impl From<han_halight::halight::InvoiceState> for InvoiceState {
+    fn from(state: han_halight::halight::InvoiceState) -> Self {
+        Self {
+            status: state.into(),
+        }
+    }
+}
+
+impl From<han_halight::Request> for SwapParameters {
+    fn from(request: han_halight::Request) -> Self {
+        Self {
+            alpha_ledger: HttpLedger::from(request.alpha_ledger),
+            beta_ledger: LightningLedger::Lightning {
+                network: Http(request.beta_ledger.network),
+            },
+            alpha_asset: HttpAsset::from(request.alpha_asset),
+            beta_asset: HttpAsset::from(request.beta_asset),
+        }
+    }
+}
+
+pub fn swap_status(
+    swap_communication_state: SwapCommunicationState,
+    alpha_ledger: HtlcState,
+    beta_ledger: InvoiceStatus,
+) -> SwapStatus {
+    if swap_communication_state == SwapCommunicationState::Declined {
+        return SwapStatus::NotSwapped;
+    }
+
+    match (alpha_ledger, beta_ledger) {
+        (HtlcState::Redeemed, InvoiceStatus::Settled) => SwapStatus::Swapped,
+        (HtlcState::IncorrectlyFunded, _) => SwapStatus::NotSwapped,
+        (HtlcState::Refunded, _) | (_, InvoiceStatus::Cancelled) => SwapStatus::NotSwapped,
+        _ => SwapStatus::InProgress,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_alpha_redeemed_and_invoice_settled_should_be_swapped() {
+        assert_eq!(
+            swap_status(
+                SwapCommunicationState::Accepted,
+                HtlcState::Redeemed,
+                InvoiceStatus::Settled
+            ),
+            SwapStatus::Swapped
+        )
+    }
+
+    #[test]
+    fn given_invoice_cancelled_should_not_be_swapped() {
+        assert_eq!(
+            swap_status(
+                SwapCommunicationState::Accepted,
+                HtlcState::Funded,
+                InvoiceStatus::Cancelled
+            ),
+            SwapStatus::NotSwapped
+        )
+    }
+
+    #[test]
+    fn given_declined_should_not_be_swapped() {
+        assert_eq!(
+            swap_status(
+                SwapCommunicationState::Declined,
+                HtlcState::NotDeployed,
+                InvoiceStatus::None
+            ),
+            SwapStatus::NotSwapped
+        )
+    }
+}
@@ -0,0 +1,24 @@
+use crate::swap_protocols::Facade;
+use warp::{http::StatusCode, Rejection, Reply};
+
+/// Reports the health of every component, always answers with 200 as long
+/// as cnd is up.
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_health(dependencies: Facade) -> Result<impl Reply, Rejection> {
+    let report = dependencies.health.latest();
+
+    Ok(warp::reply::json(&report))
+}
+
+/// Answers with 503 until cnd can reach the ledgers and the database.
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_ready(dependencies: Facade) -> Result<impl Reply, Rejection> {
+    let report = dependencies.health.latest();
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&report), status))
+}
@@ -1,24 +1,173 @@
 use crate::{
-    db::{DetermineTypes, Retrieve},
-    http_api::swap_resource::{build_rfc003_siren_entity, IncludeState, OnFail},
-    swap_protocols::Facade,
+    db::{
+        AssetName, DetermineTypes, LedgerName, LoadListedSwaps, PageRequest, ProtocolName, Swap,
+        SwapFilter,
+    },
+    http_api::{
+        routes::han_halight::handlers::build_han_halight_siren_entity,
+        swap_resource::{build_rfc003_siren_entity, IncludeState, OnFail},
+        Http, SwapStatus, PATH,
+    },
+    swap_protocols::{Facade, Role},
+    timestamp::Timestamp,
 };
+use chrono::NaiveDateTime;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+/// Filters the swaps and selects the page to return, all parameters are
+/// optional.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SwapsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<SwapStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<Role>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    counterparty: Option<Http<PeerId>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alpha_ledger: Option<LedgerName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beta_ledger: Option<LedgerName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alpha_asset: Option<AssetName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beta_asset: Option<AssetName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_after: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_before: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+    /// Cursor taken from the `next` link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    older_than: Option<i32>,
+    /// Cursor taken from the `prev` link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    newer_than: Option<i32>,
+}
+
+impl SwapsQuery {
+    fn filter(&self) -> SwapFilter {
+        SwapFilter {
+            status: self.status,
+            role: self.role,
+            counterparty: self.counterparty.clone().map(|Http(peer_id)| peer_id),
+            alpha_ledger: self.alpha_ledger,
+            beta_ledger: self.beta_ledger,
+            alpha_asset: self.alpha_asset,
+            beta_asset: self.beta_asset,
+            created_after: self.created_after.map(naive_date_time),
+            created_before: self.created_before.map(naive_date_time),
+        }
+    }
+
+    fn page(&self) -> PageRequest {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+        match (self.older_than, self.newer_than) {
+            (Some(cursor), _) => PageRequest::OlderThan { cursor, limit },
+            (None, Some(cursor)) => PageRequest::NewerThan { cursor, limit },
+            (None, None) => PageRequest::Newest { limit },
+        }
+    }
+
+    /// Links to another page with the same filters.
+    fn link(&self, older_than: Option<i32>, newer_than: Option<i32>) -> String {
+        let query = SwapsQuery {
+            older_than,
+            newer_than,
+            ..self.clone()
+        };
+        let query = serde_urlencoded::to_string(&query).expect("query always serializes");
+
+        format!("/{}?{}", PATH, query)
+    }
+}
+
+fn naive_date_time(timestamp: Timestamp) -> NaiveDateTime {
+    NaiveDateTime::from_timestamp(i64::from(timestamp), 0)
+}
+
+pub async fn handle_get_swaps(
+    dependencies: Facade,
+    query: SwapsQuery,
+) -> anyhow::Result<siren::Entity> {
+    let page = dependencies
+        .db
+        .load_swap_page(query.filter(), query.page())
+        .await?;
+    let suggested_fee_per_wu = dependencies.bitcoin_fee_estimates.suggested_fee_per_wu();
 
-pub async fn handle_get_swaps(dependencies: Facade) -> anyhow::Result<siren::Entity> {
     let mut entity = siren::Entity::default().with_class_member("swaps");
 
-    for swap in Retrieve::all(&dependencies).await?.into_iter() {
-        let types = dependencies.determine_types(&swap.swap_id).await?;
+    for listed in page.swaps.into_iter() {
+        let swap = Swap::new(listed.swap_id, listed.role, listed.counterparty);
+
+        let sub_entity = match listed.protocol {
+            ProtocolName::Rfc003 => {
+                let types = dependencies.determine_types(&swap.swap_id).await?;
 
-        let sub_entity = build_rfc003_siren_entity(
-            &dependencies,
-            swap,
-            types,
-            IncludeState::No,
-            OnFail::NoAction,
-        )?;
+                build_rfc003_siren_entity(
+                    &dependencies,
+                    swap,
+                    types,
+                    Vec::new(),
+                    false,
+                    IncludeState::No,
+                    OnFail::NoAction,
+                    suggested_fee_per_wu,
+                )?
+            }
+            ProtocolName::HanHalight => build_han_halight_siren_entity(
+                &dependencies.han_halight_states,
+                swap,
+                IncludeState::No,
+            )?,
+        };
         entity.push_sub_entity(siren::SubEntity::from_entity(sub_entity, &["item"]));
     }
 
+    if let Some(cursor) = page.newer {
+        entity = entity.with_link(siren::NavigationalLink::new(
+            &["prev"],
+            query.link(None, Some(cursor)),
+        ));
+    }
+    if let Some(cursor) = page.older {
+        entity = entity.with_link(siren::NavigationalLink::new(
+            &["next"],
+            query.link(Some(cursor), None),
+        ));
+    }
+
     Ok(entity)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_keep_the_filters_and_replace_the_cursor() {
+        let query = serde_urlencoded::from_str::<SwapsQuery>(
+            "status=IN_PROGRESS&role=Alice&alpha_ledger=bitcoin&limit=10&newer_than=3",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.link(Some(7), None),
+            "/swaps?status=IN_PROGRESS&role=Alice&alpha_ledger=bitcoin&limit=10&older_than=7"
+        );
+    }
+
+    #[test]
+    fn limit_is_capped() {
+        let query = serde_urlencoded::from_str::<SwapsQuery>("limit=100000").unwrap();
+
+        assert_eq!(query.page(), PageRequest::Newest { limit: MAX_LIMIT });
+    }
+}
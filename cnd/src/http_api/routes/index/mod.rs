@@ -1,5 +1,7 @@
 mod handlers;
 
+pub use self::handlers::SwapsQuery;
+
 use self::handlers::handle_get_swaps;
 use crate::{
     http_api::{problem, routes::into_rejection, Http},
@@ -52,8 +54,8 @@ pub async fn get_info_siren(id: PeerId, dependencies: Facade) -> Result<impl Rep
 }
 
 #[allow(clippy::needless_pass_by_value)]
-pub async fn get_swaps(dependencies: Facade) -> Result<impl Reply, Rejection> {
-    handle_get_swaps(dependencies)
+pub async fn get_swaps(dependencies: Facade, query: SwapsQuery) -> Result<impl Reply, Rejection> {
+    handle_get_swaps(dependencies, query)
         .await
         .map(|swaps| {
             Ok(warp::reply::with_header(
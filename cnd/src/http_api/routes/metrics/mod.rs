@@ -0,0 +1,27 @@
+use crate::{
+    db::LoadListedSwaps,
+    http_api::{problem, routes::into_rejection},
+    metrics,
+    network::ComitPeers,
+    swap_protocols::Facade,
+};
+use warp::{Rejection, Reply};
+
+/// The content type of the text format of Prometheus.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_metrics(dependencies: Facade) -> Result<impl Reply, Rejection> {
+    handle_get_metrics(dependencies)
+        .await
+        .map(|metrics| warp::reply::with_header(metrics, "content-type", TEXT_FORMAT))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+async fn handle_get_metrics(dependencies: Facade) -> anyhow::Result<String> {
+    metrics::set_swap_counts(&dependencies.db.swap_counts().await?);
+    metrics::set_comit_peers(dependencies.comit_peers().await.count());
+
+    metrics::render()
+}
@@ -1,7 +1,12 @@
 use http_api_problem::HttpApiProblem;
 use warp::Rejection;
 
+pub mod events;
+pub mod fees;
+pub mod han_halight;
+pub mod health;
 pub mod index;
+pub mod metrics;
 pub mod peers;
 pub mod rfc003;
 
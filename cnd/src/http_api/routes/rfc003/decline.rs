@@ -11,16 +11,20 @@ pub struct DeclineBody {
 
 impl<AL, BL> ListRequiredFields for Decline<AL, BL> {
     fn list_required_fields() -> Vec<siren::Field> {
-        vec![siren::Field {
-            name: "reason".to_owned(),
-            class: vec![],
-            _type: Some("text".to_owned()),
-            value: None,
-            title: None,
-        }]
+        decline_required_fields()
     }
 }
 
+pub fn decline_required_fields() -> Vec<siren::Field> {
+    vec![siren::Field {
+        name: "reason".to_owned(),
+        class: vec![],
+        _type: Some("text".to_owned()),
+        value: None,
+        title: None,
+    }]
+}
+
 pub fn to_swap_decline_reason(
     reason: Option<HttpApiSwapDeclineReason>,
 ) -> Option<SwapDeclineReason> {
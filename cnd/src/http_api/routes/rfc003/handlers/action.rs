@@ -1,9 +1,10 @@
 use crate::{
     db::{DetermineTypes, LoadAcceptedSwap, Save},
+    health::Ledger,
     http_api::{
         action::{
-            ActionExecutionParameters, ActionResponseBody, IntoResponsePayload, ListRequiredFields,
-            ToSirenAction,
+            ActionExecutionParameters, ActionResponseBody, ExecuteAction, IntoResponsePayload,
+            ListRequiredFields, ToSirenAction,
         },
         route_factory::new_action_link,
         routes::rfc003::decline::{to_swap_decline_reason, DeclineBody},
@@ -21,6 +22,7 @@ use crate::{
             messages::{Decision, IntoAcceptMessage},
             state_store::StateStore,
         },
+        state_changes::{PublishStateChange, StateChange},
         Facade, SwapId,
     },
 };
@@ -43,6 +45,10 @@ pub async fn handle_action(
     dependencies: Facade,
 ) -> anyhow::Result<ActionResponseBody> {
     let types = dependencies.determine_types(&swap_id).await?;
+    dependencies.health.ensure_available(&[
+        Ledger::from(types.alpha_ledger),
+        Ledger::from(types.beta_ledger),
+    ])?;
 
     with_swap_types!(types, {
         let state = StateStore::get::<ROLE>(&dependencies, &swap_id)?.ok_or_else(|| {
@@ -80,6 +86,7 @@ pub async fn handle_action(
                         swap_id
                     )
                 })?;
+                dependencies.publish_state_change(swap_id, StateChange::Accepted);
 
                 let accepted = LoadAcceptedSwap::<AL, BL, AA, BA, AI, BI>::load_accepted_swap(
                     &dependencies,
@@ -121,29 +128,47 @@ pub async fn handle_action(
                 let seed = dependencies.derive_swap_seed(swap_id);
                 let state = State::declined(swap_request.clone(), decline_message, seed);
                 StateStore::insert(&dependencies, swap_id, state);
+                dependencies.publish_state_change(swap_id, StateChange::Declined);
 
                 Ok(ActionResponseBody::None)
             }
             Action::Deploy(action) => {
                 tracing::trace!("received deploy action");
-                action.into_response_payload(query_params)
+                into_response(&dependencies, action, query_params).await
             }
             Action::Fund(action) => {
                 tracing::trace!("received fund action");
-                action.into_response_payload(query_params)
+                into_response(&dependencies, action, query_params).await
             }
             Action::Redeem(action) => {
                 tracing::trace!("received redeem action");
-                action.into_response_payload(query_params)
+                into_response(&dependencies, action, query_params).await
             }
             Action::Refund(action) => {
                 tracing::trace!("received refund action");
-                action.into_response_payload(query_params)
+                into_response(&dependencies, action, query_params).await
             }
         }
     })
 }
 
+/// Lets cnd execute the action if the user asked for it, otherwise describes
+/// the action so that the user can execute it.
+async fn into_response<A>(
+    dependencies: &Facade,
+    action: A,
+    query_params: ActionExecutionParameters,
+) -> anyhow::Result<ActionResponseBody>
+where
+    A: IntoResponsePayload,
+    Facade: ExecuteAction<A>,
+{
+    match query_params {
+        ActionExecutionParameters::Execute { .. } => dependencies.execute_action(action).await,
+        query_params => action.into_response_payload(query_params),
+    }
+}
+
 #[derive(Debug, thiserror::Error, PartialEq)]
 #[error("attempt to invoke {action_kind} action with http method {method}, which is an invalid combination")]
 pub struct InvalidActionInvocation {
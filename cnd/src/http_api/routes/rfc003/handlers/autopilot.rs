@@ -0,0 +1,21 @@
+use crate::{
+    db::{DetermineTypes, EnableAutopilot, Retrieve},
+    health::Ledger,
+    swap_protocols::{Facade, SwapId},
+};
+
+pub async fn handle_enable_autopilot(dependencies: Facade, id: SwapId) -> anyhow::Result<()> {
+    // Fails if there is no such swap.
+    let _ = Retrieve::get(&dependencies, &id).await?;
+
+    let types = dependencies.determine_types(&id).await?;
+    dependencies.health.ensure_available(&[
+        Ledger::from(types.alpha_ledger),
+        Ledger::from(types.beta_ledger),
+    ])?;
+
+    dependencies.enable_autopilot(id).await?;
+    tracing::info!("enabled autopilot for swap {}", id);
+
+    Ok(())
+}
@@ -1,12 +1,23 @@
 use crate::{
-    db::{DetermineTypes, Retrieve},
+    db::{DetermineTypes, LoadAutomaticActions, Retrieve},
     http_api::swap_resource::{build_rfc003_siren_entity, IncludeState, OnFail},
-    swap_protocols::{Facade, SwapId},
+    swap_protocols::{rfc003::autopilot::Autopilot, Facade, SwapId},
 };
 
 pub async fn handle_get_swap(dependencies: Facade, id: SwapId) -> anyhow::Result<siren::Entity> {
     let swap = Retrieve::get(&dependencies, &id).await?;
     let types = dependencies.determine_types(&id).await?;
+    let automatic_actions = dependencies.load_automatic_actions(&id).await?;
+    let autopilot = dependencies.autopilot_enabled(&id).await?;
 
-    build_rfc003_siren_entity(&dependencies, swap, types, IncludeState::Yes, OnFail::Error)
+    build_rfc003_siren_entity(
+        &dependencies,
+        swap,
+        types,
+        automatic_actions,
+        autopilot,
+        IncludeState::Yes,
+        OnFail::Error,
+        dependencies.bitcoin_fee_estimates.suggested_fee_per_wu(),
+    )
 }
@@ -1,9 +1,11 @@
 mod action;
+mod autopilot;
 mod get_swap;
 pub mod post_swap;
 
 pub use self::{
     action::{handle_action, InvalidAction, InvalidActionInvocation},
+    autopilot::handle_enable_autopilot,
     get_swap::handle_get_swap,
     post_swap::handle_post_swap,
 };
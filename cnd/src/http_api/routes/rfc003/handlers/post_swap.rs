@@ -1,18 +1,31 @@
 use crate::{
-    db::{LoadAcceptedSwap, Save, Sqlite, Swap},
+    comit_api,
+    db::{
+        DetermineTypes, ListedSwap, LoadAcceptedSwap, LoadLedgerEvents, RollBackLedgerEvents, Save,
+        SaveLedgerEvent, Sqlite, Swap,
+    },
+    health,
     http_api::{HttpAsset, HttpLedger},
     identity,
     init_swap::init_accepted_swap,
     network::{DialInformation, SendRequest},
     seed::DeriveSwapSeed,
     swap_protocols::{
+        actions::Actions,
         ledger::{self},
         rfc003::{
-            self, alice,
-            events::{HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded},
+            self,
+            actions::{ActionKind, RefundAction},
+            alice,
+            auto_refund::ExecuteRefund,
+            autopilot::ExecuteAutomatically,
+            bob,
+            create_swap::HtlcParams,
+            events::{HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded, TransactionFinality},
             state_store::StateStore,
             Accept, Decline, DeriveIdentities, DeriveSecret, Ledger, Request, SecretHash,
         },
+        state_changes::{PublishStateChange, StateChange},
         Facade, HashFunction, Role, SwapId,
     },
     timestamp::Timestamp,
@@ -34,6 +47,7 @@ where
         Save<Request<AL, BL, AA, BA, AI, BI>> + Save<Accept<AI, BI>> + Save<Swap> + Save<Decline>,
     AL: Ledger,
     BL: Ledger,
+    comit_api::LedgerKind: From<AL> + From<BL>,
     AA: Clone + Ord + Send + Sync + 'static,
     BA: Clone + Ord + Send + Sync + 'static,
     AI: Clone + Send + Sync + 'static,
@@ -50,18 +64,48 @@ where
         + HtlcRedeemed<AL, AA, AI>
         + HtlcRedeemed<BL, BA, BI>
         + HtlcRefunded<AL, AA, AI>
-        + HtlcRefunded<BL, BA, BI>,
+        + HtlcRefunded<BL, BA, BI>
+        + SaveLedgerEvent<AL, BL, AA, BA>
+        + LoadLedgerEvents<AL, BL, AA, BA>
+        + RollBackLedgerEvents
+        + TransactionFinality<AL>
+        + TransactionFinality<BL>
+        + ExecuteRefund<<(AL, AA) as RefundAction>::Output>
+        + ExecuteRefund<<(BL, BA) as RefundAction>::Output>
+        + ExecuteAutomatically<<alice::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>
+        + ExecuteAutomatically<<bob::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>,
+    (AL, AA): RefundAction<
+        HtlcParams = HtlcParams<AL, AA, AI>,
+        HtlcLocation = AL::HtlcLocation,
+        FundTransaction = AL::Transaction,
+    >,
+    (BL, BA): RefundAction<
+        HtlcParams = HtlcParams<BL, BA, BI>,
+        HtlcLocation = BL::HtlcLocation,
+        FundTransaction = BL::Transaction,
+    >,
+    alice::State<AL, BL, AA, BA, AI, BI>: Actions,
+    bob::State<AL, BL, AA, BA, AI, BI>: Actions,
+    <alice::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind: Send,
+    <bob::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind: Send,
+    for<'a> ActionKind: From<&'a <alice::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>
+        + From<&'a <bob::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>,
 {
     tracing::trace!("initiating new request: {}", swap_request.swap_id);
 
     let counterparty = peer.peer_id.clone();
     let seed = dependencies.derive_swap_seed(id);
 
-    Save::save(&dependencies, Swap::new(id, Role::Alice, counterparty)).await?;
+    let swap = Swap::new(id, Role::Alice, counterparty);
+
+    Save::save(&dependencies, swap.clone()).await?;
     Save::save(&dependencies, swap_request.clone()).await?;
+    let types = dependencies.determine_types(&id).await?;
+    Save::save(&dependencies, ListedSwap::rfc003(swap, types)).await?;
 
     let state = alice::State::proposed(swap_request.clone(), seed);
     StateStore::insert(&dependencies, id, state);
+    dependencies.publish_state_change(id, StateChange::Requested);
 
     let future = {
         async move {
@@ -73,6 +117,7 @@ where
             match response {
                 Ok(accept) => {
                     Save::save(&dependencies, accept).await?;
+                    dependencies.publish_state_change(id, StateChange::Accepted);
                     let accepted = LoadAcceptedSwap::<AL, BL, AA, BA, AI, BI>::load_accepted_swap(
                         &dependencies,
                         &id,
@@ -85,6 +130,7 @@ where
                     let state = alice::State::declined(swap_request.clone(), decline, seed);
                     StateStore::insert(&dependencies, id, state);
                     Save::save(&dependencies, decline).await?;
+                    dependencies.publish_state_change(id, StateChange::Declined);
                 }
             };
             Ok(())
@@ -106,7 +152,11 @@ pub async fn handle_post_swap(
     let seed = dependencies.derive_swap_seed(id);
     let secret_hash = seed.derive_secret().hash();
 
-    let body = serde_json::from_value(body)?;
+    let body = serde_json::from_value::<SwapRequestBody>(body)?;
+    dependencies.health.ensure_available(&[
+        health_ledger(&body.alpha_ledger),
+        health_ledger(&body.beta_ledger),
+    ])?;
 
     match body {
         SwapRequestBody {
@@ -442,6 +492,15 @@ where
     }
 }
 
+fn health_ledger(ledger: &HttpLedger) -> health::Ledger {
+    match ledger {
+        HttpLedger::BitcoinMainnet | HttpLedger::BitcoinTestnet | HttpLedger::BitcoinRegtest => {
+            health::Ledger::Bitcoin
+        }
+        HttpLedger::Ethereum(_) => health::Ledger::Ethereum,
+    }
+}
+
 /// An error type for describing that a particular combination of assets and
 /// ledgers is not supported.
 #[derive(Debug, Clone, thiserror::Error)]
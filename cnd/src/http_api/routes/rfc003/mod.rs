@@ -9,7 +9,9 @@ use crate::{
         route_factory::swap_path,
         routes::{
             into_rejection,
-            rfc003::handlers::{handle_action, handle_get_swap, handle_post_swap},
+            rfc003::handlers::{
+                handle_action, handle_enable_autopilot, handle_get_swap, handle_post_swap,
+            },
         },
     },
     swap_protocols::{rfc003::actions::ActionKind, Facade, SwapId},
@@ -48,6 +50,15 @@ pub async fn get_swap(dependencies: Facade, id: SwapId) -> Result<impl Reply, Re
         .map_err(into_rejection)
 }
 
+#[allow(clippy::needless_pass_by_value)]
+pub async fn enable_autopilot(id: SwapId, dependencies: Facade) -> Result<impl Reply, Rejection> {
+    handle_enable_autopilot(dependencies, id)
+        .await
+        .map(|_| warp::reply::with_status(warp::reply(), http::StatusCode::NO_CONTENT))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub async fn action(
     method: http::Method,
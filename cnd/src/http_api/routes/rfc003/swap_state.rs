@@ -1,5 +1,6 @@
 #![allow(clippy::type_repetition_in_bounds)]
 use crate::{
+    db::AutomaticAction,
     http_api::{Http, SwapStatus},
     swap_protocols::rfc003::{self, SecretHash},
     timestamp::Timestamp,
@@ -14,6 +15,10 @@ pub struct SwapState<AI, BI, AH, BH, AT, BT> {
     pub communication: SwapCommunication<AI, BI>,
     pub alpha_ledger: LedgerState<AH, AT>,
     pub beta_ledger: LedgerState<BH, BT>,
+    /// Actions cnd executed on its own, e.g. refunds after the expiry.
+    pub automatic_actions: Vec<AutomaticAction>,
+    /// Whether the autopilot executes the actions of this swap.
+    pub autopilot: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -38,6 +43,8 @@ pub struct LedgerState<H, T> {
     pub htlc_location: Option<Http<H>>,
     pub deploy_tx: Option<Http<T>>,
     pub fund_tx: Option<Http<T>>,
+    /// Confirmations the fund transaction had when the funding was seen.
+    pub fund_confirmations: Option<u32>,
     pub redeem_tx: Option<Http<T>>,
     pub refund_tx: Option<Http<T>>,
 }
@@ -107,6 +114,7 @@ where
                 htlc_location: Some(Http(htlc_location)),
                 deploy_tx: Some(Http(deploy_transaction)),
                 fund_tx: None,
+                fund_confirmations: None,
                 refund_tx: None,
                 redeem_tx: None,
             },
@@ -114,12 +122,14 @@ where
                 htlc_location,
                 deploy_transaction,
                 fund_transaction,
+                fund_confirmations,
                 ..
             } => Self {
                 status,
                 htlc_location: Some(Http(htlc_location)),
                 deploy_tx: Some(Http(deploy_transaction)),
                 fund_tx: Some(Http(fund_transaction)),
+                fund_confirmations,
                 redeem_tx: None,
                 refund_tx: None,
             },
@@ -127,12 +137,14 @@ where
                 htlc_location,
                 deploy_transaction,
                 fund_transaction,
+                fund_confirmations,
                 ..
             } => Self {
                 status,
                 htlc_location: Some(Http(htlc_location)),
                 deploy_tx: Some(Http(deploy_transaction)),
                 fund_tx: Some(Http(fund_transaction)),
+                fund_confirmations,
                 refund_tx: None,
                 redeem_tx: None,
             },
@@ -147,6 +159,7 @@ where
                 htlc_location: Some(Http(htlc_location)),
                 deploy_tx: Some(Http(deploy_transaction)),
                 fund_tx: Some(Http(fund_transaction)),
+                fund_confirmations: None,
                 redeem_tx: Some(Http(redeem_transaction)),
                 refund_tx: None,
             },
@@ -161,6 +174,7 @@ where
                 htlc_location: Some(Http(htlc_location)),
                 deploy_tx: Some(Http(deploy_transaction)),
                 fund_tx: Some(Http(fund_transaction)),
+                fund_confirmations: None,
                 refund_tx: Some(Http(refund_transaction)),
                 redeem_tx: None,
             },
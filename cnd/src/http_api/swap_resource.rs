@@ -1,7 +1,7 @@
 #![allow(clippy::type_repetition_in_bounds)]
 
 use crate::{
-    db::{Swap, SwapTypes},
+    db::{AutomaticAction, Swap, SwapTypes},
     http_api::{
         action::ToSirenAction,
         route_factory::swap_path,
@@ -17,17 +17,18 @@ use crate::{
 use anyhow::anyhow;
 use http_api_problem::HttpApiProblem;
 use libp2p::PeerId;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
 use warp::http::StatusCode;
 
 #[derive(Debug, Serialize)]
-pub struct SwapResource<S> {
+pub struct SwapResource<S, P = SwapParameters> {
     pub id: Http<SwapId>,
     pub role: String,
     pub counterparty: Http<PeerId>,
     pub protocol: Http<SwapProtocol>,
     pub status: SwapStatus,
-    pub parameters: SwapParameters,
+    pub parameters: P,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<S>,
 }
@@ -40,8 +41,9 @@ pub struct SwapParameters {
     beta_asset: HttpAsset,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Display, EnumString, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum SwapStatus {
     InProgress,
     Swapped,
@@ -84,8 +86,11 @@ pub fn build_rfc003_siren_entity<S>(
     state_store: &S,
     swap: Swap,
     types: SwapTypes,
+    automatic_actions: Vec<AutomaticAction>,
+    autopilot: bool,
     include_state: IncludeState,
     on_fail: OnFail,
+    suggested_fee_per_wu: Option<usize>,
 ) -> anyhow::Result<siren::Entity>
 where
     S: StateStore,
@@ -134,6 +139,8 @@ where
                     communication,
                     alpha_ledger,
                     beta_ledger,
+                    automatic_actions,
+                    autopilot,
                 }),
                 IncludeState::No => None,
             },
@@ -161,10 +168,50 @@ where
         }
 
         let entity = actions.into_iter().fold(entity, |acc, action| {
-            let action = action.to_siren_action(&id);
+            let mut action = action.to_siren_action(&id);
+            if let Some(fee_per_wu) = suggested_fee_per_wu {
+                prefill_fee_per_wu(&mut action, fee_per_wu);
+            }
             acc.with_action(action)
         });
 
         Ok(entity)
     })
 }
+
+/// Determines the status of an rfc003 swap from its state.
+pub fn rfc003_swap_status<S>(
+    state_store: &S,
+    id: SwapId,
+    types: SwapTypes,
+) -> anyhow::Result<SwapStatus>
+where
+    S: StateStore,
+{
+    with_swap_types!(types, {
+        let state = state_store
+            .get::<ROLE>(&id)?
+            .ok_or_else(|| anyhow!("state store did not contain an entry for {}", id))?;
+
+        let communication = SwapCommunication::from(state.swap_communication.clone());
+        let alpha_ledger = LedgerState::from(state.alpha_ledger_state.clone());
+        let beta_ledger = LedgerState::from(state.beta_ledger_state.clone());
+
+        Ok(SwapStatus::new(
+            communication.status,
+            alpha_ledger.status,
+            beta_ledger.status,
+        ))
+    })
+}
+
+/// Suggests a fee to actions that spend a bitcoin HTLC.
+fn prefill_fee_per_wu(action: &mut siren::Action, fee_per_wu: usize) {
+    if let Some(field) = action
+        .fields
+        .iter_mut()
+        .find(|field| field.name == "fee_per_wu")
+    {
+        field.value = Some(fee_per_wu.into());
+    }
+}
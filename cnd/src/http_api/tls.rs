@@ -0,0 +1,215 @@
+use crate::config::settings::Tls;
+use anyhow::Context;
+use rcgen::{Certificate, CertificateParams, DistinguishedName, DnType, SanType};
+use std::{
+    fs::{self, OpenOptions, Permissions},
+    io::Write,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+/// The files the self-signed certificate is kept in, inside the data
+/// directory.
+const SELF_SIGNED_CERT: &str = "tls.cert";
+const SELF_SIGNED_KEY: &str = "tls.key";
+
+/// The PEM files of the certificate and key to serve HTTPS with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CertificateFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl CertificateFiles {
+    /// Generates the self-signed certificate if it does not exist yet.
+    ///
+    /// An existing certificate is not replaced, delete its files to generate
+    /// one for other names.
+    ///
+    /// Fails if the files do not contain a PEM encoded certificate and private
+    /// key, the HTTP API would only notice once it is started.
+    pub fn prepare(tls: &Tls, data_dir: &Path, socket: SocketAddr) -> anyhow::Result<Self> {
+        let extra_names = match tls {
+            Tls::Files { cert, key } => {
+                let files = Self {
+                    cert: cert.clone(),
+                    key: key.clone(),
+                };
+                files.check()?;
+
+                return Ok(files);
+            }
+            Tls::SelfSigned { extra_names } => extra_names,
+        };
+
+        let files = Self {
+            cert: data_dir.join(SELF_SIGNED_CERT),
+            key: data_dir.join(SELF_SIGNED_KEY),
+        };
+        if files.cert.exists() && files.key.exists() {
+            files.check()?;
+
+            return Ok(files);
+        }
+
+        let mut names = vec![
+            SanType::DnsName("localhost".to_owned()),
+            SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            SanType::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+        ];
+        if !socket.ip().is_unspecified() && !socket.ip().is_loopback() {
+            names.push(SanType::IpAddress(socket.ip()));
+        }
+        names.extend(extra_names.iter().map(|name| subject_alt_name(name)));
+
+        let certificate = self_signed(names)?;
+        fs::create_dir_all(data_dir)?;
+        fs::write(&files.cert, certificate.serialize_pem()?)
+            .with_context(|| format!("failed to write {}", files.cert.display()))?;
+        // only the owner may read the private key, the mode only applies if the
+        // file does not exist yet
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&files.key)
+            .and_then(|mut key| {
+                key.set_permissions(Permissions::from_mode(0o600))?;
+                key.write_all(certificate.serialize_private_key_pem().as_bytes())
+            })
+            .with_context(|| format!("failed to write {}", files.key.display()))?;
+
+        tracing::info!(
+            "Generated a self-signed certificate for the HTTP API at {}",
+            files.cert.display()
+        );
+
+        Ok(files)
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        let cert = fs::read(&self.cert)
+            .with_context(|| format!("failed to read {}", self.cert.display()))?;
+        if !pem::parse_many(&cert)
+            .iter()
+            .any(|pem| pem.tag == "CERTIFICATE")
+        {
+            anyhow::bail!(
+                "{} does not contain a PEM encoded certificate",
+                self.cert.display()
+            )
+        }
+
+        let key = fs::read(&self.key)
+            .with_context(|| format!("failed to read {}", self.key.display()))?;
+        if !pem::parse_many(&key)
+            .iter()
+            .any(|pem| pem.tag == "PRIVATE KEY" || pem.tag == "RSA PRIVATE KEY")
+        {
+            anyhow::bail!(
+                "{} does not contain a PEM encoded private key",
+                self.key.display()
+            )
+        }
+
+        Ok(())
+    }
+}
+
+fn self_signed(subject_alt_names: Vec<SanType>) -> anyhow::Result<Certificate> {
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::OrganizationName, "COMIT network daemon");
+    distinguished_name.push(DnType::CommonName, "cnd");
+
+    let mut params = CertificateParams::default();
+    params.distinguished_name = distinguished_name;
+    params.subject_alt_names = subject_alt_names;
+
+    Ok(Certificate::from_params(params)?)
+}
+
+fn subject_alt_name(name: &str) -> SanType {
+    match name.parse::<IpAddr>() {
+        Ok(ip) => SanType::IpAddress(ip),
+        Err(_) => SanType::DnsName(name.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_names_are_ip_addresses_or_dns_names() {
+        assert_eq!(
+            subject_alt_name("192.168.1.55"),
+            SanType::IpAddress(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 55)))
+        );
+        assert_eq!(
+            subject_alt_name("cnd.example.com"),
+            SanType::DnsName("cnd.example.com".to_owned())
+        );
+    }
+
+    #[test]
+    fn self_signed_certificate_is_generated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let tls = Tls::SelfSigned {
+            extra_names: vec!["cnd.example.com".to_owned()],
+        };
+        let socket = "127.0.0.1:8000".parse().unwrap();
+
+        let files = CertificateFiles::prepare(&tls, dir.path(), socket).unwrap();
+        let cert = fs::read_to_string(&files.cert).unwrap();
+        let key = fs::read_to_string(&files.key).unwrap();
+        let files_again = CertificateFiles::prepare(&tls, dir.path(), socket).unwrap();
+
+        assert!(cert.starts_with("-----BEGIN CERTIFICATE-----"));
+        assert!(key.contains("PRIVATE KEY-----"));
+        assert_eq!(
+            fs::metadata(&files.key).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(files_again, files);
+        assert_eq!(fs::read_to_string(&files.cert).unwrap(), cert);
+    }
+
+    #[test]
+    fn certificate_files_must_contain_a_certificate_and_a_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let self_signed = Tls::SelfSigned {
+            extra_names: Vec::new(),
+        };
+        let socket = "127.0.0.1:8000".parse().unwrap();
+        let generated = CertificateFiles::prepare(&self_signed, dir.path(), socket).unwrap();
+        let garbage = dir.path().join("garbage.pem");
+        fs::write(&garbage, "not a certificate").unwrap();
+
+        let files = Tls::Files {
+            cert: generated.cert.clone(),
+            key: generated.key.clone(),
+        };
+        let swapped = Tls::Files {
+            cert: generated.key.clone(),
+            key: generated.cert.clone(),
+        };
+        let invalid = Tls::Files {
+            cert: generated.cert.clone(),
+            key: garbage,
+        };
+        let missing = Tls::Files {
+            cert: dir.path().join("missing.cert"),
+            key: generated.key.clone(),
+        };
+
+        assert_eq!(
+            CertificateFiles::prepare(&files, dir.path(), socket).unwrap(),
+            generated
+        );
+        assert!(CertificateFiles::prepare(&swapped, dir.path(), socket).is_err());
+        assert!(CertificateFiles::prepare(&invalid, dir.path(), socket).is_err());
+        assert!(CertificateFiles::prepare(&missing, dir.path(), socket).is_err());
+    }
+}
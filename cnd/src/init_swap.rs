@@ -1,16 +1,32 @@
 use crate::{
-    db::AcceptedSwap,
+    asset, comit_api,
+    db::{
+        AcceptedSwap, LedgerSide, LoadAutomaticActions, LoadLedgerEvents, RollBackLedgerEvents,
+        SaveAutomaticAction, SaveLedgerEvent,
+    },
+    health::{self, EnsureAvailable},
+    identity,
     seed::DeriveSwapSeed,
     swap_protocols::{
+        actions::Actions,
+        han_halight::{self, halight::InvoiceEvents},
+        ledger::Ethereum,
         rfc003::{
-            alice, bob, create_swap,
-            events::{HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded},
+            actions::{ActionKind, RefundAction},
+            alice,
+            auto_refund::{refund_after_expiry, ExecuteRefund},
+            autopilot::{self, Autopilot, ExecuteAutomatically},
+            bob, create_swap,
+            create_swap::HtlcParams,
+            events::{HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded, TransactionFinality},
             state_store::StateStore,
-            Accept, Ledger, Request,
+            Accept, ActorState, Decline, Ledger, Request,
         },
+        state_changes::PublishStateChange,
         Role,
     },
 };
+use std::sync::Arc;
 
 #[allow(clippy::cognitive_complexity)]
 pub fn init_accepted_swap<D, AL, BL, AA, BA, AI, BI>(
@@ -29,22 +45,59 @@ where
         + HtlcRedeemed<AL, AA, AI>
         + HtlcRedeemed<BL, BA, BI>
         + HtlcRefunded<AL, AA, AI>
-        + HtlcRefunded<BL, BA, BI>,
+        + HtlcRefunded<BL, BA, BI>
+        + SaveLedgerEvent<AL, BL, AA, BA>
+        + LoadLedgerEvents<AL, BL, AA, BA>
+        + RollBackLedgerEvents
+        + TransactionFinality<AL>
+        + TransactionFinality<BL>
+        + SaveAutomaticAction
+        + LoadAutomaticActions
+        + ExecuteRefund<<(AL, AA) as RefundAction>::Output>
+        + ExecuteRefund<<(BL, BA) as RefundAction>::Output>
+        + PublishStateChange
+        + EnsureAvailable
+        + Autopilot
+        + ExecuteAutomatically<<alice::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>
+        + ExecuteAutomatically<<bob::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>,
     AL: Ledger,
     BL: Ledger,
+    comit_api::LedgerKind: From<AL> + From<BL>,
     AA: Ord + Clone + Send + Sync + 'static,
     BA: Ord + Clone + Send + Sync + 'static,
     AI: Clone + Send + Sync + 'static,
     BI: Clone + Send + Sync + 'static,
     Request<AL, BL, AA, BA, AI, BI>: Clone,
     Accept<AI, BI>: Copy,
+    (AL, AA): RefundAction<
+        HtlcParams = HtlcParams<AL, AA, AI>,
+        HtlcLocation = AL::HtlcLocation,
+        FundTransaction = AL::Transaction,
+    >,
+    (BL, BA): RefundAction<
+        HtlcParams = HtlcParams<BL, BA, BI>,
+        HtlcLocation = BL::HtlcLocation,
+        FundTransaction = BL::Transaction,
+    >,
+    alice::State<AL, BL, AA, BA, AI, BI>: Actions,
+    bob::State<AL, BL, AA, BA, AI, BI>: Actions,
+    <alice::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind: Send,
+    <bob::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind: Send,
+    for<'a> ActionKind: From<&'a <alice::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>
+        + From<&'a <bob::State<AL, BL, AA, BA, AI, BI> as Actions>::ActionKind>,
 {
     let (request, accept, _) = &accepted;
 
     let id = request.swap_id;
     let seed = dependencies.derive_swap_seed(id);
+    let ledgers = [
+        health::Ledger::from(comit_api::LedgerKind::from(request.alpha_ledger)),
+        health::Ledger::from(comit_api::LedgerKind::from(request.beta_ledger)),
+    ];
     tracing::trace!("initialising accepted swap: {}", id);
 
+    // Alice funds the alpha HTLC and Bob funds the beta HTLC, hence these are
+    // the ones we refund automatically.
     match role {
         Role::Alice => {
             let state = alice::State::accepted(request.clone(), *accept, seed);
@@ -53,9 +106,34 @@ where
             tokio::task::spawn(
                 create_swap::<D, alice::State<AL, BL, AA, BA, AI, BI>, AI, BI>(
                     dependencies.clone(),
-                    accepted,
+                    accepted.clone(),
                 ),
             );
+
+            tokio::task::spawn(refund_after_expiry::<
+                D,
+                alice::State<AL, BL, AA, BA, AI, BI>,
+                AL,
+                AA,
+                AI,
+            >(
+                dependencies.clone(),
+                id,
+                ledgers,
+                LedgerSide::Alpha,
+                HtlcParams::new_alpha_params(request, accept),
+                seed,
+                ActorState::alpha_ledger_mut,
+            ));
+
+            tokio::task::spawn(autopilot::drive::<D, alice::State<AL, BL, AA, BA, AI, BI>>(
+                dependencies.clone(),
+                id,
+                ledgers,
+                LedgerSide::Alpha,
+                request.alpha_expiry,
+                request.beta_expiry,
+            ));
         }
         Role::Bob => {
             let state = bob::State::accepted(request.clone(), *accept, seed);
@@ -64,11 +142,167 @@ where
             tokio::task::spawn(
                 create_swap::<D, bob::State<AL, BL, AA, BA, AI, BI>, AI, BI>(
                     dependencies.clone(),
-                    accepted,
+                    accepted.clone(),
                 ),
             );
+
+            tokio::task::spawn(refund_after_expiry::<
+                D,
+                bob::State<AL, BL, AA, BA, AI, BI>,
+                BL,
+                BA,
+                BI,
+            >(
+                dependencies.clone(),
+                id,
+                ledgers,
+                LedgerSide::Beta,
+                HtlcParams::new_beta_params(request, accept),
+                seed,
+                ActorState::beta_ledger_mut,
+            ));
+
+            tokio::task::spawn(autopilot::drive::<D, bob::State<AL, BL, AA, BA, AI, BI>>(
+                dependencies.clone(),
+                id,
+                ledgers,
+                LedgerSide::Beta,
+                request.beta_expiry,
+                request.alpha_expiry,
+            ));
         }
     };
 
     Ok(())
 }
+
+/// Restores the state of a swap whose request has not been answered yet.
+pub fn init_proposed_swap<D, AL, BL, AA, BA, AI, BI>(
+    dependencies: &D,
+    request: Request<AL, BL, AA, BA, AI, BI>,
+    role: Role,
+) where
+    D: StateStore + DeriveSwapSeed,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Send + 'static,
+    BA: Send + 'static,
+    AI: Send + 'static,
+    BI: Send + 'static,
+{
+    let id = request.swap_id;
+    let seed = dependencies.derive_swap_seed(id);
+    tracing::trace!("initialising proposed swap: {}", id);
+
+    match role {
+        Role::Alice => {
+            let state = alice::State::proposed(request, seed);
+            StateStore::insert(dependencies, id, state);
+        }
+        Role::Bob => {
+            let state = bob::State::proposed(request, seed);
+            StateStore::insert(dependencies, id, state);
+        }
+    };
+}
+
+/// Restores the state of a swap whose request has been declined.
+pub fn init_declined_swap<D, AL, BL, AA, BA, AI, BI>(
+    dependencies: &D,
+    request: Request<AL, BL, AA, BA, AI, BI>,
+    decline: Decline,
+    role: Role,
+) where
+    D: StateStore + DeriveSwapSeed,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Send + 'static,
+    BA: Send + 'static,
+    AI: Send + 'static,
+    BI: Send + 'static,
+{
+    let id = request.swap_id;
+    let seed = dependencies.derive_swap_seed(id);
+    tracing::trace!("initialising declined swap: {}", id);
+
+    match role {
+        Role::Alice => {
+            let state = alice::State::declined(request, decline, seed);
+            StateStore::insert(dependencies, id, state);
+        }
+        Role::Bob => {
+            let state = bob::State::declined(request, decline, seed);
+            StateStore::insert(dependencies, id, state);
+        }
+    };
+}
+
+pub fn init_accepted_han_halight_swap<D>(
+    dependencies: &D,
+    states: Arc<han_halight::States>,
+    accepted: han_halight::AcceptedSwap,
+    role: Role,
+) -> anyhow::Result<()>
+where
+    D: Clone
+        + DeriveSwapSeed
+        + HtlcFunded<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcDeployed<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcRedeemed<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcRefunded<Ethereum, asset::Ether, identity::Ethereum>
+        + InvoiceEvents,
+{
+    let (request, accept, _) = &accepted;
+
+    let id = request.swap_id;
+    let seed = dependencies.derive_swap_seed(id);
+    tracing::trace!("initialising accepted han-halight swap: {}", id);
+
+    let state = han_halight::State::accepted(role, request.clone(), *accept, seed);
+    states.insert(id, state);
+
+    tokio::task::spawn(han_halight::create_swap(
+        dependencies.clone(),
+        states,
+        accepted,
+        role,
+    ));
+
+    Ok(())
+}
+
+/// Restores the state of a han-halight swap whose request has not been
+/// answered yet.
+pub fn init_proposed_han_halight_swap<D>(
+    dependencies: &D,
+    states: Arc<han_halight::States>,
+    request: han_halight::Request,
+    role: Role,
+) where
+    D: DeriveSwapSeed,
+{
+    let id = request.swap_id;
+    let seed = dependencies.derive_swap_seed(id);
+    tracing::trace!("initialising proposed han-halight swap: {}", id);
+
+    let state = han_halight::State::proposed(role, request, seed);
+    states.insert(id, state);
+}
+
+/// Restores the state of a han-halight swap whose request has been declined.
+pub fn init_declined_han_halight_swap<D>(
+    dependencies: &D,
+    states: Arc<han_halight::States>,
+    request: han_halight::Request,
+    decline: han_halight::Decline,
+    role: Role,
+) where
+    D: DeriveSwapSeed,
+{
+    let id = request.swap_id;
+    let seed = dependencies.derive_swap_seed(id);
+    tracing::trace!("initialising declined han-halight swap: {}", id);
+
+    let state = han_halight::State::declined(role, request, decline, seed);
+    states.insert(id, state);
+}
@@ -35,9 +35,12 @@ pub mod btsieve;
 pub mod comit_api;
 pub mod config;
 pub mod ethereum;
+pub mod health;
 pub mod http_api;
 pub mod init_swap;
+pub mod lnd;
 pub mod load_swaps;
+pub mod metrics;
 #[macro_use]
 pub mod network;
 #[cfg(test)]
@@ -47,7 +50,9 @@ pub mod seed;
 #[cfg(test)]
 pub mod spectral_ext;
 pub mod swap_protocols;
+pub mod swap_statuses;
 pub mod timestamp;
+pub mod webhooks;
 
 use anyhow::Context;
 use std::{
@@ -58,7 +63,10 @@ use std::{
 /// Define domain specific terms using identity module so that we can refer to
 /// things in an ergonomic fashion e.g., `identity::Bitcoin`.
 pub mod identity {
-    pub use crate::{bitcoin::PublicKey as Bitcoin, ethereum::Address as Ethereum};
+    pub use crate::{
+        bitcoin::PublicKey as Bitcoin, bitcoin::PublicKey as Lightning,
+        ethereum::Address as Ethereum,
+    };
 }
 
 /// Define domain specific terms using transaction module so that we can refer
@@ -0,0 +1,1016 @@
+//! Connector to the REST API of an lnd node.
+//!
+//! lnd protects its REST API with a self-signed TLS certificate and
+//! macaroons, both of which are read from the lnd directory configured in
+//! `config::Lnd::dir`.
+
+use crate::{
+    asset, config, identity,
+    swap_protocols::{
+        han_halight::halight::{self, InvoiceEvents},
+        rfc003::{Secret, SecretHash},
+        Role,
+    },
+};
+use anyhow::Context;
+use reqwest::{Certificate, Client, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const MACAROON_HEADER: &str = "Grpc-Metadata-macaroon";
+const RESUBSCRIBE_MIN_DELAY: Duration = Duration::from_secs(1);
+const RESUBSCRIBE_MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+pub struct LndConnector {
+    base_url: Url,
+    client: Client,
+    admin_macaroon: Macaroon,
+    invoice_macaroon: Macaroon,
+}
+
+/// A macaroon, hex encoded as expected by the REST API of lnd.
+#[derive(Clone, PartialEq)]
+struct Macaroon(String);
+
+impl fmt::Debug for Macaroon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Macaroon(..)")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("lnd responded with {status}: {message}")]
+pub struct LndError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("lnd failed to send payment: {0}")]
+pub struct PaymentFailed(String);
+
+/// Returned for swaps over lightning if cnd has no connection to lnd.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("lnd is not configured")]
+pub struct LndNotConfigured;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InvoiceState {
+    Open,
+    Settled,
+    Canceled,
+    Accepted,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Invoice {
+    pub state: InvoiceState,
+    #[serde(default)]
+    r_preimage: Option<String>,
+}
+
+impl Invoice {
+    /// The preimage of the invoice, only known once it is settled.
+    pub fn preimage(&self) -> anyhow::Result<Option<Secret>> {
+        match &self.r_preimage {
+            None => Ok(None),
+            Some(r_preimage) if r_preimage.is_empty() => Ok(None),
+            Some(r_preimage) => {
+                let bytes = base64::decode(r_preimage).context("preimage is not base64")?;
+                let secret = Secret::from_vec(&bytes)?;
+
+                Ok(Some(secret))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PaymentStatus {
+    Unknown,
+    InFlight,
+    Succeeded,
+    Failed,
+}
+
+/// A payment made by this node as reported by the router of lnd.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Payment {
+    pub status: PaymentStatus,
+    #[serde(default)]
+    payment_preimage: Option<String>,
+}
+
+impl Payment {
+    /// The preimage the receiver revealed, only known once the payment
+    /// succeeded.
+    pub fn preimage(&self) -> anyhow::Result<Option<Secret>> {
+        match &self.payment_preimage {
+            Some(payment_preimage) if self.status == PaymentStatus::Succeeded => {
+                let bytes = hex::decode(payment_preimage).context("preimage is not hex")?;
+                let secret = Secret::from_vec(&bytes)?;
+
+                Ok(Some(secret))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AddHoldInvoiceRequest {
+    hash: String,
+    value: String,
+    expiry: String,
+    cltv_expiry: String,
+}
+
+#[derive(Deserialize)]
+struct AddHoldInvoiceResponse {
+    payment_request: String,
+}
+
+#[derive(Serialize)]
+struct SettleInvoiceRequest {
+    preimage: String,
+}
+
+#[derive(Serialize)]
+struct CancelInvoiceRequest {
+    payment_hash: String,
+}
+
+#[derive(Serialize)]
+struct SendPaymentRequest {
+    dest: String,
+    amt: String,
+    payment_hash: String,
+    final_cltv_delta: u32,
+}
+
+#[derive(Deserialize)]
+struct SendPaymentResponse {
+    #[serde(default)]
+    payment_error: String,
+    #[serde(default)]
+    payment_preimage: Option<String>,
+}
+
+/// lnd streams either an update or the error that ended the stream.
+#[derive(Deserialize)]
+struct SubscriptionMessage<T> {
+    result: Option<T>,
+    error: Option<SubscriptionError>,
+}
+
+#[derive(Deserialize)]
+struct SubscriptionError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Empty {}
+
+/// The chain lnd is on and the best block it knows about.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Info {
+    /// `None` if lnd is on a network cnd does not know.
+    pub network: Option<bitcoin::Network>,
+    pub block_height: u32,
+    pub best_header_timestamp: u32,
+}
+
+#[derive(Deserialize)]
+struct GetInfoResponse {
+    block_height: u32,
+    /// 64 bit integers are encoded as strings by the REST API.
+    best_header_timestamp: String,
+    #[serde(default)]
+    chains: Vec<Chain>,
+}
+
+#[derive(Deserialize)]
+struct Chain {
+    chain: String,
+    network: String,
+}
+
+impl LndConnector {
+    /// Connects to the lnd described by `lnd`, reading `tls.cert` from its
+    /// directory and the admin and invoice macaroons from the directory of the
+    /// given network.
+    pub fn new(lnd: config::Lnd, network: bitcoin::Network) -> anyhow::Result<Self> {
+        let socket = lnd
+            .rest_api_socket
+            .context("lnd rest api socket is not configured")?;
+        let dir = lnd.dir.context("lnd directory is not configured")?;
+
+        let certificate = read_certificate(&dir.join("tls.cert"))?;
+        let macaroon_dir = macaroon_dir(&dir, network);
+        let admin_macaroon = read_macaroon(&macaroon_dir.join("admin.macaroon"))?;
+        let invoice_macaroon = read_macaroon(&macaroon_dir.join("invoice.macaroon"))?;
+
+        let client = Client::builder()
+            .add_root_certificate(certificate)
+            .build()
+            .context("failed to build http client for lnd")?;
+        let base_url = format!("https://{}", socket).parse()?;
+
+        Ok(Self {
+            base_url,
+            client,
+            admin_macaroon,
+            invoice_macaroon,
+        })
+    }
+
+    /// Adds a hold invoice for the given secret hash, returning its payment
+    /// request.
+    pub async fn add_hold_invoice(
+        &self,
+        amount: asset::Bitcoin,
+        secret_hash: SecretHash,
+        expiry: u32,
+        cltv_expiry: u32,
+    ) -> anyhow::Result<String> {
+        let body = AddHoldInvoiceRequest {
+            hash: base64::encode(secret_hash.as_raw()),
+            value: amount.as_sat().to_string(),
+            expiry: expiry.to_string(),
+            cltv_expiry: cltv_expiry.to_string(),
+        };
+
+        let response: AddHoldInvoiceResponse = self
+            .post("v2/invoices/hodl", &self.invoice_macaroon, &body)
+            .await?;
+
+        Ok(response.payment_request)
+    }
+
+    pub async fn settle_invoice(&self, secret: Secret) -> anyhow::Result<()> {
+        let body = SettleInvoiceRequest {
+            preimage: base64::encode(secret.as_raw_secret()),
+        };
+
+        let _: Empty = self
+            .post("v2/invoices/settle", &self.invoice_macaroon, &body)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn cancel_invoice(&self, secret_hash: SecretHash) -> anyhow::Result<()> {
+        let body = CancelInvoiceRequest {
+            payment_hash: base64::encode(secret_hash.as_raw()),
+        };
+
+        let _: Empty = self
+            .post("v2/invoices/cancel", &self.invoice_macaroon, &body)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Pays the invoice identified by `secret_hash`, returning the secret once
+    /// the receiver settled it.
+    pub async fn send_payment(
+        &self,
+        to_public_key: identity::Lightning,
+        amount: asset::Bitcoin,
+        secret_hash: SecretHash,
+        final_cltv_delta: u32,
+    ) -> anyhow::Result<Secret> {
+        let body = SendPaymentRequest {
+            dest: base64::encode(&::bitcoin::PublicKey::from(to_public_key).key.serialize()[..]),
+            amt: amount.as_sat().to_string(),
+            payment_hash: base64::encode(secret_hash.as_raw()),
+            final_cltv_delta,
+        };
+
+        let response: SendPaymentResponse = self
+            .post("v1/channels/transactions", &self.admin_macaroon, &body)
+            .await?;
+
+        if !response.payment_error.is_empty() {
+            return Err(anyhow::Error::from(PaymentFailed(response.payment_error)));
+        }
+
+        let preimage = response
+            .payment_preimage
+            .context("lnd did not return the preimage of a successful payment")?;
+        let secret = Secret::from_vec(&base64::decode(&preimage)?)?;
+
+        Ok(secret)
+    }
+
+    pub async fn get_info(&self) -> anyhow::Result<Info> {
+        let response: GetInfoResponse = self.get("v1/getinfo", &self.admin_macaroon).await?;
+
+        let network = response
+            .chains
+            .iter()
+            .find(|chain| chain.chain == "bitcoin")
+            .and_then(|chain| network_from_name(&chain.network));
+        let best_header_timestamp = response
+            .best_header_timestamp
+            .parse()
+            .context("lnd returned an invalid best header timestamp")?;
+
+        Ok(Info {
+            network,
+            block_height: response.block_height,
+            best_header_timestamp,
+        })
+    }
+
+    /// Subscribes to the updates of the invoice identified by `secret_hash`.
+    ///
+    /// The first update lnd sends is the current state of the invoice.
+    pub async fn subscribe_to_invoice(
+        &self,
+        secret_hash: SecretHash,
+    ) -> anyhow::Result<Subscription<Invoice>> {
+        let r_hash = base64::encode_config(secret_hash.as_raw(), base64::URL_SAFE);
+
+        self.subscribe(
+            &format!("v2/invoices/subscribe/{}", r_hash),
+            &self.invoice_macaroon,
+        )
+        .await
+    }
+
+    /// Subscribes to the updates of the payment this node made for the
+    /// invoice identified by `secret_hash`.
+    ///
+    /// The first update lnd sends is the current state of the payment, lnd
+    /// responds with an error if no such payment was made yet.
+    pub async fn track_payment(
+        &self,
+        secret_hash: SecretHash,
+    ) -> anyhow::Result<Subscription<Payment>> {
+        let payment_hash = base64::encode_config(secret_hash.as_raw(), base64::URL_SAFE);
+
+        self.subscribe(
+            &format!("v2/router/track/{}", payment_hash),
+            &self.admin_macaroon,
+        )
+        .await
+    }
+
+    /// Waits until the invoice identified by `secret_hash` reaches one of the
+    /// given states.
+    ///
+    /// lnd ends the subscription if it shuts down or the invoice reaches a
+    /// final state, it is resubscribed to with an increasing delay until the
+    /// invoice reaches one of the given states or lnd cannot be reached.
+    async fn wait_for_invoice(
+        &self,
+        secret_hash: SecretHash,
+        states: &[InvoiceState],
+    ) -> anyhow::Result<Invoice> {
+        let mut delay = RESUBSCRIBE_MIN_DELAY;
+
+        loop {
+            let mut subscription = self.subscribe_to_invoice(secret_hash).await?;
+
+            while let Some(invoice) = subscription.next().await? {
+                if states.contains(&invoice.state) {
+                    return Ok(invoice);
+                }
+            }
+
+            tracing::debug!(
+                "invoice subscription for {} ended, resubscribing in {:?}",
+                secret_hash,
+                delay
+            );
+            tokio::time::delay_for(delay).await;
+            delay = std::cmp::min(delay * 2, RESUBSCRIBE_MAX_DELAY);
+        }
+    }
+
+    /// Waits until the payment for the invoice identified by `secret_hash`
+    /// reaches one of the given states.
+    ///
+    /// The payment is looked for again with an increasing delay as long as lnd
+    /// does not know about it or ends the subscription, i.e. until it was made
+    /// and reached one of the given states or lnd cannot be reached.
+    async fn wait_for_payment(
+        &self,
+        secret_hash: SecretHash,
+        states: &[PaymentStatus],
+    ) -> anyhow::Result<Payment> {
+        let mut delay = RESUBSCRIBE_MIN_DELAY;
+
+        loop {
+            let error = match self.track_payment(secret_hash).await {
+                Ok(mut subscription) => loop {
+                    match subscription.next().await {
+                        Ok(Some(payment)) if states.contains(&payment.status) => {
+                            return Ok(payment)
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => break None,
+                        Err(e) => break Some(e),
+                    }
+                },
+                Err(e) => Some(e),
+            };
+
+            match error {
+                // lnd does not know about the payment yet
+                Some(e) if e.is::<LndError>() => tracing::debug!(
+                    "payment for {} cannot be tracked, retrying in {:?}: {}",
+                    secret_hash,
+                    delay,
+                    e
+                ),
+                Some(e) => return Err(e),
+                None => tracing::debug!(
+                    "payment subscription for {} ended, resubscribing in {:?}",
+                    secret_hash,
+                    delay
+                ),
+            }
+
+            tokio::time::delay_for(delay).await;
+            delay = std::cmp::min(delay * 2, RESUBSCRIBE_MAX_DELAY);
+        }
+    }
+
+    async fn subscribe<T>(
+        &self,
+        path: &str,
+        macaroon: &Macaroon,
+    ) -> anyhow::Result<Subscription<T>> {
+        let url = self.base_url.join(path)?;
+
+        let response = self
+            .client
+            .get(url)
+            .header(MACAROON_HEADER, &macaroon.0)
+            .send()
+            .await?;
+        let response = error_for_status(response).await?;
+
+        Ok(Subscription {
+            response,
+            buffer: Vec::new(),
+            update: PhantomData,
+        })
+    }
+
+    async fn get<R>(&self, path: &str, macaroon: &Macaroon) -> anyhow::Result<R>
+    where
+        R: DeserializeOwned,
+    {
+        let url = self.base_url.join(path)?;
+
+        let response = self
+            .client
+            .get(url)
+            .header(MACAROON_HEADER, &macaroon.0)
+            .send()
+            .await?;
+        let response = error_for_status(response).await?;
+
+        Ok(response.json::<R>().await?)
+    }
+
+    async fn post<B, R>(&self, path: &str, macaroon: &Macaroon, body: &B) -> anyhow::Result<R>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        let url = self.base_url.join(path)?;
+
+        let response = self
+            .client
+            .post(url)
+            .header(MACAROON_HEADER, &macaroon.0)
+            .json(body)
+            .send()
+            .await?;
+        let response = error_for_status(response).await?;
+
+        Ok(response.json::<R>().await?)
+    }
+}
+
+/// The updates of a single invoice or payment as streamed by lnd, one JSON
+/// object per line.
+#[derive(Debug)]
+pub struct Subscription<T> {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    update: PhantomData<fn() -> T>,
+}
+
+impl<T> Subscription<T>
+where
+    T: DeserializeOwned,
+{
+    /// Returns the next update or `None` if lnd closed the subscription.
+    pub async fn next(&mut self) -> anyhow::Result<Option<T>> {
+        loop {
+            if let Some(position) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line = self.buffer.drain(..=position).collect::<Vec<u8>>();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+
+                let message = serde_json::from_slice::<SubscriptionMessage<T>>(&line)
+                    .context("failed to deserialize update")?;
+
+                return match (message.result, message.error) {
+                    (Some(update), _) => Ok(Some(update)),
+                    (None, error) => Err(anyhow::Error::from(LndError {
+                        status: self.response.status(),
+                        message: error.map(|error| error.message).unwrap_or_default(),
+                    })),
+                };
+            }
+
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None if self.buffer.iter().all(u8::is_ascii_whitespace) => return Ok(None),
+                // The last update is not necessarily terminated by a newline.
+                None => self.buffer.push(b'\n'),
+            }
+        }
+    }
+}
+
+/// Resolves the invoice events through lnd.
+///
+/// Alice added the invoice, her events come from subscribing to it. Bob's node
+/// does not know the invoice, his events come from tracking his payment.
+#[async_trait::async_trait]
+impl InvoiceEvents for LndConnector {
+    async fn invoice_opened(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Opened> {
+        use self::InvoiceState::*;
+
+        match role {
+            Role::Alice => {
+                self.wait_for_invoice(params.secret_hash, &[Open, Accepted, Settled, Canceled])
+                    .await?;
+            }
+            // Bob cannot see the invoice. Alice only funds the HTLC once she
+            // added it and Bob only pays once the HTLC is funded.
+            Role::Bob => {}
+        }
+
+        Ok(halight::Opened)
+    }
+
+    async fn invoice_accepted(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Accepted> {
+        use self::{InvoiceState::*, PaymentStatus::*};
+
+        match role {
+            Role::Alice => {
+                self.wait_for_invoice(params.secret_hash, &[Accepted, Settled])
+                    .await?;
+            }
+            Role::Bob => {
+                self.wait_for_payment(params.secret_hash, &[InFlight, Succeeded])
+                    .await?;
+            }
+        }
+
+        Ok(halight::Accepted)
+    }
+
+    async fn invoice_settled(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Settled> {
+        let secret = match role {
+            Role::Alice => self
+                .wait_for_invoice(params.secret_hash, &[InvoiceState::Settled])
+                .await?
+                .preimage()?
+                .context("lnd did not return the preimage of a settled invoice")?,
+            Role::Bob => self
+                .wait_for_payment(params.secret_hash, &[PaymentStatus::Succeeded])
+                .await?
+                .preimage()?
+                .context("lnd did not return the preimage of a successful payment")?,
+        };
+
+        Ok(halight::Settled { secret })
+    }
+
+    async fn invoice_cancelled(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Cancelled> {
+        match role {
+            Role::Alice => {
+                self.wait_for_invoice(params.secret_hash, &[InvoiceState::Canceled])
+                    .await?;
+            }
+            Role::Bob => {
+                self.wait_for_payment(params.secret_hash, &[PaymentStatus::Failed])
+                    .await?;
+            }
+        }
+
+        Ok(halight::Cancelled)
+    }
+}
+
+async fn error_for_status(response: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let message = response.text().await.unwrap_or_default();
+
+    Err(anyhow::Error::from(LndError { status, message }))
+}
+
+/// The directory lnd stores the macaroons for `network` in.
+fn macaroon_dir(lnd_dir: &Path, network: bitcoin::Network) -> PathBuf {
+    let network = match network {
+        bitcoin::Network::Bitcoin => "mainnet",
+        bitcoin::Network::Testnet => "testnet",
+        bitcoin::Network::Regtest => "regtest",
+    };
+
+    lnd_dir
+        .join("data")
+        .join("chain")
+        .join("bitcoin")
+        .join(network)
+}
+
+/// The network of the name lnd uses for it, the reverse of `macaroon_dir`.
+fn network_from_name(name: &str) -> Option<bitcoin::Network> {
+    match name {
+        "mainnet" => Some(bitcoin::Network::Bitcoin),
+        "testnet" => Some(bitcoin::Network::Testnet),
+        "regtest" => Some(bitcoin::Network::Regtest),
+        _ => None,
+    }
+}
+
+fn read_certificate(path: &Path) -> anyhow::Result<Certificate> {
+    let pem = fs::read(path)
+        .with_context(|| format!("failed to read lnd tls cert {}", path.display()))?;
+
+    Certificate::from_pem(&pem)
+        .with_context(|| format!("failed to parse lnd tls cert {}", path.display()))
+}
+
+fn read_macaroon(path: &Path) -> anyhow::Result<Macaroon> {
+    let bytes =
+        fs::read(path).with_context(|| format!("failed to read macaroon {}", path.display()))?;
+
+    Ok(Macaroon(hex::encode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+    use warp::{Filter, Rejection, Reply};
+
+    const ADMIN_MACAROON: &str = "0201036c6e64";
+    const INVOICE_MACAROON: &str = "0201036c6e65";
+
+    fn secret() -> Secret {
+        Secret::from(*b"hello world, you are beautiful!!")
+    }
+
+    fn connector(socket: SocketAddr) -> LndConnector {
+        LndConnector {
+            base_url: format!("http://{}", socket).parse().unwrap(),
+            client: Client::new(),
+            admin_macaroon: Macaroon(ADMIN_MACAROON.to_owned()),
+            invoice_macaroon: Macaroon(INVOICE_MACAROON.to_owned()),
+        }
+    }
+
+    /// Serves `routes` on an ephemeral local port, returning its address.
+    fn serve<F>(routes: F) -> SocketAddr
+    where
+        F: Filter<Error = Rejection> + Clone + Send + Sync + 'static,
+        F::Extract: Reply,
+    {
+        let (socket, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        socket
+    }
+
+    #[tokio::test]
+    async fn add_hold_invoice_posts_invoice_with_invoice_macaroon() {
+        let routes = warp::post()
+            .and(warp::path!("v2" / "invoices" / "hodl"))
+            .and(warp::header::exact(MACAROON_HEADER, INVOICE_MACAROON))
+            .and(warp::body::json())
+            .map(|body: serde_json::Value| {
+                let expected = serde_json::json!({
+                    "hash": base64::encode(secret().hash().as_raw()),
+                    "value": "100000",
+                    "expiry": "3600",
+                    "cltv_expiry": "144",
+                });
+                if body == expected {
+                    warp::reply::json(&serde_json::json!({ "payment_request": "lnbcrt1m" }))
+                } else {
+                    warp::reply::json(&serde_json::json!({ "payment_request": "unexpected" }))
+                }
+            });
+        let connector = connector(serve(routes));
+
+        let payment_request = connector
+            .add_hold_invoice(
+                asset::Bitcoin::from_sat(100_000),
+                secret().hash(),
+                3600,
+                144,
+            )
+            .await;
+
+        assert_that(&payment_request)
+            .is_ok()
+            .is_equal_to("lnbcrt1m".to_owned());
+    }
+
+    #[tokio::test]
+    async fn settle_invoice_posts_preimage() {
+        let routes = warp::post()
+            .and(warp::path!("v2" / "invoices" / "settle"))
+            .and(warp::header::exact(MACAROON_HEADER, INVOICE_MACAROON))
+            .and(warp::body::json())
+            .map(|body: serde_json::Value| {
+                if body
+                    == serde_json::json!({ "preimage": base64::encode(secret().as_raw_secret()) })
+                {
+                    warp::reply::with_status(
+                        warp::reply::json(&serde_json::json!({})),
+                        StatusCode::OK,
+                    )
+                } else {
+                    warp::reply::with_status(
+                        warp::reply::json(&serde_json::json!({})),
+                        StatusCode::BAD_REQUEST,
+                    )
+                }
+            });
+        let connector = connector(serve(routes));
+
+        let result = connector.settle_invoice(secret()).await;
+
+        assert_that(&result).is_ok();
+    }
+
+    #[tokio::test]
+    async fn get_info_reads_network_and_best_block() {
+        let routes = warp::get()
+            .and(warp::path!("v1" / "getinfo"))
+            .and(warp::header::exact(MACAROON_HEADER, ADMIN_MACAROON))
+            .map(|| {
+                warp::reply::json(&serde_json::json!({
+                    "block_height": 512,
+                    "best_header_timestamp": "1588000000",
+                    "synced_to_chain": true,
+                    "chains": [{ "chain": "bitcoin", "network": "regtest" }],
+                }))
+            });
+        let connector = connector(serve(routes));
+
+        let info = connector.get_info().await;
+
+        assert_that(&info).is_ok().is_equal_to(Info {
+            network: Some(bitcoin::Network::Regtest),
+            block_height: 512,
+            best_header_timestamp: 1_588_000_000,
+        });
+    }
+
+    #[tokio::test]
+    async fn lnd_error_is_returned() {
+        let routes = warp::post()
+            .and(warp::path!("v2" / "invoices" / "cancel"))
+            .map(|| {
+                warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": "unable to locate invoice" })),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )
+            });
+        let connector = connector(serve(routes));
+
+        let result = connector.cancel_invoice(secret().hash()).await;
+
+        let error = result.unwrap_err();
+        let error = error.downcast_ref::<LndError>().unwrap();
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn send_payment_returns_preimage() {
+        let routes = warp::post()
+            .and(warp::path!("v1" / "channels" / "transactions"))
+            .and(warp::header::exact(MACAROON_HEADER, ADMIN_MACAROON))
+            .map(|| {
+                warp::reply::json(&serde_json::json!({
+                    "payment_error": "",
+                    "payment_preimage": base64::encode(secret().as_raw_secret()),
+                }))
+            });
+        let connector = connector(serve(routes));
+        let to_public_key = "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+            .parse()
+            .unwrap();
+
+        let result = connector
+            .send_payment(
+                to_public_key,
+                asset::Bitcoin::from_sat(100_000),
+                secret().hash(),
+                144,
+            )
+            .await;
+
+        assert_that(&result).is_ok().is_equal_to(secret());
+    }
+
+    #[tokio::test]
+    async fn send_payment_fails_with_payment_error() {
+        let routes = warp::post()
+            .and(warp::path!("v1" / "channels" / "transactions"))
+            .map(|| warp::reply::json(&serde_json::json!({ "payment_error": "no route" })));
+        let connector = connector(serve(routes));
+        let to_public_key = "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+            .parse()
+            .unwrap();
+
+        let result = connector
+            .send_payment(
+                to_public_key,
+                asset::Bitcoin::from_sat(100_000),
+                secret().hash(),
+                144,
+            )
+            .await;
+
+        assert!(result.unwrap_err().is::<PaymentFailed>());
+    }
+
+    #[tokio::test]
+    async fn invoice_subscription_yields_updates_until_settled() {
+        let routes = warp::get()
+            .and(warp::path!("v2" / "invoices" / "subscribe" / String))
+            .and(warp::header::exact(MACAROON_HEADER, INVOICE_MACAROON))
+            .map(|_r_hash: String| {
+                let preimage = base64::encode(secret().as_raw_secret());
+                format!(
+                    "{}\n{}\n{}\n",
+                    r#"{"result":{"state":"OPEN"}}"#,
+                    r#"{"result":{"state":"ACCEPTED"}}"#,
+                    format!(
+                        r#"{{"result":{{"state":"SETTLED","r_preimage":"{}"}}}}"#,
+                        preimage
+                    ),
+                )
+            });
+        let connector = connector(serve(routes));
+        let mut subscription = connector
+            .subscribe_to_invoice(secret().hash())
+            .await
+            .unwrap();
+
+        let states = vec![
+            subscription.next().await.unwrap().unwrap().state,
+            subscription.next().await.unwrap().unwrap().state,
+        ];
+        let settled = subscription.next().await.unwrap().unwrap();
+
+        assert_eq!(states, vec![InvoiceState::Open, InvoiceState::Accepted]);
+        assert_eq!(settled.state, InvoiceState::Settled);
+        assert_eq!(settled.preimage().unwrap(), Some(secret()));
+        assert!(subscription.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn invoice_is_resubscribed_to_if_lnd_ends_the_subscription() {
+        let subscriptions = Arc::new(AtomicUsize::new(0));
+        let routes = warp::get()
+            .and(warp::path!("v2" / "invoices" / "subscribe" / String))
+            .map({
+                let subscriptions = Arc::clone(&subscriptions);
+                move |_r_hash: String| match subscriptions.fetch_add(1, Ordering::SeqCst) {
+                    0 => r#"{"result":{"state":"OPEN"}}"#,
+                    _ => r#"{"result":{"state":"CANCELED"}}"#,
+                }
+            });
+        let connector = connector(serve(routes));
+        let identity = "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+            .parse()
+            .unwrap();
+        let params = halight::Params {
+            asset: asset::Bitcoin::from_sat(100_000),
+            ledger: Default::default(),
+            redeem_identity: identity,
+            refund_identity: identity,
+            cltv_expiry: 144,
+            secret_hash: secret().hash(),
+        };
+
+        let cancelled = connector.invoice_cancelled(params, Role::Alice).await;
+
+        assert_that(&cancelled).is_ok();
+        assert_eq!(subscriptions.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn bob_sees_the_invoice_settled_through_his_payment() {
+        let tracked = Arc::new(AtomicUsize::new(0));
+        let routes = warp::get()
+            .and(warp::path!("v2" / "router" / "track" / String))
+            .and(warp::header::exact(MACAROON_HEADER, ADMIN_MACAROON))
+            .map({
+                let tracked = Arc::clone(&tracked);
+                move |_payment_hash: String| match tracked.fetch_add(1, Ordering::SeqCst) {
+                    0 => r#"{"error":{"code":5,"message":"payment isn't initiated"}}"#.to_owned(),
+                    _ => format!(
+                        "{}\n{}\n",
+                        r#"{"result":{"status":"IN_FLIGHT"}}"#,
+                        format!(
+                            r#"{{"result":{{"status":"SUCCEEDED","payment_preimage":"{}"}}}}"#,
+                            hex::encode(secret().as_raw_secret())
+                        ),
+                    ),
+                }
+            });
+        let connector = connector(serve(routes));
+        let identity = "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+            .parse()
+            .unwrap();
+        let params = halight::Params {
+            asset: asset::Bitcoin::from_sat(100_000),
+            ledger: Default::default(),
+            redeem_identity: identity,
+            refund_identity: identity,
+            cltv_expiry: 144,
+            secret_hash: secret().hash(),
+        };
+
+        let settled = connector.invoice_settled(params, Role::Bob).await;
+
+        assert_that(&settled)
+            .is_ok()
+            .is_equal_to(halight::Settled { secret: secret() });
+        assert_eq!(tracked.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn macaroon_is_read_hex_encoded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invoice.macaroon");
+        fs::write(&path, [0x02, 0x01, 0x03]).unwrap();
+
+        let macaroon = read_macaroon(&path).unwrap();
+
+        assert_eq!(macaroon, Macaroon("020103".to_owned()));
+    }
+
+    #[test]
+    fn macaroons_are_looked_up_in_network_directory() {
+        let dir = macaroon_dir(Path::new("/home/alice/.lnd"), bitcoin::Network::Bitcoin);
+
+        assert_eq!(
+            dir,
+            PathBuf::from("/home/alice/.lnd/data/chain/bitcoin/mainnet")
+        );
+    }
+}
@@ -1,10 +1,20 @@
 #![allow(clippy::type_repetition_in_bounds)]
 use crate::{
-    db::{DetermineTypes, LoadAcceptedSwap, Retrieve},
-    init_swap::init_accepted_swap,
-    swap_protocols::Facade,
+    db::{
+        self, DetermineTypes, LoadAcceptedSwap, LoadDecline, LoadHanHalightSwaps, LoadRequest,
+        Retrieve, Save, Swap,
+    },
+    init_swap::{
+        init_accepted_han_halight_swap, init_accepted_swap, init_declined_han_halight_swap,
+        init_declined_swap, init_proposed_han_halight_swap, init_proposed_swap,
+    },
+    swap_protocols::{rfc003::Decline, Facade, Role, SwapId},
 };
 
+/// Restores the state of every swap in the database.
+///
+/// A swap that cannot be restored is logged and skipped, it must not keep the
+/// other swaps from being restored.
 #[allow(clippy::cognitive_complexity)]
 pub async fn load_swaps_from_database(facade: Facade) -> anyhow::Result<()> {
     tracing::debug!("loading swaps from database ...");
@@ -15,18 +25,116 @@ pub async fn load_swaps_from_database(facade: Facade) -> anyhow::Result<()> {
 
         let types = DetermineTypes::determine_types(&facade, &swap_id).await?;
 
-        with_swap_types!(types, {
-            let accepted =
-                LoadAcceptedSwap::<AL, BL, AA, BA, AI, BI>::load_accepted_swap(&facade, &swap_id)
-                    .await;
+        let restored = with_swap_types!(types, {
+            async {
+                if let Some(decline) = facade.load_decline(&swap_id).await? {
+                    let request =
+                        LoadRequest::<AL, BL, AA, BA, AI, BI>::load_request(&facade, &swap_id)
+                            .await?;
+                    init_declined_swap(&facade, request, decline, types.role);
 
-            match accepted {
-                Ok(accepted) => {
-                    init_accepted_swap(&facade, accepted, types.role)?;
+                    return Ok(());
                 }
-                Err(e) => tracing::error!("failed to load swap: {}, continuing ...", e),
-            };
+
+                let accepted = LoadAcceptedSwap::<AL, BL, AA, BA, AI, BI>::load_accepted_swap(
+                    &facade, &swap_id,
+                )
+                .await;
+
+                match accepted {
+                    Ok(accepted) => init_accepted_swap(&facade, accepted, types.role)?,
+                    Err(e) if is_not_found(&e) => {
+                        let request =
+                            LoadRequest::<AL, BL, AA, BA, AI, BI>::load_request(&facade, &swap_id)
+                                .await?;
+
+                        match types.role {
+                            Role::Alice => init_proposed_swap(&facade, request, types.role),
+                            Role::Bob => {
+                                let decline = expire(&facade, swap_id).await?;
+                                init_declined_swap(&facade, request, decline, types.role);
+                            }
+                        }
+                    }
+                    Err(e) => return Err(e),
+                };
+
+                Ok::<(), anyhow::Error>(())
+            }
+            .await
         });
+
+        if let Err(e) = restored {
+            tracing::error!("failed to load swap: {:#}, continuing ...", e);
+        }
+    }
+
+    for swap in LoadHanHalightSwaps::han_halight_swaps(&facade)
+        .await?
+        .iter()
+    {
+        tracing::debug!("got han-halight swap from database: {}", swap.swap_id);
+
+        if let Err(e) = load_han_halight_swap(&facade, swap).await {
+            tracing::error!("failed to load swap: {:#}, continuing ...", e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_han_halight_swap(facade: &Facade, swap: &Swap) -> anyhow::Result<()> {
+    let swap_id = swap.swap_id;
+    let states = facade.han_halight_states.clone();
+
+    if let Some(decline) = facade.load_decline(&swap_id).await? {
+        let request = facade.load_han_halight_request(&swap_id).await?;
+        init_declined_han_halight_swap(facade, states, request, decline, swap.role);
+
+        return Ok(());
     }
+
+    match facade.load_han_halight_accepted_swap(&swap_id).await {
+        Ok(accepted) => init_accepted_han_halight_swap(facade, states, accepted, swap.role)?,
+        Err(e) if is_not_found(&e) => {
+            let request = facade.load_han_halight_request(&swap_id).await?;
+
+            match swap.role {
+                Role::Alice => init_proposed_han_halight_swap(facade, states, request, swap.role),
+                Role::Bob => {
+                    let decline = expire(facade, swap_id).await?;
+                    init_declined_han_halight_swap(facade, states, request, decline, swap.role);
+                }
+            }
+        }
+        Err(e) => return Err(e),
+    };
+
     Ok(())
 }
+
+/// Declines a request Bob did not answer before the restart.
+///
+/// The response channel to Alice did not survive the restart, the request can
+/// therefore not be answered anymore.
+async fn expire(facade: &Facade, swap_id: SwapId) -> anyhow::Result<Decline> {
+    tracing::info!("swap request {} expired, declining it", swap_id);
+
+    let decline = Decline {
+        swap_id,
+        reason: None,
+    };
+    Save::save(facade, decline).await?;
+
+    Ok(decline)
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    match (
+        e.downcast_ref::<diesel::result::Error>(),
+        e.downcast_ref::<db::Error>(),
+    ) {
+        (Some(diesel::result::Error::NotFound), _) | (_, Some(db::Error::SwapNotFound)) => true,
+        _ => false,
+    }
+}
@@ -15,14 +15,33 @@
 use crate::cli::Options;
 use anyhow::Context;
 use cnd::{
-    btsieve::{bitcoin, bitcoin::BitcoindConnector, ethereum, ethereum::Web3Connector},
+    bitcoin::{
+        fee_estimates::{self, FeeEstimates},
+        wallet as bitcoin_wallet,
+    },
+    btsieve::{
+        bitcoin,
+        bitcoin::{BitcoinConnector, BitcoindConnector, EsploraConnector},
+        disk_cache::DiskCache,
+        ethereum,
+        ethereum::Web3Connector,
+    },
     config::{self, Settings},
     db::Sqlite,
-    http_api::route_factory,
+    ethereum::wallet as ethereum_wallet,
+    health::{self, Health},
+    http_api::{auth::Authenticator, route_factory, tls::CertificateFiles},
+    lnd::LndConnector,
     load_swaps,
     network::Swarm,
     seed::RootSeed,
-    swap_protocols::{rfc003::state_store::InMemoryStateStore, Facade},
+    swap_protocols::{
+        han_halight,
+        rfc003::state_store::InMemoryStateStore,
+        state_changes::StateChanges,
+        Facade,
+    },
+    swap_statuses, webhooks,
 };
 use rand::rngs::OsRng;
 use std::{process, sync::Arc};
@@ -51,30 +70,163 @@ fn main() -> anyhow::Result<()> {
 
     let seed = RootSeed::from_dir_or_generate(&settings.data.dir, OsRng)?;
 
+    let authenticator = Authenticator::new(&settings.http_api.auth, &seed);
+    let tokens_dir = settings.data.dir.join("tokens");
+    authenticator
+        .write_scoped_tokens(&tokens_dir)
+        .context("failed to write the tokens of the HTTP API")?;
+    if let config::settings::Auth::ScopedTokens = settings.http_api.auth {
+        tracing::info!("Tokens of the HTTP API are in {}", tokens_dir.display());
+    }
+    let certificate_files = match &settings.http_api.tls {
+        Some(tls) => Some(CertificateFiles::prepare(
+            tls,
+            &settings.data.dir,
+            settings.http_api.socket,
+        )?),
+        None => None,
+    };
+
     let mut runtime = runtime::Builder::new()
         .stack_size(1024 * 1024 * 8) // the default is 2MB but that causes a segfault for some reason
         .build()?;
 
-    const BITCOIN_BLOCK_CACHE_CAPACITY: usize = 144;
+    let cache = settings.cache.clone();
+    let cache_dir = settings.data.dir.join("cache");
+
     let bitcoin_connector = {
-        let config::Bitcoin { network, bitcoind } = settings.clone().bitcoin;
-        bitcoin::Cache::new(
-            BitcoindConnector::new(bitcoind.node_url, network)?,
-            BITCOIN_BLOCK_CACHE_CAPACITY,
-        )
+        let config::Bitcoin {
+            network,
+            bitcoind,
+            esplora,
+            ..
+        } = settings.clone().bitcoin;
+        let connector = match esplora {
+            Some(esplora) => BitcoinConnector::from(EsploraConnector::new(esplora.url, network)?),
+            None => {
+                let mut connector = BitcoindConnector::new(bitcoind.node_url, network)?;
+                if let Some(publisher) = bitcoin::Publisher::from_config(
+                    bitcoind.zmqpubrawblock,
+                    bitcoind.zmqpubhashblock,
+                ) {
+                    connector = connector.with_block_notifications(publisher)?;
+                }
+
+                BitcoinConnector::from(connector)
+            }
+        };
+
+        let mut connector = bitcoin::Cache::new(connector, cache.bitcoin_blocks);
+        if let Some(disk) = cache.disk {
+            connector = connector.with_disk_cache(DiskCache::open(
+                &cache_dir.join("bitcoin").join("blocks"),
+                disk.bitcoin_blocks,
+                disk.eviction,
+            )?);
+        }
+
+        connector
     };
 
-    const ETHEREUM_BLOCK_CACHE_CAPACITY: usize = 720;
-    const ETHEREUM_RECEIPT_CACHE_CAPACITY: usize = 720;
-    let ethereum_connector = ethereum::Cache::new(
-        Web3Connector::new(settings.clone().ethereum.parity.node_url),
-        ETHEREUM_BLOCK_CACHE_CAPACITY,
-        ETHEREUM_RECEIPT_CACHE_CAPACITY,
+    let ethereum_connector = {
+        let config::Ethereum {
+            chain_id, parity, ..
+        } = settings.clone().ethereum;
+        let config::Parity { node_url, ws_url } = parity;
+        let mut connector = Web3Connector::new(node_url, chain_id);
+        if let Some(ws_url) = ws_url {
+            connector = connector.with_websocket(ws_url);
+        }
+
+        let mut connector =
+            ethereum::Cache::new(connector, cache.ethereum_blocks, cache.ethereum_receipts);
+        if let Some(disk) = cache.disk {
+            connector = connector.with_disk_cache(
+                DiskCache::open(
+                    &cache_dir.join("ethereum").join("blocks"),
+                    disk.ethereum_blocks,
+                    disk.eviction,
+                )?,
+                DiskCache::open(
+                    &cache_dir.join("ethereum").join("receipts"),
+                    disk.ethereum_receipts,
+                    disk.eviction,
+                )?,
+            );
+        }
+
+        connector
+    };
+
+    let database = Sqlite::new_in_dir(&settings.data.dir)?;
+
+    // Nothing is watched before the nodes are known to be on the configured
+    // networks.
+    let health = Health::new(
+        &settings,
+        bitcoin_connector.connector.clone(),
+        ethereum_connector.connector.clone(),
+        database.clone(),
     );
+    health::startup_check(&runtime.block_on_std(health.refresh()))?;
+    runtime.spawn_std(health::keep_up_to_date(health.clone()));
+
+    let bitcoin_chain_follower = bitcoin::ChainFollower::new(bitcoin_connector.clone());
+    runtime.spawn_std(
+        bitcoin_chain_follower
+            .clone()
+            .follow(bitcoin_connector.connector.subscribe_new_blocks()),
+    );
+    let ethereum_chain_follower = ethereum::ChainFollower::new(ethereum_connector.clone());
+    let ethereum_new_heads =
+        runtime.block_on_std(ethereum_connector.connector.subscribe_new_heads())?;
+    runtime.spawn_std(ethereum_chain_follower.clone().follow(ethereum_new_heads));
+
+    let bitcoin_fee_estimates = FeeEstimates::new(bitcoin_connector.connector.clone());
+    runtime.spawn_std(fee_estimates::keep_up_to_date(bitcoin_fee_estimates.clone()));
 
     let state_store = Arc::new(InMemoryStateStore::default());
+    let han_halight_states = Arc::new(han_halight::States::default());
+    let state_changes = Arc::new(StateChanges::default());
 
-    let database = Sqlite::new_in_dir(&settings.data.dir)?;
+    let bitcoin_wallet = match settings.bitcoin.wallet {
+        Some(config::BitcoinWallet { fee_per_wu }) => {
+            let wallet = runtime.block_on_std(bitcoin_wallet::Wallet::new(
+                &seed,
+                settings.bitcoin.network,
+                fee_per_wu,
+                bitcoin_connector.clone(),
+                database.clone(),
+            ))?;
+            tracing::info!("Bitcoin wallet address: {}", wallet.address());
+
+            runtime.spawn_std(bitcoin_wallet::keep_in_sync(wallet.clone()));
+            Some(wallet)
+        }
+        None => None,
+    };
+
+    let ethereum_wallet = ethereum_wallet::Wallet::from_config(
+        &seed,
+        &settings.ethereum,
+        ethereum_connector.connector.clone(),
+    );
+    if let Some(wallet) = &ethereum_wallet {
+        tracing::info!("Ethereum wallet address: {:x}", wallet.address());
+    }
+
+    // Without lnd swaps over lightning can still be created, they fail once
+    // their invoice is to be watched.
+    let lnd_connector = match settings.lightning.lnd.clone() {
+        Some(lnd) => match LndConnector::new(lnd, settings.lightning.network) {
+            Ok(connector) => Some(connector),
+            Err(e) => {
+                tracing::warn!("failed to connect to lnd: {:#}", e);
+                None
+            }
+        },
+        None => None,
+    };
 
     let swarm = Swarm::new(
         &settings,
@@ -83,20 +235,54 @@ fn main() -> anyhow::Result<()> {
         &bitcoin_connector,
         &ethereum_connector,
         &state_store,
+        &han_halight_states,
+        &state_changes,
         &database,
     )?;
 
+    runtime.spawn_std(webhooks::queue_webhooks(
+        settings.webhooks.clone(),
+        Arc::clone(&state_changes),
+        database.clone(),
+    ));
+    runtime.spawn_std(webhooks::deliver_webhooks(
+        settings.webhooks.clone(),
+        database.clone(),
+    ));
+
     let deps = Facade {
         bitcoin_connector,
         ethereum_connector,
+        bitcoin_chain_follower,
+        ethereum_chain_follower,
         state_store: Arc::clone(&state_store),
+        han_halight_states: Arc::clone(&han_halight_states),
         seed,
         swarm,
         db: database,
+        bitcoin_auto_refund: settings.bitcoin.auto_refund.clone(),
+        ethereum_auto_refund: settings.ethereum.auto_refund,
+        bitcoin_wallet,
+        ethereum_wallet,
+        bitcoin_fee_estimates,
+        health,
+        lnd_connector,
+        autopilot: settings.autopilot,
+        state_changes,
+        bitcoin_min_confirmations: settings.bitcoin.min_confirmations,
+        ethereum_min_confirmations: settings.ethereum.min_confirmations,
+        bitcoin_finality_depth: settings.bitcoin.finality_depth,
+        ethereum_finality_depth: settings.ethereum.finality_depth,
     };
 
     runtime.block_on_std(load_swaps::load_swaps_from_database(deps.clone()))?;
-    runtime.spawn_std(spawn_warp_instance(settings, deps));
+    runtime.spawn_std(swap_statuses::keep_up_to_date(deps.clone()));
+    runtime.spawn_std(spawn_warp_instance(
+        settings,
+        deps,
+        authenticator,
+        certificate_files,
+    ));
 
     // Block the current thread.
     ::std::thread::park();
@@ -114,14 +300,37 @@ fn version() {
     println!("{} {} ({})", name, version, short);
 }
 
-async fn spawn_warp_instance(settings: Settings, dependencies: Facade) {
-    let routes = route_factory::create(dependencies, &settings.http_api.cors.allowed_origins);
+async fn spawn_warp_instance(
+    settings: Settings,
+    dependencies: Facade,
+    authenticator: Authenticator,
+    certificate_files: Option<CertificateFiles>,
+) {
+    let routes = route_factory::create(
+        dependencies,
+        &settings.http_api.cors.allowed_origins,
+        authenticator,
+    );
 
     let listen_addr = settings.http_api.socket;
 
-    tracing::info!("Starting HTTP server on {}", listen_addr);
+    match certificate_files {
+        Some(CertificateFiles { cert, key }) => {
+            tracing::info!("Starting HTTPS server on {}", listen_addr);
+
+            warp::serve(routes)
+                .tls()
+                .cert_path(cert)
+                .key_path(key)
+                .bind(listen_addr)
+                .await
+        }
+        None => {
+            tracing::info!("Starting HTTP server on {}", listen_addr);
 
-    warp::serve(routes).bind(listen_addr).await
+            warp::serve(routes).bind(listen_addr).await
+        }
+    }
 }
 
 #[allow(clippy::print_stdout)] // We cannot use `log` before we have the config file
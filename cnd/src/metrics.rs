@@ -0,0 +1,208 @@
+//! Prometheus metrics of cnd, exported by the HTTP API at `/metrics`.
+//!
+//! Metrics that are cheap to count where they happen are counted there, the
+//! number of swaps and peers are looked up when the metrics are gathered.
+
+use crate::{
+    db::LedgerSide,
+    http_api::SwapStatus,
+    swap_protocols::{state_changes::LedgerEvent, Role, SwapId},
+};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use strum_macros::IntoStaticStr;
+
+lazy_static! {
+    static ref SWAPS: IntGaugeVec = register_int_gauge_vec!(
+        "cnd_swaps",
+        "Number of swaps by status and role.",
+        &["status", "role"]
+    )
+    .expect("metric can be registered");
+    static ref LEDGER_EVENTS: IntCounterVec = register_int_counter_vec!(
+        "cnd_ledger_events_total",
+        "Events observed on the ledgers of rfc003 swaps.",
+        &["ledger", "event"]
+    )
+    .expect("metric can be registered");
+    static ref FETCHES: IntCounterVec = register_int_counter_vec!(
+        "cnd_btsieve_fetches_total",
+        "Blocks and receipts fetched from the blockchain connectors.",
+        &["blockchain", "kind"]
+    )
+    .expect("metric can be registered");
+    static ref CONNECTOR_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "cnd_btsieve_connector_errors_total",
+        "Failed requests to the blockchain connectors.",
+        &["blockchain", "kind"]
+    )
+    .expect("metric can be registered");
+    static ref CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "cnd_btsieve_cache_lookups_total",
+        "Lookups of blocks and receipts by whether they were in memory, on disk or fetched.",
+        &["blockchain", "kind", "result"]
+    )
+    .expect("metric can be registered");
+    static ref COMIT_PEERS: IntGauge = register_int_gauge!(
+        "cnd_comit_peers",
+        "Number of connected peers that speak COMIT."
+    )
+    .expect("metric can be registered");
+    static ref COMIT_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "cnd_comit_requests_total",
+        "Swap requests sent to and received from other COMIT nodes.",
+        &["direction"]
+    )
+    .expect("metric can be registered");
+    static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "cnd_http_request_duration_seconds",
+        "Latency of the requests to the HTTP API by route.",
+        &["method", "route", "status"]
+    )
+    .expect("metric can be registered");
+}
+
+#[derive(Clone, Copy, Debug, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum CacheLookup {
+    Hit,
+    DiskHit,
+    Miss,
+}
+
+#[derive(Clone, Copy, Debug, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// Renders all metrics in the text format of Prometheus.
+pub fn render() -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// Replaces the swap counts, combinations that are not given are dropped.
+pub fn set_swap_counts(counts: &[(SwapStatus, Role, i64)]) {
+    SWAPS.reset();
+    for (status, role, count) in counts {
+        SWAPS
+            .with_label_values(&[&status.to_string(), &role.to_string()])
+            .set(*count);
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub fn set_comit_peers(count: usize) {
+    COMIT_PEERS.set(count as i64);
+}
+
+pub fn ledger_event_observed(ledger: LedgerSide, event: LedgerEvent) {
+    let ledger = match ledger {
+        LedgerSide::Alpha => "alpha",
+        LedgerSide::Beta => "beta",
+    };
+    let event = match event {
+        LedgerEvent::Deployed => "deployed",
+        LedgerEvent::Funded => "funded",
+        LedgerEvent::Redeemed => "redeemed",
+        LedgerEvent::Refunded => "refunded",
+    };
+
+    LEDGER_EVENTS.with_label_values(&[ledger, event]).inc();
+}
+
+/// Counts a fetch from a blockchain connector and passes its result on.
+pub fn fetched<T>(blockchain: &str, kind: &str, result: anyhow::Result<T>) -> anyhow::Result<T> {
+    FETCHES.with_label_values(&[blockchain, kind]).inc();
+    if result.is_err() {
+        CONNECTOR_ERRORS
+            .with_label_values(&[blockchain, kind])
+            .inc();
+    }
+
+    result
+}
+
+pub fn cache_lookup(blockchain: &str, kind: &str, result: CacheLookup) {
+    CACHE_LOOKUPS
+        .with_label_values(&[blockchain, kind, result.into()])
+        .inc();
+}
+
+pub fn comit_request(direction: Direction) {
+    COMIT_REQUESTS.with_label_values(&[direction.into()]).inc();
+}
+
+/// Records the latency of a request to the HTTP API, to be used with
+/// `warp::log::custom`.
+pub fn http_request(info: warp::log::Info<'_>) {
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[
+            info.method().as_str(),
+            &route(info.path(), info.status()),
+            info.status().as_str(),
+        ])
+        .observe(info.elapsed().as_secs_f64());
+}
+
+/// Turns the path of a request into its route by replacing swap ids, so that
+/// there is one time series per route instead of one per swap.
+///
+/// Requests that failed with a client error are all recorded as "unmatched".
+/// A path that did not match any route is not only rejected with a 404, but
+/// also with e.g. a 405 or a 400 if a route with the same prefix exists, and
+/// would add a time series each.
+fn route(path: &str, status: warp::http::StatusCode) -> String {
+    if status.is_client_error() {
+        return "unmatched".to_owned();
+    }
+
+    path.split('/')
+        .map(|segment| match segment.parse::<SwapId>() {
+            Ok(_) => "{id}",
+            Err(_) => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::http::StatusCode;
+
+    #[test]
+    fn swap_ids_are_removed_from_routes() {
+        let path = format!("/swaps/rfc003/{}/accept", SwapId::default());
+
+        assert_eq!(route(&path, StatusCode::OK), "/swaps/rfc003/{id}/accept");
+        assert_eq!(route("/swaps", StatusCode::OK), "/swaps");
+        assert_eq!(route("/wp-admin", StatusCode::NOT_FOUND), "unmatched");
+        assert_eq!(
+            route("/swaps/wp-admin", StatusCode::METHOD_NOT_ALLOWED),
+            "unmatched"
+        );
+        assert_eq!(
+            route("/swaps/rfc003/foo", StatusCode::BAD_REQUEST),
+            "unmatched"
+        );
+    }
+
+    #[test]
+    fn metrics_are_rendered_in_text_format() {
+        set_swap_counts(&[(SwapStatus::InProgress, Role::Alice, 2)]);
+        ledger_event_observed(LedgerSide::Alpha, LedgerEvent::Funded);
+
+        let metrics = render().unwrap();
+
+        assert!(metrics.contains(r#"cnd_swaps{role="Alice",status="IN_PROGRESS"} 2"#));
+        assert!(metrics.contains(r#"cnd_ledger_events_total{event="funded",ledger="alpha"}"#));
+    }
+}
@@ -4,20 +4,24 @@ pub use transport::ComitTransport;
 
 use crate::{
     asset::AssetKind,
-    btsieve::{bitcoin, bitcoin::BitcoindConnector, ethereum, ethereum::Web3Connector},
+    btsieve::{bitcoin, bitcoin::BitcoinConnector, ethereum, ethereum::Web3Connector},
     comit_api::LedgerKind,
     config::Settings,
-    db::{Save, Sqlite, Swap},
+    db::{DetermineTypes, HanHalightSwap, ListedSwap, Save, Sqlite, Swap},
     libp2p_comit_ext::{FromHeader, ToHeader},
+    metrics::{self, Direction},
     seed::{DeriveSwapSeed, RootSeed},
     swap_protocols::{
-        ledger,
+        han_halight, ledger,
         rfc003::{
-            self, bob,
+            self,
+            actions::ActionKind,
+            bob,
             messages::{Decision, DeclineResponseBody, Request, SwapDeclineReason},
             state_store::{InMemoryStateStore, StateStore},
             Ledger,
         },
+        state_changes::{PublishStateChange, StateChange, StateChanges},
         HashFunction, Role, SwapId, SwapProtocol,
     },
 };
@@ -46,7 +50,7 @@ use libp2p_comit::{
 use serde::de::DeserializeOwned;
 use std::{
     collections::{HashMap, HashSet},
-    convert::TryInto,
+    convert::{TryFrom, TryInto},
     fmt::{Debug, Display},
     io,
     sync::Arc,
@@ -78,9 +82,11 @@ impl Swarm {
         settings: &Settings,
         seed: RootSeed,
         runtime: &mut Runtime,
-        bitcoin_connector: &bitcoin::Cache<BitcoindConnector>,
+        bitcoin_connector: &bitcoin::Cache<BitcoinConnector>,
         ethereum_connector: &ethereum::Cache<Web3Connector>,
         state_store: &Arc<InMemoryStateStore>,
+        han_halight_states: &Arc<han_halight::States>,
+        state_changes: &Arc<StateChanges>,
         database: &Sqlite,
     ) -> anyhow::Result<Self> {
         let local_key_pair = derive_key_pair(&seed);
@@ -92,6 +98,8 @@ impl Swarm {
             bitcoin_connector.clone(),
             ethereum_connector.clone(),
             Arc::clone(&state_store),
+            Arc::clone(&han_halight_states),
+            Arc::clone(&state_changes),
             seed,
             database.clone(),
             runtime.executor(),
@@ -143,12 +151,16 @@ pub struct ComitNode {
     mdns: Mdns,
 
     #[behaviour(ignore)]
-    pub bitcoin_connector: bitcoin::Cache<BitcoindConnector>,
+    pub bitcoin_connector: bitcoin::Cache<BitcoinConnector>,
     #[behaviour(ignore)]
     pub ethereum_connector: ethereum::Cache<Web3Connector>,
     #[behaviour(ignore)]
     pub state_store: Arc<InMemoryStateStore>,
     #[behaviour(ignore)]
+    pub han_halight_states: Arc<han_halight::States>,
+    #[behaviour(ignore)]
+    pub state_changes: Arc<StateChanges>,
+    #[behaviour(ignore)]
     pub seed: RootSeed,
     #[behaviour(ignore)]
     pub db: Sqlite,
@@ -192,9 +204,11 @@ pub struct Reason {
 
 impl ComitNode {
     pub fn new(
-        bitcoin_connector: bitcoin::Cache<BitcoindConnector>,
+        bitcoin_connector: bitcoin::Cache<BitcoinConnector>,
         ethereum_connector: ethereum::Cache<Web3Connector>,
         state_store: Arc<InMemoryStateStore>,
+        han_halight_states: Arc<han_halight::States>,
+        state_changes: Arc<StateChanges>,
         seed: RootSeed,
         db: Sqlite,
         task_executor: TaskExecutor,
@@ -216,6 +230,8 @@ impl ComitNode {
             bitcoin_connector,
             ethereum_connector,
             state_store,
+            han_halight_states,
+            state_changes,
             seed,
             db,
             response_channels: Arc::new(Mutex::new(HashMap::new())),
@@ -228,6 +244,7 @@ impl ComitNode {
         peer_id: DialInformation,
         request: OutboundRequest,
     ) -> impl futures_core::Future<Output = Result<Response, ()>> + Send + 'static + Unpin {
+        metrics::comit_request(Direction::Outbound);
         self.comit
             .send_request((peer_id.peer_id, peer_id.address_hint), request)
     }
@@ -240,6 +257,7 @@ async fn handle_request(
     db: Sqlite,
     seed: RootSeed,
     state_store: Arc<InMemoryStateStore>,
+    han_halight_states: Arc<han_halight::States>,
     counterparty: PeerId,
     mut request: ValidatedInboundRequest,
 ) -> Result<SwapId, Response> {
@@ -585,20 +603,58 @@ async fn handle_request(
                                     "swapping {:?} to {:?} from {:?} to {:?} is currently not supported", alpha_asset, beta_asset, alpha_ledger, beta_ledger
                                 );
 
-                            let decline_body = DeclineResponseBody {
-                                reason: Some(SwapDeclineReason::UnsupportedSwap),
-                            };
-
-                            Err(Response::empty()
-                                .with_header(
-                                    "decision",
-                                    Decision::Declined
-                                        .to_header()
-                                        .expect("Decision should not fail to serialize"),
-                                )
-                                .with_body(serde_json::to_value(decline_body).expect(
-                                    "decline body should always serialize into serde_json::Value",
-                                )))
+                            Err(unsupported_swap_response())
+                        }
+                    }
+                }
+                SwapProtocol::HanHalight(hash_function) => {
+                    let swap_id = header!(request.take_header("id").map(SwapId::from_header));
+                    let alpha_ledger = header!(request
+                        .take_header("alpha_ledger")
+                        .map(LedgerKind::from_header));
+                    let beta_ledger = header!(request
+                        .take_header("beta_ledger")
+                        .map(LedgerKind::from_header));
+                    let alpha_asset = header!(request
+                        .take_header("alpha_asset")
+                        .map(AssetKind::from_header));
+                    let beta_asset = header!(request
+                        .take_header("beta_asset")
+                        .map(AssetKind::from_header));
+
+                    match (alpha_ledger, beta_ledger, alpha_asset, beta_asset) {
+                        (
+                            LedgerKind::Ethereum(alpha_ledger),
+                            LedgerKind::Lightning(beta_ledger),
+                            AssetKind::Ether(alpha_asset),
+                            AssetKind::Bitcoin(beta_asset),
+                        ) => {
+                            let request = han_halight::messages::request_from_body(
+                                swap_id,
+                                alpha_ledger,
+                                beta_ledger,
+                                alpha_asset,
+                                beta_asset,
+                                hash_function,
+                                body!(request.take_body_as()),
+                            );
+                            insert_han_halight_state_for_bob(
+                                db.clone(),
+                                seed,
+                                han_halight_states.clone(),
+                                counterparty,
+                                request,
+                            )
+                            .await
+                            .expect("Could not save state to db");
+                            Ok(swap_id)
+                        }
+                        (alpha_ledger, beta_ledger, alpha_asset, beta_asset) => {
+                            tracing::warn!(
+                                    "swapping {:?} to {:?} from {:?} to {:?} is currently not supported", alpha_asset, beta_asset, alpha_ledger, beta_ledger
+                                );
+
+                            Err(unsupported_swap_response())
                         }
                     }
                 }
@@ -637,14 +693,17 @@ where
     BA: Send + 'static,
     AI: Send + 'static,
     BI: Send + 'static,
-    DB: Save<Request<AL, BL, AA, BA, AI, BI>> + Save<Swap>,
+    DB: Save<Request<AL, BL, AA, BA, AI, BI>> + Save<Swap> + Save<ListedSwap> + DetermineTypes,
     Request<AL, BL, AA, BA, AI, BI>: Clone,
 {
     let id = swap_request.swap_id;
     let seed = seed.derive_swap_seed(id);
+    let swap = Swap::new(id, Role::Bob, counterparty);
 
-    Save::save(&db, Swap::new(id, Role::Bob, counterparty)).await?;
+    Save::save(&db, swap.clone()).await?;
     Save::save(&db, swap_request.clone()).await?;
+    let types = db.determine_types(&id).await?;
+    Save::save(&db, ListedSwap::rfc003(swap, types)).await?;
 
     let state = bob::State::proposed(swap_request.clone(), seed);
     state_store.insert(id, state);
@@ -652,6 +711,48 @@ where
     Ok(())
 }
 
+fn unsupported_swap_response() -> Response {
+    let decline_body = DeclineResponseBody {
+        reason: Some(SwapDeclineReason::UnsupportedSwap),
+    };
+
+    Response::empty()
+        .with_header(
+            "decision",
+            Decision::Declined
+                .to_header()
+                .expect("Decision should not fail to serialize"),
+        )
+        .with_body(
+            serde_json::to_value(decline_body)
+                .expect("decline body should always serialize into serde_json::Value"),
+        )
+}
+
+async fn insert_han_halight_state_for_bob<DB>(
+    db: DB,
+    seed: RootSeed,
+    states: Arc<han_halight::States>,
+    counterparty: PeerId,
+    swap_request: han_halight::Request,
+) -> anyhow::Result<()>
+where
+    DB: Save<han_halight::Request> + Save<HanHalightSwap> + Save<ListedSwap>,
+{
+    let id = swap_request.swap_id;
+    let seed = seed.derive_swap_seed(id);
+    let swap = Swap::new(id, Role::Bob, counterparty);
+
+    Save::save(&db, HanHalightSwap(swap.clone())).await?;
+    Save::save(&db, swap_request.clone()).await?;
+    Save::save(&db, ListedSwap::han_halight(swap)).await?;
+
+    let state = han_halight::State::proposed(Role::Bob, swap_request, seed);
+    states.insert(id, state);
+
+    Ok(())
+}
+
 /// Get the `PeerId` of this node.
 #[ambassador::delegatable_trait]
 pub trait LocalPeerId {
@@ -820,22 +921,133 @@ impl SendRequest for Swarm {
     }
 }
 
+/// Send han-halight swap request to connected peer.
+#[async_trait]
+pub trait SendHanHalightRequest {
+    async fn send_han_halight_request(
+        &self,
+        peer_identity: DialInformation,
+        request: han_halight::Request,
+    ) -> Result<han_halight::messages::Response, RequestError>;
+}
+
+#[async_trait]
+impl SendHanHalightRequest for Swarm {
+    async fn send_han_halight_request(
+        &self,
+        dial_information: DialInformation,
+        request: han_halight::Request,
+    ) -> Result<han_halight::messages::Response, RequestError> {
+        let id = request.swap_id;
+        let request = OutboundRequest::try_from(request)
+            .expect("constructing a frame::OutoingRequest should never fail!");
+
+        let result = {
+            let mut guard = self.swarm.lock().await;
+            let swarm = &mut *guard;
+
+            tracing::debug!(
+                "Making han-halight swap request to {}: {:?}",
+                dial_information.clone(),
+                id,
+            );
+
+            swarm.send_request(dial_information.clone(), request)
+        }
+        .await;
+
+        match result {
+            Ok(mut response) => {
+                let decision = response
+                    .take_header("decision")
+                    .map(Decision::from_header)
+                    .map_or(Ok(None), |x| x.map(Some))
+                    .map_err(|e| {
+                        tracing::error!(
+                            "Could not deserialize header in response {:?}: {}",
+                            response,
+                            e,
+                        );
+                        RequestError::InvalidResponse
+                    })?;
+
+                match decision {
+                    Some(Decision::Accepted) => {
+                        match serde_json::from_value::<han_halight::messages::AcceptResponseBody>(
+                            response.body().clone(),
+                        ) {
+                            Ok(body) => Ok(Ok(han_halight::Accept {
+                                swap_id: id,
+                                alpha_ledger_redeem_identity: body.alpha_ledger_redeem_identity,
+                                beta_ledger_refund_identity: body.beta_ledger_refund_identity,
+                            })),
+                            Err(_e) => Err(RequestError::InvalidResponse),
+                        }
+                    }
+
+                    Some(Decision::Declined) => {
+                        match serde_json::from_value::<DeclineResponseBody>(response.body().clone())
+                        {
+                            Ok(body) => Ok(Err(han_halight::Decline {
+                                swap_id: id,
+                                reason: body.reason,
+                            })),
+                            Err(_e) => Err(RequestError::InvalidResponse),
+                        }
+                    }
+
+                    None => Err(RequestError::InvalidResponse),
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Unable to request over connection {:?}:{:?}",
+                    dial_information,
+                    e
+                );
+                Err(RequestError::Connection)
+            }
+        }
+    }
+}
+
 impl NetworkBehaviourEventProcess<BehaviourOutEvent> for ComitNode {
     fn inject_event(&mut self, event: BehaviourOutEvent) {
         match event {
             BehaviourOutEvent::PendingInboundRequest { request, peer_id } => {
+                metrics::comit_request(Direction::Inbound);
                 let PendingInboundRequest { request, channel } = request;
 
                 let response_channels = self.response_channels.clone();
                 let db = self.db.clone();
                 let state_store = self.state_store.clone();
+                let han_halight_states = self.han_halight_states.clone();
+                let state_changes = self.state_changes.clone();
                 let seed = self.seed;
 
                 self.task_executor.spawn_std(async move {
-                    match handle_request(db, seed, state_store, peer_id, request).await {
+                    match handle_request(
+                        db,
+                        seed,
+                        state_store,
+                        han_halight_states,
+                        peer_id,
+                        request,
+                    )
+                    .await
+                    {
                         Ok(id) => {
                             let mut response_channels = response_channels.lock().await;
                             response_channels.insert(id, channel);
+
+                            // It is up to us to accept or decline the request.
+                            state_changes.publish_state_change(id, StateChange::Requested);
+                            for action in &[ActionKind::Accept, ActionKind::Decline] {
+                                state_changes.publish_state_change(
+                                    id,
+                                    StateChange::ActionAvailable { action: *action },
+                                );
+                            }
                         }
                         Err(response) => channel.send(response).unwrap_or_else(|_| {
                             tracing::debug!("failed to send response through channel")
@@ -5,7 +5,7 @@ use crate::{
     ethereum::Bytes,
     identity,
     swap_protocols::{
-        ledger,
+        han_halight, ledger,
         ledger::{bitcoin, ethereum::ChainId},
         rfc003::{Accept, Request, SecretHash},
         HashFunction, Role, SwapId,
@@ -281,6 +281,42 @@ where
     }
 }
 
+impl Arbitrary for Quickcheck<ledger::Lightning> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let network = *Quickcheck::<::bitcoin::Network>::arbitrary(g);
+
+        Quickcheck(ledger::Lightning::new(network))
+    }
+}
+
+impl Arbitrary for Quickcheck<han_halight::Request> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Quickcheck(han_halight::Request {
+            swap_id: *Quickcheck::<SwapId>::arbitrary(g),
+            alpha_ledger: *Quickcheck::<ledger::Ethereum>::arbitrary(g),
+            beta_ledger: *Quickcheck::<ledger::Lightning>::arbitrary(g),
+            alpha_asset: Quickcheck::<asset::Ether>::arbitrary(g).0,
+            beta_asset: *Quickcheck::<asset::Bitcoin>::arbitrary(g),
+            hash_function: *Quickcheck::<HashFunction>::arbitrary(g),
+            alpha_ledger_refund_identity: *Quickcheck::<identity::Ethereum>::arbitrary(g),
+            beta_ledger_redeem_identity: *Quickcheck::<identity::Lightning>::arbitrary(g),
+            alpha_expiry: *Quickcheck::<Timestamp>::arbitrary(g),
+            beta_cltv_expiry: u32::arbitrary(g),
+            secret_hash: *Quickcheck::<SecretHash>::arbitrary(g),
+        })
+    }
+}
+
+impl Arbitrary for Quickcheck<han_halight::Accept> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Quickcheck(han_halight::Accept {
+            swap_id: *Quickcheck::<SwapId>::arbitrary(g),
+            alpha_ledger_redeem_identity: *Quickcheck::<identity::Ethereum>::arbitrary(g),
+            beta_ledger_refund_identity: *Quickcheck::<identity::Lightning>::arbitrary(g),
+        })
+    }
+}
+
 impl Arbitrary for Quickcheck<Role> {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         let role = match g.next_u32() % 2 {
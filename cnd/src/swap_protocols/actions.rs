@@ -64,3 +64,47 @@ pub mod ethereum {
         pub min_block_timestamp: Option<Timestamp>,
     }
 }
+
+pub mod lnd {
+    use crate::{
+        asset, identity,
+        swap_protocols::{
+            ledger::Lightning,
+            rfc003::{Secret, SecretHash},
+        },
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct AddHoldInvoice {
+        pub amount: asset::Bitcoin,
+        pub secret_hash: SecretHash,
+        pub expiry: u32,
+        pub cltv_expiry: u32,
+        pub ledger: Lightning,
+        pub self_public_key: identity::Lightning,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SettleInvoice {
+        pub secret: Secret,
+        pub ledger: Lightning,
+        pub self_public_key: identity::Lightning,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct CancelInvoice {
+        pub secret_hash: SecretHash,
+        pub ledger: Lightning,
+        pub self_public_key: identity::Lightning,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SendPayment {
+        pub to_public_key: identity::Lightning,
+        pub amount: asset::Bitcoin,
+        pub secret_hash: SecretHash,
+        pub final_cltv_delta: u32,
+        pub ledger: Lightning,
+        pub self_public_key: identity::Lightning,
+    }
+}
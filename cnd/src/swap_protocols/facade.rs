@@ -1,29 +1,54 @@
 use crate::{
     asset::{self},
-    btsieve::{self, bitcoin::BitcoindConnector, ethereum, ethereum::Web3Connector},
-    db::{AcceptedSwap, DetermineTypes, LoadAcceptedSwap, Retrieve, Save, Sqlite, Swap, SwapTypes},
-    htlc_location, identity,
+    btsieve::{
+        self, bitcoin::BitcoinConnector, ethereum, ethereum::Web3Connector, Finality,
+        TransactionOrphaned,
+    },
+    config,
+    db::{
+        AcceptedSwap, AutomaticAction, DetermineTypes, EnableAutopilot, LedgerSide,
+        LoadAcceptedSwap, LoadAutomaticActions, LoadAutopilot, LoadDecline, LoadHanHalightSwaps,
+        LoadLedgerEvents, LoadRequest, Retrieve, RollBackLedgerEvents, Save, SaveAutomaticAction,
+        SaveLedgerEvent, Sqlite, Swap, SwapTypes,
+    },
+    health::{self, EnsureAvailable, Health, LedgerUnavailable},
+    htlc_location,
+    http_api::action::{ActionResponseBody, ExecuteAction},
+    identity,
+    lnd::{LndConnector, LndNotConfigured},
     network::{
         ComitPeers, DialInformation, ListenAddresses, LocalPeerId, PendingRequestFor, RequestError,
-        SendRequest, Swarm,
+        SendHanHalightRequest, SendRequest, Swarm,
     },
     seed::{DeriveSwapSeed, RootSeed, SwapSeed},
     swap_protocols::{
+        actions::{bitcoin::SpendOutput, ethereum::CallContract},
+        han_halight::{
+            self,
+            halight::{self, InvoiceEvents},
+        },
         ledger::{bitcoin, Ethereum},
         rfc003::{
             self,
+            actions::{Action, ActionKind},
+            auto_refund::{self, ExecuteRefund},
+            autopilot::{Autopilot, ExecuteAutomatically},
             create_swap::{HtlcParams, SwapEventOnLedger},
             events::{
                 Deployed, Funded, HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded, Redeemed,
-                Refunded,
+                Refunded, TransactionFinality,
             },
+            ledger_state::HtlcState,
             state_store::{self, InMemoryStateStore, StateStore},
-            ActorState,
+            ActorState, Ledger,
         },
-        SwapId,
+        state_changes::{PublishStateChange, StateChange, StateChanges},
+        Role, SwapId,
     },
+    timestamp::Timestamp,
     transaction,
 };
+use anyhow::{anyhow, bail};
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use futures_core::channel::oneshot::Sender;
@@ -31,7 +56,7 @@ use impl_template::impl_template;
 use libp2p::{Multiaddr, PeerId};
 use libp2p_comit::frame::{OutboundRequest, Response};
 use serde::de::DeserializeOwned;
-use std::{convert::TryInto, fmt::Debug, sync::Arc};
+use std::{convert::TryInto, fmt::Debug, sync::Arc, time::Duration};
 
 /// This is a facade that implements all the required traits and forwards them
 /// to another implementation. This allows us to keep the number of arguments to
@@ -44,13 +69,69 @@ use std::{convert::TryInto, fmt::Debug, sync::Arc};
 #[delegate(PendingRequestFor, target = "swarm")]
 #[delegate(Retrieve, target = "db")]
 #[delegate(DetermineTypes, target = "db")]
+#[delegate(LoadHanHalightSwaps, target = "db")]
 pub struct Facade {
-    pub bitcoin_connector: btsieve::bitcoin::Cache<BitcoindConnector>,
+    pub bitcoin_connector: btsieve::bitcoin::Cache<BitcoinConnector>,
     pub ethereum_connector: ethereum::Cache<Web3Connector>,
+    pub bitcoin_chain_follower:
+        btsieve::bitcoin::ChainFollower<btsieve::bitcoin::Cache<BitcoinConnector>>,
+    pub ethereum_chain_follower: ethereum::ChainFollower<ethereum::Cache<Web3Connector>>,
     pub state_store: Arc<InMemoryStateStore>,
+    pub han_halight_states: Arc<han_halight::States>,
     pub seed: RootSeed,
     pub swarm: Swarm,
     pub db: Sqlite,
+    pub bitcoin_auto_refund: Option<config::BitcoinAutoRefund>,
+    pub ethereum_auto_refund: bool,
+    pub bitcoin_wallet: Option<crate::bitcoin::wallet::Wallet>,
+    pub ethereum_wallet: Option<crate::ethereum::wallet::Wallet>,
+    pub bitcoin_fee_estimates: crate::bitcoin::fee_estimates::FeeEstimates,
+    pub health: Health,
+    pub lnd_connector: Option<LndConnector>,
+    pub autopilot: config::settings::Autopilot,
+    pub state_changes: Arc<StateChanges>,
+    pub bitcoin_min_confirmations: u32,
+    pub ethereum_min_confirmations: u32,
+    pub bitcoin_finality_depth: u32,
+    pub ethereum_finality_depth: u32,
+}
+
+impl Facade {
+    fn lnd_connector(&self) -> Result<&LndConnector, LndNotConfigured> {
+        self.lnd_connector.as_ref().ok_or(LndNotConfigured)
+    }
+
+    /// Waits until the transaction has the confirmations configured for
+    /// bitcoin on the best chain and returns them.
+    async fn bitcoin_confirmed(
+        &self,
+        transaction: &transaction::Bitcoin,
+        start_of_swap: NaiveDateTime,
+    ) -> Result<u32, TransactionOrphaned> {
+        self.bitcoin_chain_follower
+            .wait_for_confirmations(
+                transaction.txid(),
+                self.bitcoin_min_confirmations,
+                start_of_swap,
+            )
+            .await
+    }
+
+    /// Waits until the transaction has the confirmations configured for
+    /// ethereum on the best chain and returns them.
+    async fn ethereum_confirmed(
+        &self,
+        transaction: &transaction::Ethereum,
+        start_of_swap: NaiveDateTime,
+    ) -> Result<u32, TransactionOrphaned> {
+        ethereum::wait_for_confirmations(
+            self.ethereum_connector.clone(),
+            transaction.hash,
+            self.ethereum_min_confirmations,
+            start_of_swap,
+        )
+        .await
+    }
 }
 
 impl StateStore for Facade {
@@ -97,6 +178,56 @@ impl SendRequest for Facade {
     }
 }
 
+#[async_trait]
+impl SendHanHalightRequest for Facade {
+    async fn send_han_halight_request(
+        &self,
+        peer_identity: DialInformation,
+        request: han_halight::Request,
+    ) -> Result<han_halight::messages::Response, RequestError> {
+        self.swarm
+            .send_han_halight_request(peer_identity, request)
+            .await
+    }
+}
+
+/// The invoice events of han-halight swaps are reported by lnd, they cannot
+/// be watched without it. Alice watches her invoice, Bob his payment.
+#[async_trait]
+impl InvoiceEvents for Facade {
+    async fn invoice_opened(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Opened> {
+        self.lnd_connector()?.invoice_opened(params, role).await
+    }
+
+    async fn invoice_accepted(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Accepted> {
+        self.lnd_connector()?.invoice_accepted(params, role).await
+    }
+
+    async fn invoice_settled(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Settled> {
+        self.lnd_connector()?.invoice_settled(params, role).await
+    }
+
+    async fn invoice_cancelled(
+        &self,
+        params: halight::Params,
+        role: Role,
+    ) -> anyhow::Result<halight::Cancelled> {
+        self.lnd_connector()?.invoice_cancelled(params, role).await
+    }
+}
+
 #[async_trait]
 impl<AL, BL, AA, BA, AI, BI> LoadAcceptedSwap<AL, BL, AA, BA, AI, BI> for Facade
 where
@@ -111,6 +242,253 @@ where
     }
 }
 
+#[async_trait]
+impl<AL, BL, AA, BA, AI, BI> LoadRequest<AL, BL, AA, BA, AI, BI> for Facade
+where
+    Sqlite: LoadRequest<AL, BL, AA, BA, AI, BI>,
+    rfc003::Request<AL, BL, AA, BA, AI, BI>: Send + 'static,
+{
+    async fn load_request(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<rfc003::Request<AL, BL, AA, BA, AI, BI>> {
+        self.db.load_request(swap_id).await
+    }
+}
+
+#[async_trait]
+impl LoadDecline for Facade {
+    async fn load_decline(&self, swap_id: &SwapId) -> anyhow::Result<Option<rfc003::Decline>> {
+        self.db.load_decline(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<AL, BL, AA, BA> SaveLedgerEvent<AL, BL, AA, BA> for Facade
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Send + 'static,
+    BA: Send + 'static,
+    Sqlite: SaveLedgerEvent<AL, BL, AA, BA>,
+{
+    async fn save_ledger_event(
+        &self,
+        swap_id: SwapId,
+        event: SwapEventOnLedger<AL, BL, AA, BA>,
+    ) -> anyhow::Result<()> {
+        self.db.save_ledger_event(swap_id, event).await
+    }
+}
+
+#[async_trait]
+impl<AL, BL, AA, BA> LoadLedgerEvents<AL, BL, AA, BA> for Facade
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Send + 'static,
+    BA: Send + 'static,
+    Sqlite: LoadLedgerEvents<AL, BL, AA, BA>,
+{
+    async fn load_ledger_events(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<SwapEventOnLedger<AL, BL, AA, BA>>> {
+        self.db.load_ledger_events(swap_id).await
+    }
+}
+
+#[async_trait]
+impl RollBackLedgerEvents for Facade {
+    async fn roll_back_ledger_events(
+        &self,
+        swap_id: &SwapId,
+        ledger: LedgerSide,
+        to: HtlcState,
+    ) -> anyhow::Result<()> {
+        self.db.roll_back_ledger_events(swap_id, ledger, to).await
+    }
+}
+
+#[async_trait]
+impl SaveAutomaticAction for Facade {
+    async fn save_automatic_action(
+        &self,
+        swap_id: SwapId,
+        ledger: LedgerSide,
+        action: ActionKind,
+        transaction_hash: String,
+    ) -> anyhow::Result<()> {
+        self.db
+            .save_automatic_action(swap_id, ledger, action, transaction_hash)
+            .await
+    }
+}
+
+#[async_trait]
+impl LoadAutomaticActions for Facade {
+    async fn load_automatic_actions(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<AutomaticAction>> {
+        self.db.load_automatic_actions(swap_id).await
+    }
+}
+
+impl PublishStateChange for Facade {
+    fn publish_state_change(&self, swap_id: SwapId, change: StateChange) {
+        self.state_changes.publish_state_change(swap_id, change)
+    }
+}
+
+#[async_trait]
+impl EnableAutopilot for Facade {
+    async fn enable_autopilot(&self, swap_id: SwapId) -> anyhow::Result<()> {
+        self.db.enable_autopilot(swap_id).await
+    }
+}
+
+impl EnsureAvailable for Facade {
+    fn ensure_available(&self, ledgers: &[health::Ledger]) -> Result<(), LedgerUnavailable> {
+        self.health.ensure_available(ledgers)
+    }
+}
+
+#[async_trait]
+impl Autopilot for Facade {
+    async fn autopilot_enabled(&self, id: &SwapId) -> anyhow::Result<bool> {
+        if self.autopilot.all_swaps {
+            return Ok(true);
+        }
+
+        self.db.autopilot_enabled(id).await
+    }
+
+    fn autopilot_safety_margin(&self) -> Duration {
+        Duration::from_secs(u64::from(self.autopilot.safety_margin_secs))
+    }
+}
+
+#[async_trait]
+impl<Accept, Decline, Deploy, Fund, Redeem, Refund>
+    ExecuteAutomatically<Action<Accept, Decline, Deploy, Fund, Redeem, Refund>> for Facade
+where
+    Accept: Send + 'static,
+    Decline: Send + 'static,
+    Deploy: Send + 'static,
+    Fund: Send + 'static,
+    Redeem: Send + 'static,
+    Refund: Send + 'static,
+    Facade:
+        ExecuteAction<Deploy> + ExecuteAction<Fund> + ExecuteAction<Redeem> + ExecuteAction<Refund>,
+{
+    async fn execute_automatically(
+        &self,
+        action: Action<Accept, Decline, Deploy, Fund, Redeem, Refund>,
+    ) -> anyhow::Result<String> {
+        let response = match action {
+            Action::Accept(_) | Action::Decline(_) => {
+                bail!("accepting or declining a swap is up to the user")
+            }
+            Action::Deploy(action) => self.execute_action(action).await?,
+            Action::Fund(action) => self.execute_action(action).await?,
+            Action::Redeem(action) => self.execute_action(action).await?,
+            Action::Refund(action) => self.execute_action(action).await?,
+        };
+
+        match response {
+            ActionResponseBody::BitcoinTransactionBroadcast { txid, .. } => Ok(txid.to_string()),
+            ActionResponseBody::EthereumTransactionSent {
+                transaction_hash, ..
+            } => Ok(format!("{:x}", transaction_hash)),
+            response => Err(anyhow!("action resulted in no transaction: {:?}", response)),
+        }
+    }
+}
+
+#[async_trait]
+impl ExecuteRefund<SpendOutput> for Facade {
+    fn auto_refund_enabled(&self) -> bool {
+        self.bitcoin_auto_refund.is_some()
+    }
+
+    /// The refund is locked until the expiry, bitcoin only accepts it once the
+    /// median time past of the chain passed the expiry. That lags behind the
+    /// wall clock by about an hour.
+    async fn wait_until_refundable(&self, expiry: Timestamp) {
+        auto_refund::wait_until(expiry).await;
+
+        loop {
+            match self.bitcoin_connector.connector.chain_tip().await {
+                Ok(tip) if tip.median_time_past > u32::from(expiry) => return,
+                Ok(tip) => tracing::debug!(
+                    "median time past {} has not passed refund expiry {} yet",
+                    tip.median_time_past,
+                    u32::from(expiry)
+                ),
+                Err(e) => tracing::warn!("Could not get the bitcoin chain tip: {}", e),
+            }
+
+            tokio::time::delay_for(auto_refund::RETRY_INTERVAL).await;
+        }
+    }
+
+    async fn execute_refund(&self, action: SpendOutput) -> anyhow::Result<String> {
+        let config::BitcoinAutoRefund {
+            sweep_address,
+            fee_per_wu,
+        } = self
+            .bitcoin_auto_refund
+            .clone()
+            .ok_or_else(|| anyhow!("automatic refunds are not enabled for bitcoin"))?;
+
+        if action.network != sweep_address.network {
+            bail!(
+                "refund on {} cannot be swept to {} address",
+                action.network,
+                sweep_address.network
+            );
+        }
+
+        let transaction = action
+            .spend_to(sweep_address)
+            .sign_with_rate(&*crate::SECP, fee_per_wu)
+            .map_err(|e| anyhow!("failed to sign refund transaction: {:?}", e))?;
+
+        let txid = self
+            .bitcoin_connector
+            .connector
+            .send_raw_transaction(&transaction)
+            .await?;
+
+        Ok(txid.to_string())
+    }
+}
+
+/// Ethereum refunds are sent from the wallet, which keeps track of the nonces
+/// of all transactions cnd sends.
+#[async_trait]
+impl ExecuteRefund<CallContract> for Facade {
+    fn auto_refund_enabled(&self) -> bool {
+        self.ethereum_auto_refund && self.ethereum_wallet.is_some()
+    }
+
+    async fn wait_until_refundable(&self, expiry: Timestamp) {
+        auto_refund::wait_until(expiry).await
+    }
+
+    async fn execute_refund(&self, action: CallContract) -> anyhow::Result<String> {
+        let wallet = self
+            .ethereum_wallet
+            .as_ref()
+            .ok_or_else(|| anyhow!("automatic refunds on ethereum require the wallet"))?;
+
+        let hash = wallet.call_contract(action).await?;
+
+        Ok(format!("{:x}", hash))
+    }
+}
+
 #[async_trait]
 impl<T> Save<T> for Facade
 where
@@ -137,9 +515,29 @@ impl
         htlc_deployment: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Funded<transaction::Bitcoin, asset::Bitcoin>> {
-        self.bitcoin_connector
-            .htlc_funded(htlc_params, htlc_deployment, start_of_swap)
-            .await
+        loop {
+            let funded = self
+                .bitcoin_chain_follower
+                .htlc_funded(htlc_params, htlc_deployment, start_of_swap)
+                .await?;
+
+            match self
+                .bitcoin_confirmed(&funded.transaction, start_of_swap)
+                .await
+            {
+                Ok(confirmations) => {
+                    return Ok(Funded {
+                        confirmations: Some(confirmations),
+                        ..funded
+                    })
+                }
+                Err(e) => tracing::warn!(
+                    "fund transaction {}: {}, watching the htlc again",
+                    funded.transaction.txid(),
+                    e
+                ),
+            }
+        }
     }
 }
 
@@ -157,9 +555,24 @@ impl
         htlc_params: &HtlcParams<__TYPE0__, asset::Bitcoin, identity::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Deployed<transaction::Bitcoin, htlc_location::Bitcoin>> {
-        self.bitcoin_connector
-            .htlc_deployed(htlc_params, start_of_swap)
-            .await
+        loop {
+            let deployed = self
+                .bitcoin_chain_follower
+                .htlc_deployed(htlc_params, start_of_swap)
+                .await?;
+
+            match self
+                .bitcoin_confirmed(&deployed.transaction, start_of_swap)
+                .await
+            {
+                Ok(_) => return Ok(deployed),
+                Err(e) => tracing::warn!(
+                    "deploy transaction {}: {}, watching the htlc again",
+                    deployed.transaction.txid(),
+                    e
+                ),
+            }
+        }
     }
 }
 
@@ -178,7 +591,7 @@ impl
         htlc_deployment: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Redeemed<transaction::Bitcoin>> {
-        self.bitcoin_connector
+        self.bitcoin_chain_follower
             .htlc_redeemed(htlc_params, htlc_deployment, start_of_swap)
             .await
     }
@@ -199,7 +612,7 @@ impl
         htlc_deployment: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Refunded<transaction::Bitcoin>> {
-        self.bitcoin_connector
+        self.bitcoin_chain_follower
             .htlc_refunded(htlc_params, htlc_deployment, start_of_swap)
             .await
     }
@@ -214,9 +627,29 @@ impl HtlcFunded<Ethereum, ((asset::Ether, asset::Erc20)), identity::Ethereum> fo
         htlc_deployment: &Deployed<transaction::Ethereum, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Funded<transaction::Ethereum, __TYPE0__>> {
-        self.ethereum_connector
-            .htlc_funded(htlc_params, htlc_deployment, start_of_swap)
-            .await
+        loop {
+            let funded = self
+                .ethereum_chain_follower
+                .htlc_funded(htlc_params, htlc_deployment, start_of_swap)
+                .await?;
+
+            match self
+                .ethereum_confirmed(&funded.transaction, start_of_swap)
+                .await
+            {
+                Ok(confirmations) => {
+                    return Ok(Funded {
+                        confirmations: Some(confirmations),
+                        ..funded
+                    })
+                }
+                Err(e) => tracing::warn!(
+                    "fund transaction {:x}: {}, watching the htlc again",
+                    funded.transaction.hash,
+                    e
+                ),
+            }
+        }
     }
 }
 
@@ -228,9 +661,24 @@ impl HtlcDeployed<Ethereum, ((asset::Ether, asset::Erc20)), identity::Ethereum>
         htlc_params: &HtlcParams<Ethereum, __TYPE0__, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Deployed<transaction::Ethereum, identity::Ethereum>> {
-        self.ethereum_connector
-            .htlc_deployed(htlc_params, start_of_swap)
-            .await
+        loop {
+            let deployed = self
+                .ethereum_chain_follower
+                .htlc_deployed(htlc_params, start_of_swap)
+                .await?;
+
+            match self
+                .ethereum_confirmed(&deployed.transaction, start_of_swap)
+                .await
+            {
+                Ok(_) => return Ok(deployed),
+                Err(e) => tracing::warn!(
+                    "deploy transaction {:x}: {}, watching the htlc again",
+                    deployed.transaction.hash,
+                    e
+                ),
+            }
+        }
     }
 }
 
@@ -243,7 +691,7 @@ impl HtlcRedeemed<Ethereum, ((asset::Ether, asset::Erc20)), identity::Ethereum>
         htlc_deployment: &Deployed<transaction::Ethereum, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Redeemed<transaction::Ethereum>> {
-        self.ethereum_connector
+        self.ethereum_chain_follower
             .htlc_redeemed(htlc_params, htlc_deployment, start_of_swap)
             .await
     }
@@ -258,8 +706,43 @@ impl HtlcRefunded<Ethereum, ((asset::Ether, asset::Erc20)), identity::Ethereum>
         htlc_deployment: &Deployed<transaction::Ethereum, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Refunded<transaction::Ethereum>> {
-        self.ethereum_connector
+        self.ethereum_chain_follower
             .htlc_refunded(htlc_params, htlc_deployment, start_of_swap)
             .await
     }
 }
+
+#[impl_template]
+#[async_trait::async_trait]
+impl TransactionFinality<((bitcoin::Mainnet, bitcoin::Testnet, bitcoin::Regtest))> for Facade {
+    async fn transaction_finality(
+        &self,
+        transaction: &transaction::Bitcoin,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<Finality> {
+        self.bitcoin_chain_follower
+            .transaction_finality(
+                transaction.txid(),
+                self.bitcoin_finality_depth,
+                start_of_swap,
+            )
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionFinality<Ethereum> for Facade {
+    async fn transaction_finality(
+        &self,
+        transaction: &transaction::Ethereum,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<Finality> {
+        ethereum::transaction_finality(
+            self.ethereum_connector.clone(),
+            transaction.hash,
+            self.ethereum_finality_depth,
+            start_of_swap,
+        )
+        .await
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    asset,
+    swap_protocols::{
+        actions::{
+            ethereum::{CallContract, DeployContract},
+            lnd::{AddHoldInvoice, SendPayment, SettleInvoice},
+            Actions,
+        },
+        han_halight::{halight::InvoiceState, State, SwapCommunication},
+        ledger::Ethereum,
+        rfc003::{
+            actions::{FundAction, RedeemAction, RefundAction},
+            DeriveSecret, LedgerState,
+        },
+        Role,
+    },
+};
+
+/// How long, in seconds, an invoice added by Alice stays payable.
+pub const INVOICE_EXPIRY_SECS: u32 = 3600;
+
+/// Defines the set of actions available in the han-halight protocol
+#[derive(Debug, Clone, PartialEq, strum_macros::EnumDiscriminants)]
+#[strum_discriminants(
+    name(ActionKind),
+    derive(Display, EnumString),
+    strum(serialize_all = "snake_case")
+)]
+pub enum Action {
+    Accept,
+    Decline,
+    Init(AddHoldInvoice),
+    Fund(Fund),
+    Redeem(Redeem),
+    Refund(CallContract),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fund {
+    Han(DeployContract),
+    Halight(SendPayment),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redeem {
+    Han(CallContract),
+    Halight(SettleInvoice),
+}
+
+impl Actions for State {
+    type ActionKind = Action;
+
+    fn actions(&self) -> Vec<Action> {
+        match self.role {
+            Role::Alice => self.alice_actions(),
+            Role::Bob => self.bob_actions(),
+        }
+    }
+}
+
+impl State {
+    fn alice_actions(&self) -> Vec<Action> {
+        let (htlc_params, invoice_params) =
+            match (self.alpha_htlc_params(), self.beta_invoice_params()) {
+                (Some(htlc_params), Some(invoice_params)) => (htlc_params, invoice_params),
+                _ => return vec![],
+            };
+
+        let mut actions = vec![];
+
+        match self.beta_ledger_state {
+            InvoiceState::None => actions.push(Action::Init(AddHoldInvoice {
+                amount: invoice_params.asset,
+                secret_hash: invoice_params.secret_hash,
+                expiry: INVOICE_EXPIRY_SECS,
+                cltv_expiry: invoice_params.cltv_expiry,
+                ledger: invoice_params.ledger,
+                self_public_key: invoice_params.redeem_identity,
+            })),
+            InvoiceState::Accepted => {
+                actions.push(Action::Redeem(Redeem::Halight(SettleInvoice {
+                    secret: self.secret_source.derive_secret(),
+                    ledger: invoice_params.ledger,
+                    self_public_key: invoice_params.redeem_identity,
+                })))
+            }
+            _ => {}
+        }
+
+        match &self.alpha_ledger_state {
+            LedgerState::NotDeployed if self.beta_ledger_state == InvoiceState::Opened => actions
+                .push(Action::Fund(Fund::Han(
+                    <(Ethereum, asset::Ether)>::fund_action(htlc_params),
+                ))),
+            LedgerState::Funded {
+                htlc_location,
+                fund_transaction,
+                ..
+            } => actions.push(Action::Refund(<(Ethereum, asset::Ether)>::refund_action(
+                htlc_params,
+                *htlc_location,
+                &self.secret_source,
+                fund_transaction,
+            ))),
+            _ => {}
+        }
+
+        actions
+    }
+
+    fn bob_actions(&self) -> Vec<Action> {
+        if let SwapCommunication::Proposed { .. } = self.swap_communication {
+            return vec![Action::Accept, Action::Decline];
+        }
+
+        let (htlc_params, invoice_params) =
+            match (self.alpha_htlc_params(), self.beta_invoice_params()) {
+                (Some(htlc_params), Some(invoice_params)) => (htlc_params, invoice_params),
+                _ => return vec![],
+            };
+
+        let htlc_location = match self.alpha_ledger_state {
+            LedgerState::Funded { htlc_location, .. } => htlc_location,
+            _ => return vec![],
+        };
+
+        match self.beta_ledger_state {
+            InvoiceState::Opened => vec![Action::Fund(Fund::Halight(SendPayment {
+                to_public_key: invoice_params.redeem_identity,
+                amount: invoice_params.asset,
+                secret_hash: invoice_params.secret_hash,
+                final_cltv_delta: invoice_params.cltv_expiry,
+                ledger: invoice_params.ledger,
+                self_public_key: invoice_params.refund_identity,
+            }))],
+            InvoiceState::Settled { secret } => vec![Action::Redeem(Redeem::Han(<(
+                Ethereum,
+                asset::Ether,
+            )>::redeem_action(
+                htlc_params,
+                htlc_location,
+                &self.secret_source,
+                secret,
+            )))],
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_kind_should_render_to_string() {
+        assert_eq!(ActionKind::Accept.to_string(), "accept".to_string());
+        assert_eq!(ActionKind::Decline.to_string(), "decline".to_string());
+        assert_eq!(ActionKind::Init.to_string(), "init".to_string());
+        assert_eq!(ActionKind::Fund.to_string(), "fund".to_string());
+        assert_eq!(ActionKind::Redeem.to_string(), "redeem".to_string());
+        assert_eq!(ActionKind::Refund.to_string(), "refund".to_string());
+    }
+}
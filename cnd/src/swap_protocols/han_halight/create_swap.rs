@@ -0,0 +1,330 @@
+use crate::{
+    asset, identity,
+    swap_protocols::{
+        han_halight::{
+            halight::{self, InvoiceEvents},
+            state::{States, SwapEvent},
+            AcceptedSwap,
+        },
+        ledger::Ethereum,
+        rfc003::{
+            create_swap::HtlcParams,
+            events::{HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded},
+        },
+        Role,
+    },
+};
+use chrono::NaiveDateTime;
+use futures_core::future::{self, Either};
+use genawaiter::{
+    sync::{Co, Gen},
+    GeneratorState,
+};
+use std::sync::Arc;
+
+/// Returns a future that tracks the han-ether/halight-bitcoin swap negotiated
+/// from the given request and accept response.
+///
+/// Alpha ledger events are found by watching the Ethereum blockchain, beta
+/// ledger events are reported by the lightning node of the given role.
+pub async fn create_swap<D>(
+    dependencies: D,
+    states: Arc<States>,
+    accepted: AcceptedSwap,
+    role: Role,
+) where
+    D: HtlcFunded<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcDeployed<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcRedeemed<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcRefunded<Ethereum, asset::Ether, identity::Ethereum>
+        + InvoiceEvents
+        + Clone,
+{
+    let (request, accept, at) = accepted;
+
+    let id = request.swap_id;
+    let htlc_params = HtlcParams {
+        asset: request.alpha_asset.clone(),
+        ledger: request.alpha_ledger,
+        redeem_identity: accept.alpha_ledger_redeem_identity,
+        refund_identity: request.alpha_ledger_refund_identity,
+        expiry: request.alpha_expiry,
+        secret_hash: request.secret_hash,
+    };
+    let invoice_params = halight::Params {
+        asset: request.beta_asset,
+        ledger: request.beta_ledger,
+        redeem_identity: request.beta_ledger_redeem_identity,
+        refund_identity: accept.beta_ledger_refund_identity,
+        cltv_expiry: request.beta_cltv_expiry,
+        secret_hash: request.secret_hash,
+    };
+
+    let mut generator = Gen::new({
+        let dependencies = dependencies.clone();
+        |co| async move {
+            future::try_join(
+                watch_alpha_ledger(&dependencies, &co, htlc_params, at),
+                watch_beta_ledger(&dependencies, &co, invoice_params, role),
+            )
+            .await
+        }
+    });
+
+    loop {
+        match generator.async_resume().await {
+            GeneratorState::Yielded(event) => {
+                tracing::info!("swap {} yielded event {}", id, event);
+                states.update(&id, event);
+            }
+            GeneratorState::Complete(Ok(_)) => {
+                tracing::info!("swap {} finished", id);
+                return;
+            }
+            GeneratorState::Complete(Err(e)) => {
+                tracing::error!("swap {} failed with {:?}", id, e);
+                return;
+            }
+        }
+    }
+}
+
+/// Returns a future that waits for events on the Ethereum HTLC to happen.
+async fn watch_alpha_ledger<D>(
+    dependencies: &D,
+    co: &Co<SwapEvent>,
+    htlc_params: HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+    start_of_swap: NaiveDateTime,
+) -> anyhow::Result<()>
+where
+    D: HtlcFunded<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcDeployed<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcRedeemed<Ethereum, asset::Ether, identity::Ethereum>
+        + HtlcRefunded<Ethereum, asset::Ether, identity::Ethereum>,
+{
+    let deployed = dependencies
+        .htlc_deployed(&htlc_params, start_of_swap)
+        .await?;
+    co.yield_(SwapEvent::AlphaDeployed(deployed.clone())).await;
+
+    let funded = dependencies
+        .htlc_funded(&htlc_params, &deployed, start_of_swap)
+        .await?;
+    co.yield_(SwapEvent::AlphaFunded(funded)).await;
+
+    let redeemed = dependencies.htlc_redeemed(&htlc_params, &deployed, start_of_swap);
+    let refunded = dependencies.htlc_refunded(&htlc_params, &deployed, start_of_swap);
+
+    match future::try_select(redeemed, refunded).await {
+        Ok(Either::Left((redeemed, _))) => {
+            co.yield_(SwapEvent::AlphaRedeemed(redeemed)).await;
+        }
+        Ok(Either::Right((refunded, _))) => {
+            co.yield_(SwapEvent::AlphaRefunded(refunded)).await;
+        }
+        Err(either) => {
+            let (error, _other_future) = either.factor_first();
+
+            return Err(error);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a future that waits for the hold invoice to progress.
+async fn watch_beta_ledger<D>(
+    dependencies: &D,
+    co: &Co<SwapEvent>,
+    params: halight::Params,
+    role: Role,
+) -> anyhow::Result<()>
+where
+    D: InvoiceEvents,
+{
+    let opened = dependencies.invoice_opened(params, role).await?;
+    co.yield_(SwapEvent::BetaOpened(opened)).await;
+
+    let accepted = dependencies.invoice_accepted(params, role);
+    let cancelled = dependencies.invoice_cancelled(params, role);
+
+    match future::try_select(accepted, cancelled).await {
+        Ok(Either::Left((accepted, _))) => {
+            co.yield_(SwapEvent::BetaAccepted(accepted)).await;
+        }
+        Ok(Either::Right((cancelled, _))) => {
+            co.yield_(SwapEvent::BetaCancelled(cancelled)).await;
+            return Ok(());
+        }
+        Err(either) => {
+            let (error, _other_future) = either.factor_first();
+
+            return Err(error);
+        }
+    }
+
+    let settled = dependencies.invoice_settled(params, role);
+    let cancelled = dependencies.invoice_cancelled(params, role);
+
+    match future::try_select(settled, cancelled).await {
+        Ok(Either::Left((settled, _))) => {
+            co.yield_(SwapEvent::BetaSettled(settled)).await;
+        }
+        Ok(Either::Right((cancelled, _))) => {
+            co.yield_(SwapEvent::BetaCancelled(cancelled)).await;
+        }
+        Err(either) => {
+            let (error, _other_future) = either.factor_first();
+
+            return Err(error);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        quickcheck::Quickcheck,
+        seed::{DeriveSwapSeed, RootSeed},
+        swap_protocols::{
+            han_halight::{halight::InvoiceState, Accept, Request, State},
+            rfc003::{
+                events::{Deployed, Funded, Redeemed, Refunded},
+                Secret,
+            },
+        },
+        transaction,
+    };
+    use quickcheck::{Arbitrary, StdThreadGen};
+    use std::time::Duration;
+
+    /// Dependencies of a han-halight swap whose invoice is settled right
+    /// away, the Ethereum HTLC is never deployed.
+    #[derive(Clone, Copy, Debug)]
+    struct StubFacade {
+        secret: Secret,
+    }
+
+    #[async_trait::async_trait]
+    impl InvoiceEvents for StubFacade {
+        async fn invoice_opened(
+            &self,
+            _: halight::Params,
+            _: Role,
+        ) -> anyhow::Result<halight::Opened> {
+            Ok(halight::Opened)
+        }
+
+        async fn invoice_accepted(
+            &self,
+            _: halight::Params,
+            _: Role,
+        ) -> anyhow::Result<halight::Accepted> {
+            Ok(halight::Accepted)
+        }
+
+        async fn invoice_settled(
+            &self,
+            _: halight::Params,
+            _: Role,
+        ) -> anyhow::Result<halight::Settled> {
+            Ok(halight::Settled {
+                secret: self.secret,
+            })
+        }
+
+        async fn invoice_cancelled(
+            &self,
+            _: halight::Params,
+            _: Role,
+        ) -> anyhow::Result<halight::Cancelled> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcDeployed<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_deployed(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Deployed<transaction::Ethereum, identity::Ethereum>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcFunded<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_funded(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: &Deployed<transaction::Ethereum, identity::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Funded<transaction::Ethereum, asset::Ether>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcRedeemed<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_redeemed(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: &Deployed<transaction::Ethereum, identity::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Redeemed<transaction::Ethereum>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcRefunded<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_refunded(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: &Deployed<transaction::Ethereum, identity::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Refunded<transaction::Ethereum>> {
+            future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn han_halight_swap_follows_invoice_past_accepted() {
+        let secret = Secret::from(*b"hello world, you are beautiful!!");
+        let mut g = StdThreadGen::new(100);
+        let request = Quickcheck::<Request>::arbitrary(&mut g).0;
+        let accept = Quickcheck::<Accept>::arbitrary(&mut g).0;
+        let id = request.swap_id;
+        let states = Arc::new(States::default());
+        states.insert(
+            id,
+            State::accepted(
+                Role::Alice,
+                request.clone(),
+                accept,
+                RootSeed::from([0u8; 32]).derive_swap_seed(id),
+            ),
+        );
+        tokio::spawn(create_swap(
+            StubFacade { secret },
+            Arc::clone(&states),
+            (request, accept, NaiveDateTime::from_timestamp(0, 0)),
+            Role::Alice,
+        ));
+
+        let settled = InvoiceState::Settled { secret };
+        let poll = async {
+            while states.get(&id).unwrap().beta_ledger_state != settled {
+                tokio::time::delay_for(Duration::from_millis(10)).await;
+            }
+        };
+
+        assert!(tokio::time::timeout(Duration::from_secs(5), poll)
+            .await
+            .is_ok());
+    }
+}
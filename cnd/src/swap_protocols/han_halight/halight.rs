@@ -0,0 +1,172 @@
+//! HALight: a hold invoice on the Lightning Network used as the "HTLC" of the
+//! beta ledger.
+//!
+//! In contrast to an on-chain HTLC, there is nothing to watch on a blockchain.
+//! The lifecycle of the invoice is observed through the lightning node of the
+//! party involved.
+
+use crate::{
+    asset, identity,
+    swap_protocols::{
+        ledger::Lightning,
+        rfc003::{Secret, SecretHash},
+        Role,
+    },
+};
+use serde::Serialize;
+use strum_macros::EnumDiscriminants;
+
+/// Everything that is needed to add, pay or watch the hold invoice of a swap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Params {
+    pub asset: asset::Bitcoin,
+    pub ledger: Lightning,
+    /// The public key of the node receiving the payment.
+    pub redeem_identity: identity::Lightning,
+    /// The public key of the node making the payment.
+    pub refund_identity: identity::Lightning,
+    pub cltv_expiry: u32,
+    pub secret_hash: SecretHash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Opened;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Accepted;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settled {
+    pub secret: Secret,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cancelled;
+
+/// Resolve the lifecycle events of a hold invoice.
+///
+/// Each method returns once the invoice identified by the given `Params` has
+/// reached the respective state. The events are observed differently depending
+/// on `role`: Alice added the invoice and watches it, Bob only knows about the
+/// payment he made.
+#[async_trait::async_trait]
+pub trait InvoiceEvents: Send + Sync + 'static {
+    async fn invoice_opened(&self, params: Params, role: Role) -> anyhow::Result<Opened>;
+    async fn invoice_accepted(&self, params: Params, role: Role) -> anyhow::Result<Accepted>;
+    async fn invoice_settled(&self, params: Params, role: Role) -> anyhow::Result<Settled>;
+    async fn invoice_cancelled(&self, params: Params, role: Role) -> anyhow::Result<Cancelled>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, EnumDiscriminants)]
+#[strum_discriminants(
+    name(InvoiceStatus),
+    derive(Serialize, Display),
+    serde(rename_all = "SCREAMING_SNAKE_CASE")
+)]
+pub enum InvoiceState {
+    /// The invoice has not been added by the receiving node yet.
+    None,
+    /// The invoice has been added and is waiting for payment.
+    Opened,
+    /// A payment for the invoice has been locked in but not yet settled.
+    Accepted,
+    /// The receiving node settled the invoice, revealing the secret.
+    Settled { secret: Secret },
+    /// The invoice was cancelled, any locked in payment has been returned.
+    Cancelled,
+}
+
+impl Default for InvoiceState {
+    fn default() -> Self {
+        InvoiceState::None
+    }
+}
+
+/// Updates of the invoice that do not fit its current state, e.g. one that was
+/// reported twice after resubscribing, are logged and ignored.
+impl InvoiceState {
+    pub fn transition_to_opened(&mut self, _opened: Opened) {
+        match *self {
+            InvoiceState::None => *self = InvoiceState::Opened,
+            other => other.ignore_transition("OPENED"),
+        }
+    }
+
+    pub fn transition_to_accepted(&mut self, _accepted: Accepted) {
+        match *self {
+            InvoiceState::Opened => *self = InvoiceState::Accepted,
+            other => other.ignore_transition("ACCEPTED"),
+        }
+    }
+
+    pub fn transition_to_settled(&mut self, settled: Settled) {
+        match *self {
+            InvoiceState::Accepted => {
+                *self = InvoiceState::Settled {
+                    secret: settled.secret,
+                }
+            }
+            other => other.ignore_transition("SETTLED"),
+        }
+    }
+
+    pub fn transition_to_cancelled(&mut self, _cancelled: Cancelled) {
+        match *self {
+            // Alice can cancel the invoice before Bob paid
+            InvoiceState::Opened | InvoiceState::Accepted => *self = InvoiceState::Cancelled,
+            other => other.ignore_transition("CANCELLED"),
+        }
+    }
+
+    fn ignore_transition(&self, to: &str) {
+        tracing::warn!(
+            "ignoring transition of invoice from {} to {}",
+            InvoiceStatus::from(*self),
+            to
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invoice_status_serializes_as_screaming_snake_case() {
+        let status = InvoiceStatus::from(InvoiceState::Opened);
+
+        assert_eq!(serde_json::to_string(&status).unwrap(), r#""OPENED""#);
+    }
+
+    #[test]
+    fn invoice_can_be_cancelled_before_payment() {
+        let mut state = InvoiceState::None;
+
+        state.transition_to_opened(Opened);
+        state.transition_to_cancelled(Cancelled);
+
+        assert_eq!(state, InvoiceState::Cancelled);
+    }
+
+    #[test]
+    fn invoice_cannot_be_settled_without_payment() {
+        let mut state = InvoiceState::Opened;
+
+        state.transition_to_settled(Settled {
+            secret: Secret::from([0u8; 32]),
+        });
+
+        assert_eq!(state, InvoiceState::Opened);
+    }
+
+    #[test]
+    fn repeated_update_is_ignored() {
+        let mut state = InvoiceState::None;
+
+        state.transition_to_opened(Opened);
+        state.transition_to_accepted(Accepted);
+        state.transition_to_opened(Opened);
+
+        assert_eq!(state, InvoiceState::Accepted);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    asset::{self, AssetKind},
+    comit_api::LedgerKind,
+    identity,
+    libp2p_comit_ext::ToHeader,
+    swap_protocols::{
+        ledger::{Ethereum, Lightning},
+        rfc003::SecretHash,
+        HashFunction, SwapId, SwapProtocol,
+    },
+    timestamp::Timestamp,
+};
+use libp2p_comit::frame::OutboundRequest;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+pub use crate::swap_protocols::rfc003::messages::{
+    Decision, Decline, DeclineResponseBody, SwapDeclineReason,
+};
+
+/// High-level message that represents a han-ether/halight-bitcoin swap
+/// request to another party.
+///
+/// Alpha is always an ether HTLC on Ethereum (HAN), beta is always a hold
+/// invoice on Lightning (HALight). This does _not_ represent the actual network
+/// message, that is why it also does not implement Serialize.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Request {
+    pub swap_id: SwapId,
+    pub alpha_ledger: Ethereum,
+    pub beta_ledger: Lightning,
+    pub alpha_asset: asset::Ether,
+    pub beta_asset: asset::Bitcoin,
+    pub hash_function: HashFunction,
+    pub alpha_ledger_refund_identity: identity::Ethereum,
+    pub beta_ledger_redeem_identity: identity::Lightning,
+    pub alpha_expiry: Timestamp,
+    /// The CLTV delta of the final hop of the payment, in blocks.
+    pub beta_cltv_expiry: u32,
+    pub secret_hash: SecretHash,
+}
+
+impl TryFrom<Request> for OutboundRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(request: Request) -> anyhow::Result<Self> {
+        let request_body = RequestBody::from(request.clone());
+        let protocol = SwapProtocol::HanHalight(request.hash_function).to_header()?;
+
+        let alpha_ledger = LedgerKind::Ethereum(request.alpha_ledger).to_header()?;
+        let beta_ledger = LedgerKind::Lightning(request.beta_ledger).to_header()?;
+        let alpha_asset = AssetKind::Ether(request.alpha_asset).to_header()?;
+        let beta_asset = AssetKind::Bitcoin(request.beta_asset).to_header()?;
+
+        Ok(OutboundRequest::new("SWAP")
+            .with_header("id", request.swap_id.to_header()?)
+            .with_header("alpha_ledger", alpha_ledger)
+            .with_header("beta_ledger", beta_ledger)
+            .with_header("alpha_asset", alpha_asset)
+            .with_header("beta_asset", beta_asset)
+            .with_header("protocol", protocol)
+            .with_body(serde_json::to_value(request_body)?))
+    }
+}
+
+/// High-level message that represents accepting a han-ether/halight-bitcoin
+/// swap request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Accept {
+    pub swap_id: SwapId,
+    pub alpha_ledger_redeem_identity: identity::Ethereum,
+    pub beta_ledger_refund_identity: identity::Lightning,
+}
+
+/// Swap request response as received from peer node acting as Bob.
+pub type Response = Result<Accept, Decline>;
+
+/// Body of the han-halight request message
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct RequestBody {
+    pub alpha_ledger_refund_identity: identity::Ethereum,
+    pub beta_ledger_redeem_identity: identity::Lightning,
+    pub alpha_expiry: Timestamp,
+    pub beta_cltv_expiry: u32,
+    pub secret_hash: SecretHash,
+}
+
+impl From<Request> for RequestBody {
+    fn from(request: Request) -> Self {
+        RequestBody {
+            alpha_ledger_refund_identity: request.alpha_ledger_refund_identity,
+            beta_ledger_redeem_identity: request.beta_ledger_redeem_identity,
+            alpha_expiry: request.alpha_expiry,
+            beta_cltv_expiry: request.beta_cltv_expiry,
+            secret_hash: request.secret_hash,
+        }
+    }
+}
+
+/// Body of the han-halight accept message
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcceptResponseBody {
+    pub alpha_ledger_redeem_identity: identity::Ethereum,
+    pub beta_ledger_refund_identity: identity::Lightning,
+}
+
+impl From<Accept> for AcceptResponseBody {
+    fn from(accept: Accept) -> Self {
+        AcceptResponseBody {
+            alpha_ledger_redeem_identity: accept.alpha_ledger_redeem_identity,
+            beta_ledger_refund_identity: accept.beta_ledger_refund_identity,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn request_from_body(
+    swap_id: SwapId,
+    alpha_ledger: Ethereum,
+    beta_ledger: Lightning,
+    alpha_asset: asset::Ether,
+    beta_asset: asset::Bitcoin,
+    hash_function: HashFunction,
+    body: RequestBody,
+) -> Request {
+    Request {
+        swap_id,
+        alpha_ledger,
+        beta_ledger,
+        alpha_asset,
+        beta_asset,
+        hash_function,
+        alpha_ledger_refund_identity: body.alpha_ledger_refund_identity,
+        beta_ledger_redeem_identity: body.beta_ledger_redeem_identity,
+        alpha_expiry: body.alpha_expiry,
+        beta_cltv_expiry: body.beta_cltv_expiry,
+        secret_hash: body.secret_hash,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn serialize_request_body() {
+        let body = RequestBody {
+            alpha_ledger_refund_identity: identity::Ethereum::zero(),
+            beta_ledger_redeem_identity: identity::Lightning::from_str(
+                "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+            )
+            .unwrap(),
+            alpha_expiry: Timestamp::from(2_000_000_000),
+            beta_cltv_expiry: 144,
+            secret_hash: SecretHash::from_str(
+                "68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4cec",
+            )
+            .unwrap(),
+        };
+
+        let json = serde_json::to_string(&body).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"alpha_ledger_refund_identity":"0x0000000000000000000000000000000000000000","beta_ledger_redeem_identity":"02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275","alpha_expiry":2000000000,"beta_cltv_expiry":144,"secret_hash":"68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4cec"}"#
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! Swap ether on Ethereum (HAN) for bitcoin on the Lightning Network
+//! (HALight).
+//!
+//! Alice sells ether and buys bitcoin: she adds a hold invoice to her lightning
+//! node and locks the ether in an HTLC. Bob pays the invoice, Alice settles it
+//! revealing the secret which Bob then uses to redeem the ether.
+
+pub mod actions;
+pub mod create_swap;
+pub mod halight;
+pub mod messages;
+mod state;
+
+pub use self::{
+    create_swap::create_swap,
+    messages::{Accept, Decline, Request},
+    state::{AlphaLedgerState, State, States, SwapCommunication, SwapEvent},
+};
+
+use chrono::NaiveDateTime;
+
+/// A swap that has been accepted by Bob, together with the time it was
+/// accepted at.
+pub type AcceptedSwap = (Request, Accept, NaiveDateTime);
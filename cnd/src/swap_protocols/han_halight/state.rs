@@ -0,0 +1,215 @@
+use crate::{
+    asset, htlc_location, identity,
+    seed::SwapSeed,
+    swap_protocols::{
+        han_halight::{
+            halight::{self, InvoiceState},
+            messages::{Accept, Decline, Request},
+        },
+        ledger::Ethereum,
+        rfc003::{
+            create_swap::HtlcParams,
+            events::{Deployed, Funded, Redeemed, Refunded},
+            LedgerState,
+        },
+        Role, SwapId,
+    },
+    transaction,
+};
+use derivative::Derivative;
+use std::{collections::HashMap, sync::Mutex};
+
+pub type AlphaLedgerState =
+    LedgerState<htlc_location::Ethereum, transaction::Ethereum, asset::Ether>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SwapCommunication {
+    Proposed { request: Request },
+    Accepted { request: Request, response: Accept },
+    Declined { request: Request, response: Decline },
+}
+
+impl SwapCommunication {
+    pub fn request(&self) -> &Request {
+        match self {
+            SwapCommunication::Proposed { request } => request,
+            SwapCommunication::Accepted { request, .. } => request,
+            SwapCommunication::Declined { request, .. } => request,
+        }
+    }
+}
+
+/// The state of a han-ether/halight-bitcoin swap from the perspective of one
+/// of the two parties.
+#[derive(Clone, Derivative)]
+#[derivative(Debug, PartialEq)]
+pub struct State {
+    pub role: Role,
+    pub swap_communication: SwapCommunication,
+    pub alpha_ledger_state: AlphaLedgerState,
+    pub beta_ledger_state: InvoiceState,
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub secret_source: SwapSeed,
+}
+
+impl State {
+    pub fn proposed(role: Role, request: Request, secret_source: SwapSeed) -> Self {
+        Self::new(role, SwapCommunication::Proposed { request }, secret_source)
+    }
+
+    pub fn accepted(
+        role: Role,
+        request: Request,
+        response: Accept,
+        secret_source: SwapSeed,
+    ) -> Self {
+        Self::new(
+            role,
+            SwapCommunication::Accepted { request, response },
+            secret_source,
+        )
+    }
+
+    pub fn declined(
+        role: Role,
+        request: Request,
+        response: Decline,
+        secret_source: SwapSeed,
+    ) -> Self {
+        Self::new(
+            role,
+            SwapCommunication::Declined { request, response },
+            secret_source,
+        )
+    }
+
+    fn new(role: Role, swap_communication: SwapCommunication, secret_source: SwapSeed) -> Self {
+        Self {
+            role,
+            swap_communication,
+            alpha_ledger_state: LedgerState::NotDeployed,
+            beta_ledger_state: InvoiceState::None,
+            secret_source,
+        }
+    }
+
+    pub fn request(&self) -> &Request {
+        self.swap_communication.request()
+    }
+
+    pub fn alpha_htlc_params(
+        &self,
+    ) -> Option<HtlcParams<Ethereum, asset::Ether, identity::Ethereum>> {
+        match &self.swap_communication {
+            SwapCommunication::Accepted { request, response } => Some(HtlcParams {
+                asset: request.alpha_asset.clone(),
+                ledger: request.alpha_ledger,
+                redeem_identity: response.alpha_ledger_redeem_identity,
+                refund_identity: request.alpha_ledger_refund_identity,
+                expiry: request.alpha_expiry,
+                secret_hash: request.secret_hash,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn beta_invoice_params(&self) -> Option<halight::Params> {
+        match &self.swap_communication {
+            SwapCommunication::Accepted { request, response } => Some(halight::Params {
+                asset: request.beta_asset,
+                ledger: request.beta_ledger,
+                redeem_identity: request.beta_ledger_redeem_identity,
+                refund_identity: response.beta_ledger_refund_identity,
+                cltv_expiry: request.beta_cltv_expiry,
+                secret_hash: request.secret_hash,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&mut self, event: SwapEvent) {
+        match event {
+            SwapEvent::AlphaDeployed(deployed) => {
+                self.alpha_ledger_state.transition_to_deployed(deployed)
+            }
+            SwapEvent::AlphaFunded(funded) => {
+                let expected_asset = &self.request().alpha_asset;
+
+                match expected_asset.cmp(&funded.asset) {
+                    std::cmp::Ordering::Equal => {
+                        self.alpha_ledger_state.transition_to_funded(funded)
+                    }
+                    _ => self
+                        .alpha_ledger_state
+                        .transition_to_incorrectly_funded(funded),
+                }
+            }
+            SwapEvent::AlphaRedeemed(redeemed) => {
+                self.alpha_ledger_state.transition_to_redeemed(redeemed)
+            }
+            SwapEvent::AlphaRefunded(refunded) => {
+                self.alpha_ledger_state.transition_to_refunded(refunded)
+            }
+            SwapEvent::BetaOpened(opened) => self.beta_ledger_state.transition_to_opened(opened),
+            SwapEvent::BetaAccepted(accepted) => {
+                self.beta_ledger_state.transition_to_accepted(accepted)
+            }
+            SwapEvent::BetaSettled(settled) => {
+                self.beta_ledger_state.transition_to_settled(settled)
+            }
+            SwapEvent::BetaCancelled(cancelled) => {
+                self.beta_ledger_state.transition_to_cancelled(cancelled)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, strum_macros::Display)]
+pub enum SwapEvent {
+    AlphaDeployed(Deployed<transaction::Ethereum, htlc_location::Ethereum>),
+    AlphaFunded(Funded<transaction::Ethereum, asset::Ether>),
+    AlphaRedeemed(Redeemed<transaction::Ethereum>),
+    AlphaRefunded(Refunded<transaction::Ethereum>),
+
+    BetaOpened(halight::Opened),
+    BetaAccepted(halight::Accepted),
+    BetaSettled(halight::Settled),
+    BetaCancelled(halight::Cancelled),
+}
+
+/// In-memory storage of the state of all han-halight swaps.
+///
+/// Unlike `InMemoryStateStore` this only ever holds one type of state, hence no
+/// downcasting is necessary.
+#[derive(Default, Debug)]
+pub struct States {
+    states: Mutex<HashMap<SwapId, State>>,
+}
+
+impl States {
+    pub fn insert(&self, key: SwapId, value: State) {
+        let mut states = self.states.lock().unwrap();
+        states.insert(key, value);
+    }
+
+    pub fn get(&self, key: &SwapId) -> Option<State> {
+        let states = self.states.lock().unwrap();
+        states.get(key).cloned()
+    }
+
+    pub fn all(&self) -> Vec<(SwapId, State)> {
+        let states = self.states.lock().unwrap();
+        states
+            .iter()
+            .map(|(id, state)| (*id, state.clone()))
+            .collect()
+    }
+
+    pub fn update(&self, key: &SwapId, event: SwapEvent) {
+        let mut states = self.states.lock().unwrap();
+        match states.get_mut(key) {
+            Some(state) => state.apply(event),
+            None => tracing::warn!("value not found for key {}", key),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::comit_api::LedgerKind;
+
+/// The Lightning Network, identified by the Bitcoin network it settles on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Lightning {
+    pub network: ::bitcoin::Network,
+}
+
+impl Lightning {
+    pub fn new(network: ::bitcoin::Network) -> Self {
+        Lightning { network }
+    }
+}
+
+impl Default for Lightning {
+    fn default() -> Self {
+        Lightning {
+            network: ::bitcoin::Network::Regtest,
+        }
+    }
+}
+
+impl From<Lightning> for LedgerKind {
+    fn from(lightning: Lightning) -> Self {
+        LedgerKind::Lightning(lightning)
+    }
+}
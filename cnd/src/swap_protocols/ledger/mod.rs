@@ -1,4 +1,5 @@
 pub mod bitcoin;
 pub mod ethereum;
+pub mod lightning;
 
-pub use self::{bitcoin::Bitcoin, ethereum::Ethereum};
+pub use self::{bitcoin::Bitcoin, ethereum::Ethereum, lightning::Lightning};
@@ -1,7 +1,9 @@
 pub mod actions;
 mod facade;
+pub mod han_halight;
 pub mod ledger;
 pub mod rfc003;
+pub mod state_changes;
 mod swap_id;
 
 pub use self::{facade::*, swap_id::*};
@@ -30,9 +32,10 @@ pub enum HashFunction {
 #[derive(Debug, Clone, Copy)]
 pub enum SwapProtocol {
     Rfc003(HashFunction),
+    HanHalight(HashFunction),
 }
 
-#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
+#[derive(Clone, Copy, Debug, Display, EnumString, Deserialize, Serialize, PartialEq)]
 pub enum Role {
     Alice,
     Bob,
@@ -3,14 +3,16 @@ pub mod erc20;
 pub mod ether;
 
 use crate::swap_protocols::rfc003::{DeriveIdentities, Secret};
+use serde::Serialize;
 use std::marker::PhantomData;
 
 /// Defines the set of actions available in the RFC003 protocol
 #[derive(Debug, Clone, PartialEq, strum_macros::EnumDiscriminants)]
 #[strum_discriminants(
     name(ActionKind),
-    derive(Display, EnumString),
-    strum(serialize_all = "snake_case")
+    derive(Display, EnumString, Serialize),
+    strum(serialize_all = "snake_case"),
+    serde(rename_all = "snake_case")
 )]
 pub enum Action<Accept, Decline, Deploy, Fund, Redeem, Refund> {
     Accept(Accept),
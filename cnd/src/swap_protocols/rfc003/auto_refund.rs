@@ -0,0 +1,179 @@
+use crate::{
+    db::{LedgerSide, LoadAutomaticActions, SaveAutomaticAction},
+    health::{self, EnsureAvailable},
+    seed::SwapSeed,
+    swap_protocols::{
+        rfc003::{
+            actions::{ActionKind, RefundAction},
+            create_swap::HtlcParams,
+            state_store::StateStore,
+            ActorState, Ledger, LedgerState,
+        },
+        SwapId,
+    },
+    timestamp::Timestamp,
+};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// How long to wait before looking at the HTLC again if it cannot be refunded
+/// yet.
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Execute a refund action without involving the user.
+///
+/// `O` is the type of refund action as returned by `RefundAction`.
+#[async_trait]
+pub trait ExecuteRefund<O>: Send + Sync + 'static {
+    /// Returns true if refunds of this kind should be executed automatically.
+    fn auto_refund_enabled(&self) -> bool;
+
+    /// Resolves once the ledger accepts a refund of an HTLC that expires at
+    /// `expiry`.
+    async fn wait_until_refundable(&self, expiry: Timestamp);
+
+    /// Signs and broadcasts the refund transaction and returns its hash.
+    async fn execute_refund(&self, action: O) -> anyhow::Result<String>;
+}
+
+/// Resolves once the wall clock passed the given time.
+pub async fn wait_until(time: Timestamp) {
+    let now = u32::from(Timestamp::now());
+    let time = u32::from(time);
+    if time > now {
+        tokio::time::delay_for(Duration::from_secs(u64::from(time - now))).await;
+    }
+}
+
+/// Refunds the HTLC we funded on the given ledger once it expired.
+///
+/// Nothing happens unless automatic refunds are enabled for the ledger. After
+/// the expiry, the refund is retried until it succeeds or the HTLC is redeemed
+/// or refunded by other means. Every executed refund is saved to the database
+/// so that it is not executed again after a restart. The refund waits while
+/// one of the `ledgers` of the swap is unavailable.
+pub async fn refund_after_expiry<D, S, L, A, I>(
+    dependencies: D,
+    id: SwapId,
+    ledgers: [health::Ledger; 2],
+    ledger: LedgerSide,
+    htlc_params: HtlcParams<L, A, I>,
+    secret_source: SwapSeed,
+    ledger_state: fn(&mut S) -> &mut LedgerState<L::HtlcLocation, L::Transaction, A>,
+) where
+    D: StateStore
+        + ExecuteRefund<<(L, A) as RefundAction>::Output>
+        + EnsureAvailable
+        + SaveAutomaticAction
+        + LoadAutomaticActions,
+    S: ActorState + Clone,
+    L: Ledger,
+    A: Clone,
+    I: Clone,
+    (L, A): RefundAction<
+        HtlcParams = HtlcParams<L, A, I>,
+        HtlcLocation = L::HtlcLocation,
+        FundTransaction = L::Transaction,
+    >,
+{
+    if !dependencies.auto_refund_enabled() {
+        return;
+    }
+
+    match dependencies.load_automatic_actions(&id).await {
+        Ok(actions) => {
+            if actions
+                .iter()
+                .any(|action| action.ledger == ledger && action.action == ActionKind::Refund)
+            {
+                tracing::info!("{} ledger of swap {} was already refunded", ledger, id);
+                return;
+            }
+        }
+        Err(e) => {
+            tracing::error!("failed to load automatic actions of swap {}: {:?}", id, e);
+            return;
+        }
+    }
+
+    dependencies.wait_until_refundable(htlc_params.expiry).await;
+
+    loop {
+        let action = {
+            let mut state = match dependencies.get::<S>(&id) {
+                Ok(Some(state)) => state,
+                Ok(None) => {
+                    tracing::error!("state of swap {} not found", id);
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!("failed to get state of swap {}: {:?}", id, e);
+                    return;
+                }
+            };
+
+            match ledger_state(&mut state) {
+                LedgerState::Funded {
+                    htlc_location,
+                    fund_transaction,
+                    ..
+                }
+                | LedgerState::IncorrectlyFunded {
+                    htlc_location,
+                    fund_transaction,
+                    ..
+                } => Some(<(L, A)>::refund_action(
+                    htlc_params.clone(),
+                    htlc_location.clone(),
+                    &secret_source,
+                    fund_transaction,
+                )),
+                LedgerState::Redeemed { .. } | LedgerState::Refunded { .. } => return,
+                LedgerState::NotDeployed | LedgerState::Deployed { .. } => None,
+            }
+        };
+
+        let action = match action {
+            Some(action) => action,
+            None => {
+                tokio::time::delay_for(RETRY_INTERVAL).await;
+                continue;
+            }
+        };
+
+        if let Err(e) = dependencies.ensure_available(&ledgers) {
+            tracing::warn!("not refunding {} ledger of swap {}: {}", ledger, id, e);
+            tokio::time::delay_for(RETRY_INTERVAL).await;
+            continue;
+        }
+
+        match dependencies.execute_refund(action).await {
+            Ok(transaction_hash) => {
+                tracing::info!(
+                    "refunded {} ledger of swap {} in transaction {}",
+                    ledger,
+                    id,
+                    transaction_hash
+                );
+
+                if let Err(e) = dependencies
+                    .save_automatic_action(id, ledger, ActionKind::Refund, transaction_hash)
+                    .await
+                {
+                    tracing::error!("failed to save automatic refund of swap {}: {:?}", id, e);
+                }
+
+                return;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "failed to refund {} ledger of swap {}, retrying: {:?}",
+                    ledger,
+                    id,
+                    e
+                );
+                tokio::time::delay_for(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    db::{LedgerSide, LoadAutomaticActions, SaveAutomaticAction},
+    health::{self, EnsureAvailable},
+    swap_protocols::{
+        actions::Actions,
+        rfc003::{actions::ActionKind, state_store::StateStore, ActorState},
+        SwapId,
+    },
+    timestamp::Timestamp,
+};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// How long to wait before looking at the actions of a swap again.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Decides which swaps are executed by the autopilot.
+#[async_trait]
+pub trait Autopilot: Send + Sync + 'static {
+    /// Returns true if the autopilot should execute the actions of this swap.
+    async fn autopilot_enabled(&self, id: &SwapId) -> anyhow::Result<bool>;
+
+    /// Deploy and fund actions are only executed if at least this much time is
+    /// left until our HTLC expires.
+    fn autopilot_safety_margin(&self) -> Duration;
+}
+
+/// Execute an action of a swap without involving the user.
+///
+/// `A` is the type of action as returned by `Actions::actions`.
+#[async_trait]
+pub trait ExecuteAutomatically<A>: Send + Sync + 'static {
+    /// Executes the action and returns the hash of the transaction it resulted
+    /// in.
+    async fn execute_automatically(&self, action: A) -> anyhow::Result<String>;
+}
+
+/// Executes the actions of a swap as they become available.
+///
+/// `our_ledger` is the ledger we fund and refund on, Alice's is alpha and
+/// Bob's is beta. The counterparty's HTLC is redeemed as soon as possible, our
+/// HTLC is only deployed and funded if the safety margin before its expiry is
+/// not exceeded and only refunded once it expired. Every executed action is
+/// saved to the database, which makes the autopilot pick up where it left off
+/// after a restart.
+///
+/// Nothing is executed while one of the `ledgers` of the swap is unavailable.
+pub async fn drive<D, S>(
+    dependencies: D,
+    id: SwapId,
+    ledgers: [health::Ledger; 2],
+    our_ledger: LedgerSide,
+    our_expiry: Timestamp,
+    their_expiry: Timestamp,
+) where
+    D: StateStore
+        + Autopilot
+        + EnsureAvailable
+        + ExecuteAutomatically<<S as Actions>::ActionKind>
+        + SaveAutomaticAction
+        + LoadAutomaticActions,
+    S: ActorState + Actions + Clone,
+    <S as Actions>::ActionKind: Send,
+    for<'a> ActionKind: From<&'a <S as Actions>::ActionKind>,
+{
+    let their_ledger = match our_ledger {
+        LedgerSide::Alpha => LedgerSide::Beta,
+        LedgerSide::Beta => LedgerSide::Alpha,
+    };
+    let ledger_of = |kind: ActionKind| match kind {
+        ActionKind::Redeem => their_ledger,
+        _ => our_ledger,
+    };
+
+    loop {
+        match dependencies.autopilot_enabled(&id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tokio::time::delay_for(POLL_INTERVAL).await;
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("failed to check autopilot of swap {}: {:?}", id, e);
+                return;
+            }
+        }
+
+        let executed = match dependencies.load_automatic_actions(&id).await {
+            Ok(executed) => executed,
+            Err(e) => {
+                tracing::error!("failed to load automatic actions of swap {}: {:?}", id, e);
+                return;
+            }
+        };
+
+        // Once we redeemed or refunded there is nothing left for us to do.
+        if executed.iter().any(|action| {
+            action.action == ActionKind::Redeem || action.action == ActionKind::Refund
+        }) {
+            tracing::info!("autopilot finished swap {}", id);
+            return;
+        }
+
+        let actions = match dependencies.get::<S>(&id) {
+            Ok(Some(state)) if state.swap_failed() => {
+                tracing::warn!("autopilot stopped because swap {} failed", id);
+                return;
+            }
+            Ok(Some(state)) => state.actions(),
+            Ok(None) => {
+                tracing::error!("state of swap {} not found", id);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("failed to get state of swap {}: {:?}", id, e);
+                return;
+            }
+        };
+
+        let now = Timestamp::now();
+        if actions.is_empty() && now > our_expiry && now > their_expiry {
+            tracing::info!("autopilot has nothing left to do for swap {}", id);
+            return;
+        }
+
+        let margin = dependencies.autopilot_safety_margin().as_secs() as u32;
+        let mut next = None;
+        for action in actions {
+            let kind = ActionKind::from(&action);
+            if executed
+                .iter()
+                .any(|executed| executed.action == kind && executed.ledger == ledger_of(kind))
+            {
+                continue;
+            }
+
+            let ready = match kind {
+                ActionKind::Accept | ActionKind::Decline => false,
+                ActionKind::Deploy | ActionKind::Fund => {
+                    // Waiting does not make this any safer, hence we give up.
+                    if now.plus(margin) >= our_expiry {
+                        tracing::warn!(
+                            "autopilot stopped because the {} HTLC of swap {} expires too soon to {} it",
+                            our_ledger,
+                            id,
+                            kind
+                        );
+                        return;
+                    }
+                    true
+                }
+                ActionKind::Redeem => true,
+                ActionKind::Refund => now >= our_expiry,
+            };
+
+            if ready {
+                next = Some(action);
+                break;
+            }
+        }
+
+        if let Some(action) = next {
+            let kind = ActionKind::from(&action);
+            let ledger = ledger_of(kind);
+
+            if let Err(e) = dependencies.ensure_available(&ledgers) {
+                tracing::warn!(
+                    "autopilot does not execute {} on {} ledger of swap {}: {}",
+                    kind,
+                    ledger,
+                    id,
+                    e
+                );
+                tokio::time::delay_for(POLL_INTERVAL).await;
+                continue;
+            }
+
+            match dependencies.execute_automatically(action).await {
+                Ok(transaction_hash) => {
+                    tracing::info!(
+                        "autopilot executed {} on {} ledger of swap {} in transaction {}",
+                        kind,
+                        ledger,
+                        id,
+                        transaction_hash
+                    );
+
+                    if let Err(e) = dependencies
+                        .save_automatic_action(id, ledger, kind, transaction_hash)
+                        .await
+                    {
+                        tracing::error!(
+                            "failed to save automatic {} of swap {}: {:?}",
+                            kind,
+                            id,
+                            e
+                        );
+                        return;
+                    }
+
+                    continue;
+                }
+                Err(e) => tracing::warn!(
+                    "autopilot failed to execute {} on {} ledger of swap {}, retrying: {:?}",
+                    kind,
+                    ledger,
+                    id,
+                    e
+                ),
+            }
+        }
+
+        tokio::time::delay_for(POLL_INTERVAL).await;
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     asset,
     btsieve::bitcoin::{
-        matching_transaction, BitcoindConnector, Cache, TransactionExt, TransactionPattern,
+        BitcoinConnector, Cache, ChainFollower, TransactionExt, TransactionPattern,
     },
     htlc_location, identity,
     swap_protocols::{
@@ -21,7 +21,7 @@ use anyhow::Context;
 use chrono::NaiveDateTime;
 
 #[async_trait::async_trait]
-impl<B> HtlcFunded<B, asset::Bitcoin, identity::Bitcoin> for Cache<BitcoindConnector>
+impl<B> HtlcFunded<B, asset::Bitcoin, identity::Bitcoin> for ChainFollower<Cache<BitcoinConnector>>
 where
     B: bitcoin::Bitcoin + bitcoin::Network,
 {
@@ -38,12 +38,14 @@ where
         Ok(Funded {
             transaction: tx.clone(),
             asset,
+            confirmations: None,
         })
     }
 }
 
 #[async_trait::async_trait]
-impl<B> HtlcDeployed<B, asset::Bitcoin, identity::Bitcoin> for Cache<BitcoindConnector>
+impl<B> HtlcDeployed<B, asset::Bitcoin, identity::Bitcoin>
+    for ChainFollower<Cache<BitcoinConnector>>
 where
     B: bitcoin::Bitcoin + bitcoin::Network,
 {
@@ -52,19 +54,26 @@ where
         htlc_params: &HtlcParams<B, asset::Bitcoin, identity::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Deployed<transaction::Bitcoin, htlc_location::Bitcoin>> {
-        let connector = self.clone();
+        let address = htlc_params.compute_address();
         let pattern = TransactionPattern {
-            to_address: Some(htlc_params.compute_address()),
+            to_address: Some(address.clone()),
             from_outpoint: None,
             unlock_script: None,
         };
 
-        let transaction = matching_transaction(connector, pattern, start_of_swap)
+        // The HTLC address is known upfront, backends that index addresses
+        // spare us walking the chain back to the start of the swap.
+        let transaction = self
+            .matching_transaction_indexed(
+                pattern,
+                self.connector.connector.transaction_to_address(&address),
+                start_of_swap,
+            )
             .await
             .context("failed to find transaction to deploy htlc")?;
 
         let (vout, _txout) = transaction
-            .find_output(&htlc_params.compute_address())
+            .find_output(&address)
             .expect("Deployment transaction must contain outpoint described in pattern");
 
         Ok(Deployed {
@@ -78,7 +87,8 @@ where
 }
 
 #[async_trait::async_trait]
-impl<B> HtlcRedeemed<B, asset::Bitcoin, identity::Bitcoin> for Cache<BitcoindConnector>
+impl<B> HtlcRedeemed<B, asset::Bitcoin, identity::Bitcoin>
+    for ChainFollower<Cache<BitcoinConnector>>
 where
     B: bitcoin::Bitcoin + bitcoin::Network,
 {
@@ -88,14 +98,14 @@ where
         htlc_deployment: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Redeemed<transaction::Bitcoin>> {
-        let connector = self.clone();
         let pattern = TransactionPattern {
             to_address: None,
             from_outpoint: Some(htlc_deployment.location),
             unlock_script: Some(vec![vec![1u8]]),
         };
 
-        let transaction = matching_transaction(connector, pattern, start_of_swap)
+        let transaction = self
+            .matching_transaction(pattern, start_of_swap)
             .await
             .context("failed to find transaction to redeem from htlc")?;
         let secret = extract_secret(&transaction, &htlc_params.secret_hash)
@@ -109,7 +119,8 @@ where
 }
 
 #[async_trait::async_trait]
-impl<B> HtlcRefunded<B, asset::Bitcoin, identity::Bitcoin> for Cache<BitcoindConnector>
+impl<B> HtlcRefunded<B, asset::Bitcoin, identity::Bitcoin>
+    for ChainFollower<Cache<BitcoinConnector>>
 where
     B: bitcoin::Bitcoin + bitcoin::Network,
 {
@@ -119,13 +130,13 @@ where
         htlc_deployment: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Refunded<transaction::Bitcoin>> {
-        let connector = self.clone();
         let pattern = TransactionPattern {
             to_address: None,
             from_outpoint: Some(htlc_deployment.location),
             unlock_script: Some(vec![vec![]]),
         };
-        let transaction = matching_transaction(connector, pattern, start_of_swap)
+        let transaction = self
+            .matching_transaction(pattern, start_of_swap)
             .await
             .context("failed to find transaction to refund from htlc")?;
 
@@ -1,22 +1,31 @@
 use crate::{
-    db::AcceptedSwap,
+    btsieve::Finality,
+    db::{AcceptedSwap, LedgerSide, LoadLedgerEvents, RollBackLedgerEvents, SaveLedgerEvent},
+    metrics,
     swap_protocols::{
+        actions::Actions,
         rfc003::{
             self,
+            actions::ActionKind,
             events::{
                 Deployed, Funded, HtlcDeployed, HtlcFunded, HtlcRedeemed, HtlcRefunded, Redeemed,
-                Refunded,
+                Refunded, TransactionFinality,
             },
             ledger::Ledger,
+            ledger_state::{HtlcState, LedgerState},
             state_store::StateStore,
             Accept, ActorState, Request, SecretHash,
         },
-        HashFunction,
+        state_changes::{LedgerEvent, PublishStateChange, StateChange},
+        HashFunction, SwapId,
     },
     timestamp::Timestamp,
 };
 use chrono::NaiveDateTime;
-use futures_core::future::{self, Either};
+use futures_core::{
+    future::{self, BoxFuture, Either, FutureExt},
+    stream::{FuturesUnordered, StreamExt},
+};
 use genawaiter::{
     sync::{Co, Gen},
     GeneratorState,
@@ -33,6 +42,19 @@ use genawaiter::{
 ///
 /// It is highly unlikely for Bob to fund the HTLC now, yet the current
 /// implementation is still waiting for that.
+///
+/// Every event is saved to the database before it is applied to the state
+/// store. Events saved by a previous run are replayed first and the ledgers
+/// are only watched for the events that did not happen yet.
+///
+/// New events and the actions they make available are published as state
+/// changes. Replayed events are not published, the actions available after the
+/// replay are.
+///
+/// The transaction of every event is watched until it is final. If a chain
+/// reorganisation orphans one of them, the event and all later events on the
+/// same ledger are removed again and the ledgers are watched from the state
+/// that is left.
 pub async fn create_swap<D, A, AI, BI>(
     dependencies: D,
     accepted: AcceptedSwap<A::AL, A::BL, A::AA, A::BA, AI, BI>,
@@ -46,6 +68,12 @@ pub async fn create_swap<D, A, AI, BI>(
         + HtlcRedeemed<A::BL, A::BA, BI>
         + HtlcRefunded<A::AL, A::AA, AI>
         + HtlcRefunded<A::BL, A::BA, BI>
+        + TransactionFinality<A::AL>
+        + TransactionFinality<A::BL>
+        + SaveLedgerEvent<A::AL, A::BL, A::AA, A::BA>
+        + LoadLedgerEvents<A::AL, A::BL, A::AA, A::BA>
+        + RollBackLedgerEvents
+        + PublishStateChange
         + Clone,
     A::AA: Ord + Clone,
     A::BA: Ord + Clone,
@@ -53,58 +81,291 @@ pub async fn create_swap<D, A, AI, BI>(
     A::BL: Clone,
     AI: Clone,
     BI: Clone,
-    A: ActorState,
+    A: ActorState + Actions + Clone + Send,
+    for<'a> ActionKind: From<&'a <A as Actions>::ActionKind>,
     AcceptedSwap<A::AL, A::BL, A::AA, A::BA, AI, BI>: Clone,
 {
     let (request, accept, at) = accepted;
 
     let id = request.swap_id;
     let swap = OngoingSwap::new(request, accept);
+    let alpha_htlc_params = swap.alpha_htlc_params();
+    let beta_htlc_params = swap.beta_htlc_params();
+    let mut available_actions = Vec::new();
 
-    // construct a generator that watches alpha and beta ledger concurrently
-    let mut generator = Gen::new({
-        let dependencies = dependencies.clone();
-        |co| async move {
-            future::try_join(
-                watch_alpha_ledger::<_, A::AL, A::BL, _, _, AI, BI>(
-                    &dependencies,
-                    &co,
-                    swap.alpha_htlc_params(),
-                    at,
-                ),
-                watch_beta_ledger::<_, A::AL, A::BL, _, _, AI, BI>(
-                    &dependencies,
-                    &co,
-                    swap.beta_htlc_params(),
-                    at,
+    loop {
+        let mut unfinal_transactions = FuturesUnordered::new();
+
+        match dependencies.load_ledger_events(&id).await {
+            Ok(events) => {
+                for event in events {
+                    tracing::info!("swap {} replayed event {}", id, event);
+                    unfinal_transactions.push(transaction_finality(&dependencies, &event, at));
+                    dependencies.update::<A>(&id, event);
+                }
+            }
+            Err(e) => {
+                tracing::error!("failed to load ledger events of swap {}: {:?}", id, e);
+                return;
+            }
+        }
+
+        let mut state = match dependencies.get::<A>(&id) {
+            Ok(Some(state)) => state,
+            Ok(None) => {
+                tracing::error!("state of swap {} not found", id);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("failed to get state of swap {}: {:?}", id, e);
+                return;
+            }
+        };
+        available_actions = publish_new_actions(&dependencies, id, &state, &available_actions);
+        let alpha_ledger_state = state.alpha_ledger_mut().clone();
+        let beta_ledger_state = state.beta_ledger_mut().clone();
+
+        // construct a generator that watches alpha and beta ledger concurrently
+        let mut generator = Gen::new({
+            let dependencies = dependencies.clone();
+            let alpha_htlc_params = alpha_htlc_params.clone();
+            let beta_htlc_params = beta_htlc_params.clone();
+            |co| async move {
+                future::try_join(
+                    watch_alpha_ledger::<_, A::AL, A::BL, _, _, AI, BI>(
+                        &dependencies,
+                        &co,
+                        alpha_htlc_params,
+                        alpha_ledger_state,
+                        at,
+                    ),
+                    watch_beta_ledger::<_, A::AL, A::BL, _, _, AI, BI>(
+                        &dependencies,
+                        &co,
+                        beta_htlc_params,
+                        beta_ledger_state,
+                        at,
+                    ),
+                )
+                .await
+            }
+        });
+        let mut finished = false;
+
+        let (ledger, roll_back_to) = loop {
+            // wait for events to be emitted as the generator executes and for the
+            // transactions of past events to become final
+            let next = if finished {
+                match unfinal_transactions.next().await {
+                    Some(finality) => Either::Right(finality),
+                    None => return,
+                }
+            } else if unfinal_transactions.is_empty() {
+                Either::Left(generator.async_resume().await)
+            } else {
+                match future::select(
+                    Box::pin(generator.async_resume()),
+                    unfinal_transactions.next(),
+                )
+                .await
+                {
+                    Either::Left((generator_state, _)) => Either::Left(generator_state),
+                    Either::Right((Some(finality), _)) => Either::Right(finality),
+                    Either::Right((None, _)) => continue,
+                }
+            };
+
+            match next {
+                // every event that is yielded is passed on
+                Either::Left(GeneratorState::Yielded(event)) => {
+                    tracing::info!("swap {} yielded event {}", id, event);
+                    if let Err(e) = dependencies.save_ledger_event(id, event.clone()).await {
+                        tracing::error!("failed to save event of swap {}: {:?}", id, e);
+                    }
+                    let change = state_change(&event);
+                    if let StateChange::LedgerEvent {
+                        ledger,
+                        event: ledger_event,
+                    } = change
+                    {
+                        metrics::ledger_event_observed(ledger, ledger_event);
+                    }
+                    unfinal_transactions.push(transaction_finality(&dependencies, &event, at));
+                    dependencies.update::<A>(&id, event);
+                    dependencies.publish_state_change(id, change);
+
+                    if let Ok(Some(state)) = dependencies.get::<A>(&id) {
+                        available_actions =
+                            publish_new_actions(&dependencies, id, &state, &available_actions);
+                    }
+                }
+                // the generator stopped executing, this means there are no more events that
+                // can be watched. The transactions are watched until they are final though.
+                Either::Left(GeneratorState::Complete(Ok(_))) => {
+                    tracing::info!("swap {} finished", id);
+                    finished = true;
+                }
+                Either::Left(GeneratorState::Complete(Err(e))) => {
+                    tracing::error!("swap {} failed with {:?}", id, e);
+                    return;
+                }
+                Either::Right((_, _, Ok(Finality::Final))) => {}
+                Either::Right((ledger, roll_back_to, Ok(Finality::Orphaned))) => {
+                    break (ledger, roll_back_to)
+                }
+                Either::Right((ledger, _, Err(e))) => tracing::warn!(
+                    "failed to watch finality of a transaction on {} ledger of swap {}: {:?}",
+                    ledger,
+                    id,
+                    e
                 ),
-            )
+            }
+        };
+
+        tracing::warn!(
+            "swap {} rolls {} ledger back to {} because a transaction was orphaned",
+            id,
+            ledger,
+            roll_back_to
+        );
+        if let Err(e) = dependencies
+            .roll_back_ledger_events(&id, ledger, roll_back_to)
             .await
+        {
+            tracing::error!("failed to roll back ledger events of swap {}: {:?}", id, e);
+            return;
         }
-    });
 
-    loop {
-        // wait for events to be emitted as the generator executes
-        match generator.async_resume().await {
-            // every event that is yielded is passed on
-            GeneratorState::Yielded(event) => {
-                tracing::info!("swap {} yielded event {}", id, event);
-                dependencies.update::<A>(&id, event);
+        // the remaining events are replayed onto fresh ledgers
+        match dependencies.get::<A>(&id) {
+            Ok(Some(mut state)) => {
+                *state.alpha_ledger_mut() = LedgerState::NotDeployed;
+                *state.beta_ledger_mut() = LedgerState::NotDeployed;
+                dependencies.insert(id, state);
             }
-            // the generator stopped executing, this means there are no more events that can be
-            // watched.
-            GeneratorState::Complete(Ok(_)) => {
-                tracing::info!("swap {} finished", id);
+            Ok(None) => {
+                tracing::error!("state of swap {} not found", id);
                 return;
             }
-            GeneratorState::Complete(Err(e)) => {
-                tracing::error!("swap {} failed with {:?}", id, e);
+            Err(e) => {
+                tracing::error!("failed to get state of swap {}: {:?}", id, e);
                 return;
             }
         }
     }
 }
 
+/// Publishes the actions of `state` that are not contained in `before` and
+/// returns all of them.
+fn publish_new_actions<D, A>(
+    dependencies: &D,
+    id: SwapId,
+    state: &A,
+    before: &[ActionKind],
+) -> Vec<ActionKind>
+where
+    D: PublishStateChange,
+    A: Actions,
+    for<'a> ActionKind: From<&'a <A as Actions>::ActionKind>,
+{
+    let actions = state
+        .actions()
+        .iter()
+        .map(ActionKind::from)
+        .collect::<Vec<_>>();
+
+    for action in actions.iter().filter(|action| !before.contains(action)) {
+        dependencies.publish_state_change(id, StateChange::ActionAvailable { action: *action });
+    }
+
+    actions
+}
+
+/// The ledger a transaction happened on, the state that ledger is rolled back to
+/// if the transaction is orphaned and whether it is final.
+type FinalityOnLedger = (LedgerSide, HtlcState, anyhow::Result<Finality>);
+
+/// Returns a future that watches the transaction of the given event until it is
+/// final or orphaned.
+fn transaction_finality<D, AL, BL, AA, BA>(
+    dependencies: &D,
+    event: &SwapEventOnLedger<AL, BL, AA, BA>,
+    start_of_swap: NaiveDateTime,
+) -> BoxFuture<'static, FinalityOnLedger>
+where
+    D: TransactionFinality<AL> + TransactionFinality<BL> + Clone,
+    AL: Ledger,
+    BL: Ledger,
+{
+    let (ledger, roll_back_to) = match event {
+        SwapEvent::AlphaDeployed(_) => (LedgerSide::Alpha, HtlcState::NotDeployed),
+        SwapEvent::AlphaFunded(_) => (LedgerSide::Alpha, HtlcState::Deployed),
+        SwapEvent::AlphaRedeemed(_) | SwapEvent::AlphaRefunded(_) => {
+            (LedgerSide::Alpha, HtlcState::Funded)
+        }
+        SwapEvent::BetaDeployed(_) => (LedgerSide::Beta, HtlcState::NotDeployed),
+        SwapEvent::BetaFunded(_) => (LedgerSide::Beta, HtlcState::Deployed),
+        SwapEvent::BetaRedeemed(_) | SwapEvent::BetaRefunded(_) => {
+            (LedgerSide::Beta, HtlcState::Funded)
+        }
+    };
+    let dependencies = dependencies.clone();
+
+    match event {
+        SwapEvent::AlphaDeployed(Deployed { transaction, .. })
+        | SwapEvent::AlphaFunded(Funded { transaction, .. })
+        | SwapEvent::AlphaRedeemed(Redeemed { transaction, .. })
+        | SwapEvent::AlphaRefunded(Refunded { transaction }) => {
+            let transaction = transaction.clone();
+
+            async move {
+                let finality = TransactionFinality::<AL>::transaction_finality(
+                    &dependencies,
+                    &transaction,
+                    start_of_swap,
+                )
+                .await;
+
+                (ledger, roll_back_to, finality)
+            }
+            .boxed()
+        }
+        SwapEvent::BetaDeployed(Deployed { transaction, .. })
+        | SwapEvent::BetaFunded(Funded { transaction, .. })
+        | SwapEvent::BetaRedeemed(Redeemed { transaction, .. })
+        | SwapEvent::BetaRefunded(Refunded { transaction }) => {
+            let transaction = transaction.clone();
+
+            async move {
+                let finality = TransactionFinality::<BL>::transaction_finality(
+                    &dependencies,
+                    &transaction,
+                    start_of_swap,
+                )
+                .await;
+
+                (ledger, roll_back_to, finality)
+            }
+            .boxed()
+        }
+    }
+}
+
+fn state_change<AH, AT, BH, BT, AA, BA>(event: &SwapEvent<AH, AT, BH, BT, AA, BA>) -> StateChange {
+    let (ledger, event) = match event {
+        SwapEvent::AlphaDeployed(_) => (LedgerSide::Alpha, LedgerEvent::Deployed),
+        SwapEvent::AlphaFunded(_) => (LedgerSide::Alpha, LedgerEvent::Funded),
+        SwapEvent::AlphaRedeemed(_) => (LedgerSide::Alpha, LedgerEvent::Redeemed),
+        SwapEvent::AlphaRefunded(_) => (LedgerSide::Alpha, LedgerEvent::Refunded),
+        SwapEvent::BetaDeployed(_) => (LedgerSide::Beta, LedgerEvent::Deployed),
+        SwapEvent::BetaFunded(_) => (LedgerSide::Beta, LedgerEvent::Funded),
+        SwapEvent::BetaRedeemed(_) => (LedgerSide::Beta, LedgerEvent::Redeemed),
+        SwapEvent::BetaRefunded(_) => (LedgerSide::Beta, LedgerEvent::Refunded),
+    };
+
+    StateChange::LedgerEvent { ledger, event }
+}
+
 /// Returns a future that waits for events on alpha ledger to happen.
 ///
 /// Each event is yielded through the controller handle (co) of the coroutine.
@@ -112,6 +373,7 @@ async fn watch_alpha_ledger<D, AL, BL, AA, BA, AI, BI>(
     dependencies: &D,
     co: &Co<SwapEventOnLedger<AL, BL, AA, BA>>,
     htlc_params: HtlcParams<AL, AA, AI>,
+    ledger_state: LedgerState<AL::HtlcLocation, AL::Transaction, AA>,
     start_of_swap: NaiveDateTime,
 ) -> anyhow::Result<()>
 where
@@ -122,15 +384,30 @@ where
         + HtlcRedeemed<AL, AA, AI>
         + HtlcRefunded<AL, AA, AI>,
 {
-    let deployed = dependencies
-        .htlc_deployed(&htlc_params, start_of_swap)
-        .await?;
-    co.yield_(SwapEvent::AlphaDeployed(deployed.clone())).await;
+    let htlc_state = HtlcState::from(&ledger_state);
 
-    let funded = dependencies
-        .htlc_funded(&htlc_params, &deployed, start_of_swap)
-        .await?;
-    co.yield_(SwapEvent::AlphaFunded(funded)).await;
+    let deployed = match ledger_state.deployed() {
+        Some(deployed) => deployed,
+        None => {
+            let deployed = dependencies
+                .htlc_deployed(&htlc_params, start_of_swap)
+                .await?;
+            co.yield_(SwapEvent::AlphaDeployed(deployed.clone())).await;
+
+            deployed
+        }
+    };
+
+    match htlc_state {
+        HtlcState::NotDeployed | HtlcState::Deployed => {
+            let funded = dependencies
+                .htlc_funded(&htlc_params, &deployed, start_of_swap)
+                .await?;
+            co.yield_(SwapEvent::AlphaFunded(funded)).await;
+        }
+        HtlcState::Redeemed | HtlcState::Refunded => return Ok(()),
+        HtlcState::Funded | HtlcState::IncorrectlyFunded => {}
+    }
 
     let redeemed = dependencies.htlc_redeemed(&htlc_params, &deployed, start_of_swap);
 
@@ -160,6 +437,7 @@ async fn watch_beta_ledger<D, AL, BL, AA, BA, AI, BI>(
     dependencies: &D,
     co: &Co<SwapEventOnLedger<AL, BL, AA, BA>>,
     htlc_params: HtlcParams<BL, BA, BI>,
+    ledger_state: LedgerState<BL::HtlcLocation, BL::Transaction, BA>,
     start_of_swap: NaiveDateTime,
 ) -> anyhow::Result<()>
 where
@@ -170,15 +448,30 @@ where
         + HtlcRedeemed<BL, BA, BI>
         + HtlcRefunded<BL, BA, BI>,
 {
-    let deployed = dependencies
-        .htlc_deployed(&htlc_params, start_of_swap)
-        .await?;
-    co.yield_(SwapEvent::BetaDeployed(deployed.clone())).await;
+    let htlc_state = HtlcState::from(&ledger_state);
 
-    let funded = dependencies
-        .htlc_funded(&htlc_params, &deployed, start_of_swap)
-        .await?;
-    co.yield_(SwapEvent::BetaFunded(funded)).await;
+    let deployed = match ledger_state.deployed() {
+        Some(deployed) => deployed,
+        None => {
+            let deployed = dependencies
+                .htlc_deployed(&htlc_params, start_of_swap)
+                .await?;
+            co.yield_(SwapEvent::BetaDeployed(deployed.clone())).await;
+
+            deployed
+        }
+    };
+
+    match htlc_state {
+        HtlcState::NotDeployed | HtlcState::Deployed => {
+            let funded = dependencies
+                .htlc_funded(&htlc_params, &deployed, start_of_swap)
+                .await?;
+            co.yield_(SwapEvent::BetaFunded(funded)).await;
+        }
+        HtlcState::Redeemed | HtlcState::Refunded => return Ok(()),
+        HtlcState::Funded | HtlcState::IncorrectlyFunded => {}
+    }
 
     let redeemed = dependencies.htlc_redeemed(&htlc_params, &deployed, start_of_swap);
 
@@ -342,7 +635,304 @@ pub enum SwapEvent<AH, AT, BH, BT, AA, BA> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{asset, ethereum, htlc_location, identity, transaction};
+    use crate::{
+        asset,
+        asset::ethereum::FromWei,
+        db::Sqlite,
+        ethereum, htlc_location, identity,
+        seed::{DeriveSwapSeed, RootSeed},
+        swap_protocols::{
+            ledger::{bitcoin::Regtest, Ethereum},
+            rfc003::{alice, state_store::InMemoryStateStore, Secret},
+        },
+        transaction,
+    };
+    use std::{
+        path::Path,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    type AliceState = alice::State<
+        Regtest,
+        Ethereum,
+        asset::Bitcoin,
+        asset::Ether,
+        identity::Bitcoin,
+        identity::Ethereum,
+    >;
+
+    /// Dependencies of an rfc003 swap whose alpha HTLC is deployed by a
+    /// transaction that is orphaned the first time its finality is watched.
+    /// Every deployment that is found has a different lock time, nothing
+    /// else ever happens on either ledger.
+    #[derive(Clone, Debug)]
+    struct StubFacade {
+        db: Sqlite,
+        state_store: Arc<InMemoryStateStore>,
+        alpha_deployments: Arc<AtomicU32>,
+        alpha_finality_checks: Arc<AtomicU32>,
+    }
+
+    fn deployed(lock_time: u32) -> Deployed<transaction::Bitcoin, htlc_location::Bitcoin> {
+        let transaction = transaction::Bitcoin {
+            version: 2,
+            lock_time,
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+
+        Deployed {
+            location: htlc_location::Bitcoin::new(transaction.txid(), 0),
+            transaction,
+        }
+    }
+
+    impl StateStore for StubFacade {
+        fn insert<A>(&self, key: SwapId, value: A)
+        where
+            A: ActorState + Send + 'static,
+        {
+            self.state_store.insert(key, value)
+        }
+
+        fn get<A>(&self, key: &SwapId) -> Result<Option<A>, rfc003::state_store::Error>
+        where
+            A: ActorState + Clone,
+        {
+            self.state_store.get(key)
+        }
+
+        fn update<A>(&self, key: &SwapId, update: SwapEventOnLedger<A::AL, A::BL, A::AA, A::BA>)
+        where
+            A: ActorState,
+            A::AA: Ord,
+            A::BA: Ord,
+        {
+            self.state_store.update::<A>(key, update)
+        }
+    }
+
+    impl PublishStateChange for StubFacade {
+        fn publish_state_change(&self, _: SwapId, _: StateChange) {}
+    }
+
+    #[async_trait::async_trait]
+    impl SaveLedgerEvent<Regtest, Ethereum, asset::Bitcoin, asset::Ether> for StubFacade {
+        async fn save_ledger_event(
+            &self,
+            swap_id: SwapId,
+            event: SwapEventOnLedger<Regtest, Ethereum, asset::Bitcoin, asset::Ether>,
+        ) -> anyhow::Result<()> {
+            self.db.save_ledger_event(swap_id, event).await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LoadLedgerEvents<Regtest, Ethereum, asset::Bitcoin, asset::Ether> for StubFacade {
+        async fn load_ledger_events(
+            &self,
+            swap_id: &SwapId,
+        ) -> anyhow::Result<Vec<SwapEventOnLedger<Regtest, Ethereum, asset::Bitcoin, asset::Ether>>>
+        {
+            self.db.load_ledger_events(swap_id).await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RollBackLedgerEvents for StubFacade {
+        async fn roll_back_ledger_events(
+            &self,
+            swap_id: &SwapId,
+            ledger: LedgerSide,
+            to: HtlcState,
+        ) -> anyhow::Result<()> {
+            self.db.roll_back_ledger_events(swap_id, ledger, to).await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcDeployed<Regtest, asset::Bitcoin, identity::Bitcoin> for StubFacade {
+        async fn htlc_deployed(
+            &self,
+            _: &HtlcParams<Regtest, asset::Bitcoin, identity::Bitcoin>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Deployed<transaction::Bitcoin, htlc_location::Bitcoin>> {
+            let lock_time = self.alpha_deployments.fetch_add(1, Ordering::SeqCst);
+
+            Ok(deployed(lock_time))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcFunded<Regtest, asset::Bitcoin, identity::Bitcoin> for StubFacade {
+        async fn htlc_funded(
+            &self,
+            _: &HtlcParams<Regtest, asset::Bitcoin, identity::Bitcoin>,
+            _: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Funded<transaction::Bitcoin, asset::Bitcoin>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcRedeemed<Regtest, asset::Bitcoin, identity::Bitcoin> for StubFacade {
+        async fn htlc_redeemed(
+            &self,
+            _: &HtlcParams<Regtest, asset::Bitcoin, identity::Bitcoin>,
+            _: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Redeemed<transaction::Bitcoin>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcRefunded<Regtest, asset::Bitcoin, identity::Bitcoin> for StubFacade {
+        async fn htlc_refunded(
+            &self,
+            _: &HtlcParams<Regtest, asset::Bitcoin, identity::Bitcoin>,
+            _: &Deployed<transaction::Bitcoin, htlc_location::Bitcoin>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Refunded<transaction::Bitcoin>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcDeployed<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_deployed(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Deployed<transaction::Ethereum, htlc_location::Ethereum>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcFunded<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_funded(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: &Deployed<transaction::Ethereum, htlc_location::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Funded<transaction::Ethereum, asset::Ether>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcRedeemed<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_redeemed(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: &Deployed<transaction::Ethereum, htlc_location::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Redeemed<transaction::Ethereum>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HtlcRefunded<Ethereum, asset::Ether, identity::Ethereum> for StubFacade {
+        async fn htlc_refunded(
+            &self,
+            _: &HtlcParams<Ethereum, asset::Ether, identity::Ethereum>,
+            _: &Deployed<transaction::Ethereum, htlc_location::Ethereum>,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Refunded<transaction::Ethereum>> {
+            future::pending().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TransactionFinality<Regtest> for StubFacade {
+        async fn transaction_finality(
+            &self,
+            _: &transaction::Bitcoin,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Finality> {
+            match self.alpha_finality_checks.fetch_add(1, Ordering::SeqCst) {
+                0 => Ok(Finality::Orphaned),
+                _ => Ok(Finality::Final),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TransactionFinality<Ethereum> for StubFacade {
+        async fn transaction_finality(
+            &self,
+            _: &transaction::Ethereum,
+            _: NaiveDateTime,
+        ) -> anyhow::Result<Finality> {
+            future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn orphaned_deployment_is_rolled_back_and_watched_again() {
+        let bitcoin_pub_key = "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+            .parse()
+            .unwrap();
+        let ethereum_address: ethereum::Address =
+            "8457037fcd80a8650c4692d7fcfc1d0a96b92867".parse().unwrap();
+        let id = SwapId::default();
+        let request = Request {
+            swap_id: id,
+            alpha_ledger: Regtest,
+            beta_ledger: Ethereum::default(),
+            alpha_asset: asset::Bitcoin::from_sat(100_000_000),
+            beta_asset: asset::Ether::from_wei(10_000_000_000_000_000_000u64),
+            hash_function: HashFunction::Sha256,
+            alpha_ledger_refund_identity: bitcoin_pub_key,
+            beta_ledger_redeem_identity: ethereum_address,
+            alpha_expiry: Timestamp::from(2_000_000_000),
+            beta_expiry: Timestamp::from(2_000_000_000),
+            secret_hash: Secret::from(*b"hello world, you are beautiful!!").hash(),
+        };
+        let accept = Accept {
+            swap_id: id,
+            beta_ledger_refund_identity: ethereum_address,
+            alpha_ledger_redeem_identity: bitcoin_pub_key,
+        };
+        let dependencies = StubFacade {
+            db: Sqlite::new(Path::new(":memory:")).unwrap(),
+            state_store: Arc::new(InMemoryStateStore::default()),
+            alpha_deployments: Arc::new(AtomicU32::new(0)),
+            alpha_finality_checks: Arc::new(AtomicU32::new(0)),
+        };
+        let seed = RootSeed::from([0u8; 32]).derive_swap_seed(id);
+        dependencies.insert(id, AliceState::accepted(request.clone(), accept, seed));
+
+        tokio::spawn(create_swap::<_, AliceState, _, _>(
+            dependencies.clone(),
+            (request, accept, NaiveDateTime::from_timestamp(0, 0)),
+        ));
+
+        let redeployed = deployed(1);
+        let poll = async {
+            loop {
+                let events = dependencies.load_ledger_events(&id).await.unwrap();
+                if events == vec![SwapEvent::AlphaDeployed(redeployed.clone())] {
+                    break;
+                }
+                tokio::time::delay_for(Duration::from_millis(10)).await;
+            }
+        };
+        assert!(tokio::time::timeout(Duration::from_secs(5), poll)
+            .await
+            .is_ok());
+
+        let mut state = dependencies.get::<AliceState>(&id).unwrap().unwrap();
+        assert_eq!(state.alpha_ledger_mut().deployed(), Some(redeployed));
+        assert_eq!(dependencies.alpha_deployments.load(Ordering::SeqCst), 2);
+    }
 
     #[test]
     fn swap_event_should_render_to_nice_string() {
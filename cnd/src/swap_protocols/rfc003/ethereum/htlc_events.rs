@@ -1,9 +1,7 @@
 use crate::{
     asset::{ethereum::FromWei, Erc20, Erc20Quantity, Ether},
-    btsieve::ethereum::{
-        watch_for_contract_creation, watch_for_event, Cache, Event, Topic, Web3Connector,
-    },
-    ethereum::{Transaction, H256, U256},
+    btsieve::ethereum::{logs_lookup, Cache, ChainFollower, Event, Topic, Web3Connector},
+    ethereum::{Log, Transaction, H256, U256},
     identity,
     swap_protocols::{
         ledger::Ethereum,
@@ -28,7 +26,7 @@ lazy_static::lazy_static! {
 }
 
 #[async_trait::async_trait]
-impl HtlcFunded<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcFunded<Ethereum, Ether, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_funded(
         &self,
         _htlc_params: &HtlcParams<Ethereum, Ether, identity::Ethereum>,
@@ -38,22 +36,22 @@ impl HtlcFunded<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector> {
         Ok(Funded {
             transaction: deploy_transaction.transaction.clone(),
             asset: Ether::from_wei(deploy_transaction.transaction.value),
+            confirmations: None,
         })
     }
 }
 
 #[async_trait::async_trait]
-impl HtlcDeployed<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcDeployed<Ethereum, Ether, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_deployed(
         &self,
         htlc_params: &HtlcParams<Ethereum, Ether, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Deployed<Transaction, identity::Ethereum>> {
-        let connector = self.clone();
-        let (transaction, location) =
-            watch_for_contract_creation(connector, start_of_swap, htlc_params.bytecode())
-                .instrument(tracing::info_span!("htlc_deployed"))
-                .await?;
+        let (transaction, location) = self
+            .contract_creation(htlc_params.bytecode(), start_of_swap)
+            .instrument(tracing::info_span!("htlc_deployed"))
+            .await?;
 
         Ok(Deployed {
             transaction,
@@ -63,20 +61,19 @@ impl HtlcDeployed<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector>
 }
 
 #[async_trait::async_trait]
-impl HtlcRedeemed<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcRedeemed<Ethereum, Ether, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_redeemed(
         &self,
         _htlc_params: &HtlcParams<Ethereum, Ether, identity::Ethereum>,
         htlc_deployment: &Deployed<Transaction, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Redeemed<Transaction>> {
-        let connector = self.clone();
         let event = Event {
             address: htlc_deployment.location,
             topics: vec![Some(Topic(*REDEEM_LOG_MSG))],
         };
 
-        let (transaction, log) = watch_for_event(connector, start_of_swap, event)
+        let (transaction, log) = watch_for_event(self, event, start_of_swap)
             .instrument(tracing::info_span!("htlc_redeemed"))
             .await?;
 
@@ -92,20 +89,19 @@ impl HtlcRedeemed<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector>
 }
 
 #[async_trait::async_trait]
-impl HtlcRefunded<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcRefunded<Ethereum, Ether, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_refunded(
         &self,
         _htlc_params: &HtlcParams<Ethereum, Ether, identity::Ethereum>,
         htlc_deployment: &Deployed<Transaction, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Refunded<Transaction>> {
-        let connector = self.clone();
         let event = Event {
             address: htlc_deployment.location,
             topics: vec![Some(Topic(*REFUND_LOG_MSG))],
         };
 
-        let (transaction, _) = watch_for_event(connector, start_of_swap, event)
+        let (transaction, _) = watch_for_event(self, event, start_of_swap)
             .instrument(tracing::info_span!("htlc_refunded"))
             .await?;
 
@@ -114,15 +110,13 @@ impl HtlcRefunded<Ethereum, Ether, identity::Ethereum> for Cache<Web3Connector>
 }
 
 #[async_trait::async_trait]
-impl HtlcFunded<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcFunded<Ethereum, Erc20, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_funded(
         &self,
         htlc_params: &HtlcParams<Ethereum, Erc20, identity::Ethereum>,
         htlc_deployment: &Deployed<Transaction, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Funded<Transaction, Erc20>> {
-        let connector = self.clone();
-
         let event = Event {
             address: htlc_params.asset.token_contract,
             topics: vec![
@@ -132,30 +126,32 @@ impl HtlcFunded<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector> {
             ],
         };
 
-        let (transaction, log) = watch_for_event(connector, start_of_swap, event)
+        let (transaction, log) = watch_for_event(self, event, start_of_swap)
             .instrument(tracing::info_span!("htlc_funded"))
             .await?;
 
         let quantity = Erc20Quantity::from_wei(U256::from_big_endian(log.data.0.as_ref()));
         let asset = Erc20::new(log.address, quantity);
 
-        Ok(Funded { transaction, asset })
+        Ok(Funded {
+            transaction,
+            asset,
+            confirmations: None,
+        })
     }
 }
 
 #[async_trait::async_trait]
-impl HtlcDeployed<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcDeployed<Ethereum, Erc20, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_deployed(
         &self,
         htlc_params: &HtlcParams<Ethereum, Erc20, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Deployed<Transaction, identity::Ethereum>> {
-        let connector = self.clone();
-
-        let (transaction, location) =
-            watch_for_contract_creation(connector, start_of_swap, htlc_params.clone().bytecode())
-                .instrument(tracing::info_span!("htlc_deployed"))
-                .await?;
+        let (transaction, location) = self
+            .contract_creation(htlc_params.clone().bytecode(), start_of_swap)
+            .instrument(tracing::info_span!("htlc_deployed"))
+            .await?;
 
         Ok(Deployed {
             transaction,
@@ -165,20 +161,19 @@ impl HtlcDeployed<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector>
 }
 
 #[async_trait::async_trait]
-impl HtlcRedeemed<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcRedeemed<Ethereum, Erc20, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_redeemed(
         &self,
         _htlc_params: &HtlcParams<Ethereum, Erc20, identity::Ethereum>,
         htlc_deployment: &Deployed<Transaction, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Redeemed<Transaction>> {
-        let connector = self.clone();
         let event = Event {
             address: htlc_deployment.location,
             topics: vec![Some(Topic(*REDEEM_LOG_MSG))],
         };
 
-        let (transaction, log) = watch_for_event(connector, start_of_swap, event)
+        let (transaction, log) = watch_for_event(self, event, start_of_swap)
             .instrument(tracing::info_span!("htlc_redeemed"))
             .await?;
 
@@ -194,23 +189,38 @@ impl HtlcRedeemed<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector>
 }
 
 #[async_trait::async_trait]
-impl HtlcRefunded<Ethereum, Erc20, identity::Ethereum> for Cache<Web3Connector> {
+impl HtlcRefunded<Ethereum, Erc20, identity::Ethereum> for ChainFollower<Cache<Web3Connector>> {
     async fn htlc_refunded(
         &self,
         _htlc_params: &HtlcParams<Ethereum, Erc20, identity::Ethereum>,
         htlc_deployment: &Deployed<Transaction, identity::Ethereum>,
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Refunded<Transaction>> {
-        let connector = self.clone();
         let event = Event {
             address: htlc_deployment.location,
             topics: vec![Some(Topic(*REFUND_LOG_MSG))],
         };
 
-        let (transaction, _) = watch_for_event(connector, start_of_swap, event)
+        let (transaction, _) = watch_for_event(self, event, start_of_swap)
             .instrument(tracing::info_span!("htlc_refunded"))
             .await?;
 
         Ok(Refunded { transaction })
     }
 }
+
+/// Asks the node for the logs of the event in the blocks mined so far and
+/// leaves the blocks mined from now on to the chain follower.
+async fn watch_for_event(
+    follower: &ChainFollower<Cache<Web3Connector>>,
+    event: Event,
+    start_of_swap: NaiveDateTime,
+) -> anyhow::Result<(Transaction, Log)> {
+    let lookup = logs_lookup(
+        follower.connector.connector.clone(),
+        event.clone(),
+        start_of_swap,
+    );
+
+    follower.event_indexed(event, lookup, start_of_swap).await
+}
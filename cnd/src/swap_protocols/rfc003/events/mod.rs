@@ -2,7 +2,10 @@
 // see: https://github.com/rust-lang/rust/issues/21903
 #![allow(type_alias_bounds)]
 
-use crate::swap_protocols::rfc003::{create_swap::HtlcParams, ledger::Ledger, Secret};
+use crate::{
+    btsieve::Finality,
+    swap_protocols::rfc003::{create_swap::HtlcParams, ledger::Ledger, Secret},
+};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +13,9 @@ use serde::{Deserialize, Serialize};
 pub struct Funded<T, A> {
     pub transaction: T,
     pub asset: A,
+    /// Confirmations of the transaction when the funding was reported, `None`
+    /// if they were not counted.
+    pub confirmations: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -79,3 +85,17 @@ where
         start_of_swap: NaiveDateTime,
     ) -> anyhow::Result<Refunded<L::Transaction>>;
 }
+
+/// Watches a transaction that was matched on a ledger until it is either final
+/// or reorganised out of the best chain.
+#[async_trait::async_trait]
+pub trait TransactionFinality<L>: Send + Sync + Sized + 'static
+where
+    L: Ledger,
+{
+    async fn transaction_finality(
+        &self,
+        transaction: &L::Transaction,
+        start_of_swap: NaiveDateTime,
+    ) -> anyhow::Result<Finality>;
+}
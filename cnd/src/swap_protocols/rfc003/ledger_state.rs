@@ -22,6 +22,8 @@ pub enum LedgerState<H, T, A> {
         deploy_transaction: T,
         fund_transaction: T,
         asset: A,
+        /// Confirmations of the fund transaction when the funding was seen.
+        fund_confirmations: Option<u32>,
     },
     Redeemed {
         htlc_location: H,
@@ -43,6 +45,7 @@ pub enum LedgerState<H, T, A> {
         deploy_transaction: T,
         fund_transaction: T,
         asset: A,
+        fund_confirmations: Option<u32>,
     },
 }
 
@@ -65,7 +68,11 @@ impl<T, H, A> LedgerState<H, T, A> {
     }
 
     pub fn transition_to_funded(&mut self, funded: Funded<T, A>) {
-        let Funded { transaction, asset } = funded;
+        let Funded {
+            transaction,
+            asset,
+            confirmations,
+        } = funded;
 
         match std::mem::replace(self, LedgerState::NotDeployed) {
             LedgerState::Deployed {
@@ -77,6 +84,7 @@ impl<T, H, A> LedgerState<H, T, A> {
                     htlc_location,
                     fund_transaction: transaction,
                     asset,
+                    fund_confirmations: confirmations,
                 }
             }
             other => panic!("expected state Deployed, got {}", HtlcState::from(other)),
@@ -84,7 +92,11 @@ impl<T, H, A> LedgerState<H, T, A> {
     }
 
     pub fn transition_to_incorrectly_funded(&mut self, funded: Funded<T, A>) {
-        let Funded { transaction, asset } = funded;
+        let Funded {
+            transaction,
+            asset,
+            confirmations,
+        } = funded;
 
         match std::mem::replace(self, LedgerState::NotDeployed) {
             LedgerState::Deployed {
@@ -96,6 +108,7 @@ impl<T, H, A> LedgerState<H, T, A> {
                     htlc_location,
                     fund_transaction: transaction,
                     asset,
+                    fund_confirmations: confirmations,
                 }
             }
             other => panic!("expected state Deployed, got {}", HtlcState::from(other)),
@@ -114,6 +127,7 @@ impl<T, H, A> LedgerState<H, T, A> {
                 htlc_location,
                 asset,
                 fund_transaction,
+                ..
             } => {
                 *self = LedgerState::Redeemed {
                     deploy_transaction,
@@ -137,12 +151,14 @@ impl<T, H, A> LedgerState<H, T, A> {
                 htlc_location,
                 asset,
                 fund_transaction,
+                ..
             }
             | LedgerState::IncorrectlyFunded {
                 deploy_transaction,
                 htlc_location,
                 asset,
                 fund_transaction,
+                ..
             } => {
                 *self = LedgerState::Refunded {
                     deploy_transaction,
@@ -160,6 +176,46 @@ impl<T, H, A> LedgerState<H, T, A> {
     }
 }
 
+impl<T, H, A> LedgerState<H, T, A>
+where
+    T: Clone,
+    H: Clone,
+{
+    /// Returns the deployment of the HTLC, if it has already been deployed.
+    pub fn deployed(&self) -> Option<Deployed<T, H>> {
+        match self {
+            LedgerState::NotDeployed => None,
+            LedgerState::Deployed {
+                htlc_location,
+                deploy_transaction,
+            }
+            | LedgerState::Funded {
+                htlc_location,
+                deploy_transaction,
+                ..
+            }
+            | LedgerState::Redeemed {
+                htlc_location,
+                deploy_transaction,
+                ..
+            }
+            | LedgerState::Refunded {
+                htlc_location,
+                deploy_transaction,
+                ..
+            }
+            | LedgerState::IncorrectlyFunded {
+                htlc_location,
+                deploy_transaction,
+                ..
+            } => Some(Deployed {
+                transaction: deploy_transaction.clone(),
+                location: htlc_location.clone(),
+            }),
+        }
+    }
+}
+
 impl Default for HtlcState {
     fn default() -> Self {
         HtlcState::NotDeployed
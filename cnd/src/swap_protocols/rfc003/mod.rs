@@ -2,6 +2,8 @@
 mod transition_save;
 
 pub mod alice;
+pub mod auto_refund;
+pub mod autopilot;
 pub mod bitcoin;
 pub mod bob;
 pub mod create_swap;
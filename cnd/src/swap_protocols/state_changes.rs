@@ -0,0 +1,178 @@
+use crate::{
+    db::LedgerSide,
+    swap_protocols::{rfc003::actions::ActionKind, SwapId},
+};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::broadcast;
+
+/// How many notifications are kept around for clients that resume a stream.
+const HISTORY: usize = 1000;
+
+/// A transition in the state of a swap.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StateChange {
+    Requested,
+    Accepted,
+    Declined,
+    LedgerEvent {
+        ledger: LedgerSide,
+        event: LedgerEvent,
+    },
+    ActionAvailable {
+        action: ActionKind,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEvent {
+    Deployed,
+    Funded,
+    Redeemed,
+    Refunded,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Notification {
+    /// Increases with every notification, clients resume a stream from here.
+    #[serde(skip)]
+    pub id: u64,
+    pub swap_id: SwapId,
+    #[serde(flatten)]
+    pub change: StateChange,
+}
+
+pub trait PublishStateChange {
+    fn publish_state_change(&self, swap_id: SwapId, change: StateChange);
+}
+
+/// Hands out the state changes of all swaps to any number of subscribers.
+///
+/// The most recent notifications are kept in memory so that a subscriber can
+/// catch up on what it missed while it was disconnected.
+#[derive(Debug)]
+pub struct StateChanges {
+    history: Mutex<History>,
+    sender: broadcast::Sender<Notification>,
+}
+
+#[derive(Debug)]
+struct History {
+    next_id: u64,
+    recent: VecDeque<Notification>,
+}
+
+impl Default for StateChanges {
+    fn default() -> Self {
+        // Starting at the current time in milliseconds keeps the ids of this
+        // run above the ids handed out before a restart.
+        let next_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+        let (sender, _) = broadcast::channel(HISTORY);
+
+        StateChanges {
+            history: Mutex::new(History {
+                next_id,
+                recent: VecDeque::with_capacity(HISTORY),
+            }),
+            sender,
+        }
+    }
+}
+
+impl StateChanges {
+    /// Returns the notifications published after `last_id` that are still
+    /// known and a receiver for the ones that are published from now on.
+    ///
+    /// No notification is contained in both.
+    pub fn subscribe(
+        &self,
+        last_id: Option<u64>,
+    ) -> (Vec<Notification>, broadcast::Receiver<Notification>) {
+        let history = self.history.lock().unwrap();
+        let receiver = self.sender.subscribe();
+
+        let missed = match last_id {
+            Some(last_id) => history
+                .recent
+                .iter()
+                .filter(|notification| notification.id > last_id)
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        (missed, receiver)
+    }
+}
+
+impl PublishStateChange for StateChanges {
+    fn publish_state_change(&self, swap_id: SwapId, change: StateChange) {
+        let mut history = self.history.lock().unwrap();
+
+        let notification = Notification {
+            id: history.next_id,
+            swap_id,
+            change,
+        };
+        history.next_id += 1;
+
+        if history.recent.len() == HISTORY {
+            history.recent.pop_front();
+        }
+        history.recent.push_back(notification);
+
+        // Sending only fails if nobody is subscribed.
+        let _ = self.sender.send(notification);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resuming_returns_only_notifications_after_last_id() {
+        let state_changes = StateChanges::default();
+        let swap_id = SwapId::default();
+
+        state_changes.publish_state_change(swap_id, StateChange::Requested);
+        let (_, mut receiver) = state_changes.subscribe(None);
+        state_changes.publish_state_change(swap_id, StateChange::Accepted);
+
+        let accepted = receiver.try_recv().unwrap();
+        assert_eq!(accepted.change, StateChange::Accepted);
+
+        let (missed, _) = state_changes.subscribe(Some(accepted.id - 1));
+        assert_eq!(missed, vec![accepted]);
+
+        let (missed, _) = state_changes.subscribe(Some(accepted.id));
+        assert!(missed.is_empty());
+    }
+
+    #[test]
+    fn notification_serializes_flat() {
+        let notification = Notification {
+            id: 1,
+            swap_id: SwapId::default(),
+            change: StateChange::LedgerEvent {
+                ledger: LedgerSide::Alpha,
+                event: LedgerEvent::Funded,
+            },
+        };
+
+        let json = serde_json::to_value(&notification).unwrap();
+
+        assert_eq!(json["type"], "ledger_event");
+        assert_eq!(json["ledger"], "alpha");
+        assert_eq!(json["event"], "funded");
+    }
+}
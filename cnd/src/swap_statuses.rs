@@ -0,0 +1,85 @@
+use crate::{
+    db::{DetermineTypes, LoadListedSwaps, ProtocolName, UpdateSwapStatus},
+    http_api::{
+        routes::han_halight::handlers::han_halight_swap_status, swap_resource::rfc003_swap_status,
+        SwapStatus,
+    },
+    swap_protocols::{Facade, SwapId},
+};
+use futures_core::future;
+use std::time::Duration;
+use tokio::sync::broadcast::RecvError;
+
+/// Not every change of a swap's state is announced, the swaps that are in
+/// progress are looked at this often to catch the others.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keeps the status of the listed swaps up to date so that they can be
+/// filtered by status, never returns.
+pub async fn keep_up_to_date(dependencies: Facade) {
+    future::join(
+        follow_state_changes(dependencies.clone()),
+        refresh_in_progress(dependencies),
+    )
+    .await;
+}
+
+async fn follow_state_changes(dependencies: Facade) {
+    let (_, mut receiver) = dependencies.state_changes.subscribe(None);
+
+    loop {
+        match receiver.recv().await {
+            Ok(notification) => {
+                let swap_id = notification.swap_id;
+                if let Err(e) = update(&dependencies, swap_id).await {
+                    tracing::warn!("failed to update status of swap {}: {:?}", swap_id, e);
+                }
+            }
+            // The swaps we missed are caught up on by the next refresh.
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn refresh_in_progress(dependencies: Facade) {
+    loop {
+        match dependencies
+            .db
+            .swap_ids_with_status(SwapStatus::InProgress)
+            .await
+        {
+            Ok(swap_ids) => {
+                for swap_id in swap_ids {
+                    // Swaps that were not restored after a restart end up
+                    // here every time, this is not worth a warning.
+                    if let Err(e) = update(&dependencies, swap_id).await {
+                        tracing::debug!("failed to refresh status of swap {}: {:?}", swap_id, e);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("failed to load swaps in progress: {:?}", e),
+        }
+
+        tokio::time::delay_for(REFRESH_INTERVAL).await;
+    }
+}
+
+async fn update(dependencies: &Facade, swap_id: SwapId) -> anyhow::Result<()> {
+    let status = status(dependencies, swap_id).await?;
+    dependencies.db.update_swap_status(&swap_id, status).await
+}
+
+async fn status(dependencies: &Facade, swap_id: SwapId) -> anyhow::Result<SwapStatus> {
+    let listed = dependencies.db.listed_swap(&swap_id).await?;
+
+    match listed.protocol {
+        ProtocolName::Rfc003 => {
+            let types = dependencies.determine_types(&swap_id).await?;
+            rfc003_swap_status(dependencies, swap_id, types)
+        }
+        ProtocolName::HanHalight => {
+            han_halight_swap_status(&dependencies.han_halight_states, swap_id)
+        }
+    }
+}
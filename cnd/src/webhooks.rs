@@ -0,0 +1,489 @@
+use crate::{
+    config,
+    db::{LedgerSide, LoadWebhookDeliveries, SaveWebhookDelivery, WebhookDelivery},
+    swap_protocols::{
+        state_changes::{LedgerEvent, Notification, StateChange, StateChanges},
+        SwapId,
+    },
+    timestamp::Timestamp,
+};
+use bitcoin::hashes::{
+    hmac::{Hmac, HmacEngine},
+    sha256, Hash, HashEngine,
+};
+use chrono::Utc;
+use futures_core::future;
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::{Deserialize, Serialize};
+use std::{cmp, collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::broadcast::RecvError;
+
+/// Carries the HMAC-SHA256 of the payload, keyed with the secret of the
+/// endpoint, in the form `sha256=<hex>`.
+pub const SIGNATURE_HEADER: &str = "X-Cnd-Signature";
+
+/// A delivery is dropped after failing this many times.
+const MAX_ATTEMPTS: u32 = 10;
+
+/// How long to wait before looking for due deliveries again.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A delivery fails if the endpoint does not answer within this time.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest we wait between two attempts of the same delivery.
+const MAX_BACKOFF_SECS: i64 = 3600;
+
+/// The events a webhook endpoint can subscribe to.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum WebhookEvent {
+    Requested,
+    Accepted,
+    Declined,
+    AlphaDeployed,
+    AlphaFunded,
+    AlphaRedeemed,
+    AlphaRefunded,
+    BetaDeployed,
+    BetaFunded,
+    BetaRedeemed,
+    BetaRefunded,
+}
+
+impl WebhookEvent {
+    /// Returns `None` for state changes that are not delivered to webhooks.
+    fn from_state_change(change: StateChange) -> Option<Self> {
+        use self::{LedgerEvent::*, LedgerSide::*};
+
+        let event = match change {
+            StateChange::Requested => WebhookEvent::Requested,
+            StateChange::Accepted => WebhookEvent::Accepted,
+            StateChange::Declined => WebhookEvent::Declined,
+            StateChange::LedgerEvent { ledger, event } => match (ledger, event) {
+                (Alpha, Deployed) => WebhookEvent::AlphaDeployed,
+                (Alpha, Funded) => WebhookEvent::AlphaFunded,
+                (Alpha, Redeemed) => WebhookEvent::AlphaRedeemed,
+                (Alpha, Refunded) => WebhookEvent::AlphaRefunded,
+                (Beta, Deployed) => WebhookEvent::BetaDeployed,
+                (Beta, Funded) => WebhookEvent::BetaFunded,
+                (Beta, Redeemed) => WebhookEvent::BetaRedeemed,
+                (Beta, Refunded) => WebhookEvent::BetaRefunded,
+            },
+            StateChange::ActionAvailable { .. } => return None,
+        };
+
+        Some(event)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+struct Payload {
+    event: WebhookEvent,
+    swap_id: SwapId,
+    timestamp: Timestamp,
+}
+
+/// Returns the hex encoded HMAC-SHA256 of `payload`.
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut engine = HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+    engine.input(payload.as_bytes());
+
+    hex::encode(Hmac::<sha256::Hash>::from_engine(engine).into_inner())
+}
+
+/// Saves a delivery for every endpoint that subscribed to a state change.
+///
+/// The deliveries are sent by `deliver_webhooks`, saving them first makes
+/// sure they are not lost if cnd is stopped before they succeed.
+pub async fn queue_webhooks<DB>(
+    webhooks: config::Webhooks,
+    state_changes: Arc<StateChanges>,
+    db: DB,
+) where
+    DB: SaveWebhookDelivery,
+{
+    if webhooks.endpoints.is_empty() {
+        return;
+    }
+
+    let (_, mut receiver) = state_changes.subscribe(None);
+    let mut last_id = None;
+
+    loop {
+        let notifications = match receiver.recv().await {
+            Ok(notification) => vec![notification],
+            Err(RecvError::Lagged(_)) => {
+                let (missed, new_receiver) = state_changes.subscribe(last_id);
+                receiver = new_receiver;
+                missed
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        for notification in notifications {
+            last_id = Some(notification.id);
+
+            if let Err(e) = queue(&webhooks, &db, notification).await {
+                tracing::error!(
+                    "failed to queue webhooks for swap {}: {:?}",
+                    notification.swap_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
+async fn queue<DB>(
+    webhooks: &config::Webhooks,
+    db: &DB,
+    notification: Notification,
+) -> anyhow::Result<()>
+where
+    DB: SaveWebhookDelivery,
+{
+    let event = match WebhookEvent::from_state_change(notification.change) {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+    let payload = serde_json::to_string(&Payload {
+        event,
+        swap_id: notification.swap_id,
+        timestamp: Timestamp::now(),
+    })?;
+
+    for endpoint in webhooks.endpoints.iter().filter(|endpoint| {
+        endpoint
+            .events
+            .as_ref()
+            .map_or(true, |events| events.contains(&event))
+    }) {
+        db.save_webhook_delivery(endpoint.url.clone(), payload.clone())
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Sends the saved deliveries, including the ones left over from a previous
+/// run.
+///
+/// A failed delivery is retried with exponential backoff until it succeeds or
+/// failed `MAX_ATTEMPTS` times.
+///
+/// Endpoints are delivered to concurrently, an endpoint that does not answer
+/// does not hold back the deliveries to the others.
+pub async fn deliver_webhooks<DB>(webhooks: config::Webhooks, db: DB)
+where
+    DB: LoadWebhookDeliveries + SaveWebhookDelivery,
+{
+    if webhooks.endpoints.is_empty() {
+        return;
+    }
+
+    let client = match Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("failed to build webhook client: {:?}", e);
+            return;
+        }
+    };
+
+    loop {
+        if let Err(e) = deliver_due_webhooks(&client, &webhooks, &db).await {
+            tracing::error!("failed to deliver webhooks: {:?}", e);
+        }
+
+        tokio::time::delay_for(POLL_INTERVAL).await;
+    }
+}
+
+async fn deliver_due_webhooks<DB>(
+    client: &Client,
+    webhooks: &config::Webhooks,
+    db: &DB,
+) -> anyhow::Result<()>
+where
+    DB: LoadWebhookDeliveries + SaveWebhookDelivery,
+{
+    let mut by_url = HashMap::<_, Vec<_>>::new();
+    for delivery in db
+        .load_due_webhook_deliveries(Utc::now().naive_utc())
+        .await?
+    {
+        by_url
+            .entry(delivery.url.clone())
+            .or_default()
+            .push(delivery);
+    }
+
+    // Endpoints are served concurrently, each one sequentially. A failed
+    // delivery is rescheduled without holding back the ones after it, the order
+    // in which an endpoint receives them is therefore not guaranteed.
+    let endpoints = by_url.into_iter().map(|(url, deliveries)| async move {
+        for delivery in deliveries {
+            if let Err(e) = deliver(client, webhooks, db, delivery).await {
+                tracing::error!("failed to deliver webhooks to {}: {:?}", url, e);
+                return;
+            }
+        }
+    });
+    future::join_all(endpoints).await;
+
+    Ok(())
+}
+
+async fn deliver<DB>(
+    client: &Client,
+    webhooks: &config::Webhooks,
+    db: &DB,
+    delivery: WebhookDelivery,
+) -> anyhow::Result<()>
+where
+    DB: SaveWebhookDelivery,
+{
+    let endpoint = match webhooks
+        .endpoints
+        .iter()
+        .find(|endpoint| endpoint.url == delivery.url)
+    {
+        Some(endpoint) => endpoint,
+        None => {
+            tracing::warn!(
+                "dropping webhook to {} because it is no longer configured",
+                delivery.url
+            );
+            db.remove_webhook_delivery(delivery.id).await?;
+            return Ok(());
+        }
+    };
+
+    match post(client, endpoint, &delivery).await {
+        Ok(()) => {
+            tracing::debug!("delivered webhook to {}", delivery.url);
+            db.remove_webhook_delivery(delivery.id).await?;
+        }
+        Err(e) if delivery.attempts + 1 >= MAX_ATTEMPTS => {
+            tracing::error!(
+                "giving up on webhook to {} after {} attempts: {:?}",
+                delivery.url,
+                MAX_ATTEMPTS,
+                e
+            );
+            db.remove_webhook_delivery(delivery.id).await?;
+        }
+        Err(e) => {
+            tracing::warn!("failed to deliver webhook to {}: {:?}", delivery.url, e);
+            let next_attempt_at = Utc::now().naive_utc() + backoff(delivery.attempts);
+            db.reschedule_webhook_delivery(delivery.id, next_attempt_at)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn post(
+    client: &Client,
+    endpoint: &config::Webhook,
+    delivery: &WebhookDelivery,
+) -> anyhow::Result<()> {
+    client
+        .post(endpoint.url.clone())
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            SIGNATURE_HEADER,
+            format!("sha256={}", sign(&endpoint.secret, &delivery.payload)),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// 5 seconds after the first failure, doubling with every further one.
+fn backoff(attempts: u32) -> chrono::Duration {
+    let secs = 5i64.saturating_mul(2i64.saturating_pow(attempts));
+
+    chrono::Duration::seconds(cmp::min(secs, MAX_BACKOFF_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Sqlite;
+    use std::{net::SocketAddr, path::Path};
+    use tokio::sync::mpsc;
+    use warp::{http::StatusCode, Filter};
+
+    const SECRET: &str = "s3cr3t";
+
+    fn webhooks(url: reqwest::Url) -> config::Webhooks {
+        config::Webhooks {
+            endpoints: vec![config::Webhook {
+                url,
+                secret: SECRET.to_owned(),
+                events: Some(vec![WebhookEvent::AlphaFunded]),
+            }],
+        }
+    }
+
+    /// Serves a receiver on an ephemeral local port that answers with `status`
+    /// and passes the signature and body of every request on.
+    fn serve(status: StatusCode) -> (SocketAddr, mpsc::UnboundedReceiver<(String, String)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let routes = warp::post()
+            .and(warp::header::<String>(SIGNATURE_HEADER))
+            .and(warp::body::bytes())
+            .map(move |signature: String, body: bytes::Bytes| {
+                let body = String::from_utf8(body.to_vec()).unwrap();
+                sender.send((signature, body)).unwrap();
+
+                warp::reply::with_status(warp::reply(), status)
+            });
+
+        let (socket, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        (socket, receiver)
+    }
+
+    fn url(socket: SocketAddr) -> reqwest::Url {
+        format!("http://{}/", socket).parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn only_subscribed_events_are_queued() -> anyhow::Result<()> {
+        let db = Sqlite::new(&Path::new(":memory:"))?;
+        let webhooks = webhooks("http://localhost:3000/".parse()?);
+        let swap_id = SwapId::default();
+
+        for change in vec![
+            StateChange::Requested,
+            StateChange::LedgerEvent {
+                ledger: LedgerSide::Alpha,
+                event: LedgerEvent::Funded,
+            },
+        ] {
+            queue(
+                &webhooks,
+                &db,
+                Notification {
+                    id: 1,
+                    swap_id,
+                    change,
+                },
+            )
+            .await?;
+        }
+
+        let deliveries = db
+            .load_due_webhook_deliveries(Utc::now().naive_utc())
+            .await?;
+
+        assert_eq!(deliveries.len(), 1);
+        let payload = serde_json::from_str::<serde_json::Value>(&deliveries[0].payload)?;
+        assert_eq!(payload["event"], "alpha_funded");
+        assert_eq!(payload["swap_id"], swap_id.to_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delivery_is_signed_and_removed_once_delivered() -> anyhow::Result<()> {
+        let (socket, mut received) = serve(StatusCode::OK);
+        let db = Sqlite::new(&Path::new(":memory:"))?;
+        let payload = r#"{"event":"alpha_funded"}"#.to_owned();
+
+        db.save_webhook_delivery(url(socket), payload.clone())
+            .await?;
+        deliver_due_webhooks(&Client::new(), &webhooks(url(socket)), &db).await?;
+
+        let (signature, body) = received.recv().await.unwrap();
+        assert_eq!(body, payload);
+        assert_eq!(signature, format!("sha256={}", sign(SECRET, &payload)));
+
+        let remaining = db
+            .load_due_webhook_deliveries(Utc::now().naive_utc())
+            .await?;
+        assert!(remaining.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_rescheduled() -> anyhow::Result<()> {
+        let (socket, mut received) = serve(StatusCode::INTERNAL_SERVER_ERROR);
+        let db = Sqlite::new(&Path::new(":memory:"))?;
+
+        db.save_webhook_delivery(url(socket), "{}".to_owned())
+            .await?;
+        deliver_due_webhooks(&Client::new(), &webhooks(url(socket)), &db).await?;
+        received.recv().await.unwrap();
+
+        let due_now = db
+            .load_due_webhook_deliveries(Utc::now().naive_utc())
+            .await?;
+        assert!(due_now.is_empty());
+
+        let due_later = db
+            .load_due_webhook_deliveries(Utc::now().naive_utc() + backoff(0))
+            .await?;
+        assert_eq!(due_later.len(), 1);
+        assert_eq!(due_later[0].attempts, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn endpoint_that_does_not_answer_does_not_hold_back_the_others() -> anyhow::Result<()> {
+        let hanging = warp::any().and_then(|| async {
+            tokio::time::delay_for(Duration::from_secs(3600)).await;
+            Ok::<_, warp::Rejection>(warp::reply())
+        });
+        let (hanging, server) = warp::serve(hanging).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let (answering, mut received) = serve(StatusCode::OK);
+
+        let mut webhooks = webhooks(url(hanging));
+        let answering_endpoint = config::Webhook {
+            url: url(answering),
+            ..webhooks.endpoints[0].clone()
+        };
+        webhooks.endpoints.push(answering_endpoint);
+        let db = Sqlite::new(&Path::new(":memory:"))?;
+        db.save_webhook_delivery(url(hanging), "{}".to_owned())
+            .await?;
+        db.save_webhook_delivery(url(answering), "{}".to_owned())
+            .await?;
+
+        let client = Client::builder().timeout(Duration::from_secs(1)).build()?;
+        let delivering = deliver_due_webhooks(&client, &webhooks, &db);
+        tokio::pin!(delivering);
+
+        tokio::select! {
+            _ = &mut delivering => panic!("delivery to the hanging endpoint did not time out"),
+            request = received.recv() => assert!(request.is_some()),
+        }
+        delivering.await?;
+
+        let due_later = db
+            .load_due_webhook_deliveries(Utc::now().naive_utc() + backoff(0))
+            .await?;
+        assert_eq!(due_later.len(), 1);
+        assert_eq!(due_later[0].url, url(hanging));
+
+        Ok(())
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_an_hour() {
+        assert_eq!(backoff(0), chrono::Duration::seconds(5));
+        assert_eq!(backoff(1), chrono::Duration::seconds(10));
+        assert_eq!(backoff(20), chrono::Duration::seconds(MAX_BACKOFF_SECS));
+    }
+}
@@ -0,0 +1,196 @@
+pub mod bitcoin_helper;
+
+use bitcoin::Address;
+use bitcoin_helper::BitcoinConnectorMock;
+use chrono::{offset::Utc, NaiveDateTime};
+use cnd::btsieve::bitcoin::{ChainFollower, IndexLookup, TransactionPattern};
+use futures_core::future;
+use std::{str::FromStr, time::Duration};
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn chain_follower_finds_transaction_in_block_it_missed() {
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"),
+            include_hex!(
+                "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"
+            ),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let pattern = TransactionPattern {
+        to_address: Some(
+            Address::from_str(
+                include_str!(
+                    "test_data/bitcoin/find_transaction_missed_previous_latest_block/address"
+                )
+                .trim(),
+            )
+            .unwrap(),
+        ),
+        from_outpoint: None,
+        unlock_script: None,
+    };
+    let start_of_swap = Utc::now().naive_local();
+
+    let transaction = timeout(
+        Duration::from_secs(5),
+        follower.matching_transaction(pattern, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        transaction,
+        include_hex!(
+            "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+        )
+    );
+}
+
+#[tokio::test]
+async fn chain_follower_serves_all_watchers_of_a_pattern() {
+    let block1_with_transaction: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_go_back_into_the_past/block1_with_transaction.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block3.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block4.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block5.hex"),
+        ],
+        vec![
+            block1_with_transaction.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block2.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block3.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block4.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/block5.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let pattern = TransactionPattern {
+        to_address: Some(
+            Address::from_str(
+                include_str!("test_data/bitcoin/find_transaction_go_back_into_the_past/address")
+                    .trim(),
+            )
+            .unwrap(),
+        ),
+        from_outpoint: None,
+        unlock_script: None,
+    };
+    let start_of_swap =
+        NaiveDateTime::from_timestamp(block1_with_transaction.header.time as i64, 0);
+
+    let (first, second) = timeout(Duration::from_secs(5), async {
+        tokio::join!(
+            follower.matching_transaction(pattern.clone(), start_of_swap),
+            follower.matching_transaction(pattern, start_of_swap)
+        )
+    })
+    .await
+    .unwrap();
+
+    let expected_transaction: bitcoin::Transaction =
+        include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/transaction.hex");
+    assert_eq!(first.unwrap(), expected_transaction);
+    assert_eq!(second.unwrap(), expected_transaction);
+}
+
+#[tokio::test]
+async fn chain_follower_takes_transaction_found_in_index_without_walking_the_chain() {
+    // the parents of the latest block are unknown, walking the chain fails
+    let connector = BitcoinConnectorMock::new(
+        vec![include_hex!(
+            "./test_data/bitcoin/find_transaction_go_back_into_the_past/block5.hex"
+        )],
+        vec![include_hex!(
+            "./test_data/bitcoin/find_transaction_go_back_into_the_past/block5.hex"
+        )],
+    );
+    let follower = ChainFollower::new(connector);
+
+    let pattern = TransactionPattern {
+        to_address: Some(
+            Address::from_str(
+                include_str!("test_data/bitcoin/find_transaction_go_back_into_the_past/address")
+                    .trim(),
+            )
+            .unwrap(),
+        ),
+        from_outpoint: None,
+        unlock_script: None,
+    };
+    let expected_transaction: bitcoin::Transaction =
+        include_hex!("./test_data/bitcoin/find_transaction_go_back_into_the_past/transaction.hex");
+    let lookup = future::ok(IndexLookup::Found(expected_transaction.clone()));
+
+    let transaction = timeout(
+        Duration::from_secs(5),
+        follower.matching_transaction_indexed(pattern, lookup, Utc::now().naive_local()),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(transaction, expected_transaction);
+}
+
+#[tokio::test]
+async fn chain_follower_only_scans_new_blocks_if_index_knows_no_transaction() {
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"),
+            include_hex!(
+                "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"
+            ),
+        ],
+        vec![
+            include_hex!(
+                "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"
+            ),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+
+    let pattern = TransactionPattern {
+        to_address: Some(
+            Address::from_str(
+                include_str!(
+                    "test_data/bitcoin/find_transaction_missed_previous_latest_block/address"
+                )
+                .trim(),
+            )
+            .unwrap(),
+        ),
+        from_outpoint: None,
+        unlock_script: None,
+    };
+    let lookup = future::ok(IndexLookup::Absent);
+    let matching_transaction =
+        follower.matching_transaction_indexed(pattern, lookup, Utc::now().naive_local());
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction = timeout(Duration::from_secs(5), matching_transaction)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        transaction,
+        include_hex!(
+            "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+        )
+    );
+}
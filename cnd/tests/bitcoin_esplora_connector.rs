@@ -0,0 +1,187 @@
+pub mod bitcoin_helper;
+
+use bitcoin::{consensus::serialize, Address, BitcoinHash, Network, Transaction};
+use cnd::btsieve::{
+    bitcoin::{BitcoinConnector, EsploraConnector, IndexLookup, TransactionExt},
+    BlockByHash, LatestBlock,
+};
+use futures_core::compat::Future01CompatExt;
+use std::{collections::HashMap, net::SocketAddr, str::FromStr, sync::Arc};
+use warp::{http::StatusCode, Filter};
+
+/// Serves the given blocks through the parts of the Esplora HTTP API the
+/// connector uses. The last block is the tip of the chain.
+fn serve(blocks: Vec<bitcoin::Block>) -> SocketAddr {
+    let tip = blocks.last().expect("at least one block").bitcoin_hash();
+    let transactions: Arc<HashMap<String, Transaction>> = Arc::new(
+        blocks
+            .iter()
+            .flat_map(|block| block.txdata.iter())
+            .map(|transaction| (transaction.txid().to_string(), transaction.clone()))
+            .collect(),
+    );
+    let blocks: Arc<HashMap<String, bitcoin::Block>> = Arc::new(
+        blocks
+            .into_iter()
+            .map(|block| (block.bitcoin_hash().to_string(), block))
+            .collect(),
+    );
+
+    let tip_hash = warp::path!("blocks" / "tip" / "hash").map(move || tip.to_string());
+
+    let raw_block =
+        warp::path!("block" / String / "raw").map(move |hash: String| match blocks.get(&hash) {
+            Some(block) => warp::reply::with_status(serialize(block), StatusCode::OK),
+            None => warp::reply::with_status(Vec::new(), StatusCode::NOT_FOUND),
+        });
+
+    let address_transactions = warp::path!("address" / String / "txs" / "chain").map({
+        let transactions = Arc::clone(&transactions);
+        move |address: String| {
+            let address = Address::from_str(&address).unwrap();
+            let txids = transactions
+                .values()
+                .filter(|transaction| transaction.find_output(&address).is_some())
+                .map(|transaction| serde_json::json!({ "txid": transaction.txid() }))
+                .collect::<Vec<_>>();
+
+            warp::reply::json(&txids)
+        }
+    });
+
+    let raw_transaction =
+        warp::path!("tx" / String / "raw").map(move |txid: String| match transactions.get(&txid) {
+            Some(transaction) => warp::reply::with_status(serialize(transaction), StatusCode::OK),
+            None => warp::reply::with_status(Vec::new(), StatusCode::NOT_FOUND),
+        });
+
+    let fee_estimates = warp::path!("fee-estimates").map(|| {
+        warp::reply::json(
+            &serde_json::json!({ "1": 87.882, "2": 87.882, "6": 68.285, "144": 1.027 }),
+        )
+    });
+
+    let routes = warp::get().and(warp::path("api")).and(
+        tip_hash
+            .or(raw_block)
+            .or(address_transactions)
+            .or(raw_transaction)
+            .or(fee_estimates),
+    );
+
+    let (socket, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    socket
+}
+
+fn connector(socket: SocketAddr) -> EsploraConnector {
+    EsploraConnector::new(
+        format!("http://{}/api", socket).parse().unwrap(),
+        Network::Regtest,
+    )
+    .unwrap()
+}
+
+fn blocks() -> Vec<bitcoin::Block> {
+    vec![
+        include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"),
+        include_hex!(
+            "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"
+        ),
+        include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+    ]
+}
+
+#[tokio::test]
+async fn esplora_connector_returns_tip_of_the_chain_as_latest_block() {
+    let blocks = blocks();
+    let mut connector = connector(serve(blocks.clone()));
+
+    let latest_block = connector.latest_block().compat().await.unwrap();
+
+    assert_eq!(latest_block, blocks[2]);
+}
+
+#[tokio::test]
+async fn esplora_connector_fetches_block_by_hash() {
+    let blocks = blocks();
+    let connector = connector(serve(blocks.clone()));
+
+    let block = connector
+        .block_by_hash(blocks[1].bitcoin_hash())
+        .compat()
+        .await
+        .unwrap();
+
+    assert_eq!(block, blocks[1]);
+}
+
+#[tokio::test]
+async fn esplora_connector_fails_for_unknown_block() {
+    let blocks = blocks();
+    let connector = connector(serve(vec![blocks[0].clone()]));
+
+    let result = connector
+        .block_by_hash(blocks[1].bitcoin_hash())
+        .compat()
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn esplora_connector_finds_transactions_of_address() {
+    let connector = connector(serve(blocks()));
+    let address = Address::from_str(
+        include_str!("test_data/bitcoin/find_transaction_missed_previous_latest_block/address")
+            .trim(),
+    )
+    .unwrap();
+
+    let transactions = connector.transactions_by_address(&address).await.unwrap();
+
+    let expected: Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+    );
+    assert_eq!(transactions, vec![expected]);
+}
+
+#[tokio::test]
+async fn esplora_index_knows_whether_address_received_transaction() {
+    let connector = BitcoinConnector::from(connector(serve(blocks())));
+    let address = Address::from_str(
+        include_str!("test_data/bitcoin/find_transaction_missed_previous_latest_block/address")
+            .trim(),
+    )
+    .unwrap();
+    let other_address = Address::from_str(
+        include_str!("test_data/bitcoin/find_transaction_go_back_into_the_past/address").trim(),
+    )
+    .unwrap();
+
+    let lookup = connector.transaction_to_address(&address).await.unwrap();
+    let other_lookup = connector
+        .transaction_to_address(&other_address)
+        .await
+        .unwrap();
+
+    let expected: Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+    );
+    assert_eq!(lookup, IndexLookup::Found(expected));
+    assert_eq!(other_lookup, IndexLookup::Absent);
+}
+
+#[tokio::test]
+async fn esplora_fee_estimates_are_converted_to_fee_per_wu() {
+    let connector = BitcoinConnector::from(connector(serve(blocks())));
+
+    let next_block = connector.estimate_fee_per_wu(1).await.unwrap();
+    let between_targets = connector.estimate_fee_per_wu(10).await.unwrap();
+    let one_day = connector.estimate_fee_per_wu(144).await.unwrap();
+
+    assert_eq!(next_block, Some(22));
+    assert_eq!(between_targets, Some(18));
+    assert_eq!(one_day, Some(1));
+}
@@ -0,0 +1,115 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::BitcoinConnectorMock;
+use chrono::NaiveDateTime;
+use cnd::btsieve::{bitcoin::ChainFollower, Finality};
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn transaction_is_final_once_buried_deeply_enough() {
+    let block1: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+        vec![
+            block1.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction: bitcoin::Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block1.header.time as i64, 0);
+
+    let finality = timeout(
+        Duration::from_secs(5),
+        follower.transaction_finality(transaction.txid(), 2, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(finality, Finality::Final);
+}
+
+#[tokio::test]
+async fn transaction_is_orphaned_if_blockchain_reorganisation_removes_its_block() {
+    let block1: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block1.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block5_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block4b_stale.hex"),
+        ],
+        vec![
+            block1.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block2.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block3.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block4.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block5_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/block4b_stale.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction: bitcoin::Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/transaction.hex"
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block1.header.time as i64 + 1, 0);
+
+    let finality = timeout(
+        Duration::from_secs(5),
+        follower.transaction_finality(transaction.txid(), 3, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(finality, Finality::Orphaned);
+}
+
+#[tokio::test]
+async fn finality_is_unknown_if_transaction_is_not_found() {
+    let block1: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+        vec![
+            block1.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction: bitcoin::Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/transaction.hex"
+    );
+    let start_of_swap = NaiveDateTime::from_timestamp(block1.header.time as i64 + 1, 0);
+
+    let finality = timeout(
+        Duration::from_secs(5),
+        follower.transaction_finality(transaction.txid(), 2, start_of_swap),
+    )
+    .await
+    .unwrap();
+
+    assert!(finality.is_err());
+}
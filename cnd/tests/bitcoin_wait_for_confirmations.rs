@@ -0,0 +1,109 @@
+pub mod bitcoin_helper;
+
+use bitcoin_helper::BitcoinConnectorMock;
+use chrono::NaiveDateTime;
+use cnd::btsieve::bitcoin::ChainFollower;
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn resolves_once_transaction_has_enough_confirmations() {
+    let block1: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+        vec![
+            block1.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction: bitcoin::Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block1.header.time as i64, 0);
+
+    let confirmed = timeout(
+        Duration::from_secs(5),
+        follower.wait_for_confirmations(transaction.txid(), 2, start_of_swap),
+    )
+    .await;
+
+    assert_eq!(confirmed.unwrap().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn waits_while_transaction_has_too_few_confirmations() {
+    let block1: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/block1.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+        vec![
+            block1.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_missed_previous_latest_block/block3.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction: bitcoin::Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_missed_previous_latest_block/transaction.hex"
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block1.header.time as i64, 0);
+
+    let confirmed = timeout(
+        Duration::from_secs(3),
+        follower.wait_for_confirmations(transaction.txid(), 3, start_of_swap),
+    )
+    .await;
+
+    assert!(confirmed.is_err());
+}
+
+#[tokio::test]
+async fn fails_if_transaction_is_orphaned() {
+    let block1: bitcoin::Block = include_hex!(
+        "./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/block1.hex"
+    );
+    let connector = BitcoinConnectorMock::new(
+        vec![
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/block1b_stale.hex"),
+        ],
+        vec![
+            block1.clone(),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/block2_with_transaction.hex"),
+            include_hex!("./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/block1b_stale.hex"),
+        ],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let transaction: bitcoin::Transaction = include_hex!(
+        "./test_data/bitcoin/find_transaction_if_blockchain_reorganisation/transaction.hex"
+    );
+    let start_of_swap = NaiveDateTime::from_timestamp(block1.header.time as i64 + 1, 0);
+
+    let confirmed = timeout(
+        Duration::from_secs(5),
+        follower.wait_for_confirmations(transaction.txid(), 2, start_of_swap),
+    )
+    .await;
+
+    let confirmed = confirmed.expect("orphaned transaction was awaited");
+    assert!(confirmed.is_err());
+}
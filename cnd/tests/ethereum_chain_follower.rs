@@ -0,0 +1,174 @@
+pub mod ethereum_helper;
+
+use chrono::NaiveDateTime;
+use cnd::{
+    btsieve::ethereum::{ChainFollower, Event, IndexLookup, Topic},
+    ethereum::{Address, Block, Bytes, Transaction, TransactionReceipt},
+};
+use ethereum_helper::EthereumConnectorMock;
+use futures_core::future;
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn chain_follower_finds_contract_creation_in_block_it_missed() {
+    let bytecode = Bytes(vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+    let contract_address: Address = "0x49a1897a4b65ab6e87fa4fbbf7bf148ffbcccdae"
+        .parse()
+        .unwrap();
+
+    // turn the transaction into one that deploys a contract
+    let mut want_transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/transaction.json"
+    );
+    want_transaction.to = None;
+    want_transaction.input = bytecode.clone();
+    let mut receipt: TransactionReceipt = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/receipt.json"
+    );
+    receipt.contract_address = Some(contract_address);
+    let mut block3: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+    );
+    for transaction in block3.transactions.iter_mut() {
+        if transaction.hash == want_transaction.hash {
+            *transaction = want_transaction.clone();
+        }
+    }
+
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    let connector = EthereumConnectorMock::new(
+        vec![
+            block2.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block1.json"
+            ),
+            block2.clone(),
+            block3,
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![(want_transaction.hash, receipt)],
+    );
+    let follower = ChainFollower::new(connector);
+    tokio::spawn(follower.clone().follow(None));
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.as_u32() as i64, 0);
+
+    let (got_transaction, got_location) = timeout(
+        Duration::from_secs(5),
+        follower.contract_creation(bytecode, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        (got_transaction, got_location),
+        (want_transaction, contract_address)
+    );
+}
+
+fn event_of(receipt: &TransactionReceipt) -> Event {
+    let log = &receipt.logs[0];
+
+    Event {
+        address: log.address,
+        topics: vec![Some(Topic(log.topics[0])), None, None, None],
+    }
+}
+
+#[tokio::test]
+async fn chain_follower_takes_event_found_in_index_without_walking_the_chain() {
+    let want_transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/transaction.json"
+    );
+    let receipt: TransactionReceipt = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/receipt.json"
+    );
+    // the parents of the latest block are unknown, walking the chain fails
+    let block5: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/block5.json"
+    );
+    let connector = EthereumConnectorMock::new(vec![block5.clone()], vec![block5], vec![]);
+    let follower = ChainFollower::new(connector);
+
+    let lookup = future::ok(IndexLookup::Found(
+        want_transaction.clone(),
+        receipt.clone(),
+    ));
+    let start_of_swap = NaiveDateTime::from_timestamp(0, 0);
+
+    let (got_transaction, got_log) = timeout(
+        Duration::from_secs(5),
+        follower.event_indexed(event_of(&receipt), lookup, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        (got_transaction, got_log),
+        (want_transaction, receipt.logs[0].clone())
+    );
+}
+
+#[tokio::test]
+async fn chain_follower_walks_the_chain_if_index_is_inconclusive() {
+    let block1_with_transaction: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/block1_with_transaction.json"
+    );
+    let want_transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/transaction.json"
+    );
+    let receipt: TransactionReceipt = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/receipt.json"
+    );
+    let connector = EthereumConnectorMock::new(
+        vec![include_json_test_data!(
+            "./test_data/ethereum/find_transaction_go_back_into_the_past/block5.json"
+        )],
+        vec![
+            block1_with_transaction.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_go_back_into_the_past/block2.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_go_back_into_the_past/block3.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_go_back_into_the_past/block4.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_go_back_into_the_past/block5.json"
+            ),
+        ],
+        vec![(want_transaction.hash, receipt.clone())],
+    );
+    let follower = ChainFollower::new(connector);
+
+    let lookup = future::ok(IndexLookup::Inconclusive);
+    let start_of_swap =
+        NaiveDateTime::from_timestamp(block1_with_transaction.timestamp.low_u32() as i64, 0);
+
+    let (got_transaction, got_log) = timeout(
+        Duration::from_secs(5),
+        follower.event_indexed(event_of(&receipt), lookup, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        (got_transaction, got_log),
+        (want_transaction, receipt.logs[0].clone())
+    );
+}
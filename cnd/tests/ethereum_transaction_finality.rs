@@ -0,0 +1,131 @@
+pub mod ethereum_helper;
+
+use chrono::NaiveDateTime;
+use cnd::{
+    btsieve::{ethereum::transaction_finality, Finality},
+    ethereum::{Block, Transaction, H256},
+};
+use ethereum_helper::EthereumConnectorMock;
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn transaction_is_final_once_buried_deeply_enough() {
+    let transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/transaction.json"
+    );
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    let connector = EthereumConnectorMock::new(
+        vec![
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![
+            block2.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![],
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.low_u32() as i64, 0);
+
+    let finality = timeout(
+        Duration::from_secs(5),
+        transaction_finality(connector, transaction.hash, 2, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(finality, Finality::Final);
+}
+
+#[tokio::test]
+async fn transaction_is_orphaned_if_blockchain_reorganisation_removes_its_block() {
+    let transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/transaction.json"
+    );
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    let block3: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+    );
+
+    // A competing block on top of block2 that replaces block3.
+    let mut block3b: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+    );
+    block3b.hash = Some(H256::repeat_byte(0xb3));
+    block3b.parent_hash = block2.hash.unwrap();
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.low_u32() as i64, 0);
+
+    let connector = EthereumConnectorMock::new(
+        vec![block3.clone(), block3b.clone()],
+        vec![
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block1.json"
+            ),
+            block2,
+            block3,
+            block3b,
+        ],
+        vec![],
+    );
+
+    let finality = timeout(
+        Duration::from_secs(5),
+        transaction_finality(connector, transaction.hash, 3, start_of_swap),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(finality, Finality::Orphaned);
+}
+
+#[tokio::test]
+async fn finality_is_unknown_if_transaction_is_not_found() {
+    let transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_go_back_into_the_past/transaction.json"
+    );
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    let connector = EthereumConnectorMock::new(
+        vec![include_json_test_data!(
+            "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+        )],
+        vec![
+            block2.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![],
+    );
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.low_u32() as i64 + 1, 0);
+
+    let finality = timeout(
+        Duration::from_secs(5),
+        transaction_finality(connector, transaction.hash, 2, start_of_swap),
+    )
+    .await
+    .unwrap();
+
+    assert!(finality.is_err());
+}
@@ -2,6 +2,7 @@ use chrono::offset::Utc;
 use cnd::{
     btsieve::ethereum::{matching_transaction_and_receipt, Web3Connector},
     ethereum::{TransactionRequest, U256},
+    swap_protocols::ledger::ethereum::ChainId,
 };
 use futures_core::compat::Future01CompatExt;
 use reqwest::Url;
@@ -35,7 +36,7 @@ async fn ethereum_transaction_matching_smoke_test() {
     ))
     .unwrap();
 
-    let connector = Web3Connector::new(url);
+    let connector = Web3Connector::new(url, ChainId::regtest());
 
     let accounts = client
         .eth()
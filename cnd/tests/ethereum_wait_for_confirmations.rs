@@ -0,0 +1,131 @@
+pub mod ethereum_helper;
+
+use chrono::NaiveDateTime;
+use cnd::{
+    btsieve::ethereum::wait_for_confirmations,
+    ethereum::{Block, Transaction},
+};
+use ethereum_helper::EthereumConnectorMock;
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn resolves_once_transaction_has_enough_confirmations() {
+    let transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/transaction.json"
+    );
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    let connector = EthereumConnectorMock::new(
+        vec![
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![
+            block2.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![],
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.low_u32() as i64, 0);
+
+    let confirmed = timeout(
+        Duration::from_secs(5),
+        wait_for_confirmations(connector, transaction.hash, 2, start_of_swap),
+    )
+    .await;
+
+    assert_eq!(confirmed.unwrap().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn waits_while_transaction_has_too_few_confirmations() {
+    let transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/transaction.json"
+    );
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    let connector = EthereumConnectorMock::new(
+        vec![
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![
+            block2.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![],
+    );
+
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.low_u32() as i64, 0);
+
+    let confirmed = timeout(
+        Duration::from_secs(3),
+        wait_for_confirmations(connector, transaction.hash, 3, start_of_swap),
+    )
+    .await;
+
+    assert!(confirmed.is_err());
+}
+
+#[tokio::test]
+async fn fails_if_transaction_is_orphaned() {
+    let transaction: Transaction = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/transaction.json"
+    );
+    let block2: Block<Transaction> = include_json_test_data!(
+        "./test_data/ethereum/find_transaction_missed_previous_latest_block/block2.json"
+    );
+    // block4 builds on the variant of block3 without the transaction
+    let connector = EthereumConnectorMock::new(
+        vec![
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3_with_transaction.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![
+            block2.clone(),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block3.json"
+            ),
+            include_json_test_data!(
+                "./test_data/ethereum/find_transaction_missed_previous_latest_block/block4.json"
+            ),
+        ],
+        vec![],
+    );
+    let start_of_swap = NaiveDateTime::from_timestamp(block2.timestamp.low_u32() as i64, 0);
+
+    let confirmed = timeout(
+        Duration::from_secs(5),
+        wait_for_confirmations(connector, transaction.hash, 2, start_of_swap),
+    )
+    .await;
+
+    let confirmed = confirmed.expect("orphaned transaction was awaited");
+    assert!(confirmed.is_err());
+}